@@ -42,6 +42,12 @@ CREATE TABLE "user" (
     biography TEXT,
     user_page TEXT,
 
+    -- Privacy settings
+    dm_contacts_only BOOLEAN NOT NULL DEFAULT false,
+
+    -- Overrides the configured storage quota, in bytes
+    storage_quota BIGINT,
+
     -- Name uniqueness constraints
     UNIQUE (name, deleted_at),
     UNIQUE (slug, deleted_at),
@@ -60,6 +66,7 @@ CREATE TABLE "user" (
     CHECK (user_page IS NULL OR (length(user_page) > 0 AND length(user_page) < 100)),
 
     CHECK (name_changes_left >= 0),                                 -- Value cannot be negative
+    CHECK (storage_quota IS NULL OR storage_quota >= 0),            -- Value cannot be negative
     CHECK (avatar_s3_hash IS NULL OR length(avatar_s3_hash) = 64)   -- SHA-512 hash size (if set)
 );
 
@@ -73,10 +80,37 @@ CREATE TABLE user_bot_owner (
     PRIMARY KEY (bot_user_id, human_user_id)
 );
 
+-- History of user renames, kept even after the old name's alias is gone.
+--
+-- used_token is whether a name change token was spent on the rename.
+CREATE TABLE user_name_change (
+    name_change_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    changed_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    old_name TEXT NOT NULL,
+    old_slug TEXT NOT NULL,
+    new_name TEXT NOT NULL,
+    new_slug TEXT NOT NULL,
+    used_token BOOLEAN NOT NULL,
+
+    CHECK (old_name != new_name)
+);
+
+CREATE INDEX user_name_change_user_idx ON user_name_change (user_id);
+CREATE INDEX user_name_change_old_slug_idx ON user_name_change (old_slug);
+
 --
 -- Site
 --
 
+CREATE TYPE site_join_policy AS ENUM (
+    'open',         -- anyone may join
+    'password',     -- anyone with the join password may join
+    'application',  -- users apply, and staff accept or reject them
+    'invite'        -- only users with an invitation may join
+);
+
 CREATE TABLE site (
     site_id BIGSERIAL PRIMARY KEY,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
@@ -91,6 +125,11 @@ CREATE TABLE site (
     default_page TEXT NOT NULL DEFAULT 'start',
     custom_domain TEXT,  -- Dependency cycle, add foreign key constraint after
     layout TEXT,  -- Default page layout for the site
+    is_template BOOLEAN NOT NULL DEFAULT false,  -- Whether new sites may be created from this one
+    join_policy site_join_policy NOT NULL DEFAULT 'invite',
+    join_password TEXT,  -- Argon2 hash, used by the 'password' join policy
+    join_questions TEXT[] NOT NULL DEFAULT '{}',  -- Asked of users applying to join
+    storage_quota BIGINT CHECK (storage_quota IS NULL OR storage_quota >= 0),  -- Overrides the configured quota, in bytes
 
     UNIQUE (slug, deleted_at)
 );
@@ -164,6 +203,11 @@ CREATE TABLE relation (
     )
 );
 
+-- For looking up active relations from the other side,
+-- such as walking up a page's parents
+CREATE INDEX relation_from_idx ON relation (relation_type, from_type, from_id)
+    WHERE overwritten_at IS NULL AND deleted_at IS NULL;
+
 --
 -- Session
 --
@@ -173,11 +217,23 @@ CREATE TABLE session (
     user_id BIGINT NOT NULL REFERENCES "user"(user_id),
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     expires_at TIMESTAMP WITH TIME ZONE NOT NULL CHECK (expires_at > created_at),
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     ip_address TEXT NOT NULL,  -- TODO change to INET
     user_agent TEXT NOT NULL,
     restricted BOOLEAN NOT NULL
 );
 
+-- Devices a user has previously logged in from, used for new login alerts.
+-- The device is the description derived from the user-agent, not the raw string.
+CREATE TABLE user_device (
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    device TEXT NOT NULL,
+    first_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+
+    PRIMARY KEY (user_id, device)
+);
+
 --
 -- Page
 --
@@ -189,6 +245,11 @@ CREATE TABLE page_category (
     site_id BIGINT NOT NULL REFERENCES site(site_id),
     slug TEXT NOT NULL,
     layout TEXT, -- category-specific override for DOM layout
+    template_page TEXT, -- slug of the page whose wikitext new pages in this category start with
+    license TEXT, -- category-specific override for the license pages are published under
+    permissions JSON NOT NULL DEFAULT '{}', -- category-specific overrides for who can do what
+    score_type TEXT, -- category-specific override for how pages are rated
+    hidden BOOLEAN NOT NULL DEFAULT false, -- excluded from ListPages unless explicitly included
 
     UNIQUE (site_id, slug)
 );
@@ -203,12 +264,24 @@ CREATE TABLE page (
     latest_revision_id BIGINT, -- nullable to avoid an initial page_revision dependency cycle
     page_category_id BIGINT NOT NULL REFERENCES page_category(category_id),
     slug TEXT NOT NULL,
-    discussion_thread_id BIGINT, -- TODO: add REFERENCES to forum threads
+    discussion_thread_id BIGINT, -- foreign key constraint added after forum_thread
     layout TEXT, -- page-specific override for DOM layout
 
     UNIQUE (site_id, slug, deleted_at)
 );
 
+-- Old slugs which should send visitors to a page, such as after its category was renamed.
+-- These are only used if there is no page at that slug.
+CREATE TABLE page_redirect (
+    redirect_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    slug TEXT NOT NULL,
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+
+    UNIQUE (site_id, slug)
+);
+
 --
 -- Page revisions and contents
 --
@@ -241,13 +314,25 @@ CREATE TYPE page_revision_change AS ENUM (
 -- If the KangarooTwelve hash algorithm was available in pgcrypto
 -- we'd check directly (hash = digest(contents, 'kangarootwelve')),
 -- but since we can't we'll just verify the hash length.
+-- Text is stored either as-is in contents, or zstd-compressed in compressed_contents.
+-- If base_hash is set, it was compressed using that text as a dictionary (i.e. a delta).
+-- Base texts are never themselves deltas, so at most one other row is needed to decode.
 CREATE TABLE text (
     hash BYTEA PRIMARY KEY,
-    contents TEXT NOT NULL,
-
-    CHECK (length(hash) = 16)  -- KangarooTwelve hash size, 128 bits
+    contents TEXT,
+    compressed_contents BYTEA,
+    base_hash BYTEA REFERENCES text(hash),
+    size BIGINT NOT NULL,  -- length of the original text, in bytes
+
+    CHECK (length(hash) = 16),  -- KangarooTwelve hash size, 128 bits
+    CHECK ((contents IS NULL) != (compressed_contents IS NULL)),  -- exactly one is set
+    CHECK (base_hash IS NULL OR compressed_contents IS NOT NULL),  -- only compressed text has a base
+    CHECK (base_hash IS NULL OR base_hash != hash),
+    CHECK (size >= 0)
 );
 
+CREATE INDEX text_base_hash_idx ON text (base_hash) WHERE base_hash IS NOT NULL;
+
 -- Main revision table
 CREATE TABLE page_revision (
     revision_id BIGSERIAL PRIMARY KEY,
@@ -315,14 +400,6 @@ ALTER TABLE page ADD CONSTRAINT page_revision_revision_id_fk
 -- Page metadata
 --
 
-CREATE TABLE page_parent (
-    parent_page_id BIGINT REFERENCES page(page_id),
-    child_page_id BIGINT REFERENCES page(page_id),
-    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
-
-    PRIMARY KEY (parent_page_id, child_page_id)
-);
-
 CREATE TABLE page_attribution (
     page_id BIGINT REFERENCES page(page_id),
     user_id BIGINT REFERENCES "user"(user_id),
@@ -352,6 +429,80 @@ CREATE TABLE page_lock (
     UNIQUE (page_id, deleted_at)
 );
 
+--
+-- Page drafts
+--
+
+-- Autosaved, unsubmitted edits. Each user has at most one draft per page.
+CREATE TABLE page_draft (
+    draft_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    base_revision_id BIGINT NOT NULL REFERENCES page_revision(revision_id),
+    wikitext_hash BYTEA NOT NULL REFERENCES text(hash),
+    title TEXT NOT NULL,
+    alt_title TEXT,
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    comments TEXT NOT NULL DEFAULT '',
+
+    UNIQUE (page_id, user_id)
+);
+
+--
+-- Scheduled page changes
+--
+
+CREATE TYPE page_schedule_type AS ENUM (
+    'create',
+    'edit'
+);
+
+CREATE TYPE page_schedule_status AS ENUM (
+    'pending',
+    'published',
+    'cancelled',
+    'failed'
+);
+
+-- Page creations and edits which go live at a given time.
+--
+-- The changes array works like the one in page_revision, listing which
+-- of the fields are to be set. A creation always sets the wikitext, title,
+-- and alt title, and has the slug of the new page.
+CREATE TABLE page_schedule (
+    schedule_id BIGSERIAL PRIMARY KEY,
+    schedule_type page_schedule_type NOT NULL,
+    status page_schedule_status NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    publish_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    published_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    page_id BIGINT REFERENCES page(page_id),  -- Set for creations once published
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    slug TEXT,
+    changes TEXT[] NOT NULL,
+    wikitext_hash BYTEA REFERENCES text(hash),
+    title TEXT,
+    alt_title TEXT,
+    tags TEXT[],
+    comments TEXT NOT NULL,
+    revision_id BIGINT REFERENCES page_revision(revision_id),
+    error TEXT,
+
+    CHECK (changes <@ '{
+        wikitext,
+        title,
+        alt_title,
+        tags
+    }'),
+    CHECK (schedule_type != 'create' OR (slug IS NOT NULL AND changes @> '{wikitext, title, alt_title}')),
+    CHECK (schedule_type != 'edit' OR (page_id IS NOT NULL AND changes != '{}'))
+);
+
 --
 -- Page backlinks tracking
 --
@@ -362,7 +513,8 @@ CREATE TYPE page_connection_type AS ENUM (
     'include-elements',
     'component',
     'link',
-    'redirect'
+    'redirect',
+    'list-pages'
 );
 
 CREATE TABLE page_link (
@@ -417,6 +569,17 @@ CREATE TABLE page_vote (
     CHECK ((disabled_at IS NULL) = (disabled_by IS NULL))
 );
 
+-- Materialized scores, kept up-to-date when votes change
+CREATE TABLE page_score (
+    page_id BIGINT PRIMARY KEY REFERENCES page(page_id),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    score DOUBLE PRECISION NOT NULL, -- used for sorting, regardless of score type
+    score_integer BIGINT, -- set if the score is an integer, to avoid conversion loss
+    vote_count INTEGER NOT NULL,
+
+    CHECK (vote_count >= 0)
+);
+
 --
 -- Blobs
 --
@@ -532,6 +695,10 @@ CREATE TABLE file_revision (
     UNIQUE (file_id, page_id, revision_number)
 );
 
+-- For computing storage usage
+CREATE INDEX file_revision_site_blob_idx ON file_revision (site_id, s3_hash);
+CREATE INDEX file_revision_user_blob_idx ON file_revision (user_id, s3_hash);
+
 --
 -- Direct Messages
 --
@@ -656,3 +823,215 @@ CREATE TABLE filter (
 
     UNIQUE (site_id, regex, deleted_at)
 );
+
+--
+-- Site tags
+--
+
+-- If a site has any tags configured here, then only those tags (and their aliases) may be used.
+-- Otherwise any tag is permitted, though group rules still apply.
+CREATE TABLE site_tag_group (
+    tag_group_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    exclusive BOOLEAN NOT NULL DEFAULT false,  -- at most one tag from this group per page
+    required BOOLEAN NOT NULL DEFAULT false,  -- at least one tag from this group per page
+
+    UNIQUE (site_id, name)
+);
+
+CREATE TABLE site_tag (
+    tag_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    tag_group_id BIGINT REFERENCES site_tag_group(tag_group_id) ON DELETE SET NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    aliases TEXT[] NOT NULL DEFAULT '{}',  -- alternate names, rewritten to this tag on save
+
+    UNIQUE (site_id, name)
+);
+
+--
+-- Site backups
+--
+
+CREATE TYPE site_backup_status AS ENUM (
+    'pending',
+    'running',
+    'completed',
+    'failed'
+);
+
+-- Tracks exports of a whole site to a portable archive.
+--
+-- The progress columns are updated while the export job is running,
+-- and storage_path is set once the archive has been written.
+CREATE TABLE site_backup (
+    backup_id BIGSERIAL PRIMARY KEY,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    created_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    status site_backup_status NOT NULL DEFAULT 'pending',
+    progress_done INTEGER NOT NULL DEFAULT 0,
+    progress_total INTEGER NOT NULL DEFAULT 0,
+    storage_path TEXT,
+    archive_size BIGINT,
+    archive_hash BYTEA,
+    error TEXT,
+
+    CHECK (progress_done <= progress_total),
+    CHECK (archive_hash IS NULL OR length(archive_hash) = 64)  -- SHA-512 hash size, if present
+);
+
+--
+-- Site membership
+--
+
+CREATE TYPE site_application_status AS ENUM (
+    'pending',
+    'accepted',
+    'rejected'
+);
+
+-- Applications to join a site using the 'application' join policy.
+--
+-- The answers array corresponds to the site's join_questions at the time of applying.
+CREATE TABLE site_application (
+    application_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    answers TEXT[] NOT NULL DEFAULT '{}',
+    status site_application_status NOT NULL DEFAULT 'pending',
+    reviewed_at TIMESTAMP WITH TIME ZONE,
+    reviewed_by BIGINT REFERENCES "user"(user_id),
+    review_message TEXT,
+
+    CHECK ((status = 'pending') = (reviewed_at IS NULL)),
+    CHECK ((reviewed_at IS NULL) = (reviewed_by IS NULL))
+);
+
+-- Each user may only have one pending application per site
+CREATE UNIQUE INDEX site_application_pending_idx
+    ON site_application (site_id, user_id)
+    WHERE status = 'pending';
+
+-- Invitation links, which let users join a site regardless of its join policy.
+CREATE TABLE site_invitation (
+    invitation_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    created_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    token TEXT NOT NULL UNIQUE,
+    expires_at TIMESTAMP WITH TIME ZONE,  -- NULL means it never expires
+    max_uses INTEGER,  -- NULL means it may be used any number of times
+    use_count INTEGER NOT NULL DEFAULT 0,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    revoked_by BIGINT REFERENCES "user"(user_id),
+
+    CHECK (max_uses IS NULL OR max_uses > 0),
+    CHECK (use_count >= 0),
+    CHECK ((revoked_at IS NULL) = (revoked_by IS NULL))
+);
+
+--
+-- Forum
+--
+
+CREATE TABLE forum_group (
+    forum_group_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    from_wikidot BOOLEAN NOT NULL DEFAULT false,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    sort_index INTEGER NOT NULL DEFAULT 0,
+    visible BOOLEAN NOT NULL DEFAULT true
+);
+
+CREATE TABLE forum_category (
+    forum_category_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    from_wikidot BOOLEAN NOT NULL DEFAULT false,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    forum_group_id BIGINT NOT NULL REFERENCES forum_group(forum_group_id),
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    sort_index INTEGER NOT NULL DEFAULT 0,
+    max_nest_level INTEGER NOT NULL DEFAULT 10,
+    per_page_discussion BOOLEAN NOT NULL DEFAULT false,
+
+    CHECK (max_nest_level >= 0)
+);
+
+-- Each site has at most one category holding per-page discussion threads
+CREATE UNIQUE INDEX forum_category_per_page_discussion_idx
+    ON forum_category (site_id)
+    WHERE per_page_discussion AND deleted_at IS NULL;
+
+CREATE TABLE forum_thread (
+    forum_thread_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    from_wikidot BOOLEAN NOT NULL DEFAULT false,
+    created_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    forum_category_id BIGINT NOT NULL REFERENCES forum_category(forum_category_id),
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    sticky BOOLEAN NOT NULL DEFAULT false,
+    locked BOOLEAN NOT NULL DEFAULT false,
+
+    -- Cached post statistics, updated whenever a post is added or removed
+    post_count INTEGER NOT NULL DEFAULT 0,
+    last_post_at TIMESTAMP WITH TIME ZONE,
+    last_post_by BIGINT REFERENCES "user"(user_id)
+);
+
+-- Add foreign key constraint for page discussions
+ALTER TABLE page ADD CONSTRAINT page_discussion_thread_id_fk
+    FOREIGN KEY (discussion_thread_id) REFERENCES forum_thread(forum_thread_id);
+
+-- Posts only track their position in the thread,
+-- all of their contents are stored in forum_post_revision.
+CREATE TABLE forum_post (
+    forum_post_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    from_wikidot BOOLEAN NOT NULL DEFAULT false,
+    created_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    forum_thread_id BIGINT NOT NULL REFERENCES forum_thread(forum_thread_id),
+    parent_post_id BIGINT REFERENCES forum_post(forum_post_id),
+
+    CHECK (parent_post_id != forum_post_id)
+);
+
+CREATE TABLE forum_post_revision (
+    revision_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    revision_number INTEGER NOT NULL,
+    from_wikidot BOOLEAN NOT NULL DEFAULT false,
+    forum_post_id BIGINT NOT NULL REFERENCES forum_post(forum_post_id),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    title TEXT NOT NULL,
+    wikitext_hash BYTEA NOT NULL REFERENCES text(hash),
+    compiled_hash BYTEA NOT NULL REFERENCES text(hash),
+    compiled_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    compiled_generator TEXT NOT NULL,
+
+    UNIQUE (forum_post_id, revision_number)
+);
@@ -28,24 +28,28 @@
 
 use crate::config::{Config, Secrets};
 use crate::endpoints::{
-    auth::*, blob::*, category::*, domain::*, email::*, file::*, file_revision::*,
-    info::*, link::*, locale::*, message::*, misc::*, page::*, page_revision::*,
-    parent::*, site::*, site_member::*, text::*, user::*, user_bot::*, view::*, vote::*,
+    auth::*, blob::*, category::*, domain::*, email::*, feed::*, file::*,
+    file_revision::*, forum::*, info::*, job::*, link::*, locale::*, message::*, misc::*,
+    page::*, page_draft::*, page_revision::*, page_schedule::*, parent::*,
+    recent_changes::*, site::*, site_backup::*, site_member::*, tag::*, text::*, user::*,
+    user_bot::*, user_contact::*, view::*, vote::*,
 };
-use crate::locales::Localizations;
+use crate::locales::{add_request_locales, Localizations, RequestLocales};
 use crate::services::blob::MimeAnalyzer;
 use crate::services::job::JobWorker;
-use crate::services::{into_rpc_error, ServiceContext};
+use crate::services::{
+    into_rpc_error, Error as ServiceError, OutdateService, RateLimitService,
+    ServiceContext,
+};
+use crate::storage::{self, BlobStorage, LocalUploadLayer};
 use crate::utils::debug_pointer;
 use crate::{database, redis as redis_db};
 use jsonrpsee::server::{RpcModule, Server, ServerHandle};
-use jsonrpsee::types::error::ErrorObjectOwned;
 use rsmq_async::PooledRsmq;
-use s3::bucket::Bucket;
 use sea_orm::{DatabaseConnection, TransactionTrait};
 use std::fmt::{self, Debug};
 use std::sync::Arc;
-use std::time::Duration;
+use tower::ServiceBuilder;
 
 pub type ServerState = Arc<ServerStateInner>;
 
@@ -56,7 +60,7 @@ pub struct ServerStateInner {
     pub rsmq: PooledRsmq,
     pub localizations: Localizations,
     pub mime_analyzer: MimeAnalyzer,
-    pub s3_bucket: Box<Bucket>,
+    pub blob_storage: Arc<dyn BlobStorage>,
 }
 
 impl Debug for ServerStateInner {
@@ -68,7 +72,7 @@ impl Debug for ServerStateInner {
             .field("rsmq", &debug_pointer(&self.rsmq))
             .field("localizations", &self.localizations)
             .field("mime_analyzer", &self.mime_analyzer)
-            .field("s3_bucket", &self.s3_bucket)
+            .field("blob_storage", &self.blob_storage)
             .finish()
     }
 }
@@ -91,23 +95,8 @@ pub async fn build_server_state(
     // Load magic data and start MIME thread
     let mime_analyzer = MimeAnalyzer::spawn();
 
-    // Create S3 bucket
-    info!("Opening S3 bucket");
-
-    let s3_bucket = {
-        let mut bucket = Bucket::new(
-            &secrets.s3_bucket,
-            secrets.s3_region.clone(),
-            secrets.s3_credentials.clone(),
-        )?;
-
-        if secrets.s3_path_style {
-            bucket = bucket.with_path_style();
-        }
-
-        bucket.request_timeout = Some(Duration::from_millis(500));
-        bucket
-    };
+    // Set up blob storage
+    let blob_storage = storage::open(&config, &secrets).await?;
 
     // Build server state
     let state = Arc::new(ServerStateInner {
@@ -117,19 +106,28 @@ pub async fn build_server_state(
         rsmq,
         localizations,
         mime_analyzer,
-        s3_bucket,
+        blob_storage,
     });
 
-    // Start workers listening to the job queue (requires ServerState)
-    JobWorker::spawn_all(&state);
-
     // Return server state
     Ok(state)
 }
 
 pub async fn build_server(app_state: ServerState) -> anyhow::Result<ServerHandle> {
+    // Start workers listening to the job queue (requires ServerState)
+    JobWorker::spawn_all(&app_state);
+
+    // Receive uploads for local blob storage, passing through everything else,
+    // and note the caller's locales so errors can be localized.
+    let http_middleware = ServiceBuilder::new()
+        .layer(LocalUploadLayer::new(&app_state))
+        .map_request(add_request_locales);
+
     let socket_address = app_state.config.address;
-    let server = Server::builder().build(socket_address).await?;
+    let server = Server::builder()
+        .set_http_middleware(http_middleware)
+        .build(socket_address)
+        .await?;
     let module = build_module(app_state).await?;
     let handle = server.start(module);
     Ok(handle)
@@ -144,28 +142,49 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
             //
             // Contains a wrapper around each to set up state, convert error types,
             // and produce a transaction used in ServiceContext, passed in.
-            module.register_async_method($name, |params, state, _extensions| async move {
+            module.register_async_method($name, |params, state, extensions| async move {
                 // NOTE: We have our own Arc because we need to share it in some places
                 //       before setting up, but RpcModule insists on adding its own.
                 //       So we need to "unwrap it" before each method invocation.
                 //       Oh well.
                 let state = Arc::clone(&*state);
+                let locales = extensions
+                    .get::<RequestLocales>()
+                    .cloned()
+                    .unwrap_or_default();
 
                 // Wrap each call in a transaction, which commits or rolls back
                 // automatically based on whether the Result is Ok or Err.
                 //
                 // At this level, we take the database-or-RPC error and make it just an RPC error.
+                // Service errors are localized using the caller's requested locales.
+                //
+                // Any response delay (e.g. after a failed login) is slept once
+                // the transaction is finished, so it doesn't hold a connection.
+                // Score rerenders are only queued once the transaction has committed.
                 let db_state = Arc::clone(&state);
-                db_state
+                let transaction = db_state
                     .database
                     .transaction(move |txn| {
                         Box::pin(async move {
-                            // Run the endpoint's implementation, and convert from
+                            // Check the caller's rate limit for this method, then run
+                            // the endpoint's implementation, and convert from
                             // ServiceError to an RPC error.
                             let ctx = ServiceContext::new(&state, &txn);
-                            $method(&ctx, params).await.map_err(ErrorObjectOwned::from)
+                            let into_rpc = |error: ServiceError| {
+                                error.into_rpc_localized(&state.localizations, &locales.0)
+                            };
+
+                            RateLimitService::check(&ctx, $name, params.as_str())
+                                .await
+                                .map_err(into_rpc)?;
+
+                            $method(&ctx, params).await.map_err(into_rpc)
                         })
-                    })
+                    });
+
+                let transaction = OutdateService::with_pending_scores(&db_state, transaction);
+                RateLimitService::with_response_delay(transaction)
                     .await
                     .map_err(into_rpc_error)
             })?;
@@ -207,6 +226,7 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("site_create", site_create);
     register!("site_get", site_get);
     register!("site_update", site_update);
+    register!("site_get_templates", site_get_templates);
     register!("site_from_domain", site_get_from_domain);
 
     // Site custom domain
@@ -214,14 +234,33 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("custom_domain_get", site_custom_domain_get);
     register!("custom_domain_delete", site_custom_domain_delete);
 
+    // Site backups
+    register!("site_backup_create", site_backup_create);
+    register!("site_backup_get", site_backup_get);
+    register!("site_backup_list", site_backup_list);
+    register!("site_backup_restore", site_backup_restore);
+
     // Site membership
     register!("member_set", membership_set);
     register!("member_get", membership_get);
     register!("member_delete", membership_delete);
+    register!("member_join", membership_join);
+    register!("member_apply", membership_apply);
+    register!("member_application_get", membership_application_get);
+    register!("member_application_list", membership_application_list);
+    register!("member_application_accept", membership_application_accept);
+    register!("member_application_reject", membership_application_reject);
+    register!("member_invitation_create", membership_invitation_create);
+    register!("member_invitation_list", membership_invitation_list);
+    register!("member_invitation_revoke", membership_invitation_revoke);
 
     // Category
+    register!("category_create", category_create);
     register!("category_get", category_get);
     register!("category_get_all", category_get_all);
+    register!("category_update", category_update);
+    register!("category_rename", category_rename);
+    register!("category_delete", category_delete);
 
     // Page
     register!("page_create", page_create);
@@ -229,6 +268,7 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("page_get_direct", page_get_direct);
     register!("page_get_deleted", page_get_deleted);
     register!("page_get_score", page_get_score);
+    register!("page_refresh_scores", page_refresh_scores);
     register!("page_edit", page_edit);
     register!("page_delete", page_delete);
     register!("page_move", page_move);
@@ -242,6 +282,38 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("page_revision_get", page_revision_get);
     register!("page_revision_count", page_revision_count);
     register!("page_revision_range", page_revision_range);
+    register!("page_revision_diff", page_revision_diff);
+    register!("page_revision_preview", page_revision_preview);
+
+    // Page drafts
+    register!("page_draft_save", page_draft_save);
+    register!("page_draft_get", page_draft_get);
+    register!("page_draft_list", page_draft_list);
+    register!("page_draft_delete", page_draft_delete);
+
+    // Scheduled page changes
+    register!("page_schedule_create", page_schedule_create);
+    register!("page_schedule_edit", page_schedule_edit);
+    register!("page_schedule_get", page_schedule_get);
+    register!("page_schedule_list", page_schedule_list);
+    register!("page_schedule_cancel", page_schedule_cancel);
+
+    // Recent changes
+    register!("recent_changes", recent_changes_get);
+    register!("feed_recent_changes", feed_recent_changes);
+    register!("feed_page_history", feed_page_history);
+
+    // Tags
+    register!("tag_group_create", tag_group_create);
+    register!("tag_group_update", tag_group_update);
+    register!("tag_group_delete", tag_group_delete);
+    register!("tag_create", tag_create);
+    register!("tag_get", tag_get);
+    register!("tag_list", tag_list);
+    register!("tag_update", tag_update);
+    register!("tag_delete", tag_delete);
+    register!("tag_validate", tag_validate);
+    register!("tag_rename", tag_rename);
 
     // Page links
     register!("page_get_links_from", page_links_from_get);
@@ -257,12 +329,22 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("parent_relationships_get", parent_relationships_get);
     register!("parent_get_all", parent_get_all);
     register!("parent_update", parent_update);
+    register!("parent_ancestors_get", parent_ancestors_get);
+    register!("parent_descendants_get", parent_descendants_get);
+    register!("parent_breadcrumbs_get", parent_breadcrumbs_get);
+    register!("parent_tree_get", parent_tree_get);
 
     // Blob data
     register!("blob_get", blob_get);
     register!("blob_upload", blob_upload);
     register!("blob_cancel", blob_cancel);
 
+    // Storage quotas
+    register!("storage_user_get", storage_user_get);
+    register!("storage_site_get", storage_site_get);
+    register!("storage_user_set_quota", storage_user_set_quota);
+    register!("storage_site_set_quota", storage_site_set_quota);
+
     // Files
     register!("file_create", file_create);
     register!("file_edit", file_edit);
@@ -278,6 +360,32 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("file_revision_edit", file_revision_edit);
     register!("file_revision_count", file_revision_count);
     register!("file_revision_range", file_revision_range);
+    register!("file_revision_diff", file_revision_diff);
+
+    // Forum
+    register!("forum_group_create", forum_group_create);
+    register!("forum_group_get", forum_group_get);
+    register!("forum_group_list", forum_group_list);
+    register!("forum_group_update", forum_group_update);
+    register!("forum_group_delete", forum_group_delete);
+    register!("forum_category_create", forum_category_create);
+    register!("forum_category_get", forum_category_get);
+    register!("forum_category_list", forum_category_list);
+    register!("forum_category_update", forum_category_update);
+    register!("forum_category_delete", forum_category_delete);
+    register!("forum_thread_create", forum_thread_create);
+    register!("forum_thread_get", forum_thread_get);
+    register!("forum_thread_list", forum_thread_list);
+    register!("forum_thread_update", forum_thread_update);
+    register!("forum_thread_delete", forum_thread_delete);
+    register!("page_discussion_get", page_discussion_get);
+    register!("forum_post_create", forum_post_create);
+    register!("forum_post_get", forum_post_get);
+    register!("forum_post_list", forum_post_list);
+    register!("forum_post_edit", forum_post_edit);
+    register!("forum_post_delete", forum_post_delete);
+    register!("forum_post_revision_get", forum_post_revision_get);
+    register!("forum_post_revision_list", forum_post_revision_list);
 
     // Text
     register!("text_create", text_create);
@@ -290,6 +398,8 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("user_edit", user_edit);
     register!("user_delete", user_delete);
     register!("user_add_name_change", user_add_name_change);
+    register!("user_name_history", user_name_history);
+    register!("user_find_past_name", user_find_past_name);
 
     // Bot user
     register!("bot_user_create", bot_user_create);
@@ -297,6 +407,15 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("bot_user_owner_set", bot_user_owner_set);
     register!("bot_user_owner_remove", bot_user_owner_remove);
 
+    // User contacts
+    register!("contact_request_send", contact_request_send);
+    register!("contact_request_accept", contact_request_accept);
+    register!("contact_request_decline", contact_request_decline);
+    register!("contact_request_cancel", contact_request_cancel);
+    register!("contact_request_list", contact_request_list);
+    register!("contact_list", contact_list);
+    register!("contact_remove", contact_remove);
+
     // Direct messages
     register!("message_draft_create", message_draft_create);
     register!("message_draft_edit", message_draft_edit);
@@ -306,6 +425,13 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     // Email
     register!("email_validate", validate_email);
 
+    // Jobs
+    register!("job_status", job_status);
+    register!("job_list", job_list);
+    register!("job_retry", job_retry);
+    register!("job_discard", job_discard);
+    register!("job_trigger", job_trigger);
+
     // Votes
     register!("vote_set", vote_set);
     register!("vote_get", vote_get);
@@ -18,10 +18,11 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::Config;
+use super::{BlobStorageConfig, Config};
 use anyhow::Result;
 use femme::LevelFilter;
 use ftml::layout::Layout;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fs::File;
 use std::io::Read;
@@ -54,7 +55,12 @@ pub struct ConfigFile {
     special_pages: SpecialPages,
     user: User,
     file: FileSection,
+    text: Text,
     message: Message,
+    page: PageSection,
+    draft: Draft,
+    backup: Backup,
+    rate_limit: RateLimit,
 }
 
 /// Structure containing extra fields not found in `ConfigFile`.
@@ -92,6 +98,8 @@ struct Database {
 #[serde(rename_all = "kebab-case")]
 struct Security {
     authentication_fail_delay_ms: u64,
+    authentication_fail_delay_max_ms: u64,
+    authentication_fail_window_secs: u64,
     session: Session,
     mfa: Mfa,
 }
@@ -103,6 +111,11 @@ struct Session {
     token_length: usize,
     duration_session_minutes: u64,
     duration_login_minutes: u64,
+    enforce_ip_subnet: bool,
+    ipv4_subnet_prefix: u8,
+    ipv6_subnet_prefix: u8,
+    enforce_user_agent: bool,
+    notify_new_device: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -124,8 +137,11 @@ struct Job {
     max_delay_poll_secs: u64,
     prune_session_secs: u64,
     prune_text_secs: u64,
+    prune_draft_secs: u64,
     name_change_refill_secs: u64,
     lift_expired_punishments_secs: u64,
+    score_rerender_delay_secs: u64,
+    retry_delay_secs: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -186,10 +202,30 @@ struct User {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 struct FileSection {
+    storage: BlobStorageKind,
+    local_directory: PathBuf,
+    local_upload_url: String,
     presigned_path_length: usize,
     presigned_expiration_minutes: u32,
     maximum_blob_size_kb: i64,
     maximum_avatar_size_kb: i64,
+    maximum_user_storage_mb: i64,
+    maximum_site_storage_mb: i64,
+    storage_warning_percent: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[serde(rename_all = "kebab-case")]
+enum BlobStorageKind {
+    S3,
+    Local,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Text {
+    compression_level: i32,
+    delta_encoding: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -200,6 +236,33 @@ struct Message {
     maximum_recipients: usize,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct PageSection {
+    maximum_parent_depth: u32,
+    maximum_recent_changes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Draft {
+    expiry_days: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Backup {
+    local_directory: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct RateLimit {
+    enable: bool,
+    window_secs: u64,
+    methods: HashMap<String, u32>,
+}
+
 impl ConfigFile {
     pub fn load(path: PathBuf) -> Result<(Self, ExtraConfig)> {
         // Read TOML
@@ -254,12 +317,19 @@ impl ConfigFile {
             security:
                 Security {
                     authentication_fail_delay_ms,
+                    authentication_fail_delay_max_ms,
+                    authentication_fail_window_secs,
                     session:
                         Session {
                             token_prefix,
                             token_length,
                             duration_session_minutes,
                             duration_login_minutes,
+                            enforce_ip_subnet,
+                            ipv4_subnet_prefix,
+                            ipv6_subnet_prefix,
+                            enforce_user_agent,
+                            notify_new_device,
                         },
                     mfa:
                         Mfa {
@@ -283,8 +353,11 @@ impl ConfigFile {
                     max_delay_poll_secs: job_max_poll_delay_secs,
                     prune_session_secs: job_prune_session_secs,
                     prune_text_secs: job_prune_text_secs,
+                    prune_draft_secs: job_prune_draft_secs,
                     name_change_refill_secs: job_name_change_refill_secs,
                     lift_expired_punishments_secs: job_lift_expired_punishments_secs,
+                    score_rerender_delay_secs: job_score_rerender_delay_secs,
+                    retry_delay_secs: job_retry_delay_secs,
                 },
             locale: Locale {
                 path: localization_path,
@@ -316,10 +389,21 @@ impl ConfigFile {
                 },
             file:
                 FileSection {
+                    storage: blob_storage_kind,
+                    local_directory: blob_local_directory,
+                    local_upload_url: blob_local_upload_url,
                     presigned_path_length,
                     presigned_expiration_minutes,
                     maximum_blob_size_kb,
                     maximum_avatar_size_kb,
+                    maximum_user_storage_mb,
+                    maximum_site_storage_mb,
+                    storage_warning_percent,
+                },
+            text:
+                Text {
+                    compression_level: text_compression_level,
+                    delta_encoding: text_delta_encoding,
                 },
             message:
                 Message {
@@ -327,6 +411,24 @@ impl ConfigFile {
                     maximum_body_bytes: maximum_message_body_bytes,
                     maximum_recipients: maximum_message_recipients,
                 },
+            page:
+                PageSection {
+                    maximum_parent_depth,
+                    maximum_recent_changes,
+                },
+            draft: Draft {
+                expiry_days: draft_expiry_days,
+            },
+            backup:
+                Backup {
+                    local_directory: mut backup_local_directory,
+                },
+            rate_limit:
+                RateLimit {
+                    enable: rate_limit_enable,
+                    window_secs: rate_limit_window_secs,
+                    methods: rate_limit_methods,
+                },
         } = self;
 
         // Assertions for bad values
@@ -340,6 +442,10 @@ impl ConfigFile {
             job_prune_text_secs < RSMQ_DELAY_LIMIT,
             "Text prune job period time too long",
         );
+        assert!(
+            job_prune_draft_secs < RSMQ_DELAY_LIMIT,
+            "Draft prune job period time too long",
+        );
         assert!(
             job_name_change_refill_secs < RSMQ_DELAY_LIMIT,
             "Name change refill job period time too long",
@@ -348,6 +454,34 @@ impl ConfigFile {
             job_lift_expired_punishments_secs < RSMQ_DELAY_LIMIT,
             "Expired punishment cleanup job period time too long",
         );
+        assert!(
+            job_score_rerender_delay_secs < RSMQ_DELAY_LIMIT,
+            "Score rerender delay time too long",
+        );
+        assert!(
+            job_retry_delay_secs < RSMQ_DELAY_LIMIT,
+            "Job retry delay time too long",
+        );
+        assert!(
+            zstd::compression_level_range().contains(&text_compression_level),
+            "Text compression level out of range",
+        );
+        assert!(
+            authentication_fail_delay_max_ms >= authentication_fail_delay_ms,
+            "Maximum authentication failure delay shorter than base delay",
+        );
+        assert!(
+            rate_limit_window_secs > 0,
+            "Rate limit window cannot be zero"
+        );
+        assert!(
+            ipv4_subnet_prefix <= 32,
+            "IPv4 session subnet prefix longer than an address",
+        );
+        assert!(
+            ipv6_subnet_prefix <= 128,
+            "IPv6 session subnet prefix longer than an address",
+        );
 
         // Prefix domains with '.' so we can do easy subdomain checks
         // and concatenations.
@@ -362,6 +496,32 @@ impl ConfigFile {
             }
         }
 
+        let blob_storage = match blob_storage_kind {
+            BlobStorageKind::S3 => BlobStorageConfig::S3,
+            BlobStorageKind::Local => {
+                assert!(
+                    !blob_local_directory.as_os_str().is_empty(),
+                    "Local blob storage requires a directory",
+                );
+                assert!(
+                    !blob_local_upload_url.is_empty(),
+                    "Local blob storage requires an upload URL",
+                );
+
+                BlobStorageConfig::Local {
+                    directory: blob_local_directory,
+                    upload_url: blob_local_upload_url,
+                }
+            }
+        };
+
+        // Same for the backup directory, where empty means "use blob storage".
+        if let Some(ref path) = backup_local_directory {
+            if path.as_os_str().is_empty() {
+                backup_local_directory = None;
+            }
+        }
+
         Config {
             raw_toml,
             raw_toml_path,
@@ -380,6 +540,12 @@ impl ConfigFile {
             authentication_fail_delay: StdDuration::from_millis(
                 authentication_fail_delay_ms,
             ),
+            authentication_fail_delay_max: StdDuration::from_millis(
+                authentication_fail_delay_max_ms,
+            ),
+            authentication_fail_window: StdDuration::from_secs(
+                authentication_fail_window_secs,
+            ),
             session_token_prefix: token_prefix,
             session_token_length: token_length,
             normal_session_duration: time_duration!(
@@ -390,6 +556,11 @@ impl ConfigFile {
                 from_secs,
                 duration_login_minutes * 60,
             ),
+            session_enforce_ip_subnet: enforce_ip_subnet,
+            session_ipv4_subnet_prefix: ipv4_subnet_prefix,
+            session_ipv6_subnet_prefix: ipv6_subnet_prefix,
+            session_enforce_user_agent: enforce_user_agent,
+            session_notify_new_device: notify_new_device,
             recovery_code_count,
             recovery_code_length,
             totp_time_step: time_step,
@@ -401,10 +572,15 @@ impl ConfigFile {
             job_max_poll_delay: StdDuration::from_secs(job_max_poll_delay_secs),
             job_prune_session: StdDuration::from_secs(job_prune_session_secs),
             job_prune_text: StdDuration::from_secs(job_prune_text_secs),
+            job_prune_draft: StdDuration::from_secs(job_prune_draft_secs),
             job_name_change_refill: StdDuration::from_secs(job_name_change_refill_secs),
             job_lift_expired_punishments: StdDuration::from_secs(
                 job_lift_expired_punishments_secs,
             ),
+            job_score_rerender_delay: StdDuration::from_secs(
+                job_score_rerender_delay_secs,
+            ),
+            job_retry_delay: StdDuration::from_secs(job_retry_delay_secs),
             render_timeout: StdDuration::from_millis(render_timeout_ms),
             rerender_skip: rerender_skip
                 .iter()
@@ -442,13 +618,38 @@ impl ConfigFile {
                 ))
             },
             minimum_name_bytes,
+            blob_storage,
             presigned_path_length,
             presigned_expiry_secs: presigned_expiration_minutes * 60,
             maximum_blob_size: maximum_blob_size_kb * 1024,
             maximum_avatar_size: maximum_avatar_size_kb * 1024,
+            maximum_user_storage: if maximum_user_storage_mb == 0 {
+                None
+            } else {
+                Some(maximum_user_storage_mb * 1024 * 1024)
+            },
+            maximum_site_storage: if maximum_site_storage_mb == 0 {
+                None
+            } else {
+                Some(maximum_site_storage_mb * 1024 * 1024)
+            },
+            storage_warning_percent,
+            text_compression_level,
+            text_delta_encoding,
             maximum_message_subject_bytes,
             maximum_message_body_bytes,
             maximum_message_recipients,
+            maximum_parent_depth,
+            maximum_recent_changes,
+            draft_expiry: if draft_expiry_days == 0 {
+                None
+            } else {
+                Some(StdDuration::from_secs(draft_expiry_days * 24 * 60 * 60))
+            },
+            backup_local_directory,
+            rate_limit_enable,
+            rate_limit_window: StdDuration::from_secs(rate_limit_window_secs),
+            rate_limit_methods,
         }
     }
 }
@@ -24,8 +24,9 @@ mod object;
 mod secrets;
 mod special_action;
 
-pub use self::object::Config;
+pub use self::object::{BlobStorageConfig, Config};
 pub use self::secrets::Secrets;
+pub use self::special_action::SpecialAction;
 
 use self::args::parse_args;
 use self::special_action::run_special_action;
@@ -34,14 +35,19 @@ use self::special_action::run_special_action;
 pub struct SetupConfig {
     pub secrets: Secrets,
     pub config: Config,
+    pub special_action: Option<SpecialAction>,
 }
 
 impl SetupConfig {
     pub fn load() -> Self {
-        run_special_action();
+        let special_action = run_special_action();
         let secrets = Secrets::load();
         let config = parse_args();
 
-        SetupConfig { secrets, config }
+        SetupConfig {
+            secrets,
+            config,
+            special_action,
+        }
     }
 }
@@ -22,6 +22,7 @@ use super::file::ConfigFile;
 use anyhow::Result;
 use femme::LevelFilter;
 use ftml::layout::Layout;
+use std::collections::HashMap;
 use std::env;
 use std::net::SocketAddr;
 use std::num::NonZeroU16;
@@ -92,6 +93,12 @@ pub struct Config {
     /// The duration to sleep after failed authentication attempts.
     pub authentication_fail_delay: StdDuration,
 
+    /// The longest the failed authentication delay can escalate to.
+    pub authentication_fail_delay_max: StdDuration,
+
+    /// How long failed logins are remembered for escalating the delay.
+    pub authentication_fail_window: StdDuration,
+
     /// Fixed prefix for all session tokens.
     pub session_token_prefix: String,
 
@@ -104,6 +111,21 @@ pub struct Config {
     /// How long restricted sessions last before expiry.
     pub restricted_session_duration: TimeDuration,
 
+    /// Whether sessions must be used from the same subnet they were created in.
+    pub session_enforce_ip_subnet: bool,
+
+    /// Prefix length of the IPv4 subnet a session is bound to.
+    pub session_ipv4_subnet_prefix: u8,
+
+    /// Prefix length of the IPv6 subnet a session is bound to.
+    pub session_ipv6_subnet_prefix: u8,
+
+    /// Whether sessions must be used with the same user-agent they were created with.
+    pub session_enforce_user_agent: bool,
+
+    /// Whether to message users when they log in from a previously unseen device.
+    pub session_notify_new_device: bool,
+
     /// The number of recovery codes to have per user.
     pub recovery_code_count: usize,
 
@@ -139,12 +161,23 @@ pub struct Config {
     /// How often to run the "prune unused text" recurring job.
     pub job_prune_text: StdDuration,
 
+    /// How often to run the "prune expired page drafts" recurring job.
+    pub job_prune_draft: StdDuration,
+
     /// How often to run the "refill name change tokens" recurring job.
     pub job_name_change_refill: StdDuration,
 
     /// How often to run the "lift expired punishments" recurring job.
     pub job_lift_expired_punishments: StdDuration,
 
+    /// How long to wait after a vote before rerendering the page.
+    /// Further votes during this period do not cause additional rerenders.
+    pub job_score_rerender_delay: StdDuration,
+
+    /// The base delay before retrying a failed job.
+    /// This doubles with each further attempt.
+    pub job_retry_delay: StdDuration,
+
     /// Maximum run time for a render request.
     pub render_timeout: StdDuration,
 
@@ -200,10 +233,13 @@ pub struct Config {
     /// Minimum length of bytes in a username.
     pub minimum_name_bytes: usize,
 
-    /// Length of randomly-generated portion of S3 presigned URLs.
+    /// Where blobs are stored.
+    pub blob_storage: BlobStorageConfig,
+
+    /// Length of randomly-generated portion of presigned upload URLs.
     pub presigned_path_length: usize,
 
-    /// How long S3 presigned URLs will last before expiry.
+    /// How long presigned upload URLs will last before expiry.
     pub presigned_expiry_secs: u32,
 
     /// Maximum size of a blob globally.
@@ -212,6 +248,23 @@ pub struct Config {
     /// Maximum size of a user's avatar image.
     pub maximum_avatar_size: i64,
 
+    /// Default maximum of total file storage a user may upload.
+    /// `None` means that there is no limit.
+    pub maximum_user_storage: Option<i64>,
+
+    /// Default maximum of total file storage a site may use.
+    /// `None` means that there is no limit.
+    pub maximum_site_storage: Option<i64>,
+
+    /// Percentage of a site's storage quota at which its staff are warned.
+    pub storage_warning_percent: u8,
+
+    /// The zstd compression level used when storing text.
+    pub text_compression_level: i32,
+
+    /// Whether page wikitext is stored as a delta against the previous revision.
+    pub text_delta_encoding: bool,
+
     /// Maximum size of the subject line allowed in a direct message.
     pub maximum_message_subject_bytes: usize,
 
@@ -220,6 +273,45 @@ pub struct Config {
 
     /// Maximum number of total recipients allowed in a direct message.
     pub maximum_message_recipients: usize,
+
+    /// Maximum number of levels of parents or children a page may have.
+    pub maximum_parent_depth: u32,
+
+    /// Maximum number of revisions returned at once in recent changes or page history.
+    pub maximum_recent_changes: u64,
+
+    /// How long a page draft is kept after it was last saved.
+    /// `None` means that drafts never expire.
+    pub draft_expiry: Option<StdDuration>,
+
+    /// Local directory to write site backup archives to.
+    /// `None` means that archives are kept in blob storage instead.
+    pub backup_local_directory: Option<PathBuf>,
+
+    /// Whether RPC methods are rate limited.
+    pub rate_limit_enable: bool,
+
+    /// The fixed window over which method calls are counted.
+    pub rate_limit_window: StdDuration,
+
+    /// How many calls each caller may make per window, by method name.
+    /// Methods not present here are not rate limited.
+    pub rate_limit_methods: HashMap<String, u32>,
+}
+
+/// Which backend blobs are stored in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlobStorageConfig {
+    /// Store blobs in the S3 bucket given in the secrets.
+    S3,
+
+    /// Store blobs in a local directory.
+    ///
+    /// Uploads are made to DEEPWELL directly, at `upload_url`.
+    Local {
+        directory: PathBuf,
+        upload_url: String,
+    },
 }
 
 impl Config {
@@ -249,6 +341,12 @@ impl Config {
         info!("Seeder: {}", bool_str(self.run_seeder));
         info!("Localization path: {}", self.localization_path.display());
         info!("Seeder path: {}", self.seeder_path.display());
+        match self.blob_storage {
+            BlobStorageConfig::S3 => info!("Blob storage: S3"),
+            BlobStorageConfig::Local { ref directory, .. } => {
+                info!("Blob storage: local ({})", directory.display());
+            }
+        }
         info!(
             "Current working directory: {}",
             env::current_dir()
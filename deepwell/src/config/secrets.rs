@@ -35,22 +35,40 @@ pub struct Secrets {
     /// Set using environment variable `REDIS_URL`.
     pub redis_url: String,
 
+    /// The S3 bucket that file blobs are kept in, if configured.
+    ///
+    /// This is only required when using S3 for blob storage,
+    /// and is read if environment variable `S3_BUCKET` is set.
+    pub s3: Option<S3Secrets>,
+
+    /// The key used to sign upload URLs when using local blob storage.
+    ///
+    /// This must be the same across restarts and between instances sharing
+    /// the same storage, otherwise outstanding upload URLs will be rejected.
+    ///
+    /// Set using environment variable `LOCAL_UPLOAD_KEY`.
+    /// If unset, a random key is generated on startup.
+    pub local_upload_key: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct S3Secrets {
     /// The name of the S3 bucket that file blobs are kept in.
     /// The bucket must already exist prior to program invocation.
     ///
     /// Set using environment variable `S3_BUCKET`.
-    pub s3_bucket: String,
+    pub bucket: String,
 
     /// The region to use for S3.
     ///
     /// Set using environment variable `S3_AWS_REGION` if standard,
     /// or `S3_REGION_NAME` and `S3_CUSTOM_ENDPOINT` if custom.
-    pub s3_region: Region,
+    pub region: Region,
 
     /// Whether to use path style for S3.
     ///
     /// Set using environment variable `S3_PATH_STYLE`.
-    pub s3_path_style: bool,
+    pub path_style: bool,
 
     /// The credentials to use for S3.
     ///
@@ -58,7 +76,7 @@ pub struct Secrets {
     ///
     /// Alternatively you can have it read from the AWS credentials file.
     /// The profile to read from can be set in the `AWS_PROFILE_NAME` environment variable.
-    pub s3_credentials: Credentials,
+    pub credentials: Credentials,
 }
 
 impl Secrets {
@@ -84,72 +102,82 @@ impl Secrets {
         let database_url = get_env!("DATABASE_URL");
         let redis_url = get_env!("REDIS_URL");
 
-        let s3_bucket = get_env!("S3_BUCKET");
-        let s3_region = match env::var("S3_AWS_REGION") {
-            // Standard AWS S3 region, parse out into enum.
-            Ok(value) => {
-                match value.parse() {
-                    Ok(region) => region,
-                    Err(error) => {
-                        eprintln!("S3_AWS_REGION variable is not a valid AWS region ID: {error}");
-                        process::exit(1);
+        let s3 = env::var("S3_BUCKET").ok().map(|bucket| {
+            let s3_region = match env::var("S3_AWS_REGION") {
+                // Standard AWS S3 region, parse out into enum.
+                Ok(value) => {
+                    match value.parse() {
+                        Ok(region) => region,
+                        Err(error) => {
+                            eprintln!("S3_AWS_REGION variable is not a valid AWS region ID: {error}");
+                            process::exit(1);
+                        }
                     }
                 }
-            }
 
-            // Custom region, with a specific S3 endpoint.
-            Err(_) => {
-                let region = get_env!("S3_REGION_NAME");
-                let endpoint = get_env!("S3_CUSTOM_ENDPOINT");
+                // Custom region, with a specific S3 endpoint.
+                Err(_) => {
+                    let region = get_env!("S3_REGION_NAME");
+                    let endpoint = get_env!("S3_CUSTOM_ENDPOINT");
 
-                Region::Custom { region, endpoint }
-            }
-        };
+                    Region::Custom { region, endpoint }
+                }
+            };
 
-        let s3_path_style = match get_env!("S3_PATH_STYLE").parse() {
-            Ok(path_style) => path_style,
-            Err(_) => {
-                eprintln!("S3_PATH_STYLE variable is not a valid boolean");
-                process::exit(1);
-            }
-        };
-
-        let s3_credentials = {
-            // Try to read from environment
-            // Reads from S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY
-            let env_creds = Credentials::from_env_specific(
-                Some("S3_ACCESS_KEY_ID"),
-                Some("S3_SECRET_ACCESS_KEY"),
-                None,
-                None,
-            );
-
-            match env_creds {
-                Ok(credentials) => credentials,
+            let s3_path_style = match get_env!("S3_PATH_STYLE").parse() {
+                Ok(path_style) => path_style,
                 Err(_) => {
-                    // Try to read from profile
-                    let profile_name = env::var("AWS_PROFILE_NAME").ok();
-                    let profile_name = profile_name.ref_map(|s| s.as_str());
+                    eprintln!("S3_PATH_STYLE variable is not a valid boolean");
+                    process::exit(1);
+                }
+            };
 
-                    match Credentials::from_profile(profile_name) {
-                        Ok(credentials) => credentials,
-                        Err(error) => {
-                            eprintln!("Unable to read AWS credentials file: {error}");
-                            process::exit(1);
+            let s3_credentials = {
+                // Try to read from environment
+                // Reads from S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY
+                let env_creds = Credentials::from_env_specific(
+                    Some("S3_ACCESS_KEY_ID"),
+                    Some("S3_SECRET_ACCESS_KEY"),
+                    None,
+                    None,
+                );
+
+                match env_creds {
+                    Ok(credentials) => credentials,
+                    Err(_) => {
+                        // Try to read from profile
+                        let profile_name = env::var("AWS_PROFILE_NAME").ok();
+                        let profile_name = profile_name.ref_map(|s| s.as_str());
+
+                        match Credentials::from_profile(profile_name) {
+                            Ok(credentials) => credentials,
+                            Err(error) => {
+                                eprintln!("Unable to read AWS credentials file: {error}");
+                                process::exit(1);
+                            }
                         }
                     }
                 }
+            };
+
+            S3Secrets {
+                bucket,
+                region: s3_region,
+                path_style: s3_path_style,
+                credentials: s3_credentials,
             }
-        };
+        });
+
+        let local_upload_key = env::var("LOCAL_UPLOAD_KEY")
+            .ok()
+            .filter(|key| !key.is_empty());
 
         // Build and return
         Secrets {
             database_url,
             redis_url,
-            s3_bucket,
-            s3_region,
-            s3_path_style,
-            s3_credentials,
+            s3,
+            local_upload_key,
         }
     }
 }
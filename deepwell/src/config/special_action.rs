@@ -23,21 +23,77 @@
 //! This is useful in contexts such as CI, where we want DEEPWELL to
 //! not run as a daemon, but instead perform a special action or check,
 //! as if motivated by a script.
+//!
+//! The action is selected by setting `DEEPWELL_SPECIAL_ACTION`:
+//! * `validate-config` - Check the configuration files passed as arguments.
+//! * `rerender-pages` - Rerender all pages.
+//! * `rebuild-links` - Rebuild the link connections of all pages.
+//! * `refresh-scores` - Recompute the materialized scores of all pages.
+//! * `prune` - Remove unused text rows and blobs.
+//! * `verify-hashes` - Check that stored text and blobs match their hashes.
+//! * `compact-text` - Compress text stored as-is, using deltas for page revisions.
+//! * `seed` - Run the seeder against the database.
+//!
+//! All but the first connect to the database using the normal configuration,
+//! print a summary, and exit with a non-zero code if anything failed.
 
 use super::Config;
 use std::path::PathBuf;
 use std::{env, process};
 
-pub fn run_special_action() {
+/// A special action which requires a connection to the database.
+///
+/// Unlike configuration validation, which is performed immediately,
+/// these are run after the server state is built, but before the
+/// server itself is started.
+///
+/// Actions which operate on pages can be limited to one site by
+/// setting `DEEPWELL_SPECIAL_ACTION_SITE` to its ID, otherwise
+/// they are run on all sites.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SpecialAction {
+    RerenderPages { site_id: Option<i64> },
+    RebuildLinks { site_id: Option<i64> },
+    RefreshScores { site_id: Option<i64> },
+    Prune,
+    VerifyHashes,
+    CompactText,
+    Seed,
+}
+
+/// Runs the special action given in the environment, if any.
+///
+/// Actions which can run on their own do so here, exiting the process.
+/// Those which need the database are returned so they can be run later.
+pub fn run_special_action() -> Option<SpecialAction> {
     // Get action name, if specified.
     // Otherwise return and perform normal execution.
     let Ok(action_name) = env::var("DEEPWELL_SPECIAL_ACTION") else {
-        return;
+        return None;
     };
 
     // Run appropriate special action.
     let return_code = match action_name.as_str() {
         "config" | "validate-config" => validate_config(),
+        "rerender" | "rerender-pages" => {
+            return Some(SpecialAction::RerenderPages {
+                site_id: get_site_id(),
+            })
+        }
+        "links" | "rebuild-links" => {
+            return Some(SpecialAction::RebuildLinks {
+                site_id: get_site_id(),
+            })
+        }
+        "scores" | "refresh-scores" => {
+            return Some(SpecialAction::RefreshScores {
+                site_id: get_site_id(),
+            })
+        }
+        "prune" => return Some(SpecialAction::Prune),
+        "verify" | "verify-hashes" => return Some(SpecialAction::VerifyHashes),
+        "compact" | "compact-text" => return Some(SpecialAction::CompactText),
+        "seed" | "run-seeder" => return Some(SpecialAction::Seed),
         _ => {
             eprintln!("Unknown special action: {action_name}");
             process::exit(1);
@@ -48,6 +104,17 @@ pub fn run_special_action() {
     process::exit(return_code);
 }
 
+fn get_site_id() -> Option<i64> {
+    let value = env::var("DEEPWELL_SPECIAL_ACTION_SITE").ok()?;
+    match value.parse() {
+        Ok(site_id) => Some(site_id),
+        Err(_) => {
+            eprintln!("Invalid site ID for special action: {value}");
+            process::exit(1);
+        }
+    }
+}
+
 fn validate_config() -> i32 {
     println!("Running special action: Validate configuration");
 
@@ -29,7 +29,7 @@ use crate::services::mfa::{
 };
 use crate::services::session::{
     CreateSession, GetOtherSessions, GetOtherSessionsOutput, InvalidateOtherSessions,
-    RenewSession,
+    RenewSession, SessionInfo, ValidateSession,
 };
 use crate::services::user::GetUser;
 use crate::services::Error;
@@ -64,11 +64,16 @@ pub async fn auth_login(
     // * success
     // * invalid authentication
     // * server error
+    let name_or_email = authenticate.name_or_email.clone();
     let result = AuthenticationService::auth_password(ctx, authenticate).await;
     let AuthenticateUserOutput { needs_mfa, user_id } = match result {
         Ok(output) => output,
         Err(mut error) => {
-            if !matches!(error, Error::InvalidAuthentication) {
+            if matches!(error, Error::InvalidAuthentication) {
+                // Repeated failures are delayed further, on top of the base delay.
+                RateLimitService::login_failure_delay(ctx, ip_address, &name_or_email)
+                    .await?;
+            } else {
                 error!("Unexpected error during user authentication: {error}");
                 error = Error::AuthenticationBackend(Box::new(error));
             }
@@ -77,6 +82,8 @@ pub async fn auth_login(
         }
     };
 
+    RateLimitService::clear_login_failures(ctx, &name_or_email).await?;
+
     let login_complete = !needs_mfa;
     info!(
         "Password authentication for user ID {user_id} succeeded (login complete: {login_complete})",
@@ -153,8 +160,8 @@ pub async fn auth_session_get_others(
     };
 
     Ok(GetOtherSessionsOutput {
-        current,
-        others: sessions,
+        current: current.into(),
+        others: sessions.into_iter().map(SessionInfo::from).collect(),
     })
 }
 
@@ -188,6 +195,8 @@ pub async fn auth_mfa_verify(
         MultiFactorAuthenticateUser {
             session_token: &session_token,
             totp_or_code: &totp_or_code,
+            ip_address,
+            user_agent: &user_agent,
         },
     )
     .await?;
@@ -220,9 +229,20 @@ pub async fn auth_mfa_disable(
     let MultiFactorConfigure {
         user_id,
         session_token,
+        ip_address,
+        user_agent,
     } = params.parse()?;
 
-    let user = SessionService::get_user(ctx, &session_token, false).await?;
+    let user = SessionService::get_user(
+        ctx,
+        ValidateSession {
+            session_token: &session_token,
+            ip_address: Some(ip_address),
+            user_agent: Some(&user_agent),
+        },
+        false,
+    )
+    .await?;
     if user.user_id != user_id {
         error!(
             "Passed user ID ({}) does not match session token ({})",
@@ -245,9 +265,20 @@ pub async fn auth_mfa_reset_recovery(
     let MultiFactorConfigure {
         user_id,
         session_token,
+        ip_address,
+        user_agent,
     } = params.parse()?;
 
-    let user = SessionService::get_user(ctx, &session_token, false).await?;
+    let user = SessionService::get_user(
+        ctx,
+        ValidateSession {
+            session_token: &session_token,
+            ip_address: Some(ip_address),
+            user_agent: Some(&user_agent),
+        },
+        false,
+    )
+    .await?;
     if user.user_id != user_id {
         error!(
             "Passed user ID ({}) does not match session token ({})",
@@ -22,6 +22,11 @@ use super::prelude::*;
 use crate::services::blob::{
     BlobMetadata, CancelBlobUpload, GetBlobOutput, StartBlobUpload, StartBlobUploadOutput,
 };
+use crate::services::site::GetSite;
+use crate::services::storage_quota::{
+    SetSiteStorageQuota, SetUserStorageQuota, StorageUsage,
+};
+use crate::services::user::GetUser;
 use crate::services::Result;
 use crate::types::Bytes;
 
@@ -74,3 +79,41 @@ pub async fn blob_upload(
     let input: StartBlobUpload = params.parse()?;
     BlobService::start_upload(ctx, input).await
 }
+
+/// Gets how much storage a user is using, and their quota.
+pub async fn storage_user_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<StorageUsage> {
+    let GetUser { user: reference } = params.parse()?;
+    info!("Getting storage usage for user {:?}", reference);
+    StorageQuotaService::get_user_usage(ctx, reference).await
+}
+
+/// Gets how much storage a site is using, and its quota.
+pub async fn storage_site_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<StorageUsage> {
+    let GetSite { site: reference } = params.parse()?;
+    info!("Getting storage usage for site {:?}", reference);
+    StorageQuotaService::get_site_usage(ctx, reference).await
+}
+
+/// Overrides the storage quota for a user, or reverts it to the default.
+pub async fn storage_user_set_quota(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<StorageUsage> {
+    let input: SetUserStorageQuota = params.parse()?;
+    StorageQuotaService::set_user_quota(ctx, input).await
+}
+
+/// Overrides the storage quota for a site, or reverts it to the default.
+pub async fn storage_site_set_quota(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<StorageUsage> {
+    let input: SetSiteStorageQuota = params.parse()?;
+    StorageQuotaService::set_site_quota(ctx, input).await
+}
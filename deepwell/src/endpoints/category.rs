@@ -20,7 +20,9 @@
 
 use super::prelude::*;
 use crate::models::page_category::Model as PageCategoryModel;
-use crate::services::category::GetCategory;
+use crate::services::category::{
+    CreateCategory, GetCategory, RenameCategory, RenameCategoryOutput, UpdateCategory,
+};
 use crate::services::site::GetSite;
 
 pub async fn category_get(
@@ -42,3 +44,54 @@ pub async fn category_get_all(
     info!("Getting all page categories in site ID {site_id}");
     CategoryService::get_all(ctx, site_id).await
 }
+
+pub async fn category_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageCategoryModel> {
+    let CreateCategory { site, slug, body } = params.parse()?;
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Creating page category '{slug}' in site ID {site_id}");
+    CategoryService::create(ctx, site_id, slug, body).await
+}
+
+pub async fn category_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageCategoryModel> {
+    let UpdateCategory {
+        site,
+        category,
+        body,
+    } = params.parse()?;
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Updating page category {category:?} in site ID {site_id}");
+    CategoryService::update(ctx, site_id, category, body).await
+}
+
+pub async fn category_rename(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RenameCategoryOutput> {
+    let RenameCategory {
+        site,
+        category,
+        new_slug,
+        user_id,
+        revision_comments,
+    } = params.parse()?;
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Renaming page category {category:?} in site ID {site_id} to '{new_slug}'");
+    CategoryService::rename(ctx, site_id, category, new_slug, user_id, revision_comments)
+        .await
+}
+
+pub async fn category_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let GetCategory { site, category } = params.parse()?;
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Deleting page category {category:?} in site ID {site_id}");
+    CategoryService::delete(ctx, site_id, category).await
+}
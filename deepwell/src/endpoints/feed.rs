@@ -0,0 +1,50 @@
+/*
+ * endpoints/feed.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::feed::{FeedOutput, GetPageHistoryFeed, GetRecentChangesFeed};
+
+pub async fn feed_recent_changes(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FeedOutput> {
+    let input: GetRecentChangesFeed = params.parse()?;
+
+    info!(
+        "Getting {:?} recent changes feed for site ID {}",
+        input.format, input.query.site_id,
+    );
+
+    FeedService::recent_changes(ctx, input).await
+}
+
+pub async fn feed_page_history(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FeedOutput> {
+    let input: GetPageHistoryFeed = params.parse()?;
+
+    info!(
+        "Getting {:?} page history feed for page {:?} in site ID {}",
+        input.format, input.page, input.site_id,
+    );
+
+    FeedService::page_history(ctx, input).await
+}
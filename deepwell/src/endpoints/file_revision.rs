@@ -20,6 +20,7 @@
 
 use super::prelude::*;
 use crate::models::file_revision::Model as FileRevisionModel;
+use crate::services::diff::{DiffFileRevisions, FileRevisionDiff};
 use crate::services::file::GetFile;
 use crate::services::file_revision::{
     FileRevisionCountOutput, GetFileRevision, GetFileRevisionRange, UpdateFileRevision,
@@ -82,3 +83,17 @@ pub async fn file_revision_edit(
 
     FileRevisionService::update(ctx, input).await
 }
+
+pub async fn file_revision_diff(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FileRevisionDiff> {
+    let input: DiffFileRevisions = params.parse()?;
+
+    info!(
+        "Comparing revisions {} and {} for file ID {} on page ID {}",
+        input.from_revision, input.to_revision, input.file_id, input.page_id,
+    );
+
+    DiffService::file_revisions(ctx, input).await
+}
@@ -0,0 +1,279 @@
+/*
+ * endpoints/forum.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::forum_category::Model as ForumCategoryModel;
+use crate::models::forum_group::Model as ForumGroupModel;
+use crate::models::forum_post_revision::Model as ForumPostRevisionModel;
+use crate::models::forum_thread::Model as ForumThreadModel;
+use crate::services::forum_category::{
+    CreateForumCategory, GetForumCategories, GetForumCategory, UpdateForumCategory,
+};
+use crate::services::forum_group::{
+    CreateForumGroup, GetForumGroup, GetForumGroups, UpdateForumGroup,
+};
+use crate::services::forum_post::{
+    CreateForumPost, CreateForumPostOutput, EditForumPost, EditForumPostOutput,
+    ForumPostOutput, GetForumPost, GetForumPostRevision, GetForumPostRevisions,
+    GetForumPosts,
+};
+use crate::services::forum_thread::{
+    CreateForumThread, CreateForumThreadOutput, GetForumThread, GetForumThreads,
+    UpdateForumThread,
+};
+use crate::services::page::GetPageReference;
+
+// Forum groups
+
+pub async fn forum_group_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ForumGroupModel> {
+    let input: CreateForumGroup = params.parse()?;
+    info!(
+        "Creating forum group '{}' in site ID {}",
+        input.name, input.site_id,
+    );
+    ForumGroupService::create(ctx, input).await
+}
+
+pub async fn forum_group_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<ForumGroupModel>> {
+    let GetForumGroup { forum_group_id } = params.parse()?;
+    info!("Getting forum group ID {forum_group_id}");
+    ForumGroupService::get_optional(ctx, forum_group_id).await
+}
+
+pub async fn forum_group_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<ForumGroupModel>> {
+    let GetForumGroups { site_id } = params.parse()?;
+    info!("Getting all forum groups in site ID {site_id}");
+    ForumGroupService::get_all(ctx, site_id).await
+}
+
+pub async fn forum_group_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ForumGroupModel> {
+    let input: UpdateForumGroup = params.parse()?;
+    info!("Updating forum group ID {}", input.forum_group_id);
+    ForumGroupService::update(ctx, input).await
+}
+
+pub async fn forum_group_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let GetForumGroup { forum_group_id } = params.parse()?;
+    info!("Deleting forum group ID {forum_group_id}");
+    ForumGroupService::delete(ctx, forum_group_id).await
+}
+
+// Forum categories
+
+pub async fn forum_category_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ForumCategoryModel> {
+    let input: CreateForumCategory = params.parse()?;
+    info!(
+        "Creating forum category '{}' in group ID {}",
+        input.name, input.forum_group_id,
+    );
+    ForumCategoryService::create(ctx, input).await
+}
+
+pub async fn forum_category_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<ForumCategoryModel>> {
+    let GetForumCategory { forum_category_id } = params.parse()?;
+    info!("Getting forum category ID {forum_category_id}");
+    ForumCategoryService::get_optional(ctx, forum_category_id).await
+}
+
+pub async fn forum_category_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<ForumCategoryModel>> {
+    let GetForumCategories {
+        site_id,
+        forum_group_id,
+    } = params.parse()?;
+    info!("Getting forum categories in site ID {site_id} (group {forum_group_id:?})");
+    ForumCategoryService::get_all(ctx, site_id, forum_group_id).await
+}
+
+pub async fn forum_category_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ForumCategoryModel> {
+    let input: UpdateForumCategory = params.parse()?;
+    info!("Updating forum category ID {}", input.forum_category_id);
+    ForumCategoryService::update(ctx, input).await
+}
+
+pub async fn forum_category_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let GetForumCategory { forum_category_id } = params.parse()?;
+    info!("Deleting forum category ID {forum_category_id}");
+    ForumCategoryService::delete(ctx, forum_category_id).await
+}
+
+// Forum threads
+
+pub async fn forum_thread_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<CreateForumThreadOutput> {
+    let input: CreateForumThread = params.parse()?;
+    info!(
+        "Creating forum thread '{}' in category ID {}",
+        input.title, input.forum_category_id,
+    );
+    ForumThreadService::create(ctx, input).await
+}
+
+pub async fn forum_thread_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<ForumThreadModel>> {
+    let GetForumThread { forum_thread_id } = params.parse()?;
+    info!("Getting forum thread ID {forum_thread_id}");
+    ForumThreadService::get_optional(ctx, forum_thread_id).await
+}
+
+pub async fn forum_thread_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<ForumThreadModel>> {
+    let GetForumThreads { forum_category_id } = params.parse()?;
+    info!("Getting all forum threads in category ID {forum_category_id}");
+    ForumThreadService::get_all(ctx, forum_category_id).await
+}
+
+pub async fn forum_thread_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ForumThreadModel> {
+    let input: UpdateForumThread = params.parse()?;
+    info!("Updating forum thread ID {}", input.forum_thread_id);
+    ForumThreadService::update(ctx, input).await
+}
+
+pub async fn forum_thread_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let GetForumThread { forum_thread_id } = params.parse()?;
+    info!("Deleting forum thread ID {forum_thread_id}");
+    ForumThreadService::delete(ctx, forum_thread_id).await
+}
+
+pub async fn page_discussion_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ForumThreadModel> {
+    let GetPageReference { site_id, page } = params.parse()?;
+    info!("Getting discussion thread for page {page:?} in site ID {site_id}");
+    ForumThreadService::get_or_create_for_page(ctx, site_id, page).await
+}
+
+// Forum posts
+
+pub async fn forum_post_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<CreateForumPostOutput> {
+    let input: CreateForumPost = params.parse()?;
+    info!(
+        "Creating forum post in thread ID {} (parent {:?})",
+        input.forum_thread_id, input.parent_post_id,
+    );
+    ForumPostService::create(ctx, input).await
+}
+
+pub async fn forum_post_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<ForumPostOutput>> {
+    let GetForumPost { forum_post_id } = params.parse()?;
+    info!("Getting forum post ID {forum_post_id}");
+    ForumPostService::get_with_contents(ctx, forum_post_id).await
+}
+
+pub async fn forum_post_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<ForumPostOutput>> {
+    let GetForumPosts { forum_thread_id } = params.parse()?;
+    info!("Getting all forum posts in thread ID {forum_thread_id}");
+    ForumPostService::get_all(ctx, forum_thread_id).await
+}
+
+pub async fn forum_post_edit(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<EditForumPostOutput> {
+    let input: EditForumPost = params.parse()?;
+    info!(
+        "Editing forum post ID {} by user ID {}",
+        input.forum_post_id, input.user_id,
+    );
+    ForumPostService::edit(ctx, input).await
+}
+
+pub async fn forum_post_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let GetForumPost { forum_post_id } = params.parse()?;
+    info!("Deleting forum post ID {forum_post_id}");
+    ForumPostService::delete(ctx, forum_post_id).await
+}
+
+// Forum post revisions
+
+pub async fn forum_post_revision_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<ForumPostRevisionModel>> {
+    let GetForumPostRevision {
+        forum_post_id,
+        revision_number,
+    } = params.parse()?;
+    info!("Getting revision {revision_number} of forum post ID {forum_post_id}");
+    ForumPostService::get_revision_optional(ctx, forum_post_id, revision_number).await
+}
+
+pub async fn forum_post_revision_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<ForumPostRevisionModel>> {
+    let GetForumPostRevisions { forum_post_id } = params.parse()?;
+    info!("Getting all revisions of forum post ID {forum_post_id}");
+    ForumPostService::get_revisions(ctx, forum_post_id).await
+}
@@ -0,0 +1,68 @@
+/*
+ * endpoints/job.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::job::{
+    GetJobs, JobQueueStatus, JobRecord, JobReference, JobService, TriggerJob,
+};
+
+pub async fn job_status(
+    ctx: &ServiceContext<'_>,
+    _params: Params<'static>,
+) -> Result<JobQueueStatus> {
+    info!("Getting job queue status");
+    JobService::get_status(ctx).await
+}
+
+pub async fn job_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<JobRecord>> {
+    let input: GetJobs = params.parse()?;
+    info!("Listing {:?} jobs (limit {})", input.kind, input.limit);
+    JobService::get_jobs(ctx, input).await
+}
+
+pub async fn job_retry(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<String> {
+    let JobReference { job_id } = params.parse()?;
+    info!("Retrying job ID {job_id}");
+    JobService::retry(ctx, &job_id).await
+}
+
+pub async fn job_discard(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let JobReference { job_id } = params.parse()?;
+    info!("Discarding job ID {job_id}");
+    JobService::discard(ctx, &job_id).await
+}
+
+pub async fn job_trigger(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<String> {
+    let TriggerJob { job } = params.parse()?;
+    info!("Triggering recurring job {job:?}");
+    JobService::trigger(ctx, job).await
+}
@@ -31,12 +31,15 @@
 mod prelude {
     pub use crate::api::ServerState;
     pub use crate::services::{
-        AliasService, BlobService, CategoryService, DomainService, Error as ServiceError,
-        FileRevisionService, FileService, LinkService, MessageReportService,
-        MessageService, MfaService, PageRevisionService, PageService, ParentService,
-        RelationService, RenderService, Result, ScoreService, ServiceContext,
-        SessionService, SettingsService, SiteService, StdResult, TextService,
-        UserService, ViewService, VoteService,
+        AliasService, BlobService, CategoryService, DiffService, DomainService,
+        Error as ServiceError, FeedService, FileRevisionService, FileService,
+        ForumCategoryService, ForumGroupService, ForumPostService, ForumThreadService,
+        LinkService, MessageReportService, MessageService, MfaService,
+        PageRevisionService, PageService, ParentService, RateLimitService,
+        RecentChangesService, RelationService, RenderService, Result, ScoreService,
+        ServiceContext, SessionService, SettingsService, SiteBackupService, SiteService,
+        StdResult, StorageQuotaService, TagService, TextService, UserService,
+        ViewService, VoteService,
     };
     pub use jsonrpsee::types::params::Params;
     pub use std::convert::TryFrom;
@@ -47,20 +50,29 @@ pub mod blob;
 pub mod category;
 pub mod domain;
 pub mod email;
+pub mod feed;
 pub mod file;
 pub mod file_revision;
+pub mod forum;
 pub mod info;
+pub mod job;
 pub mod link;
 pub mod locale;
 pub mod message;
 pub mod misc;
 pub mod page;
+pub mod page_draft;
 pub mod page_revision;
+pub mod page_schedule;
 pub mod parent;
+pub mod recent_changes;
 pub mod site;
+pub mod site_backup;
 pub mod site_member;
+pub mod tag;
 pub mod text;
 pub mod user;
 pub mod user_bot;
+pub mod user_contact;
 pub mod view;
 pub mod vote;
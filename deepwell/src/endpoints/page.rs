@@ -26,7 +26,8 @@ use crate::services::page::{
     GetPageReference, GetPageReferenceDetails, GetPageScoreOutput, GetPageSlug, MovePage,
     MovePageOutput, RestorePage, RestorePageOutput, RollbackPage, SetPageLayout,
 };
-use crate::services::{Result, TextService};
+use crate::services::site::GetSite;
+use crate::services::{JobService, PageDraftService, Result, TextService};
 use crate::types::{PageDetails, Reference};
 use futures::future::try_join_all;
 
@@ -106,8 +107,22 @@ pub async fn page_get_score(
 
     info!("Getting score for page {reference:?} in site ID {site_id}");
     let page_id = PageService::get_id(ctx, site_id, reference).await?;
-    let score = ScoreService::score(ctx, page_id).await?;
-    Ok(GetPageScoreOutput { page_id, score })
+    let model = ScoreService::get(ctx, page_id).await?;
+    Ok(GetPageScoreOutput {
+        page_id,
+        score: ScoreService::score_value(&model),
+        vote_count: model.vote_count,
+    })
+}
+
+pub async fn page_refresh_scores(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let GetSite { site } = params.parse()?;
+    let site_id = SiteService::get_id(ctx, site).await?;
+    info!("Queuing score refresh for all pages in site ID {site_id}");
+    JobService::queue_refresh_scores(ctx, site_id).await
 }
 
 pub async fn page_edit(
@@ -116,7 +131,14 @@ pub async fn page_edit(
 ) -> Result<Option<EditPageOutput>> {
     let input: EditPage = params.parse()?;
     info!("Editing page {:?} in site ID {}", input.page, input.site_id);
-    PageService::edit(ctx, input).await
+
+    let user_id = input.user_id;
+    let page_id = PageService::get_id(ctx, input.site_id, input.page.clone()).await?;
+    let output = PageService::edit(ctx, input).await?;
+
+    // The edit has been submitted, so the user's draft is no longer needed
+    PageDraftService::delete(ctx, page_id, user_id).await?;
+    Ok(output)
 }
 
 pub async fn page_delete(
@@ -0,0 +1,76 @@
+/*
+ * endpoints/page_draft.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::page_draft::{
+    DeletePageDraft, GetPageDraft, GetPageDraftOutput, ListPageDrafts, PageDraftListItem,
+    SavePageDraft, SavePageDraftOutput,
+};
+use crate::services::PageDraftService;
+
+pub async fn page_draft_save(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SavePageDraftOutput> {
+    let input: SavePageDraft = params.parse()?;
+    info!(
+        "Saving page draft for {:?} in site ID {} by user ID {}",
+        input.page, input.site_id, input.user_id,
+    );
+    PageDraftService::save(ctx, input).await
+}
+
+pub async fn page_draft_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GetPageDraftOutput> {
+    let input: GetPageDraft = params.parse()?;
+    info!(
+        "Getting page draft for {:?} in site ID {} by user ID {}",
+        input.page, input.site_id, input.user_id,
+    );
+    PageDraftService::resume(ctx, input).await
+}
+
+pub async fn page_draft_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageDraftListItem>> {
+    let input: ListPageDrafts = params.parse()?;
+    info!("Listing page drafts for user ID {}", input.user_id);
+    PageDraftService::list(ctx, input).await
+}
+
+pub async fn page_draft_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let DeletePageDraft {
+        site_id,
+        page: reference,
+        user_id,
+    } = params.parse()?;
+
+    info!(
+        "Deleting page draft for {reference:?} in site ID {site_id} by user ID {user_id}"
+    );
+    let page_id = PageService::get_id(ctx, site_id, reference).await?;
+    PageDraftService::delete(ctx, page_id, user_id).await
+}
@@ -20,10 +20,12 @@
 
 use super::prelude::*;
 use crate::models::page_revision::Model as PageRevisionModel;
+use crate::services::diff::{DiffPageRevisions, PageRevisionDiff};
 use crate::services::page::GetPageReference;
 use crate::services::page_revision::{
     GetPageRevision, GetPageRevisionDetails, GetPageRevisionRangeDetails,
-    PageRevisionCountOutput, PageRevisionModelFiltered, UpdatePageRevisionDetails,
+    PageRevisionCountOutput, PageRevisionModelFiltered, PreviewPageRevision,
+    PreviewPageRevisionOutput, UpdatePageRevisionDetails,
 };
 use crate::services::{Result, TextService};
 use crate::types::PageDetails;
@@ -107,6 +109,34 @@ pub async fn page_revision_range(
     filter_and_populate_revisions(ctx, revisions, details).await
 }
 
+pub async fn page_revision_diff(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageRevisionDiff> {
+    let input: DiffPageRevisions = params.parse()?;
+
+    info!(
+        "Comparing revisions {} and {} for page ID {} in site ID {}",
+        input.from_revision, input.to_revision, input.page_id, input.site_id,
+    );
+
+    DiffService::page_revisions(ctx, input).await
+}
+
+pub async fn page_revision_preview(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PreviewPageRevisionOutput> {
+    let input: PreviewPageRevision = params.parse()?;
+
+    info!(
+        "Previewing wikitext for page slug '{}' in site ID {}",
+        input.slug, input.site_id,
+    );
+
+    PageRevisionService::preview(ctx, input).await
+}
+
 // Helper functions
 
 async fn filter_and_populate_revision(
@@ -114,78 +144,26 @@ async fn filter_and_populate_revision(
     model: PageRevisionModel,
     mut details: PageDetails,
 ) -> Result<PageRevisionModelFiltered> {
-    let PageRevisionModel {
-        revision_id,
-        revision_type,
-        created_at,
-        updated_at,
-        from_wikidot,
-        revision_number,
-        page_id,
-        site_id,
-        user_id,
-        changes,
-        wikitext_hash,
-        compiled_hash,
-        compiled_at,
-        compiled_generator,
-        comments,
-        hidden,
-        title,
-        mut alt_title,
-        slug,
-        tags,
-    } = model;
-
-    // Strip hidden fields
-    let mut comments = Some(comments);
-    let mut title = Some(title);
-    // alt-title is already Option and we're not doubling up
-    let mut slug = Some(slug);
-    let mut tags = Some(tags);
-
-    for field in &hidden {
-        // TODO hidden fields aren't standardized yet
+    // Hidden text fields are never fetched
+    for field in &model.hidden {
         match field.as_str() {
             "wikitext" => details.wikitext = false,
             "compiled" => details.compiled_html = false,
-            "comments" => comments = None,
-            "title" => title = None,
-            "alt_title" => alt_title = None,
-            "slug" => slug = None,
-            "tags" => tags = None,
-            _ => panic!("Unknown field name in hidden: {field}"),
+            _ => (),
         }
     }
 
     // Get text data, if requested
     let (wikitext, compiled_html) = try_join!(
-        TextService::get_maybe(ctx, details.wikitext, &wikitext_hash),
-        TextService::get_maybe(ctx, details.compiled_html, &compiled_hash),
+        TextService::get_maybe(ctx, details.wikitext, &model.wikitext_hash),
+        TextService::get_maybe(ctx, details.compiled_html, &model.compiled_hash),
     )?;
 
-    Ok(PageRevisionModelFiltered {
-        revision_id,
-        revision_type,
-        created_at,
-        updated_at,
-        from_wikidot,
-        revision_number,
-        page_id,
-        site_id,
-        user_id,
-        changes,
-        wikitext,
-        compiled_html,
-        compiled_at,
-        compiled_generator,
-        comments,
-        hidden,
-        title,
-        alt_title,
-        slug,
-        tags,
-    })
+    // Strip hidden fields
+    let mut revision = PageRevisionModelFiltered::from(model);
+    revision.wikitext = wikitext;
+    revision.compiled_html = compiled_html;
+    Ok(revision)
 }
 
 async fn filter_and_populate_revisions(
@@ -0,0 +1,81 @@
+/*
+ * endpoints/page_schedule.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_schedule::Model as PageScheduleModel;
+use crate::services::page_schedule::{
+    CancelPageSchedule, GetPageSchedule, ListPageSchedules, SchedulePageCreate,
+    SchedulePageEdit,
+};
+use crate::services::PageScheduleService;
+
+pub async fn page_schedule_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageScheduleModel> {
+    let input: SchedulePageCreate = params.parse()?;
+    info!(
+        "Scheduling page creation of {} in site ID {}",
+        input.slug, input.site_id,
+    );
+    PageScheduleService::schedule_create(ctx, input).await
+}
+
+pub async fn page_schedule_edit(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageScheduleModel> {
+    let input: SchedulePageEdit = params.parse()?;
+    info!(
+        "Scheduling page edit of {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+    PageScheduleService::schedule_edit(ctx, input).await
+}
+
+pub async fn page_schedule_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageScheduleModel> {
+    let GetPageSchedule { schedule_id } = params.parse()?;
+    info!("Getting scheduled page change ID {schedule_id}");
+    PageScheduleService::get(ctx, schedule_id).await
+}
+
+pub async fn page_schedule_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageScheduleModel>> {
+    let input: ListPageSchedules = params.parse()?;
+    info!(
+        "Getting scheduled page changes for site ID {}",
+        input.site_id
+    );
+    PageScheduleService::list(ctx, input).await
+}
+
+pub async fn page_schedule_cancel(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageScheduleModel> {
+    let CancelPageSchedule { schedule_id } = params.parse()?;
+    info!("Cancelling scheduled page change ID {schedule_id}");
+    PageScheduleService::cancel(ctx, schedule_id).await
+}
@@ -19,11 +19,10 @@
  */
 
 use super::prelude::*;
-use crate::models::page_parent::Model as PageParentModel;
 use crate::services::page::GetPageReference;
 use crate::services::parent::{
-    GetParentRelationships, ParentDescription, RemoveParentOutput, UpdateParents,
-    UpdateParentsOutput,
+    GetPageHierarchy, GetParentRelationships, HierarchyPage, ModifyParent, PageParent,
+    PageTree, ParentDescription, RemoveParentOutput, UpdateParents, UpdateParentsOutput,
 };
 use crate::types::Reference;
 use futures::future::try_join_all;
@@ -31,7 +30,7 @@ use futures::future::try_join_all;
 pub async fn parent_relationships_get(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
-) -> Result<Vec<PageParentModel>> {
+) -> Result<Vec<PageParent>> {
     let GetParentRelationships {
         site_id,
         page: reference,
@@ -51,7 +50,7 @@ pub async fn parent_relationships_get(
 pub async fn parent_get(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
-) -> Result<Option<PageParentModel>> {
+) -> Result<Option<PageParent>> {
     let input: ParentDescription = params.parse()?;
 
     info!(
@@ -65,8 +64,8 @@ pub async fn parent_get(
 pub async fn parent_set(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
-) -> Result<Option<PageParentModel>> {
-    let input: ParentDescription = params.parse()?;
+) -> Result<Option<PageParent>> {
+    let input: ModifyParent = params.parse()?;
 
     info!(
         "Creating parental relationship {:?} -> {:?} in site ID {}",
@@ -80,7 +79,7 @@ pub async fn parent_remove(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
 ) -> Result<RemoveParentOutput> {
-    let input: ParentDescription = params.parse()?;
+    let input: ModifyParent = params.parse()?;
 
     info!(
         "Removing parental relationship {:?} -> {:?} in site ID {}",
@@ -132,10 +131,11 @@ pub async fn parent_update(
             let creation = parents.iter().map(|parent| {
                 ParentService::create(
                     ctx,
-                    ParentDescription {
+                    ModifyParent {
                         site_id: input.site_id,
                         parent: parent.to_owned(),
                         child: input.child.clone(),
+                        user_id: input.user_id,
                     },
                 )
             });
@@ -156,10 +156,11 @@ pub async fn parent_update(
             let removal = parents.iter().map(|parent| {
                 ParentService::remove(
                     ctx,
-                    ParentDescription {
+                    ModifyParent {
                         site_id: input.site_id,
                         parent: parent.to_owned(),
                         child: input.child.clone(),
+                        user_id: input.user_id,
                     },
                 )
             });
@@ -179,3 +180,59 @@ pub async fn parent_update(
         removed: removal,
     })
 }
+
+pub async fn parent_ancestors_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<HierarchyPage>> {
+    let input: GetPageHierarchy = params.parse()?;
+
+    info!(
+        "Getting ancestors of {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+
+    ParentService::get_ancestors(ctx, input).await
+}
+
+pub async fn parent_descendants_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<HierarchyPage>> {
+    let input: GetPageHierarchy = params.parse()?;
+
+    info!(
+        "Getting descendants of {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+
+    ParentService::get_descendants(ctx, input).await
+}
+
+pub async fn parent_breadcrumbs_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<HierarchyPage>> {
+    let input: GetPageHierarchy = params.parse()?;
+
+    info!(
+        "Getting breadcrumbs for {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+
+    ParentService::get_breadcrumbs(ctx, input).await
+}
+
+pub async fn parent_tree_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageTree> {
+    let input: GetPageHierarchy = params.parse()?;
+
+    info!(
+        "Getting page tree under {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+
+    ParentService::get_tree(ctx, input).await
+}
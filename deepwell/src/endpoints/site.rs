@@ -48,6 +48,7 @@ pub async fn site_get(
             )?;
 
             Ok(Some(GetSiteOutput {
+                has_join_password: site.join_password.is_some(),
                 site,
                 aliases,
                 domains,
@@ -56,6 +57,14 @@ pub async fn site_get(
     }
 }
 
+pub async fn site_get_templates(
+    ctx: &ServiceContext<'_>,
+    _params: Params<'static>,
+) -> Result<Vec<SiteModel>> {
+    info!("Getting all template sites");
+    SiteService::get_templates(ctx).await
+}
+
 pub async fn site_update(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
@@ -0,0 +1,72 @@
+/*
+ * endpoints/site_backup.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::site_backup::Model as SiteBackupModel;
+use crate::services::site_backup::{
+    CreateSiteBackup, GetSiteBackup, GetSiteBackups, RestoreSiteBackup,
+    RestoreSiteBackupOutput,
+};
+
+pub async fn site_backup_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteBackupModel> {
+    let input: CreateSiteBackup = params.parse()?;
+
+    info!(
+        "Creating backup for site ID {} (user ID {})",
+        input.site_id, input.user_id,
+    );
+
+    SiteBackupService::create(ctx, input).await
+}
+
+pub async fn site_backup_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<SiteBackupModel>> {
+    let GetSiteBackup { backup_id } = params.parse()?;
+    info!("Getting site backup ID {backup_id}");
+    SiteBackupService::get_optional(ctx, backup_id).await
+}
+
+pub async fn site_backup_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<SiteBackupModel>> {
+    let GetSiteBackups { site_id } = params.parse()?;
+    info!("Getting all backups for site ID {site_id}");
+    SiteBackupService::get_all(ctx, site_id).await
+}
+
+pub async fn site_backup_restore(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RestoreSiteBackupOutput> {
+    let input: RestoreSiteBackup = params.parse()?;
+
+    info!(
+        "Restoring site backup '{}' as site '{}'",
+        input.storage_path, input.slug,
+    );
+
+    SiteBackupService::restore(ctx, input).await
+}
@@ -20,7 +20,15 @@
 
 use super::prelude::*;
 use crate::models::relation::Model as RelationModel;
+use crate::models::site_application::Model as SiteApplicationModel;
+use crate::models::site_invitation::Model as SiteInvitationModel;
 use crate::services::relation::{CreateSiteMember, GetSiteMember, RemoveSiteMember};
+use crate::services::site_membership::{
+    ApplyToSite, CreateSiteInvitation, GetSiteApplication, JoinSite,
+    ListSiteApplications, ListSiteInvitations, ReviewSiteApplication,
+    RevokeSiteInvitation,
+};
+use crate::services::SiteMembershipService;
 
 pub async fn membership_get(
     ctx: &ServiceContext<'_>,
@@ -45,3 +53,78 @@ pub async fn membership_delete(
     let input: RemoveSiteMember = params.parse()?;
     RelationService::remove_site_member(ctx, input).await
 }
+
+pub async fn membership_join(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: JoinSite = params.parse()?;
+    SiteMembershipService::join(ctx, input).await
+}
+
+pub async fn membership_apply(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteApplicationModel> {
+    let input: ApplyToSite = params.parse()?;
+    SiteMembershipService::apply(ctx, input).await
+}
+
+pub async fn membership_application_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteApplicationModel> {
+    let GetSiteApplication { application_id } = params.parse()?;
+    info!("Getting site application ID {application_id}");
+    SiteMembershipService::get_application(ctx, application_id).await
+}
+
+pub async fn membership_application_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<SiteApplicationModel>> {
+    let input: ListSiteApplications = params.parse()?;
+    info!("Getting site applications for site ID {}", input.site_id);
+    SiteMembershipService::list_applications(ctx, input).await
+}
+
+pub async fn membership_application_accept(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteApplicationModel> {
+    let input: ReviewSiteApplication = params.parse()?;
+    SiteMembershipService::accept_application(ctx, input).await
+}
+
+pub async fn membership_application_reject(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteApplicationModel> {
+    let input: ReviewSiteApplication = params.parse()?;
+    SiteMembershipService::reject_application(ctx, input).await
+}
+
+pub async fn membership_invitation_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteInvitationModel> {
+    let input: CreateSiteInvitation = params.parse()?;
+    SiteMembershipService::create_invitation(ctx, input).await
+}
+
+pub async fn membership_invitation_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<SiteInvitationModel>> {
+    let input: ListSiteInvitations = params.parse()?;
+    info!("Getting site invitations for site ID {}", input.site_id);
+    SiteMembershipService::list_invitations(ctx, input).await
+}
+
+pub async fn membership_invitation_revoke(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteInvitationModel> {
+    let input: RevokeSiteInvitation = params.parse()?;
+    SiteMembershipService::revoke_invitation(ctx, input).await
+}
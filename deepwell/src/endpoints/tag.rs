@@ -0,0 +1,124 @@
+/*
+ * endpoints/tag.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::site_tag::Model as SiteTagModel;
+use crate::models::site_tag_group::Model as SiteTagGroupModel;
+use crate::services::tag::{
+    CreateTag, CreateTagGroup, GetSiteTags, GetSiteTagsOutput, GetTag, GetTagGroup,
+    RenameTag, RenameTagOutput, UpdateTag, UpdateTagGroup, ValidateTags,
+};
+
+// Tag groups
+
+pub async fn tag_group_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagGroupModel> {
+    let input: CreateTagGroup = params.parse()?;
+    info!(
+        "Creating tag group '{}' in site ID {}",
+        input.name, input.site_id,
+    );
+    TagService::create_group(ctx, input).await
+}
+
+pub async fn tag_group_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagGroupModel> {
+    let input: UpdateTagGroup = params.parse()?;
+    info!("Updating tag group ID {}", input.tag_group_id);
+    TagService::update_group(ctx, input).await
+}
+
+pub async fn tag_group_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let GetTagGroup { tag_group_id } = params.parse()?;
+    info!("Deleting tag group ID {tag_group_id}");
+    TagService::delete_group(ctx, tag_group_id).await
+}
+
+// Tags
+
+pub async fn tag_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagModel> {
+    let input: CreateTag = params.parse()?;
+    info!("Creating tag '{}' in site ID {}", input.name, input.site_id);
+    TagService::create(ctx, input).await
+}
+
+pub async fn tag_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<SiteTagModel>> {
+    let GetTag { tag_id } = params.parse()?;
+    info!("Getting tag ID {tag_id}");
+    TagService::get_optional(ctx, tag_id).await
+}
+
+pub async fn tag_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GetSiteTagsOutput> {
+    let GetSiteTags { site_id } = params.parse()?;
+    info!("Getting tag configuration for site ID {site_id}");
+    TagService::get_all(ctx, site_id).await
+}
+
+pub async fn tag_update(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteTagModel> {
+    let input: UpdateTag = params.parse()?;
+    info!("Updating tag ID {}", input.tag_id);
+    TagService::update(ctx, input).await
+}
+
+pub async fn tag_delete(ctx: &ServiceContext<'_>, params: Params<'static>) -> Result<()> {
+    let GetTag { tag_id } = params.parse()?;
+    info!("Deleting tag ID {tag_id}");
+    TagService::delete(ctx, tag_id).await
+}
+
+pub async fn tag_validate(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<String>> {
+    let ValidateTags { site_id, tags } = params.parse()?;
+    info!("Validating {} tags for site ID {site_id}", tags.len());
+    TagService::validate(ctx, site_id, tags).await
+}
+
+pub async fn tag_rename(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RenameTagOutput> {
+    let input: RenameTag = params.parse()?;
+    info!(
+        "Renaming tag '{}' to '{}' in site ID {}",
+        input.from, input.to, input.site_id,
+    );
+    TagService::rename(ctx, input).await
+}
@@ -21,8 +21,9 @@
 use super::prelude::*;
 use crate::models::sea_orm_active_enums::AliasType;
 use crate::models::user::Model as UserModel;
+use crate::models::user_name_change::Model as UserNameChangeModel;
 use crate::services::user::{
-    CreateUser, CreateUserOutput, GetUser, GetUserOutput, UpdateUser,
+    CreateUser, CreateUserOutput, FindUserPastName, GetUser, GetUserOutput, UpdateUser,
 };
 
 pub async fn user_create(
@@ -49,17 +50,40 @@ pub async fn user_get(
     let GetUser { user: reference } = params.parse()?;
     info!("Getting user {:?}", reference);
 
-    match UserService::get_optional(ctx, reference).await? {
-        None => Ok(None),
-        Some(user) => {
+    match UserService::get_optional_aliased(ctx, reference).await? {
+        (None, _) => Ok(None),
+        (Some(user), aliased) => {
             let aliases =
                 AliasService::get_all(ctx, AliasType::User, user.user_id).await?;
+            let redirect_user = aliased.then(|| user.slug.clone());
 
-            Ok(Some(GetUserOutput { user, aliases }))
+            Ok(Some(GetUserOutput {
+                user,
+                aliases,
+                redirect_user,
+            }))
         }
     }
 }
 
+pub async fn user_name_history(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<UserNameChangeModel>> {
+    let GetUser { user: reference } = params.parse()?;
+    info!("Getting name history for user {:?}", reference);
+    let user_id = UserService::get_id(ctx, reference).await?;
+    UserService::get_name_history(ctx, user_id).await
+}
+
+pub async fn user_find_past_name(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<UserNameChangeModel>> {
+    let FindUserPastName { name } = params.parse()?;
+    UserService::find_past_name(ctx, &name).await
+}
+
 pub async fn user_edit(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
@@ -0,0 +1,119 @@
+/*
+ * endpoints/user_contact.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::relation::Model as RelationModel;
+use crate::services::relation::{
+    AcceptUserContactRequest, CreateUserContactRequest, GetUserContactRequests,
+    GetUserContacts, RemoveUserContact, RemoveUserContactRequest,
+};
+
+pub async fn contact_request_send(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: CreateUserContactRequest = params.parse()?;
+
+    info!(
+        "Sending contact request from user ID {} to user ID {}",
+        input.requesting_user, input.requested_user,
+    );
+
+    RelationService::create_user_contact_request(ctx, input).await
+}
+
+pub async fn contact_request_accept(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: AcceptUserContactRequest = params.parse()?;
+
+    info!(
+        "Accepting contact request from user ID {} to user ID {}",
+        input.requesting_user, input.requested_user,
+    );
+
+    RelationService::accept_user_contact_request(ctx, input).await
+}
+
+pub async fn contact_request_decline(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationModel> {
+    let input: RemoveUserContactRequest = params.parse()?;
+
+    info!(
+        "Declining contact request from user ID {} to user ID {}",
+        input.requesting_user, input.requested_user,
+    );
+
+    RelationService::remove_user_contact_request(ctx, input).await
+}
+
+pub async fn contact_request_cancel(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationModel> {
+    let input: RemoveUserContactRequest = params.parse()?;
+
+    info!(
+        "Cancelling contact request from user ID {} to user ID {}",
+        input.requesting_user, input.requested_user,
+    );
+
+    RelationService::remove_user_contact_request(ctx, input).await
+}
+
+pub async fn contact_request_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<RelationModel>> {
+    let input: GetUserContactRequests = params.parse()?;
+
+    info!(
+        "Getting {:?} contact requests for user ID {}",
+        input.direction, input.user,
+    );
+
+    RelationService::get_user_contact_requests(ctx, input).await
+}
+
+pub async fn contact_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<RelationModel>> {
+    let input: GetUserContacts = params.parse()?;
+    info!("Getting contacts for user ID {}", input.user);
+    RelationService::get_user_contacts(ctx, input).await
+}
+
+pub async fn contact_remove(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: RemoveUserContact = params.parse()?;
+
+    info!(
+        "Removing contact between user ID {} and user ID {}",
+        input.user, input.contact_user,
+    );
+
+    RelationService::remove_mutual_user_contact(ctx, input).await
+}
@@ -23,3 +23,16 @@ mod text;
 
 pub use self::blob::*;
 pub use self::text::*;
+
+/// The result of checking stored data against the hashes it is addressed by.
+#[derive(Debug, Default)]
+pub struct HashVerification {
+    /// How many items were checked.
+    pub checked: u64,
+
+    /// Hex hashes of items whose contents do not match their hash.
+    pub mismatched: Vec<String>,
+
+    /// Hex hashes of items which are referenced but could not be found.
+    pub missing: Vec<String>,
+}
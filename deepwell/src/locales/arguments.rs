@@ -22,7 +22,7 @@ use fluent::{FluentArgs, FluentValue};
 use std::borrow::Cow;
 use std::collections::HashMap;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct MessageArguments<'a> {
     #[serde(flatten)]
     inner: HashMap<Cow<'a, str>, MessageValue<'a>>,
@@ -39,6 +39,14 @@ impl<'a> MessageArguments<'a> {
         args
     }
 
+    pub fn set<K, V>(&mut self, name: K, value: V)
+    where
+        K: Into<Cow<'a, str>>,
+        V: Into<MessageValue<'a>>,
+    {
+        self.inner.insert(name.into(), value.into());
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.inner.len()
@@ -64,3 +72,44 @@ impl<'a> From<MessageValue<'a>> for FluentValue<'a> {
         }
     }
 }
+
+impl<'a> From<&'a str> for MessageValue<'a> {
+    #[inline]
+    fn from(value: &'a str) -> MessageValue<'a> {
+        MessageValue::String(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for MessageValue<'_> {
+    #[inline]
+    fn from(value: String) -> Self {
+        MessageValue::String(Cow::Owned(value))
+    }
+}
+
+impl From<i64> for MessageValue<'_> {
+    #[inline]
+    fn from(value: i64) -> Self {
+        MessageValue::Float(value as f64)
+    }
+}
+
+impl From<usize> for MessageValue<'_> {
+    #[inline]
+    fn from(value: usize) -> Self {
+        MessageValue::Float(value as f64)
+    }
+}
+
+impl<'a, T> From<Option<T>> for MessageValue<'a>
+where
+    T: Into<MessageValue<'a>>,
+{
+    #[inline]
+    fn from(value: Option<T>) -> MessageValue<'a> {
+        match value {
+            Some(value) => value.into(),
+            None => MessageValue::Null,
+        }
+    }
+}
@@ -24,8 +24,10 @@ mod arguments;
 mod error;
 mod fallback;
 mod fluent;
+mod request;
 
 pub use self::arguments::{MessageArguments, MessageValue};
 pub use self::error::*;
 pub use self::fallback::iterate_locale_fallbacks;
 pub use self::fluent::Localizations;
+pub use self::request::{add_request_locales, RequestLocales};
@@ -0,0 +1,101 @@
+/*
+ * locales/request.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Determines which locales the caller of a request wants.
+//!
+//! This uses the `Accept-Language` header, and is used to localize errors.
+
+use http::header::ACCEPT_LANGUAGE;
+use jsonrpsee::server::HttpRequest;
+use unic_langid::LanguageIdentifier;
+
+/// The locales requested by the caller, in order of preference.
+///
+/// This is added to the extensions of each request.
+#[derive(Debug, Clone, Default)]
+pub struct RequestLocales(pub Vec<LanguageIdentifier>);
+
+/// HTTP middleware function which adds `RequestLocales` to a request.
+pub fn add_request_locales(mut request: HttpRequest) -> HttpRequest {
+    let locales = request
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .map(parse_accept_language)
+        .unwrap_or_default();
+
+    request.extensions_mut().insert(RequestLocales(locales));
+    request
+}
+
+/// Parses an `Accept-Language` header into a list of locales.
+///
+/// The locales are ordered by their quality value, and any which
+/// are invalid or are wildcards are skipped.
+pub fn parse_accept_language(header: &str) -> Vec<LanguageIdentifier> {
+    let mut entries = Vec::new();
+
+    for item in header.split(',') {
+        let mut parts = item.split(';').map(str::trim);
+        let locale = parts.next().unwrap_or_default();
+        let quality = parts
+            .find_map(|part| part.strip_prefix("q="))
+            .and_then(|value| value.parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        if locale.is_empty() || locale == "*" || quality <= 0.0 {
+            continue;
+        }
+
+        match locale.parse::<LanguageIdentifier>() {
+            Ok(locale) => entries.push((locale, quality)),
+            Err(error) => debug!("Skipping invalid locale '{locale}': {error}"),
+        }
+    }
+
+    // Stable sort, so equal quality values keep their order
+    entries.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    entries.into_iter().map(|(locale, _)| locale).collect()
+}
+
+#[test]
+fn accept_language() {
+    macro_rules! check {
+        ($header:expr, $expected:expr $(,)?) => {{
+            let expected: Vec<LanguageIdentifier> = $expected
+                .iter()
+                .map(|locale: &&str| locale.parse().unwrap())
+                .collect();
+
+            assert_eq!(
+                parse_accept_language($header),
+                expected,
+                "Actual parsed locales don't match expected",
+            );
+        }};
+    }
+
+    check!("", [] as [&str; 0]);
+    check!("en", ["en"]);
+    check!("en-US,en;q=0.5", ["en-US", "en"]);
+    check!("fr;q=0.7, de-CH, *;q=0.1, en;q=0.9", ["de-CH", "en", "fr"]);
+    check!("ko, zh-Hans, !!!", ["ko", "zh-Hans"]);
+    check!("pl;q=0, vi", ["vi"]);
+}
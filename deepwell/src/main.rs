@@ -50,8 +50,10 @@ mod endpoints;
 mod hash;
 mod info;
 mod locales;
+mod maintenance;
 mod redis;
 mod services;
+mod storage;
 mod types;
 mod utils;
 
@@ -68,7 +70,11 @@ use std::process;
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load the configuration so we can set up
-    let SetupConfig { secrets, config } = SetupConfig::load();
+    let SetupConfig {
+        secrets,
+        config,
+        special_action,
+    } = SetupConfig::load();
     let run_seeder = config.run_seeder;
 
     // Configure the logger
@@ -110,6 +116,12 @@ async fn main() -> Result<()> {
     // Set up server state
     let app_state = api::build_server_state(config, secrets).await?;
 
+    // Run maintenance action instead of the server, if requested
+    if let Some(action) = special_action {
+        let return_code = maintenance::run_maintenance(&app_state, action).await;
+        process::exit(return_code);
+    }
+
     // Run seeder, if enabled
     if run_seeder {
         database::seed(&app_state).await?;
@@ -0,0 +1,277 @@
+/*
+ * maintenance.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Implementations of the special actions which run against the database.
+//!
+//! These are one-shot maintenance tasks, which report a summary of what
+//! they did and then exit, rather than starting the server.
+//! See `config/special_action.rs` for how they are selected.
+
+use crate::api::ServerState;
+use crate::config::SpecialAction;
+use crate::database;
+use crate::hash::HashVerification;
+use crate::models::page::{self, Entity as Page};
+use crate::models::site::{self, Entity as Site};
+use crate::services::text::TextCompaction;
+use crate::services::{
+    BlobService, PageRevisionService, Result, ScoreService, ServiceContext, TextService,
+};
+use sea_orm::{
+    ColumnTrait, Condition, EntityTrait, QueryFilter, QuerySelect, TransactionTrait,
+};
+
+/// Runs the given special action, returning the process exit code.
+pub async fn run_maintenance(state: &ServerState, action: SpecialAction) -> i32 {
+    let result = match action {
+        SpecialAction::RerenderPages { site_id } => rerender_pages(state, site_id).await,
+        SpecialAction::RebuildLinks { site_id } => rebuild_links(state, site_id).await,
+        SpecialAction::RefreshScores { site_id } => refresh_scores(state, site_id).await,
+        SpecialAction::Prune => prune(state).await,
+        SpecialAction::VerifyHashes => verify_hashes(state).await,
+        SpecialAction::CompactText => compact_text(state).await,
+        SpecialAction::Seed => {
+            println!("Running special action: Run seeder");
+            match database::seed(state).await {
+                Ok(()) => Ok(true),
+                Err(error) => {
+                    eprintln!("Error running seeder: {error}");
+                    Ok(false)
+                }
+            }
+        }
+    };
+
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(error) => {
+            eprintln!("Error running special action: {error}");
+            2
+        }
+    }
+}
+
+/// Runs the given action on every page in the site(s), each in its own transaction.
+///
+/// Returns whether all pages succeeded.
+macro_rules! for_each_page {
+    ($state:expr, $site_id:expr, $verb:expr, |$ctx:ident, $page_site_id:ident, $page_id:ident| $action:expr $(,)?) => {{
+        let mut succeeded = 0;
+        let mut failed = 0;
+
+        for ($page_site_id, $page_id) in get_pages($state, $site_id).await? {
+            let txn = $state.database.begin().await?;
+            let $ctx = &ServiceContext::new($state, &txn);
+
+            match $action.await {
+                Ok(_) => {
+                    txn.commit().await?;
+                    succeeded += 1;
+                }
+                Err(error) => {
+                    txn.rollback().await?;
+                    eprintln!(
+                        "Failed on page ID {} in site ID {}: {error}",
+                        $page_id, $page_site_id,
+                    );
+                    failed += 1;
+                }
+            }
+        }
+
+        println!("{} {succeeded} pages, {failed} failed", $verb);
+        failed == 0
+    }};
+}
+
+async fn rerender_pages(state: &ServerState, site_id: Option<i64>) -> Result<bool> {
+    println!("Running special action: Rerender pages");
+
+    let success =
+        for_each_page!(state, site_id, "Rerendered", |ctx, site_id, page_id| {
+            PageRevisionService::rerender(ctx, site_id, page_id, 0)
+        });
+
+    Ok(success)
+}
+
+async fn rebuild_links(state: &ServerState, site_id: Option<i64>) -> Result<bool> {
+    println!("Running special action: Rebuild link connections");
+
+    let success = for_each_page!(
+        state,
+        site_id,
+        "Rebuilt links for",
+        |ctx, site_id, page_id| {
+            PageRevisionService::rebuild_links(ctx, site_id, page_id)
+        }
+    );
+
+    Ok(success)
+}
+
+async fn refresh_scores(state: &ServerState, site_id: Option<i64>) -> Result<bool> {
+    println!("Running special action: Refresh scores");
+
+    let txn = state.database.begin().await?;
+    let ctx = &ServiceContext::new(state, &txn);
+    let mut pages = 0;
+
+    for site_id in get_sites(state, site_id).await? {
+        pages += ScoreService::refresh_site(ctx, site_id).await?;
+    }
+
+    txn.commit().await?;
+    println!("Refreshed scores for {pages} pages");
+    Ok(true)
+}
+
+async fn prune(state: &ServerState) -> Result<bool> {
+    println!("Running special action: Prune unused text and blobs");
+
+    let txn = state.database.begin().await?;
+    let ctx = &ServiceContext::new(state, &txn);
+    let texts = TextService::prune(ctx).await?;
+    let blobs = BlobService::prune(ctx).await?;
+    txn.commit().await?;
+
+    println!("Pruned {texts} text rows and {blobs} blobs");
+    Ok(true)
+}
+
+async fn verify_hashes(state: &ServerState) -> Result<bool> {
+    println!("Running special action: Verify text and blob hashes");
+
+    let txn = state.database.begin().await?;
+    let ctx = &ServiceContext::new(state, &txn);
+    let texts = TextService::verify(ctx).await?;
+    let blobs = BlobService::verify(ctx).await?;
+    txn.rollback().await?;
+
+    let success = print_verification("text rows", &texts);
+    let success = print_verification("blobs", &blobs) && success;
+    Ok(success)
+}
+
+async fn compact_text(state: &ServerState) -> Result<bool> {
+    println!("Running special action: Compact text");
+
+    // Deleted pages are included, since their revisions are still stored.
+    let page_ids: Vec<i64> = Page::find()
+        .select_only()
+        .column(page::Column::PageId)
+        .into_tuple()
+        .all(&state.database)
+        .await?;
+
+    let mut total = TextCompaction::default();
+    let mut failed = 0;
+
+    for page_id in page_ids {
+        let txn = state.database.begin().await?;
+        let ctx = &ServiceContext::new(state, &txn);
+
+        match TextService::compact_page(ctx, page_id).await {
+            Ok(compaction) => {
+                txn.commit().await?;
+                total += compaction;
+            }
+            Err(error) => {
+                txn.rollback().await?;
+                eprintln!("Failed on page ID {page_id}: {error}");
+                failed += 1;
+            }
+        }
+    }
+
+    let txn = state.database.begin().await?;
+    let ctx = &ServiceContext::new(state, &txn);
+    total += TextService::compact_remaining(ctx).await?;
+    txn.commit().await?;
+
+    let saved_bytes = total.original_bytes - total.stored_bytes;
+    let saved_percent = if total.original_bytes > 0 {
+        saved_bytes as f64 / total.original_bytes as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    println!(
+        "Compacted {} text rows from {} to {} bytes, saving {saved_bytes} bytes ({saved_percent:.1}%)",
+        total.rows, total.original_bytes, total.stored_bytes,
+    );
+    println!("{failed} pages failed");
+    Ok(failed == 0)
+}
+
+fn print_verification(name: &str, verification: &HashVerification) -> bool {
+    println!(
+        "Checked {} {name}: {} mismatched, {} missing",
+        verification.checked,
+        verification.mismatched.len(),
+        verification.missing.len(),
+    );
+
+    for hash in &verification.mismatched {
+        println!("* Mismatched: {hash}");
+    }
+
+    for hash in &verification.missing {
+        println!("* Missing:    {hash}");
+    }
+
+    verification.mismatched.is_empty() && verification.missing.is_empty()
+}
+
+/// Gets the IDs of all the sites to run on, or just the specified one.
+async fn get_sites(state: &ServerState, site_id: Option<i64>) -> Result<Vec<i64>> {
+    if let Some(site_id) = site_id {
+        return Ok(vec![site_id]);
+    }
+
+    let site_ids = Site::find()
+        .select_only()
+        .column(site::Column::SiteId)
+        .filter(site::Column::DeletedAt.is_null())
+        .into_tuple()
+        .all(&state.database)
+        .await?;
+
+    Ok(site_ids)
+}
+
+/// Gets the site and page IDs of all extant pages to run on.
+async fn get_pages(state: &ServerState, site_id: Option<i64>) -> Result<Vec<(i64, i64)>> {
+    let condition = Condition::all()
+        .add(page::Column::DeletedAt.is_null())
+        .add_option(site_id.map(|site_id| page::Column::SiteId.eq(site_id)));
+
+    let pages = Page::find()
+        .select_only()
+        .column(page::Column::SiteId)
+        .column(page::Column::PageId)
+        .filter(condition)
+        .into_tuple()
+        .all(&state.database)
+        .await?;
+
+    Ok(pages)
+}
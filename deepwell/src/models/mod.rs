@@ -7,6 +7,11 @@ pub mod blob_pending;
 pub mod file;
 pub mod file_revision;
 pub mod filter;
+pub mod forum_category;
+pub mod forum_group;
+pub mod forum_post;
+pub mod forum_post_revision;
+pub mod forum_thread;
 pub mod message;
 pub mod message_draft;
 pub mod message_recipient;
@@ -17,16 +22,26 @@ pub mod page_attribution;
 pub mod page_category;
 pub mod page_connection;
 pub mod page_connection_missing;
+pub mod page_draft;
 pub mod page_link;
 pub mod page_lock;
-pub mod page_parent;
+pub mod page_redirect;
 pub mod page_revision;
+pub mod page_schedule;
+pub mod page_score;
 pub mod page_vote;
 pub mod relation;
 pub mod sea_orm_active_enums;
 pub mod session;
 pub mod site;
+pub mod site_application;
+pub mod site_backup;
 pub mod site_domain;
+pub mod site_invitation;
+pub mod site_tag;
+pub mod site_tag_group;
 pub mod text;
 pub mod user;
 pub mod user_bot_owner;
+pub mod user_device;
+pub mod user_name_change;
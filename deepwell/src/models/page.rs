@@ -31,6 +31,14 @@ pub enum Relation {
     File,
     #[sea_orm(has_many = "super::file_revision::Entity")]
     FileRevision,
+    #[sea_orm(
+        belongs_to = "super::forum_thread::Entity",
+        from = "Column::DiscussionThreadId",
+        to = "super::forum_thread::Column::ForumThreadId",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    ForumThread,
     #[sea_orm(has_many = "super::page_attribution::Entity")]
     PageAttribution,
     #[sea_orm(
@@ -43,10 +51,14 @@ pub enum Relation {
     PageCategory,
     #[sea_orm(has_many = "super::page_connection_missing::Entity")]
     PageConnectionMissing,
+    #[sea_orm(has_many = "super::page_draft::Entity")]
+    PageDraft,
     #[sea_orm(has_many = "super::page_link::Entity")]
     PageLink,
     #[sea_orm(has_many = "super::page_lock::Entity")]
     PageLock,
+    #[sea_orm(has_many = "super::page_redirect::Entity")]
+    PageRedirect,
     #[sea_orm(
         belongs_to = "super::page_revision::Entity",
         from = "Column::LatestRevisionId",
@@ -55,6 +67,10 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     PageRevision,
+    #[sea_orm(has_many = "super::page_schedule::Entity")]
+    PageSchedule,
+    #[sea_orm(has_one = "super::page_score::Entity")]
+    PageScore,
     #[sea_orm(has_many = "super::page_vote::Entity")]
     PageVote,
     #[sea_orm(
@@ -79,6 +95,12 @@ impl Related<super::file_revision::Entity> for Entity {
     }
 }
 
+impl Related<super::forum_thread::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ForumThread.def()
+    }
+}
+
 impl Related<super::page_attribution::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageAttribution.def()
@@ -97,6 +119,12 @@ impl Related<super::page_connection_missing::Entity> for Entity {
     }
 }
 
+impl Related<super::page_draft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageDraft.def()
+    }
+}
+
 impl Related<super::page_link::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageLink.def()
@@ -109,12 +137,30 @@ impl Related<super::page_lock::Entity> for Entity {
     }
 }
 
+impl Related<super::page_redirect::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageRedirect.def()
+    }
+}
+
 impl Related<super::page_revision::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageRevision.def()
     }
 }
 
+impl Related<super::page_schedule::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageSchedule.def()
+    }
+}
+
+impl Related<super::page_score::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageScore.def()
+    }
+}
+
 impl Related<super::page_vote::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageVote.def()
@@ -17,6 +17,19 @@ pub struct Model {
     pub slug: String,
     #[sea_orm(column_type = "Text", nullable)]
     pub layout: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    #[serde(default)]
+    pub template_page: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default = "default_permissions")]
+    pub permissions: Json,
+    #[sea_orm(column_type = "Text", nullable)]
+    #[serde(default)]
+    pub score_type: Option<String>,
+    #[serde(default)]
+    pub hidden: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -46,3 +59,8 @@ impl Related<super::site::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+/// Matches the column default, for archives from before per-category settings.
+fn default_permissions() -> Json {
+    Json::Object(Default::default())
+}
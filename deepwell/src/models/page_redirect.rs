@@ -4,34 +4,48 @@ use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
-#[sea_orm(table_name = "page_parent")]
+#[sea_orm(table_name = "page_redirect")]
 pub struct Model {
-    #[sea_orm(primary_key, auto_increment = false)]
-    pub parent_page_id: i64,
-    #[sea_orm(primary_key, auto_increment = false)]
-    pub child_page_id: i64,
+    #[sea_orm(primary_key)]
+    pub redirect_id: i64,
     #[serde(with = "time::serde::rfc3339")]
     pub created_at: TimeDateTimeWithTimeZone,
+    pub site_id: i64,
+    #[sea_orm(column_type = "Text")]
+    pub slug: String,
+    pub page_id: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(
         belongs_to = "super::page::Entity",
-        from = "Column::ChildPageId",
+        from = "Column::PageId",
         to = "super::page::Column::PageId",
         on_update = "NoAction",
         on_delete = "NoAction"
     )]
-    Page2,
+    Page,
     #[sea_orm(
-        belongs_to = "super::page::Entity",
-        from = "Column::ParentPageId",
-        to = "super::page::Column::PageId",
+        belongs_to = "super::site::Entity",
+        from = "Column::SiteId",
+        to = "super::site::Column::SiteId",
         on_update = "NoAction",
         on_delete = "NoAction"
     )]
-    Page1,
+    Site,
+}
+
+impl Related<super::page::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Page.def()
+    }
+}
+
+impl Related<super::site::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Site.def()
+    }
 }
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -5,6 +5,11 @@ pub use super::blob_pending::Entity as BlobPending;
 pub use super::file::Entity as File;
 pub use super::file_revision::Entity as FileRevision;
 pub use super::filter::Entity as Filter;
+pub use super::forum_category::Entity as ForumCategory;
+pub use super::forum_group::Entity as ForumGroup;
+pub use super::forum_post::Entity as ForumPost;
+pub use super::forum_post_revision::Entity as ForumPostRevision;
+pub use super::forum_thread::Entity as ForumThread;
 pub use super::message::Entity as Message;
 pub use super::message_draft::Entity as MessageDraft;
 pub use super::message_recipient::Entity as MessageRecipient;
@@ -15,15 +20,25 @@ pub use super::page_attribution::Entity as PageAttribution;
 pub use super::page_category::Entity as PageCategory;
 pub use super::page_connection::Entity as PageConnection;
 pub use super::page_connection_missing::Entity as PageConnectionMissing;
+pub use super::page_draft::Entity as PageDraft;
 pub use super::page_link::Entity as PageLink;
 pub use super::page_lock::Entity as PageLock;
-pub use super::page_parent::Entity as PageParent;
+pub use super::page_redirect::Entity as PageRedirect;
 pub use super::page_revision::Entity as PageRevision;
+pub use super::page_schedule::Entity as PageSchedule;
+pub use super::page_score::Entity as PageScore;
 pub use super::page_vote::Entity as PageVote;
 pub use super::relation::Entity as Relation;
 pub use super::session::Entity as Session;
 pub use super::site::Entity as Site;
+pub use super::site_application::Entity as SiteApplication;
+pub use super::site_backup::Entity as SiteBackup;
 pub use super::site_domain::Entity as SiteDomain;
+pub use super::site_invitation::Entity as SiteInvitation;
+pub use super::site_tag::Entity as SiteTag;
+pub use super::site_tag_group::Entity as SiteTagGroup;
 pub use super::text::Entity as Text;
 pub use super::user::Entity as User;
 pub use super::user_bot_owner::Entity as UserBotOwner;
+pub use super::user_device::Entity as UserDevice;
+pub use super::user_name_change::Entity as UserNameChange;
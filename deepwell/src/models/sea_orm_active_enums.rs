@@ -74,6 +74,36 @@ pub enum PageRevisionType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "page_schedule_status"
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum PageScheduleStatus {
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "published")]
+    Published,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "page_schedule_type")]
+#[serde(rename_all = "kebab-case")]
+pub enum PageScheduleType {
+    #[sea_orm(string_value = "create")]
+    Create,
+    #[sea_orm(string_value = "edit")]
+    Edit,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(
     rs_type = "String",
     db_type = "Enum",
@@ -93,6 +123,63 @@ pub enum RelationObjectType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "site_application_status"
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum SiteApplicationStatus {
+    #[sea_orm(string_value = "accepted")]
+    Accepted,
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "site_backup_status")]
+#[serde(rename_all = "kebab-case")]
+pub enum SiteBackupStatus {
+    #[sea_orm(string_value = "completed")]
+    Completed,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "running")]
+    Running,
+}
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    Copy,
+    Serialize,
+    Deserialize,
+    Default,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "site_join_policy")]
+#[serde(rename_all = "kebab-case")]
+pub enum SiteJoinPolicy {
+    #[sea_orm(string_value = "application")]
+    Application,
+    #[default]
+    #[sea_orm(string_value = "invite")]
+    Invite,
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "password")]
+    Password,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "user_type")]
 #[serde(rename_all = "kebab-case")]
 pub enum UserType {
@@ -13,6 +13,8 @@ pub struct Model {
     pub created_at: TimeDateTimeWithTimeZone,
     #[serde(with = "time::serde::rfc3339")]
     pub expires_at: TimeDateTimeWithTimeZone,
+    #[serde(with = "time::serde::rfc3339")]
+    pub last_seen_at: TimeDateTimeWithTimeZone,
     #[sea_orm(column_type = "Text")]
     pub ip_address: String,
     #[sea_orm(column_type = "Text")]
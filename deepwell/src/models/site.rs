@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
+use super::sea_orm_active_enums::SiteJoinPolicy;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -31,6 +32,17 @@ pub struct Model {
     pub custom_domain: Option<String>,
     #[sea_orm(column_type = "Text", nullable)]
     pub layout: Option<String>,
+    #[serde(default)]
+    pub is_template: bool,
+    #[serde(default)]
+    pub join_policy: SiteJoinPolicy,
+    #[sea_orm(column_type = "Text", nullable)]
+    #[serde(default, skip_serializing)]
+    pub join_password: Option<String>,
+    #[serde(default)]
+    pub join_questions: Vec<String>,
+    #[serde(default)]
+    pub storage_quota: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -41,6 +53,14 @@ pub enum Relation {
     FileRevision,
     #[sea_orm(has_many = "super::filter::Entity")]
     Filter,
+    #[sea_orm(has_many = "super::forum_category::Entity")]
+    ForumCategory,
+    #[sea_orm(has_many = "super::forum_group::Entity")]
+    ForumGroup,
+    #[sea_orm(has_many = "super::forum_post::Entity")]
+    ForumPost,
+    #[sea_orm(has_many = "super::forum_thread::Entity")]
+    ForumThread,
     #[sea_orm(has_many = "super::message_report::Entity")]
     MessageReport,
     #[sea_orm(has_many = "super::page::Entity")]
@@ -49,10 +69,26 @@ pub enum Relation {
     PageCategory,
     #[sea_orm(has_many = "super::page_connection_missing::Entity")]
     PageConnectionMissing,
+    #[sea_orm(has_many = "super::page_draft::Entity")]
+    PageDraft,
+    #[sea_orm(has_many = "super::page_redirect::Entity")]
+    PageRedirect,
     #[sea_orm(has_many = "super::page_revision::Entity")]
     PageRevision,
+    #[sea_orm(has_many = "super::page_schedule::Entity")]
+    PageSchedule,
+    #[sea_orm(has_many = "super::site_application::Entity")]
+    SiteApplication,
+    #[sea_orm(has_many = "super::site_backup::Entity")]
+    SiteBackup,
     #[sea_orm(has_many = "super::site_domain::Entity")]
     SiteDomain,
+    #[sea_orm(has_many = "super::site_invitation::Entity")]
+    SiteInvitation,
+    #[sea_orm(has_many = "super::site_tag::Entity")]
+    SiteTag,
+    #[sea_orm(has_many = "super::site_tag_group::Entity")]
+    SiteTagGroup,
     #[sea_orm(
         belongs_to = "super::site_domain::Entity",
         from = "Column::CustomDomain",
@@ -81,6 +117,30 @@ impl Related<super::filter::Entity> for Entity {
     }
 }
 
+impl Related<super::forum_category::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ForumCategory.def()
+    }
+}
+
+impl Related<super::forum_group::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ForumGroup.def()
+    }
+}
+
+impl Related<super::forum_post::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ForumPost.def()
+    }
+}
+
+impl Related<super::forum_thread::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ForumThread.def()
+    }
+}
+
 impl Related<super::message_report::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::MessageReport.def()
@@ -105,18 +165,66 @@ impl Related<super::page_connection_missing::Entity> for Entity {
     }
 }
 
+impl Related<super::page_draft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageDraft.def()
+    }
+}
+
 impl Related<super::page_revision::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageRevision.def()
     }
 }
 
+impl Related<super::page_redirect::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageRedirect.def()
+    }
+}
+
+impl Related<super::page_schedule::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageSchedule.def()
+    }
+}
+
+impl Related<super::site_application::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteApplication.def()
+    }
+}
+
+impl Related<super::site_backup::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteBackup.def()
+    }
+}
+
 impl Related<super::site_domain::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::SiteDomain.def()
     }
 }
 
+impl Related<super::site_invitation::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteInvitation.def()
+    }
+}
+
+impl Related<super::site_tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteTag.def()
+    }
+}
+
+impl Related<super::site_tag_group::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteTagGroup.def()
+    }
+}
+
 impl Related<super::message::Entity> for Entity {
     fn to() -> RelationDef {
         super::message_report::Relation::Message.def()
@@ -12,11 +12,25 @@ pub struct Model {
         column_type = "VarBinary(StringLen::None)"
     )]
     pub hash: Vec<u8>,
-    #[sea_orm(column_type = "Text")]
-    pub contents: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub contents: Option<String>,
+    #[sea_orm(column_type = "VarBinary(StringLen::None)", nullable)]
+    pub compressed_contents: Option<Vec<u8>>,
+    #[sea_orm(column_type = "VarBinary(StringLen::None)", nullable)]
+    pub base_hash: Option<Vec<u8>>,
+    pub size: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::BaseHash",
+        to = "Column::Hash",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    SelfRef,
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -50,6 +50,8 @@ pub struct Model {
     pub biography: Option<String>,
     #[sea_orm(column_type = "Text", nullable)]
     pub user_page: Option<String>,
+    pub dm_contacts_only: bool,
+    pub storage_quota: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -60,6 +62,10 @@ pub enum Relation {
     BlobPending,
     #[sea_orm(has_many = "super::file_revision::Entity")]
     FileRevision,
+    #[sea_orm(has_many = "super::forum_post::Entity")]
+    ForumPost,
+    #[sea_orm(has_many = "super::forum_post_revision::Entity")]
+    ForumPostRevision,
     #[sea_orm(has_many = "super::message::Entity")]
     Message,
     #[sea_orm(has_many = "super::message_draft::Entity")]
@@ -70,12 +76,20 @@ pub enum Relation {
     MessageRecord,
     #[sea_orm(has_many = "super::page_attribution::Entity")]
     PageAttribution,
+    #[sea_orm(has_many = "super::page_draft::Entity")]
+    PageDraft,
     #[sea_orm(has_many = "super::page_lock::Entity")]
     PageLock,
     #[sea_orm(has_many = "super::page_revision::Entity")]
     PageRevision,
+    #[sea_orm(has_many = "super::page_schedule::Entity")]
+    PageSchedule,
     #[sea_orm(has_many = "super::session::Entity")]
     Session,
+    #[sea_orm(has_many = "super::site_backup::Entity")]
+    SiteBackup,
+    #[sea_orm(has_many = "super::user_device::Entity")]
+    UserDevice,
 }
 
 impl Related<super::alias::Entity> for Entity {
@@ -96,6 +110,18 @@ impl Related<super::file_revision::Entity> for Entity {
     }
 }
 
+impl Related<super::forum_post::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ForumPost.def()
+    }
+}
+
+impl Related<super::forum_post_revision::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ForumPostRevision.def()
+    }
+}
+
 impl Related<super::message::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Message.def()
@@ -126,6 +152,12 @@ impl Related<super::page_attribution::Entity> for Entity {
     }
 }
 
+impl Related<super::page_draft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageDraft.def()
+    }
+}
+
 impl Related<super::page_lock::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageLock.def()
@@ -138,10 +170,28 @@ impl Related<super::page_revision::Entity> for Entity {
     }
 }
 
+impl Related<super::page_schedule::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageSchedule.def()
+    }
+}
+
 impl Related<super::session::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Session.def()
     }
 }
 
+impl Related<super::site_backup::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SiteBackup.def()
+    }
+}
+
+impl Related<super::user_device::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserDevice.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -20,6 +20,7 @@
 
 use super::prelude::*;
 use crate::models::user::{self, Entity as User, Model as UserModel};
+use crate::services::session::ValidateSession;
 use crate::services::{MfaService, PasswordService, SessionService};
 
 #[derive(Debug)]
@@ -58,13 +59,24 @@ impl AuthenticationService {
         MultiFactorAuthenticateUser {
             session_token,
             totp_or_code,
+            ip_address,
+            user_agent,
         }: MultiFactorAuthenticateUser<'_>,
     ) -> Result<UserModel> {
         // Get associated user model from the session
         //
         // Requires the session is restricted, meaning they are
         // in the middle of logging in still
-        let user = SessionService::get_user(ctx, session_token, true).await?;
+        let user = SessionService::get_user(
+            ctx,
+            ValidateSession {
+                session_token,
+                ip_address: Some(ip_address),
+                user_agent: Some(user_agent),
+            },
+            true,
+        )
+        .await?;
 
         // Process input, verifying depending on type
         match totp_or_code.parse() {
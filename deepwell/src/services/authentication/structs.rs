@@ -52,6 +52,8 @@ pub struct LoginUserOutput {
 pub struct MultiFactorAuthenticateUser<'a> {
     pub session_token: &'a str,
     pub totp_or_code: &'a str,
+    pub ip_address: IpAddr,
+    pub user_agent: &'a str,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -18,9 +18,9 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-//! The blob service, for interfacing with content-addressable S3 objects.
+//! The blob service, for interfacing with content-addressable stored objects.
 //!
-//! This is essentially just a wrapper for how DEEPWELL interacts with S3.
+//! This is essentially just a wrapper for how DEEPWELL interacts with blob storage.
 //! Method implementations should instead work with the relevant concept
 //! service instead, for instance the `FileService`.
 
@@ -19,10 +19,13 @@
  */
 
 use super::prelude::*;
-use crate::hash::slice_to_blob_hash;
+use crate::hash::{slice_to_blob_hash, HashVerification, BLOB_HASH_LENGTH};
 use crate::models::blob_pending::{
     self, Entity as BlobPending, Model as BlobPendingModel,
 };
+use crate::models::file_revision::{self, Entity as FileRevision};
+use crate::models::user::{self, Entity as User};
+use crate::services::StorageQuotaService;
 use crate::utils::assert_is_csprng;
 use bytes::Bytes;
 use cuid2::cuid;
@@ -31,17 +34,17 @@ use rand::thread_rng;
 use s3::request::request_trait::ResponseData;
 use s3::serde_types::HeadObjectResult;
 use sea_orm::TransactionTrait;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str;
 use std::sync::Arc;
-use time::format_description::well_known::Rfc2822;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
 use time::{Duration, OffsetDateTime};
 
 /// Hash for empty blobs.
 ///
 /// Even though it is not the SHA-512 hash, for simplicity we treat the hash
 /// value with all zeroes to be the blob address for the empty blob.
-/// This empty blob is not actually stored in S3 but instead is a "virtual blob",
+/// This empty blob is not actually stored anywhere but instead is a "virtual blob",
 /// considered to have always been present in `BlobService`.
 pub const EMPTY_BLOB_HASH: BlobHash = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -57,14 +60,20 @@ pub const EMPTY_BLOB_MIME: &str = "inode/x-empty; charset=binary";
 /// Timestamp is 2019/01/18 at midnight, the date of the first Wikijump commit.
 pub const EMPTY_BLOB_TIMESTAMP: i64 = 1547769600;
 
-/// The subdirectory in the S3 bucket where all pending uploads are kept.
+/// The subdirectory in blob storage where all pending uploads are kept.
 pub const PRESIGN_DIRECTORY: &str = "uploads";
 
+/// How long an unreferenced blob is kept before it can be pruned.
+///
+/// Blobs are created before the rows referencing them are committed,
+/// so recent blobs may still be in use by a request in progress.
+const BLOB_PRUNE_GRACE_PERIOD: Duration = Duration::hours(6);
+
 #[derive(Debug)]
 pub struct BlobService;
 
 impl BlobService {
-    /// Creates an S3 presign URL to allow an end user to upload a blob.
+    /// Creates a presign URL to allow an end user to upload a blob.
     /// This is the start to the upload process for any kind of file.
     ///
     /// # Returns
@@ -88,7 +97,10 @@ impl BlobService {
             return Err(Error::BlobTooBig);
         }
 
-        // Generate primary key and random S3 path
+        // Ensure the user has room for it
+        StorageQuotaService::check_user_upload(ctx, user_id, blob_size).await?;
+
+        // Generate primary key and random upload path
         let pending_blob_id = cuid();
         let s3_path = {
             let mut path = format!("{PRESIGN_DIRECTORY}/");
@@ -109,9 +121,9 @@ impl BlobService {
         info!("Creating presign upload URL for blob at path {s3_path} with primary key {pending_blob_id}");
 
         // Create presign URL
-        let bucket = ctx.s3_bucket();
-        let presign_url = bucket
-            .presign_put(&s3_path, config.presigned_expiry_secs, None, None)
+        let presign_url = ctx
+            .blob_storage()
+            .presign_upload(&s3_path, config.presigned_expiry_secs)
             .await?;
 
         // Get timestamps
@@ -188,27 +200,27 @@ impl BlobService {
             Self::get_pending_blob_path(ctx, user_id, pending_blob_id).await?;
 
         BlobPending::delete_by_id(pending_blob_id).exec(txn).await?;
-
-        if Self::head(ctx, &s3_path).await?.is_some() {
-            let bucket = ctx.s3_bucket();
-            bucket.delete_object(&s3_path).await?;
-        }
+        ctx.blob_storage().delete(&s3_path).await?;
 
         Ok(())
     }
 
     /// Helper function to do the actual "move" step of blob finalization.
-    /// This is where, after uploading to the presign URL, the S3 object is
+    /// This is where, after uploading to the presign URL, the object is
     /// then moved to its permanent location with a hashed name.
     ///
-    /// NOTE: Because S3 changes cannot be rolled back on error, we are
+    /// NOTE: Because storage changes cannot be rolled back on error, we are
     ///       creating a separate transaction here so that `blob_pending`
     ///       changes are persistent even if the outer request fails.
+    ///
+    ///       The site's storage quota is checked in the outer transaction,
+    ///       so that its lock on the site is held until the request finishes.
     async fn move_uploaded(
         ctx: &ServiceContext<'_>,
         pending_blob_id: &str,
         s3_path: &str,
         expected_length: usize,
+        site_id: Option<i64>,
     ) -> Result<FinalizeBlobUploadOutput> {
         let state = ctx.state();
         let db_state = Arc::clone(&state);
@@ -217,10 +229,12 @@ impl BlobService {
         let txn = db_state.database.begin().await?;
         let inner_ctx = ServiceContext::new(&state, &txn);
         let result = Self::move_uploaded_inner(
+            ctx,
             &inner_ctx,
             pending_blob_id,
             s3_path,
             expected_length,
+            site_id,
         )
         .await;
 
@@ -230,27 +244,23 @@ impl BlobService {
     }
 
     async fn move_uploaded_inner(
+        outer_ctx: &ServiceContext<'_>,
         ctx: &ServiceContext<'_>,
         pending_blob_id: &str,
         s3_path: &str,
         expected_length: usize,
+        site_id: Option<i64>,
     ) -> Result<FinalizeBlobUploadOutput> {
-        let bucket = ctx.s3_bucket();
+        let storage = ctx.blob_storage();
         let txn = ctx.transaction();
 
-        debug!("Download uploaded blob from S3 uploads to get metadata");
-        let response = bucket.get_object(s3_path).await?;
-        let data: Vec<u8> = match response.status_code() {
-            200 => response.into(),
-            404 => {
+        debug!("Download uploaded blob from uploads to get metadata");
+        let data = match storage.get(s3_path).await? {
+            Some(data) => data,
+            None => {
                 error!("No blob uploaded at presign path {s3_path}");
                 return Err(Error::BlobNotUploaded);
             }
-            _ => {
-                error!("Unable to retrieve uploaded blob at {s3_path} from S3");
-                let error = s3_error(&response, "finalizing uploaded blob")?;
-                return Err(error);
-            }
         };
 
         if expected_length != data.len() {
@@ -259,7 +269,7 @@ impl BlobService {
                 expected_length,
                 data.len(),
             );
-            bucket.delete_object(&s3_path).await?;
+            storage.delete(s3_path).await?;
             return Err(Error::BlobSizeMismatch);
         }
 
@@ -274,7 +284,7 @@ impl BlobService {
             });
         }
 
-        debug!("Updating blob metadata in database and S3");
+        debug!("Updating blob metadata in database and storage");
 
         // Convert size to correct integer type
         let size: i64 = data.len().try_into().expect("Buffer size exceeds i64");
@@ -282,25 +292,38 @@ impl BlobService {
         let hash = sha512_hash(&data);
         let hex_hash = blob_hash_to_hex(&hash);
 
+        // Ensure the site has room for the blob before it is stored.
+        //
+        // If not, then the upload is discarded entirely, so that it
+        // no longer counts towards the user's pending uploads.
+        if let Some(site_id) = site_id {
+            if let Err(error) =
+                StorageQuotaService::check_site_upload(outer_ctx, site_id, &hash, size)
+                    .await
+            {
+                warn!("Site cannot accept blob, deleting pending upload: {error}");
+                storage.delete(s3_path).await?;
+                BlobPending::delete_by_id(pending_blob_id).exec(txn).await?;
+                return Err(error);
+            }
+        }
+
         // If the blob exists, then just delete the uploaded one.
         //
-        // If it doesn't, then we need to move it. However, within
-        // S3 we cannot "move" objects, we have to upload and delete the original.
+        // If it doesn't, then we need to move it. However, storage backends
+        // cannot "move" objects, we have to upload and delete the original.
 
-        let result = match Self::head(ctx, &hex_hash).await? {
+        let result = match storage.head(&hex_hash).await? {
             // Blob exists, copy metadata and return that
-            Some(result) => {
+            Some(object) => {
                 debug!("Blob with hash {hex_hash} already exists");
 
                 // TODO: Should we ever update the mime type?
                 //       In case of changing file formats, etc.
 
-                // Content-Type header should be returned
-                let mime = result.content_type.ok_or(Error::S3Response)?;
-
                 Ok(FinalizeBlobUploadOutput {
                     hash,
-                    mime,
+                    mime: object.mime,
                     size,
                     created: false,
                 })
@@ -313,24 +336,18 @@ impl BlobService {
                 // Determine MIME type for the new blob
                 let mime = ctx.mime().get_mime_type(data.to_vec()).await?;
 
-                // Upload S3 object to final destination
-                let response = bucket
-                    .put_object_with_content_type(&hex_hash, &data, &mime)
-                    .await?;
-
-                // We assume all unexpected statuses are errors, even if 1XX or 2XX
-                match response.status_code() {
-                    200 => Ok(FinalizeBlobUploadOutput {
-                        hash,
-                        mime,
-                        size,
-                        created: true,
-                    }),
-                    _ => s3_error(&response, "creating final S3 blob")?,
-                }
+                // Upload object to final destination
+                storage.put(&hex_hash, &data, &mime).await?;
+
+                Ok(FinalizeBlobUploadOutput {
+                    hash,
+                    mime,
+                    size,
+                    created: true,
+                })
             }
         };
-        bucket.delete_object(&s3_path).await?;
+        storage.delete(s3_path).await?;
 
         // Update pending blob with hash
         let model = blob_pending::ActiveModel {
@@ -344,10 +361,15 @@ impl BlobService {
         result
     }
 
+    /// Finishes an upload, moving the blob to its permanent location.
+    ///
+    /// If the blob is being added to a site, then its ID should be passed,
+    /// so that the site's storage quota is checked before the blob is stored.
     pub async fn finish_upload(
         ctx: &ServiceContext<'_>,
         user_id: i64,
         pending_blob_id: &str,
+        site_id: Option<i64>,
     ) -> Result<FinalizeBlobUploadOutput> {
         info!("Finishing upload for blob for pending ID {pending_blob_id}");
 
@@ -364,8 +386,14 @@ impl BlobService {
                     .try_into()
                     .map_err(|_| Error::BlobSizeMismatch)?;
 
-                Self::move_uploaded(ctx, pending_blob_id, &s3_path, expected_length)
-                    .await?
+                Self::move_uploaded(
+                    ctx,
+                    pending_blob_id,
+                    &s3_path,
+                    expected_length,
+                    site_id,
+                )
+                .await?
             }
 
             // Already moved
@@ -375,6 +403,11 @@ impl BlobService {
 
                 debug_assert_eq!(expected_length, size);
 
+                if let Some(site_id) = site_id {
+                    StorageQuotaService::check_site_upload(ctx, site_id, &hash_vec, size)
+                        .await?;
+                }
+
                 FinalizeBlobUploadOutput {
                     hash: slice_to_blob_hash(&hash_vec),
                     mime,
@@ -388,6 +421,59 @@ impl BlobService {
         Ok(output)
     }
 
+    /// Uploads a blob directly, bypassing the presign upload process.
+    ///
+    /// This is only meant for internal use, such as restoring site backups,
+    /// where the blob data is already held by the server.
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        data: &[u8],
+    ) -> Result<FinalizeBlobUploadOutput> {
+        // Special handling for empty blobs
+        if data.is_empty() {
+            debug!("File being created is empty, special case");
+            return Ok(FinalizeBlobUploadOutput {
+                hash: EMPTY_BLOB_HASH,
+                mime: str!(EMPTY_BLOB_MIME),
+                size: 0,
+                created: false,
+            });
+        }
+
+        let size: i64 = data.len().try_into().expect("Buffer size exceeds i64");
+        let hash = sha512_hash(data);
+        let hex_hash = blob_hash_to_hex(&hash);
+        info!("Creating blob with hash {hex_hash} directly ({size} bytes)");
+
+        let storage = ctx.blob_storage();
+        match storage.head(&hex_hash).await? {
+            // Blob exists, nothing to upload
+            Some(object) => {
+                debug!("Blob with hash {hex_hash} already exists");
+
+                Ok(FinalizeBlobUploadOutput {
+                    hash,
+                    mime: object.mime,
+                    size,
+                    created: false,
+                })
+            }
+
+            // Blob doesn't exist, upload it
+            None => {
+                let mime = ctx.mime().get_mime_type(data.to_vec()).await?;
+                storage.put(&hex_hash, data, &mime).await?;
+
+                Ok(FinalizeBlobUploadOutput {
+                    hash,
+                    mime,
+                    size,
+                    created: true,
+                })
+            }
+        }
+    }
+
     pub async fn get_optional(
         ctx: &ServiceContext<'_>,
         hash: &[u8],
@@ -398,15 +484,9 @@ impl BlobService {
             return Ok(Some(Vec::new()));
         }
 
-        // Retrieve blob from S3
-        let bucket = ctx.s3_bucket();
+        // Retrieve blob from storage
         let hex_hash = blob_hash_to_hex(hash);
-        let response = bucket.get_object(&hex_hash).await?;
-        match response.status_code() {
-            200 => Ok(Some(response.into())),
-            404 => Ok(None),
-            _ => s3_error(&response, "fetching S3 blob"),
-        }
+        ctx.blob_storage().get(&hex_hash).await
     }
 
     #[inline]
@@ -428,28 +508,19 @@ impl BlobService {
             }));
         }
 
-        // Retrieve metadata from S3
+        // Retrieve metadata from storage
         let hex_hash = blob_hash_to_hex(hash);
-        match Self::head(ctx, &hex_hash).await? {
-            None => Ok(None),
-            Some(result) => {
-                // Headers should be passed in
-                let size = result.content_length.ok_or(Error::S3Response)?;
-                let mime = result.content_type.ok_or(Error::S3Response)?;
-                let created_at = {
-                    let timestamp = result.last_modified.ok_or(Error::S3Response)?;
-
-                    OffsetDateTime::parse(&timestamp, &Rfc2822)
-                        .map_err(|_| Error::S3Response)?
-                };
-
-                Ok(Some(BlobMetadata {
-                    mime,
-                    size,
-                    created_at,
-                }))
-            }
-        }
+        let metadata =
+            ctx.blob_storage()
+                .head(&hex_hash)
+                .await?
+                .map(|object| BlobMetadata {
+                    mime: object.mime,
+                    size: object.size,
+                    created_at: object.last_modified,
+                });
+
+        Ok(metadata)
     }
 
     #[inline]
@@ -468,9 +539,9 @@ impl BlobService {
             return Ok(true);
         }
 
-        // Fetch existence from S3
+        // Fetch existence from storage
         let hex_hash = blob_hash_to_hex(hash);
-        let result = Self::head(ctx, &hex_hash).await?;
+        let result = ctx.blob_storage().head(&hex_hash).await?;
         Ok(result.is_some())
     }
 
@@ -493,24 +564,130 @@ impl BlobService {
         }
     }
 
-    async fn head(
+    /// Deletes all stored blobs which are not referenced by anything.
+    ///
+    /// Returns the number of blobs which were removed.
+    pub async fn prune(ctx: &ServiceContext<'_>) -> Result<u64> {
+        info!("Pruning unused blobs from storage");
+
+        let referenced = Self::get_referenced(ctx).await?;
+        let cutoff = now() - BLOB_PRUNE_GRACE_PERIOD;
+        let mut pruned = 0;
+
+        for (hash, last_modified) in Self::list_stored(ctx).await? {
+            if referenced.contains(hash.as_slice()) {
+                continue;
+            }
+
+            // Keep if too recent, or if we don't know when it was uploaded
+            if !matches!(last_modified, Some(timestamp) if timestamp < cutoff) {
+                debug!("Skipping recent unused blob {}", blob_hash_to_hex(&hash));
+                continue;
+            }
+
+            debug!("Pruning unused blob {}", blob_hash_to_hex(&hash));
+            Self::hard_delete(ctx, &hash).await?;
+            pruned += 1;
+        }
+
+        debug!("Pruned {pruned} unused blobs");
+        Ok(pruned)
+    }
+
+    /// Checks that all stored blobs match their hashes,
+    /// and that all referenced blobs are present.
+    pub async fn verify(ctx: &ServiceContext<'_>) -> Result<HashVerification> {
+        info!("Verifying hashes of all stored blobs");
+
+        let mut output = HashVerification::default();
+        let mut stored = HashSet::new();
+
+        for (hash, _) in Self::list_stored(ctx).await? {
+            output.checked += 1;
+            stored.insert(hash);
+
+            // Could have been pruned in the meantime
+            let Some(data) = Self::get_optional(ctx, &hash).await? else {
+                continue;
+            };
+
+            if sha512_hash(&data) != hash {
+                let hex_hash = blob_hash_to_hex(&hash);
+                error!("Blob {hex_hash} does not match its hash");
+                output.mismatched.push(hex_hash.to_string());
+            }
+        }
+
+        for hash in Self::get_referenced(ctx).await? {
+            if hash != EMPTY_BLOB_HASH && !stored.contains(hash.as_slice()) {
+                let hex_hash = blob_hash_to_hex(&hash);
+                error!("Blob {hex_hash} is referenced but not present in storage");
+                output.missing.push(hex_hash.to_string());
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Lists all the blobs in storage, with their last modified time (if known).
+    ///
+    /// This excludes anything in a subdirectory, such as pending uploads.
+    async fn list_stored(
         ctx: &ServiceContext<'_>,
-        path: &str,
-    ) -> Result<Option<HeadObjectResult>> {
-        let bucket = ctx.s3_bucket();
-        let (result, status) = bucket.head_object(path).await?;
-
-        match status {
-            200 | 204 => Ok(Some(result)),
-            404 => Ok(None),
-            _ => {
-                let response = ResponseData::new(Bytes::new(), status, HashMap::new());
-                s3_error(&response, "heading S3 blob")
+    ) -> Result<Vec<(BlobHash, Option<OffsetDateTime>)>> {
+        let objects = ctx.blob_storage().list().await?;
+        let mut blobs = Vec::new();
+
+        for (path, last_modified) in objects {
+            let mut hash = [0; BLOB_HASH_LENGTH];
+            if hex::decode_to_slice(&path, &mut hash).is_err() {
+                debug!("Ignoring non-blob object '{path}'");
+                continue;
             }
+
+            blobs.push((hash, last_modified));
         }
+
+        Ok(blobs)
+    }
+
+    /// Gets the hashes of all blobs which are referenced by rows in the database.
+    async fn get_referenced(ctx: &ServiceContext<'_>) -> Result<HashSet<Vec<u8>>> {
+        let txn = ctx.transaction();
+        let mut hashes = HashSet::new();
+
+        let file_hashes: Vec<Vec<u8>> = FileRevision::find()
+            .select_only()
+            .column(file_revision::Column::S3Hash)
+            .distinct()
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let avatar_hashes: Vec<Option<Vec<u8>>> = User::find()
+            .select_only()
+            .column(user::Column::AvatarS3Hash)
+            .filter(user::Column::AvatarS3Hash.is_not_null())
+            .distinct()
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        // Blobs which have been uploaded, but not used yet
+        let pending_hashes: Vec<Option<Vec<u8>>> = BlobPending::find()
+            .select_only()
+            .column(blob_pending::Column::S3Hash)
+            .filter(blob_pending::Column::S3Hash.is_not_null())
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        hashes.extend(file_hashes);
+        hashes.extend(avatar_hashes.into_iter().flatten());
+        hashes.extend(pending_hashes.into_iter().flatten());
+        Ok(hashes)
     }
 
-    #[allow(dead_code)] // TEMP
     pub async fn hard_delete(ctx: &ServiceContext<'_>, hash: &[u8]) -> Result<()> {
         // Special handling for empty blobs
         //
@@ -521,37 +698,12 @@ impl BlobService {
             return Ok(());
         }
 
-        // Delete from S3
-        let bucket = ctx.s3_bucket();
+        // Delete from storage
         let hex_hash = blob_hash_to_hex(hash);
-
-        let response = bucket.delete_object(&hex_hash).await?;
-        match response.status_code() {
-            204 => Ok(()),
-            _ => s3_error(&response, "hard-deleting S3 blob"),
-        }
+        ctx.blob_storage().delete(&hex_hash).await
     }
 }
 
-/// Helper method to parse out an S3 error response and print the message (if any).
-fn s3_error<T>(response: &ResponseData, action: &str) -> Result<T> {
-    let error_message = match str::from_utf8(response.bytes()) {
-        Ok("") => "(no content)",
-        Ok(m) => m,
-        Err(_) => "(invalid UTF-8)",
-    };
-
-    error!(
-        "Error while {} (HTTP {}): {}",
-        action,
-        response.status_code(),
-        error_message,
-    );
-
-    // TODO replace with S3 backend-specific error
-    Err(Error::S3Response)
-}
-
 #[derive(Debug)]
 struct PendingBlob {
     s3_path: String,
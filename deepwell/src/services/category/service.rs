@@ -19,20 +19,39 @@
  */
 
 use super::prelude::*;
+use crate::models::page::{self, Entity as Page, Model as PageModel};
 use crate::models::page_category::{
     self, Entity as PageCategory, Model as PageCategoryModel,
 };
+use crate::models::sea_orm_active_enums::UserType;
+use crate::services::page::MovePage;
+use crate::services::relation::GetSiteMember;
+use crate::services::{JobService, PageService, RelationService, UserService};
+use crate::utils::split_category;
+use wikidot_normalize::normalize;
 
 #[derive(Debug)]
 pub struct CategoryService;
 
 impl CategoryService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        mut slug: String,
+        body: UpdateCategoryBody,
+    ) -> Result<PageCategoryModel> {
+        Self::normalize_slug(&mut slug)?;
+        Self::check_conflicts(ctx, site_id, &slug, "create").await?;
+
+        let category = Self::create_inner(ctx, site_id, &slug).await?;
+        Self::update(ctx, site_id, Reference::Id(category.category_id), body).await
+    }
+
     /// Internal method to create a category.
     ///
-    /// In addition to only returning the bare ID,
-    /// it also does not check for conflicts before
-    /// attempting to insert.
-    async fn create(
+    /// Unlike `create()`, this does not normalize the slug
+    /// or check for conflicts before attempting to insert.
+    async fn create_inner(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         slug: &str,
@@ -88,7 +107,7 @@ impl CategoryService {
         let category =
             match Self::get_optional(ctx, site_id, Reference::from(slug)).await? {
                 Some(category) => category,
-                None => Self::create(ctx, site_id, slug).await?,
+                None => Self::create_inner(ctx, site_id, slug).await?,
             };
 
         Ok(category)
@@ -108,4 +127,260 @@ impl CategoryService {
 
         Ok(categories)
     }
+
+    pub async fn update(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+        input: UpdateCategoryBody,
+    ) -> Result<PageCategoryModel> {
+        let txn = ctx.transaction();
+        let category = Self::get(ctx, site_id, reference).await?;
+        let mut model = page_category::ActiveModel {
+            category_id: Set(category.category_id),
+            ..Default::default()
+        };
+
+        if let Maybe::Set(layout) = input.layout {
+            model.layout = Set(layout.map(|l| str!(l.value())));
+        }
+
+        if let Maybe::Set(template_page) = input.template_page {
+            model.template_page = Set(template_page.map(|mut slug| {
+                normalize(&mut slug);
+                slug
+            }));
+        }
+
+        if let Maybe::Set(license) = input.license {
+            model.license = Set(license);
+        }
+
+        if let Maybe::Set(permissions) = input.permissions {
+            model.permissions = Set(serde_json::to_value(permissions)?);
+        }
+
+        let mut score_changed = false;
+        if let Maybe::Set(score_type) = input.score_type {
+            let score_type = score_type.map(|t| str!(t.name()));
+            score_changed = category.score_type != score_type;
+            model.score_type = Set(score_type);
+        }
+
+        if let Maybe::Set(hidden) = input.hidden {
+            model.hidden = Set(hidden);
+        }
+
+        // Update category
+        model.updated_at = Set(Some(now()));
+        let category = model.update(txn).await?;
+
+        // Page scores depend on the category's rating scheme
+        if score_changed {
+            JobService::queue_refresh_scores(ctx, site_id).await?;
+        }
+
+        Ok(category)
+    }
+
+    /// Renames a category, moving all of its pages along with it.
+    ///
+    /// Each page which is moved leaves behind a redirect from its old slug.
+    pub async fn rename(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+        mut new_slug: String,
+        user_id: i64,
+        revision_comments: String,
+    ) -> Result<RenameCategoryOutput> {
+        let txn = ctx.transaction();
+        let category = Self::get(ctx, site_id, reference).await?;
+        if category.slug == "_default" {
+            error!("Cannot rename the default category in site ID {site_id}");
+            return Err(Error::PageCategoryDefault);
+        }
+
+        Self::normalize_slug(&mut new_slug)?;
+        if new_slug == "_default" {
+            error!("Cannot rename category to the default category in site ID {site_id}");
+            return Err(Error::PageCategoryDefault);
+        }
+
+        Self::check_conflicts(ctx, site_id, &new_slug, "rename").await?;
+
+        // Update the category itself first, so moved pages land in it
+        let old_slug = category.slug;
+        let model = page_category::ActiveModel {
+            category_id: Set(category.category_id),
+            slug: Set(new_slug.clone()),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        // Move each page into the renamed category
+        let pages = Page::find()
+            .filter(page::Column::PageCategoryId.eq(category.category_id))
+            .order_by_asc(page::Column::PageId)
+            .all(txn)
+            .await?;
+
+        let mut pages_moved = 0;
+        for page in pages {
+            let (_, page_slug) = split_category(&page.slug);
+            let page_new_slug = format!("{new_slug}:{page_slug}");
+
+            // Deleted pages have no revision to add, simply update the slug
+            if page.deleted_at.is_some() {
+                let model = page::ActiveModel {
+                    page_id: Set(page.page_id),
+                    slug: Set(page_new_slug),
+                    ..Default::default()
+                };
+                model.update(txn).await?;
+                continue;
+            }
+
+            PageService::r#move(
+                ctx,
+                MovePage {
+                    site_id,
+                    page: Reference::Id(page.page_id),
+                    last_revision_id: page.latest_revision_id.unwrap_or_default(),
+                    new_slug: page_new_slug,
+                    revision_comments: revision_comments.clone(),
+                    user_id,
+                },
+            )
+            .await?;
+
+            PageService::add_redirect(ctx, site_id, &page.slug, page.page_id).await?;
+            pages_moved += 1;
+        }
+
+        Ok(RenameCategoryOutput {
+            old_slug,
+            new_slug,
+            pages_moved,
+        })
+    }
+
+    /// Deletes a category.
+    ///
+    /// Only categories without any pages, including deleted ones, may be deleted.
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let category = Self::get(ctx, site_id, reference).await?;
+        if category.slug == "_default" {
+            error!("Cannot delete the default category in site ID {site_id}");
+            return Err(Error::PageCategoryDefault);
+        }
+
+        let page_count = Page::find()
+            .filter(page::Column::PageCategoryId.eq(category.category_id))
+            .count(txn)
+            .await?;
+
+        if page_count > 0 {
+            error!(
+                "Cannot delete category ID {}, it still has {} pages",
+                category.category_id, page_count,
+            );
+            return Err(Error::PageCategoryNotEmpty);
+        }
+
+        category.delete(txn).await?;
+        Ok(())
+    }
+
+    /// Checks that a user may perform an action in this category.
+    ///
+    /// Fails with `Error::CategoryPermissionDenied` if the category
+    /// restricts this action to users the given one is not among.
+    pub async fn check_permission(
+        ctx: &ServiceContext<'_>,
+        category: &PageCategoryModel,
+        action: CategoryAction,
+        user_id: i64,
+    ) -> Result<()> {
+        let permissions: CategoryPermissions =
+            serde_json::from_value(category.permissions.clone())?;
+
+        let level = match permissions.level(action) {
+            Some(level) => level,
+            None => return Ok(()),
+        };
+
+        let site_id = category.site_id;
+        let user = UserService::get(ctx, Reference::Id(user_id)).await?;
+        let allowed = match (user.user_type, level) {
+            (UserType::Site | UserType::System, _) => true,
+            (_, PermissionLevel::Anyone) => true,
+            (user_type, PermissionLevel::Registered) => user_type == UserType::Regular,
+            (_, PermissionLevel::Members) => {
+                RelationService::site_member_exists(
+                    ctx,
+                    GetSiteMember { site_id, user_id },
+                )
+                .await?
+            }
+        };
+
+        if !allowed {
+            warn!(
+                "User ID {user_id} cannot {action:?} in category ID {} (requires {level:?})",
+                category.category_id,
+            );
+            return Err(Error::CategoryPermissionDenied);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a user may perform an action on a page, see `check_permission()`.
+    pub async fn check_page_permission(
+        ctx: &ServiceContext<'_>,
+        page: &PageModel,
+        action: CategoryAction,
+        user_id: i64,
+    ) -> Result<()> {
+        let category =
+            Self::get(ctx, page.site_id, Reference::Id(page.page_category_id)).await?;
+
+        Self::check_permission(ctx, &category, action, user_id).await
+    }
+
+    fn normalize_slug(slug: &mut String) -> Result<()> {
+        normalize(slug);
+        if slug.is_empty() || slug.contains(':') {
+            error!("Invalid category slug: '{slug}'");
+            return Err(Error::PageCategorySlugInvalid);
+        }
+
+        Ok(())
+    }
+
+    async fn check_conflicts(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+        action: &str,
+    ) -> Result<()> {
+        if let Some(category) =
+            Self::get_optional(ctx, site_id, Reference::Slug(cow!(slug))).await?
+        {
+            error!(
+                "Category {} with slug '{}' already exists on site ID {}, cannot {}",
+                category.category_id, slug, site_id, action,
+            );
+            return Err(Error::PageCategoryExists);
+        }
+
+        Ok(())
+    }
 }
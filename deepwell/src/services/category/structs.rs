@@ -18,10 +18,115 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::types::Reference;
+use crate::services::score::ScoreType;
+use crate::types::{Maybe, Reference};
+use ftml::layout::Layout;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetCategory<'a> {
     pub site: Reference<'a>,
     pub category: Reference<'a>,
 }
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateCategory<'a> {
+    pub site: Reference<'a>,
+    pub slug: String,
+
+    #[serde(flatten)]
+    pub body: UpdateCategoryBody,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateCategory<'a> {
+    pub site: Reference<'a>,
+    pub category: Reference<'a>,
+
+    #[serde(flatten)]
+    pub body: UpdateCategoryBody,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct UpdateCategoryBody {
+    pub layout: Maybe<Option<Layout>>,
+    pub template_page: Maybe<Option<String>>,
+    pub license: Maybe<Option<String>>,
+    pub permissions: Maybe<CategoryPermissions>,
+    pub score_type: Maybe<Option<ScoreType>>,
+    pub hidden: Maybe<bool>,
+}
+
+/// Per-category overrides for who may perform page actions.
+///
+/// Any action left unset is not restricted by the category.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct CategoryPermissions {
+    pub create: Option<PermissionLevel>,
+    pub edit: Option<PermissionLevel>,
+    pub rename: Option<PermissionLevel>,
+    pub delete: Option<PermissionLevel>,
+    pub upload_files: Option<PermissionLevel>,
+    pub comment: Option<PermissionLevel>,
+}
+
+impl CategoryPermissions {
+    pub fn level(&self, action: CategoryAction) -> Option<PermissionLevel> {
+        match action {
+            CategoryAction::Create => self.create,
+            CategoryAction::Edit => self.edit,
+            CategoryAction::Rename => self.rename,
+            CategoryAction::Delete => self.delete,
+            CategoryAction::UploadFiles => self.upload_files,
+            CategoryAction::Comment => self.comment,
+        }
+    }
+}
+
+/// Who may perform an action restricted by `CategoryPermissions`.
+///
+/// Internal site and system users are always permitted.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionLevel {
+    /// Any user, including bots.
+    Anyone,
+
+    /// Only regular users, excluding bots.
+    Registered,
+
+    /// Only members of the site.
+    Members,
+}
+
+/// A page action which categories can restrict.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CategoryAction {
+    Create,
+    Edit,
+    Rename,
+    Delete,
+    UploadFiles,
+    Comment,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RenameCategory<'a> {
+    pub site: Reference<'a>,
+    pub category: Reference<'a>,
+    pub new_slug: String,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub revision_comments: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RenameCategoryOutput {
+    pub old_slug: String,
+    pub new_slug: String,
+
+    /// How many pages were moved into the renamed category.
+    pub pages_moved: u64,
+}
@@ -23,9 +23,9 @@ use crate::config::Config;
 use crate::locales::Localizations;
 use crate::services::blob::MimeAnalyzer;
 use crate::services::error::Result;
+use crate::storage::BlobStorage;
 use redis::aio::MultiplexedConnection as RedisMultiplexedConnection;
 use rsmq_async::PooledRsmq;
-use s3::bucket::Bucket;
 use sea_orm::DatabaseTransaction;
 use std::sync::Arc;
 
@@ -88,8 +88,8 @@ impl<'txn> ServiceContext<'txn> {
     }
 
     #[inline]
-    pub fn s3_bucket(&self) -> &Bucket {
-        &self.state.s3_bucket
+    pub fn blob_storage(&self) -> &dyn BlobStorage {
+        &*self.state.blob_storage
     }
 
     #[inline]
@@ -0,0 +1,42 @@
+/*
+ * services/diff/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The diff service, for comparing two revisions of a page or file.
+//!
+//! Wikitext and compiled HTML are compared line by line, and lines which
+//! were changed are further compared word by word. Other fields, such as
+//! the title or tags, are reported as before and after values.
+//!
+//! The output is structured so that it can be rendered by the frontend.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+    pub use super::text::diff_text;
+}
+
+mod service;
+mod structs;
+mod text;
+
+pub use self::service::DiffService;
+pub use self::structs::*;
+pub use self::text::diff_text;
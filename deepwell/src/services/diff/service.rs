@@ -0,0 +1,239 @@
+/*
+ * services/diff/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::file_revision::Model as FileRevisionModel;
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::services::file_revision::GetFileRevision;
+use crate::services::{FileRevisionService, PageRevisionService, TextService};
+
+#[derive(Debug)]
+pub struct DiffService;
+
+impl DiffService {
+    /// Compares two revisions of a page.
+    ///
+    /// Fields which are hidden in either revision are not compared.
+    pub async fn page_revisions(
+        ctx: &ServiceContext<'_>,
+        DiffPageRevisions {
+            site_id,
+            page_id,
+            from_revision,
+            to_revision,
+            compiled_html,
+            context_lines,
+        }: DiffPageRevisions,
+    ) -> Result<PageRevisionDiff> {
+        let (from, to) = try_join!(
+            PageRevisionService::get(ctx, site_id, page_id, from_revision),
+            PageRevisionService::get(ctx, site_id, page_id, to_revision),
+        )?;
+
+        let hidden = merge_hidden(&from.hidden, &to.hidden);
+        let is_hidden = |field| hidden.iter().any(|hidden| hidden == field);
+
+        let wikitext = if is_hidden("wikitext") {
+            None
+        } else {
+            let (from_wikitext, to_wikitext) = try_join!(
+                TextService::get(ctx, &from.wikitext_hash),
+                TextService::get(ctx, &to.wikitext_hash),
+            )?;
+
+            Some(diff_text(&from_wikitext, &to_wikitext, context_lines))
+        };
+
+        let compiled_html = if !compiled_html || is_hidden("compiled") {
+            None
+        } else {
+            let (from_html, to_html) = try_join!(
+                TextService::get(ctx, &from.compiled_hash),
+                TextService::get(ctx, &to.compiled_hash),
+            )?;
+
+            Some(diff_text(&from_html, &to_html, context_lines))
+        };
+
+        let PageRevisionModel {
+            revision_id: from_revision_id,
+            title: from_title,
+            alt_title: from_alt_title,
+            slug: from_slug,
+            tags: from_tags,
+            ..
+        } = from;
+
+        let PageRevisionModel {
+            revision_id: to_revision_id,
+            title: to_title,
+            alt_title: to_alt_title,
+            slug: to_slug,
+            tags: to_tags,
+            ..
+        } = to;
+
+        macro_rules! field {
+            ($name:expr, $from:expr, $to:expr $(,)?) => {
+                if is_hidden($name) {
+                    None
+                } else {
+                    FieldChange::new($from, $to)
+                }
+            };
+        }
+
+        let title = field!("title", from_title, to_title);
+        let alt_title = field!("alt_title", from_alt_title, to_alt_title);
+        let slug = field!("slug", from_slug, to_slug);
+        let tags = if is_hidden("tags") {
+            None
+        } else {
+            diff_tags(&from_tags, &to_tags)
+        };
+
+        Ok(PageRevisionDiff {
+            from_revision_id,
+            to_revision_id,
+            wikitext,
+            compiled_html,
+            title,
+            alt_title,
+            slug,
+            tags,
+            hidden,
+        })
+    }
+
+    /// Compares the metadata of two revisions of a file.
+    pub async fn file_revisions(
+        ctx: &ServiceContext<'_>,
+        DiffFileRevisions {
+            site_id,
+            page_id,
+            file_id,
+            from_revision,
+            to_revision,
+        }: DiffFileRevisions,
+    ) -> Result<FileRevisionDiff> {
+        let get_revision = |revision_number| {
+            FileRevisionService::get(
+                ctx,
+                GetFileRevision {
+                    site_id,
+                    page_id,
+                    file_id,
+                    revision_number,
+                },
+            )
+        };
+
+        let (from, to) =
+            try_join!(get_revision(from_revision), get_revision(to_revision))?;
+        let hidden = merge_hidden(&from.hidden, &to.hidden);
+        let is_hidden = |field| hidden.iter().any(|hidden| hidden == field);
+
+        let FileRevisionModel {
+            revision_id: from_revision_id,
+            name: from_name,
+            s3_hash: from_hash,
+            mime_hint: from_mime,
+            size_hint: from_size,
+            licensing: from_licensing,
+            ..
+        } = from;
+
+        let FileRevisionModel {
+            revision_id: to_revision_id,
+            name: to_name,
+            s3_hash: to_hash,
+            mime_hint: to_mime,
+            size_hint: to_size,
+            licensing: to_licensing,
+            ..
+        } = to;
+
+        macro_rules! field {
+            ($name:expr, $from:expr, $to:expr $(,)?) => {
+                if is_hidden($name) {
+                    None
+                } else {
+                    FieldChange::new($from, $to)
+                }
+            };
+        }
+
+        let name = field!("name", from_name, to_name);
+        let mime = field!("mime", from_mime, to_mime);
+        let licensing = field!("licensing", from_licensing, to_licensing);
+
+        // The size would reveal information about a hidden blob
+        let (size, contents_changed) = if is_hidden("blob") {
+            (None, None)
+        } else {
+            (
+                field!("size", from_size, to_size),
+                Some(from_hash != to_hash),
+            )
+        };
+
+        Ok(FileRevisionDiff {
+            from_revision_id,
+            to_revision_id,
+            name,
+            mime,
+            size,
+            licensing,
+            contents_changed,
+            hidden,
+        })
+    }
+}
+
+/// Combines the hidden fields of both revisions, without duplicates.
+fn merge_hidden(from: &[String], to: &[String]) -> Vec<String> {
+    let mut hidden = Vec::new();
+    for field in from.iter().chain(to) {
+        if !hidden.contains(field) {
+            hidden.push(field.clone());
+        }
+    }
+    hidden
+}
+
+/// Finds which tags were added or removed, if any.
+fn diff_tags(from: &[String], to: &[String]) -> Option<TagsDiff> {
+    let added: Vec<String> = to
+        .iter()
+        .filter(|tag| !from.contains(tag))
+        .cloned()
+        .collect();
+    let removed: Vec<String> = from
+        .iter()
+        .filter(|tag| !to.contains(tag))
+        .cloned()
+        .collect();
+
+    if added.is_empty() && removed.is_empty() {
+        None
+    } else {
+        Some(TagsDiff { added, removed })
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ * services/diff/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DiffPageRevisions {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub from_revision: i32,
+    pub to_revision: i32,
+
+    /// Whether to also compare the compiled HTML of each revision.
+    #[serde(default)]
+    pub compiled_html: bool,
+
+    /// How many unchanged lines to keep around each change.
+    ///
+    /// If not set, then all unchanged lines are included.
+    #[serde(default)]
+    pub context_lines: Option<usize>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PageRevisionDiff {
+    pub from_revision_id: i64,
+    pub to_revision_id: i64,
+    pub wikitext: Option<TextDiff>,
+    pub compiled_html: Option<TextDiff>,
+    pub title: Option<FieldChange<String>>,
+    pub alt_title: Option<FieldChange<Option<String>>>,
+    pub slug: Option<FieldChange<String>>,
+    pub tags: Option<TagsDiff>,
+
+    /// Fields which are hidden in either revision, and so were not compared.
+    pub hidden: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DiffFileRevisions {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub file_id: i64,
+    pub from_revision: i32,
+    pub to_revision: i32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FileRevisionDiff {
+    pub from_revision_id: i64,
+    pub to_revision_id: i64,
+    pub name: Option<FieldChange<String>>,
+    pub mime: Option<FieldChange<String>>,
+    pub size: Option<FieldChange<i64>>,
+    pub licensing: Option<FieldChange<serde_json::Value>>,
+
+    /// Whether the file's contents are different between the revisions.
+    ///
+    /// This is `None` if the blob is hidden in either revision.
+    pub contents_changed: Option<bool>,
+
+    /// Fields which are hidden in either revision, and so were not compared.
+    pub hidden: Vec<String>,
+}
+
+/// A field which has a different value between two revisions.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange<T> {
+    pub from: T,
+    pub to: T,
+}
+
+impl<T: PartialEq> FieldChange<T> {
+    /// Returns a change only if the two values differ.
+    pub fn new(from: T, to: T) -> Option<Self> {
+        if from == to {
+            None
+        } else {
+            Some(FieldChange { from, to })
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TagsDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TextDiff {
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub lines: Vec<LineDiff>,
+}
+
+/// A line in a text diff.
+///
+/// Line numbers start at one, and refer to the line's position
+/// in the old or new text respectively.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum LineDiff {
+    Equal {
+        old_line: usize,
+        new_line: usize,
+        text: String,
+    },
+    Insert {
+        new_line: usize,
+        text: String,
+    },
+    Delete {
+        old_line: usize,
+        text: String,
+    },
+    Change {
+        old_line: usize,
+        new_line: usize,
+        words: Vec<WordDiff>,
+    },
+}
+
+/// A run of text within a changed line.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WordDiff {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
@@ -0,0 +1,292 @@
+/*
+ * services/diff/text.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Helpers for computing line and word diffs between two texts.
+//!
+//! The diffs themselves are computed by the `similar` crate, this
+//! groups them into lines and words in the form returned by the API.
+
+use super::structs::{DiffKind, LineDiff, TextDiff, WordDiff};
+use similar::{ChangeTag, DiffTag, TextDiff as SimilarDiff};
+use std::time::Duration;
+
+/// How long to spend searching for a minimal diff before giving up.
+///
+/// Past this point, a larger (but still correct) diff is produced instead.
+const DIFF_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Produces a line diff of two texts.
+///
+/// Each run of replaced lines is paired up, and those pairs are
+/// compared word by word. If `context` is set, then only that many
+/// unchanged lines are kept around each change.
+pub fn diff_text(old: &str, new: &str, context: Option<usize>) -> TextDiff {
+    // Split with lines() rather than using from_lines(), so that
+    // a missing trailing newline doesn't change the last line.
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let diff = SimilarDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_slices(&old_lines, &new_lines);
+
+    let ops = diff.ops();
+    let mut lines = Vec::new();
+    let mut lines_added = 0;
+    let mut lines_removed = 0;
+    let mut idx = 0;
+
+    while idx < ops.len() {
+        if ops[idx].tag() == DiffTag::Equal {
+            for (old, new) in ops[idx].old_range().zip(ops[idx].new_range()) {
+                lines.push(LineDiff::Equal {
+                    old_line: old + 1,
+                    new_line: new + 1,
+                    text: str!(old_lines[old]),
+                });
+            }
+            idx += 1;
+            continue;
+        }
+
+        // Collect the run of changed lines
+        let mut deleted = Vec::new();
+        let mut inserted = Vec::new();
+        while idx < ops.len() && ops[idx].tag() != DiffTag::Equal {
+            deleted.extend(ops[idx].old_range());
+            inserted.extend(ops[idx].new_range());
+            idx += 1;
+        }
+
+        lines_removed += deleted.len();
+        lines_added += inserted.len();
+
+        // Pair up replaced lines, leaving the remainder as-is
+        let paired = deleted.len().min(inserted.len());
+        for (&old, &new) in deleted.iter().zip(&inserted) {
+            lines.push(LineDiff::Change {
+                old_line: old + 1,
+                new_line: new + 1,
+                words: diff_words(old_lines[old], new_lines[new]),
+            });
+        }
+
+        for &old in &deleted[paired..] {
+            lines.push(LineDiff::Delete {
+                old_line: old + 1,
+                text: str!(old_lines[old]),
+            });
+        }
+
+        for &new in &inserted[paired..] {
+            lines.push(LineDiff::Insert {
+                new_line: new + 1,
+                text: str!(new_lines[new]),
+            });
+        }
+    }
+
+    if let Some(context) = context {
+        lines = trim_context(lines, context);
+    }
+
+    TextDiff {
+        lines_added,
+        lines_removed,
+        lines,
+    }
+}
+
+/// Produces a word diff of two lines.
+///
+/// Adjacent runs of the same kind are merged together.
+fn diff_words(old: &str, new: &str) -> Vec<WordDiff> {
+    let old_words = split_words(old);
+    let new_words = split_words(new);
+    let diff = SimilarDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_slices(&old_words, &new_words);
+
+    let mut words: Vec<WordDiff> = Vec::new();
+    for change in diff.iter_all_changes() {
+        let kind = match change.tag() {
+            ChangeTag::Equal => DiffKind::Equal,
+            ChangeTag::Delete => DiffKind::Delete,
+            ChangeTag::Insert => DiffKind::Insert,
+        };
+
+        match words.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(change.value()),
+            _ => words.push(WordDiff {
+                kind,
+                text: str!(change.value()),
+            }),
+        }
+    }
+
+    words
+}
+
+/// Splits a line into words, runs of whitespace, and individual symbols.
+fn split_words(text: &str) -> Vec<&str> {
+    #[derive(PartialEq, Eq)]
+    enum Class {
+        Word,
+        Space,
+        Symbol,
+    }
+
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut last_class = None;
+
+    for (idx, ch) in text.char_indices() {
+        let class = if ch.is_alphanumeric() {
+            Class::Word
+        } else if ch.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Symbol
+        };
+
+        if idx > start && (last_class.as_ref() != Some(&class) || class == Class::Symbol)
+        {
+            words.push(&text[start..idx]);
+            start = idx;
+        }
+
+        last_class = Some(class);
+    }
+
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+
+    words
+}
+
+/// Removes unchanged lines which are further than `context` lines from any change.
+fn trim_context(lines: Vec<LineDiff>, context: usize) -> Vec<LineDiff> {
+    let changes: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, LineDiff::Equal { .. }))
+        .map(|(idx, _)| idx)
+        .collect();
+
+    lines
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, line)| {
+            if !matches!(line, LineDiff::Equal { .. }) {
+                return true;
+            }
+
+            // Find the nearest changes before and after this line
+            let pos = changes.partition_point(|&change| change < *idx);
+            let after = changes.get(pos).is_some_and(|&c| c - idx <= context);
+            let before = pos > 0 && idx - changes[pos - 1] <= context;
+            after || before
+        })
+        .map(|(_, line)| line)
+        .collect()
+}
+
+#[test]
+fn test_diff_text() {
+    fn word(kind: DiffKind, text: &str) -> WordDiff {
+        WordDiff {
+            kind,
+            text: str!(text),
+        }
+    }
+
+    // Words
+    assert_eq!(
+        split_words("Hello, world!"),
+        ["Hello", ",", " ", "world", "!"]
+    );
+    assert_eq!(
+        diff_words("the quick fox", "the slow fox"),
+        [
+            word(DiffKind::Equal, "the "),
+            word(DiffKind::Delete, "quick"),
+            word(DiffKind::Insert, "slow"),
+            word(DiffKind::Equal, " fox"),
+        ],
+    );
+
+    // Lines
+    let diff = diff_text("a\nb\nc\nd\ne", "a\nB\nc\nd\ne\nf", Some(1));
+    assert_eq!(diff.lines_added, 2);
+    assert_eq!(diff.lines_removed, 1);
+    assert_eq!(
+        diff.lines,
+        [
+            LineDiff::Equal {
+                old_line: 1,
+                new_line: 1,
+                text: str!("a"),
+            },
+            LineDiff::Change {
+                old_line: 2,
+                new_line: 2,
+                words: vec![word(DiffKind::Delete, "b"), word(DiffKind::Insert, "B")],
+            },
+            LineDiff::Equal {
+                old_line: 3,
+                new_line: 3,
+                text: str!("c"),
+            },
+            LineDiff::Equal {
+                old_line: 5,
+                new_line: 5,
+                text: str!("e"),
+            },
+            LineDiff::Insert {
+                new_line: 6,
+                text: str!("f"),
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_diff_large() {
+    // Every other line changed
+    let old: String = (0..2000).map(|n| format!("line {n}\n")).collect();
+    let new: String = (0..2000)
+        .map(|n| {
+            if n % 2 == 0 {
+                format!("line {n}\n")
+            } else {
+                format!("changed {n}\n")
+            }
+        })
+        .collect();
+
+    let diff = diff_text(&old, &new, Some(0));
+    assert_eq!(diff.lines_added, 1000);
+    assert_eq!(diff.lines_removed, 1000);
+    assert_eq!(diff.lines.len(), 1000);
+    assert!(diff
+        .lines
+        .iter()
+        .all(|line| matches!(line, LineDiff::Change { .. })));
+}
@@ -18,13 +18,16 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::locales::{Localizations, MessageArguments};
 use filemagic::FileMagicError;
 use jsonrpsee::types::error::ErrorObjectOwned;
 use reqwest::Error as ReqwestError;
 use s3::error::S3Error;
 use sea_orm::{error::DbErr, TransactionError};
+use serde_json::json;
+use strum_macros::{EnumDiscriminants, EnumIter, IntoStaticStr};
 use thiserror::Error as ThisError;
-use unic_langid::LanguageIdentifierError;
+use unic_langid::{LanguageIdentifier, LanguageIdentifierError};
 
 pub use std::error::Error as StdError;
 
@@ -32,7 +35,15 @@ pub type StdResult<T, E> = std::result::Result<T, E>;
 pub type Result<T> = StdResult<T, Error>;
 
 /// Wrapper error for possible failure modes from service methods.
-#[derive(ThisError, Debug)]
+///
+/// Each variant has a corresponding `ErrorKind`, which gives its
+/// stable error code and translation key.
+#[derive(ThisError, EnumDiscriminants, Debug)]
+#[strum_discriminants(
+    name(ErrorKind),
+    derive(EnumIter, IntoStaticStr, Hash),
+    strum(serialize_all = "kebab-case")
+)]
 pub enum Error {
     // Error passed straight to ErrorObjectOwned without conversion
     #[error("{0}")]
@@ -77,6 +88,12 @@ pub enum Error {
     #[error("Serialization error: {0}")]
     Serde(#[from] serde_json::Error),
 
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Stored text could not be decoded")]
+    TextCorrupted,
+
     #[error("S3 service returned error: {0}")]
     S3Service(#[from] S3Error),
 
@@ -113,6 +130,11 @@ pub enum Error {
         session_user_id: i64,
     },
 
+    #[error(
+        "Session is being used from a different client than the one it was created for"
+    )]
+    SessionClientMismatch,
+
     #[error("A password is required")]
     EmptyPassword,
 
@@ -251,9 +273,135 @@ pub enum Error {
     #[error("Uploaded blob does not match expected length")]
     BlobSizeMismatch,
 
+    #[error("Blob upload URL is invalid or has expired")]
+    BlobUploadRejected,
+
+    #[error("Upload would exceed the user's storage quota")]
+    UserStorageQuotaExceeded,
+
+    #[error("Upload would exceed the site's storage quota")]
+    SiteStorageQuotaExceeded,
+
+    #[error("Storage quota cannot be negative")]
+    StorageQuotaInvalid,
+
     #[error("Text item does not exist")]
     TextNotFound,
 
+    #[error("Site backup does not exist")]
+    SiteBackupNotFound,
+
+    #[error("Site backup archive does not exist")]
+    SiteBackupArchiveNotFound,
+
+    #[error("Site backup archive is invalid or from an unsupported version")]
+    SiteBackupArchiveInvalid,
+
+    #[error("Forum group does not exist")]
+    ForumGroupNotFound,
+
+    #[error("Forum category does not exist")]
+    ForumCategoryNotFound,
+
+    #[error("Forum thread does not exist")]
+    ForumThreadNotFound,
+
+    #[error("Forum post does not exist")]
+    ForumPostNotFound,
+
+    #[error("Forum post revision does not exist")]
+    ForumPostRevisionNotFound,
+
+    #[error("Job does not exist in the queue")]
+    JobNotFound,
+
+    #[error("Page category slug is empty or invalid")]
+    PageCategorySlugInvalid,
+
+    #[error("Page category still has pages in it")]
+    PageCategoryNotEmpty,
+
+    #[error("Cannot perform this action on the default page category")]
+    PageCategoryDefault,
+
+    #[error("Scheduled publishing time is in the past")]
+    PageScheduleInPast,
+
+    #[error("Scheduled page change is no longer pending")]
+    PageScheduleNotPending,
+
+    #[error("Scheduled page edit does not change anything")]
+    PageScheduleEmpty,
+
+    #[error("Site is not published as a template")]
+    SiteNotTemplate,
+
+    #[error("Site cannot be joined this way")]
+    SiteJoinNotAllowed,
+
+    #[error("Site application has already been reviewed")]
+    SiteApplicationNotPending,
+
+    #[error("Site application answers do not match the site's questions")]
+    SiteApplicationAnswersMismatch,
+
+    #[error("Site invitation has expired, been revoked, or been used up")]
+    SiteInvitationExpired,
+
+    #[error("Site invitation expiry or use limit is invalid")]
+    SiteInvitationInvalid,
+
+    #[error("Page cannot be made a child of its own descendant")]
+    PageParentCycle,
+
+    #[error("Page hierarchy would exceed the maximum depth")]
+    PageParentTooDeep,
+
+    #[error("Tag does not exist")]
+    TagNotFound,
+
+    #[error("Tag group does not exist")]
+    TagGroupNotFound,
+
+    #[error("Page draft does not exist")]
+    PageDraftNotFound,
+
+    #[error("Scheduled page change does not exist")]
+    PageScheduleNotFound,
+
+    #[error("Site application does not exist")]
+    SiteApplicationNotFound,
+
+    #[error("Site invitation does not exist")]
+    SiteInvitationNotFound,
+
+    #[error("Tag name is invalid: {0}")]
+    TagInvalid(String),
+
+    #[error("Tags are not allowed on this site: {}", .0.join(", "))]
+    TagNotAllowed(Vec<String>),
+
+    #[error("Only one tag from group '{group}' may be used, found: {}", .tags.join(", "))]
+    TagGroupExclusive { group: String, tags: Vec<String> },
+
+    #[error("At least one tag from group '{0}' is required")]
+    TagGroupRequired(String),
+
+    #[error("Forum thread is locked and cannot be posted in")]
+    ForumThreadLocked,
+
+    #[error("Forum thread title cannot be empty")]
+    ForumThreadTitleEmpty,
+
+    #[error("Forum post body cannot be empty")]
+    ForumPostBodyEmpty,
+
+    #[error("Forum post reply is nested too deeply")]
+    ForumPostTooDeep,
+
+    #[error("Forum post parent is not in the same thread")]
+    ForumPostParentMismatch,
+
     #[error("Cannot perform, user already exists")]
     UserExists,
 
@@ -281,19 +429,174 @@ pub enum Error {
     #[error("Cannot perform, custom domain already exists")]
     CustomDomainExists,
 
+    #[error("Cannot perform, users are already contacts")]
+    UserContactExists,
+
+    #[error("Cannot perform, tag or tag alias already exists")]
+    TagExists,
+
+    #[error("Cannot perform, tag group already exists")]
+    TagGroupExists,
+
+    #[error("Cannot perform, page category already exists")]
+    PageCategoryExists,
+
+    #[error("Cannot perform, user is already a site member")]
+    SiteMemberExists,
+
+    #[error("Cannot perform, user already has a pending site application")]
+    SiteApplicationExists,
+
     #[error("Cannot perform this action because you are blocked by the user")]
     UserBlockedUser,
 
     #[error("Cannot perform this action because you are blocked by the site")]
     SiteBlockedUser,
 
+    #[error("Cannot perform this action because the user only allows it from contacts")]
+    UserNotContact,
+
+    #[error("Cannot add yourself as a contact")]
+    UserContactSelf,
+
     #[error("The rate limit for an external API has been reached")]
     RateLimited,
+
+    #[error("Too many requests have been made, try again later")]
+    TooManyRequests,
+
+    #[error("The user does not have permission to do this in this category")]
+    CategoryPermissionDenied,
 }
 
 impl Error {
     /// Returns the code associated with this error.
     ///
+    /// See `ErrorKind::code()` for the full listing.
+    #[inline]
+    pub fn code(&self) -> i32 {
+        ErrorKind::from(self).code()
+    }
+
+    /// Whether this error comes from a temporary server-side problem,
+    /// meaning that the same operation may succeed if tried again.
+    #[inline]
+    pub fn is_transient(&self) -> bool {
+        (3000..4000).contains(&self.code())
+    }
+
+    /// Returns the arguments used to format this error's message.
+    ///
+    /// These are passed to Fluent when localizing the error,
+    /// and are also returned to the caller as structured data.
+    pub fn arguments(&self) -> MessageArguments<'_> {
+        let mut arguments = MessageArguments::default();
+
+        match self {
+            Error::SessionUserId {
+                active_user_id,
+                session_user_id,
+            } => {
+                arguments.set("active_user_id", *active_user_id);
+                arguments.set("session_user_id", *session_user_id);
+            }
+            Error::EmailVerification(reason) => {
+                arguments.set("reason", reason.as_deref());
+            }
+            Error::TagInvalid(tag) => {
+                arguments.set("tag", tag.as_str());
+            }
+            Error::TagNotAllowed(tags) => {
+                arguments.set("tags", tags.join(", "));
+                arguments.set("count", tags.len());
+            }
+            Error::TagGroupExclusive { group, tags } => {
+                arguments.set("group", group.as_str());
+                arguments.set("tags", tags.join(", "));
+            }
+            Error::TagGroupRequired(group) => {
+                arguments.set("group", group.as_str());
+            }
+
+            // Other cases have no arguments
+            _ => (),
+        }
+
+        arguments
+    }
+
+    /// Emit partial details about the underlying error.
+    ///
+    /// Meant to be better than nothing and simply `Debug` but also not
+    /// as much boilerplate as manually implementing `Serialize` on everything.
+    /// This unwraps common cases and makes things generally clearer.
+    fn details(&self) -> serde_json::Value {
+        match self {
+            // Unwrap self-error
+            Error::AuthenticationBackend(error) => error.details(),
+
+            // Emit as a Debug string
+            Error::Cryptography(value) => json!(format!("{value:?}")),
+            Error::Database(value) => json!(format!("{value:?}")),
+            Error::Io(value) => json!(format!("{value:?}")),
+            Error::LocaleInvalid(value) => json!(format!("{value:?}")),
+            Error::Magic(value) => json!(format!("{value:?}")),
+            Error::Otp(value) => json!(format!("{value:?}")),
+            Error::Serde(value) => json!(format!("{value:?}")),
+            Error::S3Service(value) => json!(format!("{value:?}")),
+            Error::WebRequest(value) => json!(format!("{value:?}")),
+            Error::FilterRegexInvalid(value) => json!(format!("{value:?}")),
+
+            // Other cases are null enums or the values are in the arguments
+            _ => json!(null),
+        }
+    }
+
+    /// Emit structured error data.
+    ///
+    /// This has the translation key and arguments for the error, so that
+    /// callers can produce their own message, along with any details.
+    fn data(&self, arguments: &MessageArguments) -> serde_json::Value {
+        json!({
+            "key": ErrorKind::from(self).key(),
+            "arguments": arguments,
+            "details": self.details(),
+        })
+    }
+
+    /// Converts this error into an RPC error, with the message localized.
+    ///
+    /// If no translation is available in the given locales, then this falls back
+    /// to the English message, the same as the plain `From` conversion.
+    pub fn into_rpc_localized(
+        self,
+        localizations: &Localizations,
+        locales: &[LanguageIdentifier],
+    ) -> ErrorObjectOwned {
+        // Return a raw error as-is
+        if let Error::Raw(error) = self {
+            return error;
+        }
+
+        let kind = ErrorKind::from(&self);
+        let arguments = self.arguments();
+        let fluent_args = arguments.clone().into_fluent_args();
+        let message = match localizations.translate(locales, &kind.key(), &fluent_args) {
+            Ok(message) => message.into_owned(),
+            Err(error) => {
+                debug!("Unable to localize error {kind:?}, using fallback: {error}");
+                str!(self)
+            }
+        };
+
+        let data = self.data(&arguments);
+        ErrorObjectOwned::owned(kind.code(), message, Some(data))
+    }
+}
+
+impl ErrorKind {
+    /// Returns the code associated with this kind of error.
+    ///
     /// The JSON-RPC spec has each unique error case return its own integer error code.
     /// Some very negative codes are reserved for RPC internals, so we will only output
     /// positive values.
@@ -304,161 +607,180 @@ impl Error {
     /// When an error case is removed, then its number should generally not be reused,
     /// just use the next available value in line. Also be sure to update framerail
     /// accordingly when error codes are added or removed.
-    pub fn code(&self) -> i32 {
+    pub fn code(self) -> i32 {
         match self {
             // 1000 - Miscellaneous, general errors
             //        Avoid putting stuff here, prefer other categories instead
-            Error::Raw(_) => 1000,
+            ErrorKind::Raw => 1000,
 
             // 2000 - Database conflicts
             //        Missing data
-            Error::GeneralNotFound => 2000,
-            Error::AliasNotFound => 2001,
-            Error::RelationNotFound => 2002,
-            Error::UserNotFound => 2003,
-            Error::SiteNotFound => 2004,
-            Error::PageNotFound => 2005,
-            Error::PageCategoryNotFound => 2006,
-            Error::PageParentNotFound => 2007,
-            Error::PageRevisionNotFound => 2008,
-            Error::FileNotFound => 2009,
-            Error::FileRevisionNotFound => 2010,
-            Error::VoteNotFound => 2011,
-            Error::FilterNotFound => 2012,
-            Error::CustomDomainNotFound => 2013,
-            Error::MessageNotFound => 2014,
-            Error::MessageDraftNotFound => 2015,
-            Error::BlobNotFound => 2016,
-            Error::TextNotFound => 2017,
+            ErrorKind::GeneralNotFound => 2000,
+            ErrorKind::AliasNotFound => 2001,
+            ErrorKind::RelationNotFound => 2002,
+            ErrorKind::UserNotFound => 2003,
+            ErrorKind::SiteNotFound => 2004,
+            ErrorKind::PageNotFound => 2005,
+            ErrorKind::PageCategoryNotFound => 2006,
+            ErrorKind::PageParentNotFound => 2007,
+            ErrorKind::PageRevisionNotFound => 2008,
+            ErrorKind::FileNotFound => 2009,
+            ErrorKind::FileRevisionNotFound => 2010,
+            ErrorKind::VoteNotFound => 2011,
+            ErrorKind::FilterNotFound => 2012,
+            ErrorKind::CustomDomainNotFound => 2013,
+            ErrorKind::MessageNotFound => 2014,
+            ErrorKind::MessageDraftNotFound => 2015,
+            ErrorKind::BlobNotFound => 2016,
+            ErrorKind::TextNotFound => 2017,
+            ErrorKind::SiteBackupNotFound => 2018,
+            ErrorKind::SiteBackupArchiveNotFound => 2019,
+            ErrorKind::ForumGroupNotFound => 2020,
+            ErrorKind::ForumCategoryNotFound => 2021,
+            ErrorKind::ForumThreadNotFound => 2022,
+            ErrorKind::ForumPostNotFound => 2023,
+            ErrorKind::ForumPostRevisionNotFound => 2024,
+            ErrorKind::JobNotFound => 2025,
+            ErrorKind::TagNotFound => 2026,
+            ErrorKind::TagGroupNotFound => 2027,
+            ErrorKind::PageDraftNotFound => 2028,
+            ErrorKind::PageScheduleNotFound => 2029,
+            ErrorKind::SiteApplicationNotFound => 2030,
+            ErrorKind::SiteInvitationNotFound => 2031,
 
             // 2100 -- Existing data
-            Error::UserExists => 2100,
-            Error::UserMfaExists => 2101,
-            Error::SiteExists => 2102,
-            Error::PageExists => 2103,
-            Error::PageSlugExists => 2104,
-            Error::PageParentExists => 2105,
-            Error::FileExists => 2106,
-            Error::FilterExists => 2107,
-            Error::CustomDomainExists => 2108,
+            ErrorKind::UserExists => 2100,
+            ErrorKind::UserMfaExists => 2101,
+            ErrorKind::SiteExists => 2102,
+            ErrorKind::PageExists => 2103,
+            ErrorKind::PageSlugExists => 2104,
+            ErrorKind::PageParentExists => 2105,
+            ErrorKind::FileExists => 2106,
+            ErrorKind::FilterExists => 2107,
+            ErrorKind::CustomDomainExists => 2108,
+            ErrorKind::UserContactExists => 2109,
+            ErrorKind::TagExists => 2110,
+            ErrorKind::TagGroupExists => 2111,
+            ErrorKind::PageCategoryExists => 2112,
+            ErrorKind::SiteMemberExists => 2113,
+            ErrorKind::SiteApplicationExists => 2114,
 
             // 3000 - Server errors, unexpected
-            Error::RateLimited => 3000,
-            Error::WebRequest(_) => 3001,
-            Error::AuthenticationBackend(_) => 3002,
+            ErrorKind::RateLimited => 3000,
+            ErrorKind::WebRequest => 3001,
+            ErrorKind::AuthenticationBackend => 3002,
 
             // 3100 -- Remote services
-            Error::RenderTimeout => 3100,
-            Error::EmailVerification(_) => 3101,
-            Error::S3Service(_) => 3102,
-            Error::S3Response => 3103,
+            ErrorKind::RenderTimeout => 3100,
+            ErrorKind::EmailVerification => 3101,
+            ErrorKind::S3Service => 3102,
+            ErrorKind::S3Response => 3103,
 
             // 3200 -- Backend issues
-            Error::Serde(_) => 3200,
-            Error::Database(_) => 3201,
-            Error::Cryptography(_) => 3202,
-            Error::Magic(_) => 3204,
-            Error::Otp(_) => 3205,
-            Error::Redis(_) => 3206,
-            Error::Rsmq(_) => 3207,
+            ErrorKind::Serde => 3200,
+            ErrorKind::Database => 3201,
+            ErrorKind::Cryptography => 3202,
+            ErrorKind::Magic => 3204,
+            ErrorKind::Otp => 3205,
+            ErrorKind::Redis => 3206,
+            ErrorKind::Rsmq => 3207,
+            ErrorKind::Io => 3208,
+            ErrorKind::TextCorrupted => 3209,
 
             // 4000 - Client, request errors
             //        BadRequest is pretty general, avoid it except for rare weird cases
-            Error::BadRequest => 4000,
-            Error::InvalidEnumValue => 4001,
-            Error::FilterViolation => 4002,
-            Error::InsufficientNameChanges => 4003,
-            Error::CannotHideLatestRevision => 4004,
-            Error::FilterRegexInvalid(_) => 4005,
-            Error::FilterNotDeleted => 4006,
-            Error::FileNameEmpty => 4007,
-            Error::FileNameTooLong => 4008,
-            Error::FileMimeEmpty => 4009,
-            Error::FileNotDeleted => 4010,
-            Error::PageNotDeleted => 4011,
-            Error::PageSlugEmpty => 4012,
-            Error::SiteSlugEmpty => 4013,
-            Error::UserNameTooShort => 4014,
-            Error::UserSlugEmpty => 4015,
-            Error::UserEmailEmpty => 4022,
-            Error::MessageSubjectEmpty => 4016,
-            Error::MessageSubjectTooLong => 4017,
-            Error::MessageBodyEmpty => 4018,
-            Error::MessageBodyTooLong => 4019,
-            Error::MessageNoRecipients => 4020,
-            Error::MessageTooManyRecipients => 4021,
-            Error::BlobWrongUser => 4022,
-            Error::BlobTooBig => 4023,
-            Error::BlobNotUploaded => 4024,
-            Error::BlobSizeMismatch => 4025,
-            Error::NotLatestRevisionId => 4027,
+            ErrorKind::BadRequest => 4000,
+            ErrorKind::InvalidEnumValue => 4001,
+            ErrorKind::FilterViolation => 4002,
+            ErrorKind::InsufficientNameChanges => 4003,
+            ErrorKind::CannotHideLatestRevision => 4004,
+            ErrorKind::FilterRegexInvalid => 4005,
+            ErrorKind::FilterNotDeleted => 4006,
+            ErrorKind::FileNameEmpty => 4007,
+            ErrorKind::FileNameTooLong => 4008,
+            ErrorKind::FileMimeEmpty => 4009,
+            ErrorKind::FileNotDeleted => 4010,
+            ErrorKind::PageNotDeleted => 4011,
+            ErrorKind::PageSlugEmpty => 4012,
+            ErrorKind::SiteSlugEmpty => 4013,
+            ErrorKind::UserNameTooShort => 4014,
+            ErrorKind::UserSlugEmpty => 4015,
+            ErrorKind::MessageSubjectEmpty => 4016,
+            ErrorKind::MessageSubjectTooLong => 4017,
+            ErrorKind::MessageBodyEmpty => 4018,
+            ErrorKind::MessageBodyTooLong => 4019,
+            ErrorKind::MessageNoRecipients => 4020,
+            ErrorKind::MessageTooManyRecipients => 4021,
+            ErrorKind::BlobWrongUser => 4022,
+            ErrorKind::BlobTooBig => 4023,
+            ErrorKind::BlobNotUploaded => 4024,
+            ErrorKind::BlobSizeMismatch => 4025,
+            ErrorKind::NotLatestRevisionId => 4027,
+            ErrorKind::SiteBackupArchiveInvalid => 4028,
+            ErrorKind::ForumThreadLocked => 4029,
+            ErrorKind::ForumThreadTitleEmpty => 4030,
+            ErrorKind::ForumPostBodyEmpty => 4031,
+            ErrorKind::ForumPostTooDeep => 4032,
+            ErrorKind::ForumPostParentMismatch => 4033,
+            ErrorKind::BlobUploadRejected => 4034,
+            ErrorKind::TagInvalid => 4035,
+            ErrorKind::TagNotAllowed => 4036,
+            ErrorKind::TagGroupExclusive => 4037,
+            ErrorKind::TagGroupRequired => 4038,
+            ErrorKind::PageCategorySlugInvalid => 4039,
+            ErrorKind::PageCategoryNotEmpty => 4040,
+            ErrorKind::PageCategoryDefault => 4041,
+            ErrorKind::UserEmailEmpty => 4042,
+            ErrorKind::PageScheduleInPast => 4043,
+            ErrorKind::PageScheduleNotPending => 4044,
+            ErrorKind::PageScheduleEmpty => 4045,
+            ErrorKind::SiteNotTemplate => 4046,
+            ErrorKind::SiteJoinNotAllowed => 4047,
+            ErrorKind::SiteApplicationNotPending => 4048,
+            ErrorKind::SiteApplicationAnswersMismatch => 4049,
+            ErrorKind::SiteInvitationExpired => 4050,
+            ErrorKind::SiteInvitationInvalid => 4051,
+            ErrorKind::PageParentCycle => 4052,
+            ErrorKind::PageParentTooDeep => 4053,
+            ErrorKind::UserStorageQuotaExceeded => 4054,
+            ErrorKind::SiteStorageQuotaExceeded => 4055,
+            ErrorKind::StorageQuotaInvalid => 4056,
+            ErrorKind::TooManyRequests => 4057,
 
             // 4100 -- Localization
-            Error::LocaleInvalid(_) => 4100,
-            Error::LocaleMissing => 4101,
-            Error::LocaleMessageMissing => 4102,
-            Error::LocaleMessageValueMissing => 4103,
-            Error::LocaleMessageAttributeMissing => 4104,
-            Error::NoLocalesSpecified => 4105,
+            ErrorKind::LocaleInvalid => 4100,
+            ErrorKind::LocaleMissing => 4101,
+            ErrorKind::LocaleMessageMissing => 4102,
+            ErrorKind::LocaleMessageValueMissing => 4103,
+            ErrorKind::LocaleMessageAttributeMissing => 4104,
+            ErrorKind::NoLocalesSpecified => 4105,
 
             // 4200 -- Login errors
-            Error::EmptyPassword => 4200,
-            Error::InvalidEmail => 4201,
-            Error::DisallowedEmail => 4202,
+            ErrorKind::EmptyPassword => 4200,
+            ErrorKind::InvalidEmail => 4201,
+            ErrorKind::DisallowedEmail => 4202,
 
             // 4300 -- Relationship conflicts
-            Error::SiteBlockedUser => 4300,
-            Error::UserBlockedUser => 4301,
+            ErrorKind::SiteBlockedUser => 4300,
+            ErrorKind::UserBlockedUser => 4301,
+            ErrorKind::UserNotContact => 4302,
+            ErrorKind::UserContactSelf => 4303,
 
             // 5000 - Authentication, permission, or role errors
-            Error::InvalidAuthentication => 5000,
-            Error::InvalidSessionToken => 5001,
-            Error::SessionUserId { .. } => 5002,
-            // TODO: permission errors (e.g. locked page, cannot apply bans)
+            ErrorKind::InvalidAuthentication => 5000,
+            ErrorKind::InvalidSessionToken => 5001,
+            ErrorKind::SessionUserId => 5002,
+            ErrorKind::SessionClientMismatch => 5003,
+            ErrorKind::CategoryPermissionDenied => 5004,
         }
     }
 
-    /// Emit partial structured error data.
+    /// Returns the Fluent message key used to localize this kind of error.
     ///
-    /// Meant to be better than nothing and simply `Debug` but also not
-    /// as much boilerplate as manually implementing `Serialize` on everything.
-    /// This unwraps common cases and makes things generally clearer.
-    fn data(&self) -> serde_json::Value {
-        use serde_json::json;
-
-        match self {
-            // Message already has all the data
-            Error::Raw(_) => json!(null),
-
-            // Unwrap self-error
-            Error::AuthenticationBackend(error) => error.data(),
-
-            // Emit as structure
-            Error::SessionUserId {
-                active_user_id,
-                session_user_id,
-            } => json!({
-                "active_user_id": active_user_id,
-                "session_user_id": session_user_id,
-            }),
-
-            // Emit as-is
-            Error::EmailVerification(value) => json!(value),
-
-            // Emit as a Debug string
-            Error::Cryptography(value) => json!(format!("{value:?}")),
-            Error::Database(value) => json!(format!("{value:?}")),
-            Error::LocaleInvalid(value) => json!(format!("{value:?}")),
-            Error::Magic(value) => json!(format!("{value:?}")),
-            Error::Otp(value) => json!(format!("{value:?}")),
-            Error::Serde(value) => json!(format!("{value:?}")),
-            Error::S3Service(value) => json!(format!("{value:?}")),
-            Error::WebRequest(value) => json!(format!("{value:?}")),
-            Error::FilterRegexInvalid(value) => json!(format!("{value:?}")),
-
-            // Other cases are null enums or the values are ignored
-            _ => json!(null),
-        }
+    /// These are found in the `errors` component.
+    pub fn key(self) -> String {
+        let name: &'static str = self.into();
+        format!("errors-{name}")
     }
 }
 
@@ -503,7 +825,7 @@ impl From<Error> for ErrorObjectOwned {
         // Build error object
         let error_code = error.code();
         let message = str!(error);
-        let data = error.data();
+        let data = error.data(&error.arguments());
         ErrorObjectOwned::owned(error_code, message, Some(data))
     }
 }
@@ -516,3 +838,54 @@ pub fn into_rpc_error(error: TransactionError<ErrorObjectOwned>) -> ErrorObjectO
         TransactionError::Transaction(error) => error,
     }
 }
+
+#[test]
+fn error_catalog() {
+    use fluent::FluentResource;
+    use fluent_syntax::ast::Entry;
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::path::Path;
+    use strum::IntoEnumIterator;
+
+    // Ensure error codes are unique
+    let mut codes = HashMap::new();
+    for kind in ErrorKind::iter() {
+        if let Some(other) = codes.insert(kind.code(), kind) {
+            panic!(
+                "Error code {} is used by both {:?} and {:?}",
+                kind.code(),
+                other,
+                kind,
+            );
+        }
+    }
+
+    // Ensure every error kind has a message, and vice versa
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../locales/fluent/errors/en.ftl");
+    let source = fs::read_to_string(&path).expect("Unable to read error messages");
+    let resource = FluentResource::try_new(source).expect("Unable to parse messages");
+    let messages: HashSet<&str> = resource
+        .entries()
+        .filter_map(|entry| match entry {
+            Entry::Message(message) => Some(message.id.name),
+            _ => None,
+        })
+        .collect();
+
+    let keys: HashSet<String> = ErrorKind::iter().map(ErrorKind::key).collect();
+    for key in &keys {
+        assert!(
+            messages.contains(key.as_str()),
+            "No error message for key {key}",
+        );
+    }
+
+    for message in messages {
+        assert!(
+            keys.contains(message),
+            "Error message {message} has no corresponding error kind",
+        );
+    }
+}
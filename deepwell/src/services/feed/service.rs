@@ -0,0 +1,363 @@
+/*
+ * services/feed/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Service to produce syndication feeds of page changes.
+//!
+//! Feeds are available in both Atom and RSS 2.0 formats, for either
+//! the recent changes of an entire site or the history of a single page.
+//! The XML is small and fixed enough in structure that it is generated
+//! directly here rather than pulling in a serialization library.
+
+use super::prelude::*;
+use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::sea_orm_active_enums::PageRevisionType;
+use crate::models::site::Model as SiteModel;
+use crate::models::user::{self, Entity as User};
+use crate::services::page_revision::PageRevisionModelFiltered;
+use crate::services::recent_changes::GetRecentChangesOutput;
+use crate::services::{DomainService, PageService, RecentChangesService, SiteService};
+use std::cmp;
+use std::collections::HashMap;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::OffsetDateTime;
+
+#[derive(Debug)]
+pub struct FeedService;
+
+impl FeedService {
+    /// Produces a feed of the recent changes across a site.
+    ///
+    /// All the filters supported by `RecentChangesService::get()` are also
+    /// applicable here.
+    pub async fn recent_changes(
+        ctx: &ServiceContext<'_>,
+        GetRecentChangesFeed { query, format }: GetRecentChangesFeed<'_>,
+    ) -> Result<FeedOutput> {
+        info!(
+            "Building {format:?} recent changes feed for site ID {}",
+            query.site_id,
+        );
+
+        let site = SiteService::get(ctx, Reference::Id(query.site_id)).await?;
+        let GetRecentChangesOutput { revisions, .. } =
+            RecentChangesService::get(ctx, query).await?;
+
+        let title = format!("{} — Recent changes", site.name);
+        Self::build(
+            ctx,
+            &site,
+            title,
+            "system:recent-changes",
+            &revisions,
+            format,
+        )
+        .await
+    }
+
+    /// Produces a feed of the revision history of a single page, newest first.
+    pub async fn page_history(
+        ctx: &ServiceContext<'_>,
+        GetPageHistoryFeed {
+            site_id,
+            page: reference,
+            format,
+            limit,
+        }: GetPageHistoryFeed<'_>,
+    ) -> Result<FeedOutput> {
+        let limit = cmp::min(limit, ctx.config().maximum_recent_changes);
+        info!("Building {format:?} page history feed for page {reference:?} in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let page = PageService::get(ctx, site_id, reference).await?;
+        let revisions = PageRevision::find()
+            .filter(
+                Condition::all()
+                    .add(page_revision::Column::SiteId.eq(site_id))
+                    .add(page_revision::Column::PageId.eq(page.page_id)),
+            )
+            .order_by_desc(page_revision::Column::RevisionNumber)
+            .limit(limit)
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(PageRevisionModelFiltered::from)
+            .collect::<Vec<_>>();
+
+        let page_title = revisions
+            .first()
+            .and_then(|revision| revision.title.as_deref())
+            .unwrap_or(&page.slug);
+
+        let title = format!("{} — History of \"{}\"", site.name, page_title);
+        Self::build(ctx, &site, title, &page.slug, &revisions, format).await
+    }
+
+    async fn build(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+        title: String,
+        path: &str,
+        revisions: &[PageRevisionModelFiltered],
+        format: FeedFormat,
+    ) -> Result<FeedOutput> {
+        let domain = DomainService::domain_for_site(ctx.config(), site);
+        let user_names = Self::get_user_names(ctx, revisions).await?;
+
+        let entries = revisions
+            .iter()
+            .map(|revision| FeedEntry {
+                id: format!("urn:wikijump:page-revision:{}", revision.revision_id),
+                title: format!(
+                    "{} ({})",
+                    revision.title.as_deref().unwrap_or("(hidden)"),
+                    revision_type_label(revision.revision_type),
+                ),
+                link: format!(
+                    "https://{domain}/{}",
+                    revision.slug.as_deref().unwrap_or(path),
+                ),
+                author: user_names
+                    .get(&revision.user_id)
+                    .map(String::as_str)
+                    .unwrap_or("(unknown)"),
+                summary: revision.comments.as_deref().unwrap_or(""),
+                updated: revision.created_at,
+            })
+            .collect::<Vec<_>>();
+
+        let feed = Feed {
+            id: format!("https://{domain}/{path}"),
+            title: &title,
+            description: &site.tagline,
+            link: format!("https://{domain}/{path}"),
+            updated: revisions
+                .first()
+                .map(|revision| revision.created_at)
+                .unwrap_or_else(now),
+            entries: &entries,
+        };
+
+        let body = match format {
+            FeedFormat::Atom => render_atom(&feed),
+            FeedFormat::Rss => render_rss(&feed),
+        };
+
+        Ok(FeedOutput {
+            content_type: format.content_type(),
+            body,
+        })
+    }
+
+    /// Fetches the names of all users who authored the given revisions.
+    async fn get_user_names(
+        ctx: &ServiceContext<'_>,
+        revisions: &[PageRevisionModelFiltered],
+    ) -> Result<HashMap<i64, String>> {
+        let txn = ctx.transaction();
+        let mut user_ids = revisions
+            .iter()
+            .map(|revision| revision.user_id)
+            .collect::<Vec<_>>();
+
+        user_ids.sort_unstable();
+        user_ids.dedup();
+
+        let users = User::find()
+            .filter(user::Column::UserId.is_in(user_ids))
+            .all(txn)
+            .await?;
+
+        Ok(users
+            .into_iter()
+            .map(|user| (user.user_id, user.name))
+            .collect())
+    }
+}
+
+#[derive(Debug)]
+struct Feed<'a> {
+    id: String,
+    title: &'a str,
+    description: &'a str,
+    link: String,
+    updated: OffsetDateTime,
+    entries: &'a [FeedEntry<'a>],
+}
+
+#[derive(Debug)]
+struct FeedEntry<'a> {
+    id: String,
+    title: String,
+    link: String,
+    author: &'a str,
+    summary: &'a str,
+    updated: OffsetDateTime,
+}
+
+fn revision_type_label(revision_type: PageRevisionType) -> &'static str {
+    match revision_type {
+        PageRevisionType::Create => "created",
+        PageRevisionType::Regular => "edited",
+        PageRevisionType::Move => "moved",
+        PageRevisionType::Delete => "deleted",
+        PageRevisionType::Undelete => "restored",
+        PageRevisionType::Rollback => "rolled back",
+        PageRevisionType::Undo => "undone",
+    }
+}
+
+fn render_atom(feed: &Feed) -> String {
+    let mut xml = String::new();
+    str_writeln!(xml, r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    str_writeln!(xml, r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+    str_writeln!(xml, "  <id>{}</id>", escape_xml(&feed.id));
+    str_writeln!(xml, "  <title>{}</title>", escape_xml(feed.title));
+    str_writeln!(
+        xml,
+        "  <subtitle>{}</subtitle>",
+        escape_xml(feed.description)
+    );
+    str_writeln!(xml, r#"  <link href="{}" />"#, escape_xml(&feed.link));
+    str_writeln!(xml, "  <updated>{}</updated>", format_rfc3339(feed.updated));
+
+    for entry in feed.entries {
+        str_writeln!(xml, "  <entry>");
+        str_writeln!(xml, "    <id>{}</id>", escape_xml(&entry.id));
+        str_writeln!(xml, "    <title>{}</title>", escape_xml(&entry.title));
+        str_writeln!(xml, r#"    <link href="{}" />"#, escape_xml(&entry.link));
+        str_writeln!(
+            xml,
+            "    <updated>{}</updated>",
+            format_rfc3339(entry.updated)
+        );
+        str_writeln!(
+            xml,
+            "    <author><name>{}</name></author>",
+            escape_xml(entry.author),
+        );
+        str_writeln!(xml, "    <summary>{}</summary>", escape_xml(entry.summary));
+        str_writeln!(xml, "  </entry>");
+    }
+
+    str_writeln!(xml, "</feed>");
+    xml
+}
+
+fn render_rss(feed: &Feed) -> String {
+    let mut xml = String::new();
+    str_writeln!(xml, r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    str_writeln!(
+        xml,
+        r#"<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">"#,
+    );
+    str_writeln!(xml, "  <channel>");
+    str_writeln!(xml, "    <title>{}</title>", escape_xml(feed.title));
+    str_writeln!(xml, "    <link>{}</link>", escape_xml(&feed.link));
+    str_writeln!(
+        xml,
+        "    <description>{}</description>",
+        escape_xml(feed.description),
+    );
+    str_writeln!(
+        xml,
+        "    <lastBuildDate>{}</lastBuildDate>",
+        format_rfc2822(feed.updated),
+    );
+
+    for entry in feed.entries {
+        str_writeln!(xml, "    <item>");
+        str_writeln!(xml, "      <title>{}</title>", escape_xml(&entry.title));
+        str_writeln!(xml, "      <link>{}</link>", escape_xml(&entry.link));
+        str_writeln!(
+            xml,
+            r#"      <guid isPermaLink="false">{}</guid>"#,
+            escape_xml(&entry.id),
+        );
+        str_writeln!(
+            xml,
+            "      <pubDate>{}</pubDate>",
+            format_rfc2822(entry.updated)
+        );
+        str_writeln!(
+            xml,
+            "      <dc:creator>{}</dc:creator>",
+            escape_xml(entry.author),
+        );
+        str_writeln!(
+            xml,
+            "      <description>{}</description>",
+            escape_xml(entry.summary),
+        );
+        str_writeln!(xml, "    </item>");
+    }
+
+    str_writeln!(xml, "  </channel>");
+    str_writeln!(xml, "</rss>");
+    xml
+}
+
+fn format_rfc3339(timestamp: OffsetDateTime) -> String {
+    timestamp
+        .format(&Rfc3339)
+        .expect("Timestamp out of range for RFC 3339")
+}
+
+fn format_rfc2822(timestamp: OffsetDateTime) -> String {
+    timestamp
+        .format(&Rfc2822)
+        .expect("Timestamp out of range for RFC 2822")
+}
+
+fn escape_xml(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+
+    for ch in input.chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&apos;"),
+            _ => output.push(ch),
+        }
+    }
+
+    output
+}
+
+#[test]
+fn test_escape_xml() {
+    macro_rules! check {
+        ($input:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                escape_xml($input),
+                $expected,
+                "Actual XML escape output doesn't match expected",
+            );
+        };
+    }
+
+    check!("", "");
+    check!("SCP-173", "SCP-173");
+    check!("Fish & Chips", "Fish &amp; Chips");
+    check!("<b>\"bold\"</b>", "&lt;b&gt;&quot;bold&quot;&lt;/b&gt;");
+    check!("it's", "it&apos;s");
+}
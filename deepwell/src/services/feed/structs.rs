@@ -0,0 +1,59 @@
+/*
+ * services/feed/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::services::recent_changes::GetRecentChanges;
+use crate::types::Reference;
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml",
+            FeedFormat::Rss => "application/rss+xml",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetRecentChangesFeed<'a> {
+    #[serde(flatten)]
+    pub query: GetRecentChanges<'a>,
+    pub format: FeedFormat,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageHistoryFeed<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub format: FeedFormat,
+    pub limit: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FeedOutput {
+    pub content_type: &'static str,
+    pub body: String,
+}
@@ -26,13 +26,16 @@ use crate::models::file_revision::{
 };
 use crate::models::sea_orm_active_enums::FileRevisionType;
 use crate::services::blob::{FinalizeBlobUploadOutput, EMPTY_BLOB_HASH, EMPTY_BLOB_MIME};
+use crate::services::category::CategoryAction;
 use crate::services::file_revision::{
     CreateFileRevision, CreateFileRevisionBody, CreateFirstFileRevision,
     CreateResurrectionFileRevision, CreateTombstoneFileRevision, FileBlob,
     GetFileRevision,
 };
 use crate::services::filter::{FilterClass, FilterType};
-use crate::services::{BlobService, FileRevisionService, FilterService};
+use crate::services::{
+    BlobService, CategoryService, FileRevisionService, FilterService, PageService,
+};
 use sea_orm::ActiveValue;
 
 #[derive(Debug)]
@@ -64,6 +67,15 @@ impl FileService {
         // Ensure row consistency
         Self::check_conflicts(ctx, page_id, &name, "create").await?;
 
+        let page = PageService::get_direct(ctx, page_id, false).await?;
+        CategoryService::check_page_permission(
+            ctx,
+            &page,
+            CategoryAction::UploadFiles,
+            user_id,
+        )
+        .await?;
+
         // Perform filter validation
         if !bypass_filter {
             Self::run_filter(ctx, site_id, Some(&name)).await?;
@@ -75,7 +87,8 @@ impl FileService {
             mime: mime_hint,
             size: size_hint,
             created: blob_created,
-        } = BlobService::finish_upload(ctx, user_id, &uploaded_blob_id).await?;
+        } = BlobService::finish_upload(ctx, user_id, &uploaded_blob_id, Some(site_id))
+            .await?;
 
         // Add new file
         let model = file::ActiveModel {
@@ -133,6 +146,17 @@ impl FileService {
             uploaded_blob_id,
         } = body;
 
+        if uploaded_blob_id.is_set() {
+            let page = PageService::get_direct(ctx, page_id, false).await?;
+            CategoryService::check_page_permission(
+                ctx,
+                &page,
+                CategoryAction::UploadFiles,
+                user_id,
+            )
+            .await?;
+        }
+
         let mut new_name = ActiveValue::NotSet;
 
         // Verify name change
@@ -160,7 +184,7 @@ impl FileService {
                     mime: mime_hint,
                     size: size_hint,
                     created: blob_created,
-                } = BlobService::finish_upload(ctx, user_id, id).await?;
+                } = BlobService::finish_upload(ctx, user_id, id, Some(site_id)).await?;
 
                 Maybe::Set(FileBlob {
                     s3_hash,
@@ -496,7 +496,6 @@ impl FileRevisionService {
     ///
     /// See `RevisionService::get()`.
     #[inline]
-    #[allow(dead_code)]
     pub async fn get(
         ctx: &ServiceContext<'_>,
         input: GetFileRevision,
@@ -0,0 +1,245 @@
+/*
+ * services/forum_category/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Forum categories, which contain the threads of a site's forum.
+//!
+//! Not to be confused with page categories, see `CategoryService`.
+
+use super::prelude::*;
+use crate::models::forum_category::{
+    self, Entity as ForumCategory, Model as ForumCategoryModel,
+};
+use crate::models::forum_group;
+use crate::services::ForumGroupService;
+
+#[derive(Debug)]
+pub struct ForumCategoryService;
+
+impl ForumCategoryService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateForumCategory {
+            site_id,
+            forum_group_id,
+            name,
+            description,
+            sort_index,
+            max_nest_level,
+        }: CreateForumCategory,
+    ) -> Result<ForumCategoryModel> {
+        info!(
+            "Creating forum category '{name}' in group ID {forum_group_id} (site ID {site_id})",
+        );
+
+        Self::check_group(ctx, site_id, forum_group_id).await?;
+
+        let txn = ctx.transaction();
+        let model = forum_category::ActiveModel {
+            site_id: Set(site_id),
+            forum_group_id: Set(forum_group_id),
+            name: Set(name),
+            description: Set(description),
+            sort_index: Set(sort_index),
+            max_nest_level: match max_nest_level {
+                Some(level) => Set(level),
+                None => Default::default(),
+            },
+            ..Default::default()
+        };
+
+        let category = model.insert(txn).await?;
+        Ok(category)
+    }
+
+    pub async fn update(
+        ctx: &ServiceContext<'_>,
+        UpdateForumCategory {
+            forum_category_id,
+            body:
+                UpdateForumCategoryBody {
+                    forum_group_id,
+                    name,
+                    description,
+                    sort_index,
+                    max_nest_level,
+                },
+        }: UpdateForumCategory,
+    ) -> Result<ForumCategoryModel> {
+        info!("Updating forum category ID {forum_category_id}");
+
+        let category = Self::get(ctx, forum_category_id).await?;
+        if let Maybe::Set(forum_group_id) = forum_group_id {
+            Self::check_group(ctx, category.site_id, forum_group_id).await?;
+        }
+
+        let txn = ctx.transaction();
+        let model = forum_category::ActiveModel {
+            forum_category_id: Set(forum_category_id),
+            updated_at: Set(Some(now())),
+            forum_group_id: forum_group_id.into_active_value(),
+            name: name.into_active_value(),
+            description: description.into_active_value(),
+            sort_index: sort_index.into_active_value(),
+            max_nest_level: max_nest_level.into_active_value(),
+            ..Default::default()
+        };
+
+        let category = model.update(txn).await?;
+        Ok(category)
+    }
+
+    pub async fn delete(ctx: &ServiceContext<'_>, forum_category_id: i64) -> Result<()> {
+        info!("Deleting forum category ID {forum_category_id}");
+
+        // Ensure the category exists and is not already deleted
+        Self::get(ctx, forum_category_id).await?;
+
+        let txn = ctx.transaction();
+        let model = forum_category::ActiveModel {
+            forum_category_id: Set(forum_category_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    /// Gets a forum category, if it exists and has not been deleted.
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        forum_category_id: i64,
+    ) -> Result<Option<ForumCategoryModel>> {
+        let txn = ctx.transaction();
+        let category = ForumCategory::find()
+            .filter(
+                Condition::all()
+                    .add(forum_category::Column::ForumCategoryId.eq(forum_category_id))
+                    .add(forum_category::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(category)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        forum_category_id: i64,
+    ) -> Result<ForumCategoryModel> {
+        find_or_error!(Self::get_optional(ctx, forum_category_id), ForumCategory)
+    }
+
+    /// Gets all forum categories in a site, optionally only those in one group.
+    ///
+    /// Categories are returned in display order, grouped by forum group.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        forum_group_id: Option<i64>,
+    ) -> Result<Vec<ForumCategoryModel>> {
+        let txn = ctx.transaction();
+        let categories = ForumCategory::find()
+            .filter(
+                Condition::all()
+                    .add(forum_category::Column::SiteId.eq(site_id))
+                    .add_option(
+                        forum_group_id
+                            .map(|id| forum_category::Column::ForumGroupId.eq(id)),
+                    )
+                    .add(forum_category::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(forum_category::Column::ForumGroupId)
+            .order_by_asc(forum_category::Column::SortIndex)
+            .order_by_asc(forum_category::Column::ForumCategoryId)
+            .all(txn)
+            .await?;
+
+        Ok(categories)
+    }
+
+    /// Gets the category holding per-page discussion threads for a site.
+    ///
+    /// If the site doesn't have one yet, it is created, along with a hidden
+    /// forum group to contain it.
+    pub async fn get_or_create_page_discussion(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<ForumCategoryModel> {
+        let txn = ctx.transaction();
+        let category = ForumCategory::find()
+            .filter(
+                Condition::all()
+                    .add(forum_category::Column::SiteId.eq(site_id))
+                    .add(forum_category::Column::PerPageDiscussion.eq(true))
+                    .add(forum_category::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        if let Some(category) = category {
+            return Ok(category);
+        }
+
+        info!("Creating per-page discussion forum category for site ID {site_id}");
+
+        let group = forum_group::ActiveModel {
+            site_id: Set(site_id),
+            name: Set(str!("Hidden")),
+            description: Set(str!("Hidden group used for per-page discussions")),
+            visible: Set(false),
+            ..Default::default()
+        }
+        .insert(txn)
+        .await?;
+
+        let category = forum_category::ActiveModel {
+            site_id: Set(site_id),
+            forum_group_id: Set(group.forum_group_id),
+            name: Set(str!("Per page discussions")),
+            description: Set(str!("Discussion threads attached to pages")),
+            per_page_discussion: Set(true),
+            ..Default::default()
+        }
+        .insert(txn)
+        .await?;
+
+        Ok(category)
+    }
+
+    /// Ensures the forum group exists and is part of the given site.
+    async fn check_group(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        forum_group_id: i64,
+    ) -> Result<()> {
+        let group = ForumGroupService::get(ctx, forum_group_id).await?;
+        if group.site_id != site_id {
+            error!(
+                "Forum group ID {forum_group_id} is in site ID {}, not site ID {site_id}",
+                group.site_id,
+            );
+            return Err(Error::ForumGroupNotFound);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+/*
+ * services/forum_category/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::types::Maybe;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateForumCategory {
+    pub site_id: i64,
+    pub forum_group_id: i64,
+    pub name: String,
+    pub description: String,
+
+    #[serde(default)]
+    pub sort_index: i32,
+    pub max_nest_level: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetForumCategory {
+    pub forum_category_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetForumCategories {
+    pub site_id: i64,
+    pub forum_group_id: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateForumCategory {
+    pub forum_category_id: i64,
+
+    #[serde(flatten)]
+    pub body: UpdateForumCategoryBody,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct UpdateForumCategoryBody {
+    pub forum_group_id: Maybe<i64>,
+    pub name: Maybe<String>,
+    pub description: Maybe<String>,
+    pub sort_index: Maybe<i32>,
+    pub max_nest_level: Maybe<i32>,
+}
@@ -0,0 +1,152 @@
+/*
+ * services/forum_group/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Forum groups are the top level of a site's forum.
+//!
+//! Each group holds a number of forum categories, and is only used
+//! for organization when listing those categories.
+
+use super::prelude::*;
+use crate::models::forum_group::{self, Entity as ForumGroup, Model as ForumGroupModel};
+
+#[derive(Debug)]
+pub struct ForumGroupService;
+
+impl ForumGroupService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateForumGroup {
+            site_id,
+            name,
+            description,
+            sort_index,
+        }: CreateForumGroup,
+    ) -> Result<ForumGroupModel> {
+        info!("Creating forum group '{name}' in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let model = forum_group::ActiveModel {
+            site_id: Set(site_id),
+            name: Set(name),
+            description: Set(description),
+            sort_index: Set(sort_index),
+            ..Default::default()
+        };
+
+        let group = model.insert(txn).await?;
+        Ok(group)
+    }
+
+    pub async fn update(
+        ctx: &ServiceContext<'_>,
+        UpdateForumGroup {
+            forum_group_id,
+            body:
+                UpdateForumGroupBody {
+                    name,
+                    description,
+                    sort_index,
+                    visible,
+                },
+        }: UpdateForumGroup,
+    ) -> Result<ForumGroupModel> {
+        info!("Updating forum group ID {forum_group_id}");
+
+        // Ensure the group exists and is not deleted
+        Self::get(ctx, forum_group_id).await?;
+
+        let txn = ctx.transaction();
+        let model = forum_group::ActiveModel {
+            forum_group_id: Set(forum_group_id),
+            updated_at: Set(Some(now())),
+            name: name.into_active_value(),
+            description: description.into_active_value(),
+            sort_index: sort_index.into_active_value(),
+            visible: visible.into_active_value(),
+            ..Default::default()
+        };
+
+        let group = model.update(txn).await?;
+        Ok(group)
+    }
+
+    pub async fn delete(ctx: &ServiceContext<'_>, forum_group_id: i64) -> Result<()> {
+        info!("Deleting forum group ID {forum_group_id}");
+
+        // Ensure the group exists and is not already deleted
+        Self::get(ctx, forum_group_id).await?;
+
+        let txn = ctx.transaction();
+        let model = forum_group::ActiveModel {
+            forum_group_id: Set(forum_group_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    /// Gets a forum group, if it exists and has not been deleted.
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        forum_group_id: i64,
+    ) -> Result<Option<ForumGroupModel>> {
+        let txn = ctx.transaction();
+        let group = ForumGroup::find()
+            .filter(
+                Condition::all()
+                    .add(forum_group::Column::ForumGroupId.eq(forum_group_id))
+                    .add(forum_group::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(group)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        forum_group_id: i64,
+    ) -> Result<ForumGroupModel> {
+        find_or_error!(Self::get_optional(ctx, forum_group_id), ForumGroup)
+    }
+
+    /// Gets all forum groups in a site, in display order.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<ForumGroupModel>> {
+        let txn = ctx.transaction();
+        let groups = ForumGroup::find()
+            .filter(
+                Condition::all()
+                    .add(forum_group::Column::SiteId.eq(site_id))
+                    .add(forum_group::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(forum_group::Column::SortIndex)
+            .order_by_asc(forum_group::Column::ForumGroupId)
+            .all(txn)
+            .await?;
+
+        Ok(groups)
+    }
+}
@@ -0,0 +1,474 @@
+/*
+ * services/forum_post/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Forum posts and their revision history.
+//!
+//! Posts form a tree within their thread, with each reply pointing to its
+//! parent post, up to the category's maximum nesting level. The contents of
+//! a post live entirely in its revisions, stored in `text` the same as page
+//! revisions, and rendered by ftml in forum post mode.
+
+use super::prelude::*;
+use crate::models::forum_post::{self, Entity as ForumPost, Model as ForumPostModel};
+use crate::models::forum_post_revision::{
+    self, Entity as ForumPostRevision, Model as ForumPostRevisionModel,
+};
+use crate::models::page::{self, Entity as Page};
+use crate::services::category::CategoryAction;
+use crate::services::filter::{FilterClass, FilterType};
+use crate::services::render::{RenderOutput, RenderService};
+use crate::services::{
+    CategoryService, FilterService, ForumCategoryService, ForumThreadService,
+    SettingsService, SiteService, TextService,
+};
+use ftml::data::{PageInfo, ScoreValue};
+use ftml::parsing::ParseError;
+use ftml::settings::{WikitextMode, WikitextSettings};
+
+#[derive(Debug)]
+pub struct ForumPostService;
+
+impl ForumPostService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateForumPost {
+            forum_thread_id,
+            parent_post_id,
+            user_id,
+            title,
+            wikitext,
+            bypass_filter,
+        }: CreateForumPost,
+    ) -> Result<CreateForumPostOutput> {
+        info!("Creating forum post in thread ID {forum_thread_id} (user ID {user_id})");
+
+        let thread = ForumThreadService::get(ctx, forum_thread_id).await?;
+        if thread.locked {
+            error!("Forum thread ID {forum_thread_id} is locked, cannot post");
+            return Err(Error::ForumThreadLocked);
+        }
+
+        // Check reply is within the thread, and not too deeply nested
+        if let Some(parent_post_id) = parent_post_id {
+            let parent = Self::get(ctx, parent_post_id).await?;
+            if parent.forum_thread_id != forum_thread_id {
+                error!(
+                    "Parent post ID {parent_post_id} is in thread ID {}, not thread ID {forum_thread_id}",
+                    parent.forum_thread_id,
+                );
+                return Err(Error::ForumPostParentMismatch);
+            }
+
+            let category =
+                ForumCategoryService::get(ctx, thread.forum_category_id).await?;
+            let depth = Self::get_depth(ctx, &parent).await? + 1;
+            if depth > category.max_nest_level {
+                error!(
+                    "Reply would be at depth {depth}, maximum is {}",
+                    category.max_nest_level,
+                );
+                return Err(Error::ForumPostTooDeep);
+            }
+        }
+
+        // Posts in a page's discussion are subject to its category's permissions
+        let txn = ctx.transaction();
+        let discussed_page = Page::find()
+            .filter(page::Column::DiscussionThreadId.eq(forum_thread_id))
+            .one(txn)
+            .await?;
+
+        if let Some(page) = discussed_page {
+            CategoryService::check_page_permission(
+                ctx,
+                &page,
+                CategoryAction::Comment,
+                user_id,
+            )
+            .await?;
+        }
+
+        if !bypass_filter {
+            Self::run_filter(ctx, thread.site_id, Some(&title), Some(&wikitext)).await?;
+        }
+
+        let model = forum_post::ActiveModel {
+            created_by: Set(user_id),
+            site_id: Set(thread.site_id),
+            forum_thread_id: Set(forum_thread_id),
+            parent_post_id: Set(parent_post_id),
+            ..Default::default()
+        };
+        let post = model.insert(txn).await?;
+
+        let (revision, parser_errors) = Self::create_revision(
+            ctx,
+            thread.site_id,
+            CreateForumPostRevision {
+                forum_post_id: post.forum_post_id,
+                revision_number: 0,
+                user_id,
+                title,
+                wikitext,
+            },
+        )
+        .await?;
+
+        ForumThreadService::refresh_post_stats(ctx, forum_thread_id).await?;
+
+        Ok(CreateForumPostOutput {
+            post,
+            revision_id: revision.revision_id,
+            parser_errors,
+        })
+    }
+
+    /// Edits a forum post, creating a new revision.
+    ///
+    /// Any fields not being changed are carried over from the latest revision.
+    pub async fn edit(
+        ctx: &ServiceContext<'_>,
+        EditForumPost {
+            forum_post_id,
+            user_id,
+            bypass_filter,
+            body: EditForumPostBody { title, wikitext },
+        }: EditForumPost,
+    ) -> Result<EditForumPostOutput> {
+        info!("Editing forum post ID {forum_post_id} (user ID {user_id})");
+
+        let post = Self::get(ctx, forum_post_id).await?;
+        let thread = ForumThreadService::get(ctx, post.forum_thread_id).await?;
+        if thread.locked {
+            error!(
+                "Forum thread ID {} is locked, cannot edit",
+                thread.forum_thread_id
+            );
+            return Err(Error::ForumThreadLocked);
+        }
+
+        if !bypass_filter {
+            Self::run_filter(ctx, post.site_id, title.to_option(), wikitext.to_option())
+                .await?;
+        }
+
+        let last_revision = Self::get_latest_revision(ctx, forum_post_id).await?;
+        let title = match title {
+            Maybe::Set(title) => title,
+            Maybe::Unset => last_revision.title,
+        };
+        let wikitext = match wikitext {
+            Maybe::Set(wikitext) => wikitext,
+            Maybe::Unset => TextService::get(ctx, &last_revision.wikitext_hash).await?,
+        };
+
+        let (revision, parser_errors) = Self::create_revision(
+            ctx,
+            post.site_id,
+            CreateForumPostRevision {
+                forum_post_id,
+                revision_number: last_revision.revision_number + 1,
+                user_id,
+                title,
+                wikitext,
+            },
+        )
+        .await?;
+
+        let txn = ctx.transaction();
+        let model = forum_post::ActiveModel {
+            forum_post_id: Set(forum_post_id),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        Ok(EditForumPostOutput {
+            revision,
+            parser_errors,
+        })
+    }
+
+    pub async fn delete(ctx: &ServiceContext<'_>, forum_post_id: i64) -> Result<()> {
+        info!("Deleting forum post ID {forum_post_id}");
+
+        // Ensure the post exists and is not already deleted
+        let post = Self::get(ctx, forum_post_id).await?;
+
+        let txn = ctx.transaction();
+        let model = forum_post::ActiveModel {
+            forum_post_id: Set(forum_post_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        model.update(txn).await?;
+        ForumThreadService::refresh_post_stats(ctx, post.forum_thread_id).await?;
+        Ok(())
+    }
+
+    /// Renders and inserts a forum post revision.
+    ///
+    /// This does not perform any checks, and is also used when importing.
+    pub async fn create_revision(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        CreateForumPostRevision {
+            forum_post_id,
+            revision_number,
+            user_id,
+            title,
+            wikitext,
+        }: CreateForumPostRevision,
+    ) -> Result<(ForumPostRevisionModel, Vec<ParseError>)> {
+        if wikitext.is_empty() {
+            error!("Forum post body cannot be empty");
+            return Err(Error::ForumPostBodyEmpty);
+        }
+
+        let RenderOutput {
+            errors,
+            compiled_hash,
+            compiled_at,
+            compiled_generator,
+            ..
+        } = Self::render(ctx, site_id, wikitext.clone()).await?;
+        let wikitext_hash = TextService::create(ctx, wikitext).await?;
+
+        let txn = ctx.transaction();
+        let model = forum_post_revision::ActiveModel {
+            revision_number: Set(revision_number),
+            forum_post_id: Set(forum_post_id),
+            user_id: Set(user_id),
+            title: Set(title),
+            wikitext_hash: Set(wikitext_hash.to_vec()),
+            compiled_hash: Set(compiled_hash.to_vec()),
+            compiled_at: Set(compiled_at),
+            compiled_generator: Set(compiled_generator),
+            ..Default::default()
+        };
+
+        let revision = model.insert(txn).await?;
+        Ok((revision, errors))
+    }
+
+    /// Gets a forum post, if it exists and has not been deleted.
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        forum_post_id: i64,
+    ) -> Result<Option<ForumPostModel>> {
+        let txn = ctx.transaction();
+        let post = ForumPost::find()
+            .filter(
+                Condition::all()
+                    .add(forum_post::Column::ForumPostId.eq(forum_post_id))
+                    .add(forum_post::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(post)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        forum_post_id: i64,
+    ) -> Result<ForumPostModel> {
+        find_or_error!(Self::get_optional(ctx, forum_post_id), ForumPost)
+    }
+
+    /// Gets a forum post along with its current contents.
+    pub async fn get_with_contents(
+        ctx: &ServiceContext<'_>,
+        forum_post_id: i64,
+    ) -> Result<Option<ForumPostOutput>> {
+        match Self::get_optional(ctx, forum_post_id).await? {
+            Some(post) => Ok(Some(Self::with_contents(ctx, post).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets all posts in a thread along with their current contents, oldest first.
+    ///
+    /// Deleted posts are omitted. Their replies are still included,
+    /// so clients should treat a missing parent as a deleted post.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        forum_thread_id: i64,
+    ) -> Result<Vec<ForumPostOutput>> {
+        let txn = ctx.transaction();
+        let posts = ForumPost::find()
+            .filter(
+                Condition::all()
+                    .add(forum_post::Column::ForumThreadId.eq(forum_thread_id))
+                    .add(forum_post::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(forum_post::Column::CreatedAt)
+            .order_by_asc(forum_post::Column::ForumPostId)
+            .all(txn)
+            .await?;
+
+        let mut outputs = Vec::with_capacity(posts.len());
+        for post in posts {
+            outputs.push(Self::with_contents(ctx, post).await?);
+        }
+
+        Ok(outputs)
+    }
+
+    pub async fn get_latest_revision(
+        ctx: &ServiceContext<'_>,
+        forum_post_id: i64,
+    ) -> Result<ForumPostRevisionModel> {
+        let txn = ctx.transaction();
+        let revision = ForumPostRevision::find()
+            .filter(forum_post_revision::Column::ForumPostId.eq(forum_post_id))
+            .order_by_desc(forum_post_revision::Column::RevisionNumber)
+            .one(txn)
+            .await?
+            .ok_or(Error::ForumPostRevisionNotFound)?;
+
+        Ok(revision)
+    }
+
+    pub async fn get_revision_optional(
+        ctx: &ServiceContext<'_>,
+        forum_post_id: i64,
+        revision_number: i32,
+    ) -> Result<Option<ForumPostRevisionModel>> {
+        let txn = ctx.transaction();
+        let revision = ForumPostRevision::find()
+            .filter(
+                Condition::all()
+                    .add(forum_post_revision::Column::ForumPostId.eq(forum_post_id))
+                    .add(forum_post_revision::Column::RevisionNumber.eq(revision_number)),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(revision)
+    }
+
+    /// Gets all revisions of a forum post, newest first.
+    pub async fn get_revisions(
+        ctx: &ServiceContext<'_>,
+        forum_post_id: i64,
+    ) -> Result<Vec<ForumPostRevisionModel>> {
+        let txn = ctx.transaction();
+        let revisions = ForumPostRevision::find()
+            .filter(forum_post_revision::Column::ForumPostId.eq(forum_post_id))
+            .order_by_desc(forum_post_revision::Column::RevisionNumber)
+            .all(txn)
+            .await?;
+
+        Ok(revisions)
+    }
+
+    async fn with_contents(
+        ctx: &ServiceContext<'_>,
+        post: ForumPostModel,
+    ) -> Result<ForumPostOutput> {
+        let revision = Self::get_latest_revision(ctx, post.forum_post_id).await?;
+        let (wikitext, compiled_html) = try_join!(
+            TextService::get(ctx, &revision.wikitext_hash),
+            TextService::get(ctx, &revision.compiled_hash),
+        )?;
+
+        Ok(ForumPostOutput {
+            post,
+            revision,
+            wikitext,
+            compiled_html,
+        })
+    }
+
+    /// Determines how deeply nested a post is.
+    ///
+    /// Top-level posts in a thread have a depth of zero.
+    async fn get_depth(ctx: &ServiceContext<'_>, post: &ForumPostModel) -> Result<i32> {
+        let txn = ctx.transaction();
+        let mut depth = 0;
+        let mut parent_post_id = post.parent_post_id;
+
+        // Deleted posts still count towards nesting, so look them up directly
+        while let Some(post_id) = parent_post_id {
+            let parent = ForumPost::find_by_id(post_id)
+                .one(txn)
+                .await?
+                .ok_or(Error::ForumPostNotFound)?;
+
+            depth += 1;
+            parent_post_id = parent.parent_post_id;
+        }
+
+        Ok(depth)
+    }
+
+    async fn render(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        wikitext: String,
+    ) -> Result<RenderOutput> {
+        info!("Rendering forum post wikitext ({} bytes)", wikitext.len());
+
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let layout = SettingsService::get_layout(ctx, site_id, None).await?;
+        let settings = WikitextSettings::from_mode(WikitextMode::ForumPost, layout);
+        let page_info = PageInfo {
+            page: cow!(""),
+            category: None,
+            site: cow!(site.slug),
+            title: cow!(""),
+            alt_title: None,
+            score: ScoreValue::Integer(0),
+            tags: vec![],
+            language: cow!(site.locale),
+        };
+
+        RenderService::render(ctx, wikitext, &page_info, &settings).await
+    }
+
+    async fn run_filter(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        title: Option<&String>,
+        wikitext: Option<&String>,
+    ) -> Result<()> {
+        info!("Checking forum post data against filters...");
+
+        let filter_matcher = FilterService::get_matcher(
+            ctx,
+            FilterClass::PlatformAndSite(site_id),
+            FilterType::Forum,
+        )
+        .await?;
+
+        if let Some(title) = title {
+            filter_matcher.verify(ctx, title).await?;
+        }
+
+        if let Some(wikitext) = wikitext {
+            filter_matcher.verify(ctx, wikitext).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+/*
+ * services/forum_post/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::forum_post::Model as ForumPostModel;
+use crate::models::forum_post_revision::Model as ForumPostRevisionModel;
+use crate::types::Maybe;
+use ftml::parsing::ParseError;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateForumPost {
+    pub forum_thread_id: i64,
+    pub parent_post_id: Option<i64>,
+    pub user_id: i64,
+    pub title: String,
+    pub wikitext: String,
+
+    #[serde(default)]
+    pub bypass_filter: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CreateForumPostOutput {
+    pub post: ForumPostModel,
+    pub revision_id: i64,
+    pub parser_errors: Vec<ParseError>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetForumPost {
+    pub forum_post_id: i64,
+}
+
+pub type GetForumPostRevisions = GetForumPost;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetForumPosts {
+    pub forum_thread_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetForumPostRevision {
+    pub forum_post_id: i64,
+    pub revision_number: i32,
+}
+
+/// A forum post along with its current contents.
+#[derive(Serialize, Debug, Clone)]
+pub struct ForumPostOutput {
+    pub post: ForumPostModel,
+    pub revision: ForumPostRevisionModel,
+    pub wikitext: String,
+    pub compiled_html: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EditForumPost {
+    pub forum_post_id: i64,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub bypass_filter: bool,
+
+    #[serde(flatten)]
+    pub body: EditForumPostBody,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct EditForumPostBody {
+    pub title: Maybe<String>,
+    pub wikitext: Maybe<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct EditForumPostOutput {
+    pub revision: ForumPostRevisionModel,
+    pub parser_errors: Vec<ParseError>,
+}
+
+/// Helper structure for inserting a new forum post revision.
+#[derive(Debug, Clone)]
+pub struct CreateForumPostRevision {
+    pub forum_post_id: i64,
+    pub revision_number: i32,
+    pub user_id: i64,
+    pub title: String,
+    pub wikitext: String,
+}
@@ -0,0 +1,376 @@
+/*
+ * services/forum_thread/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Forum threads, each containing a tree of forum posts.
+//!
+//! A thread is always created along with its first post, which holds the
+//! contents of the thread. Its title is checked against forum filters,
+//! and the thread can be locked to prevent any further posts or edits.
+//!
+//! The exception is page discussion threads, which are created empty the
+//! first time a page's discussion is requested.
+
+use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
+use crate::models::forum_post::{self, Entity as ForumPost};
+use crate::models::forum_thread::{
+    self, Entity as ForumThread, Model as ForumThreadModel,
+};
+use crate::models::page::{self, Entity as Page, Model as PageModel};
+use crate::services::filter::{FilterClass, FilterType};
+use crate::services::forum_post::{CreateForumPost, CreateForumPostOutput};
+use crate::services::{
+    FilterService, ForumCategoryService, ForumPostService, PageRevisionService,
+    PageService,
+};
+
+#[derive(Debug)]
+pub struct ForumThreadService;
+
+impl ForumThreadService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateForumThread {
+            site_id,
+            forum_category_id,
+            user_id,
+            title,
+            description,
+            wikitext,
+            bypass_filter,
+        }: CreateForumThread,
+    ) -> Result<CreateForumThreadOutput> {
+        info!(
+            "Creating forum thread '{title}' in category ID {forum_category_id} (site ID {site_id})",
+        );
+
+        Self::check_category(ctx, site_id, forum_category_id).await?;
+
+        if title.is_empty() {
+            error!("Forum thread title cannot be empty");
+            return Err(Error::ForumThreadTitleEmpty);
+        }
+
+        if !bypass_filter {
+            Self::run_filter(ctx, site_id, Some(&title), Some(&description)).await?;
+        }
+
+        let txn = ctx.transaction();
+        let model = forum_thread::ActiveModel {
+            created_by: Set(user_id),
+            site_id: Set(site_id),
+            forum_category_id: Set(forum_category_id),
+            title: Set(title.clone()),
+            description: Set(description),
+            ..Default::default()
+        };
+        let thread = model.insert(txn).await?;
+
+        // Create the first post, which holds the thread's contents
+        let CreateForumPostOutput {
+            post,
+            revision_id,
+            parser_errors,
+        } = ForumPostService::create(
+            ctx,
+            CreateForumPost {
+                forum_thread_id: thread.forum_thread_id,
+                parent_post_id: None,
+                user_id,
+                title,
+                wikitext,
+                bypass_filter,
+            },
+        )
+        .await?;
+
+        // Re-fetch, since creating the post bumps the thread's update timestamp
+        let thread = Self::get(ctx, thread.forum_thread_id).await?;
+
+        Ok(CreateForumThreadOutput {
+            thread,
+            post,
+            revision_id,
+            parser_errors,
+        })
+    }
+
+    pub async fn update(
+        ctx: &ServiceContext<'_>,
+        UpdateForumThread {
+            forum_thread_id,
+            bypass_filter,
+            body:
+                UpdateForumThreadBody {
+                    forum_category_id,
+                    title,
+                    description,
+                    sticky,
+                    locked,
+                },
+        }: UpdateForumThread,
+    ) -> Result<ForumThreadModel> {
+        info!("Updating forum thread ID {forum_thread_id}");
+
+        let thread = Self::get(ctx, forum_thread_id).await?;
+        if let Maybe::Set(forum_category_id) = forum_category_id {
+            Self::check_category(ctx, thread.site_id, forum_category_id).await?;
+        }
+
+        if let Maybe::Set(ref title) = title {
+            if title.is_empty() {
+                error!("Forum thread title cannot be empty");
+                return Err(Error::ForumThreadTitleEmpty);
+            }
+        }
+
+        if !bypass_filter {
+            Self::run_filter(
+                ctx,
+                thread.site_id,
+                title.to_option(),
+                description.to_option(),
+            )
+            .await?;
+        }
+
+        let txn = ctx.transaction();
+        let model = forum_thread::ActiveModel {
+            forum_thread_id: Set(forum_thread_id),
+            updated_at: Set(Some(now())),
+            forum_category_id: forum_category_id.into_active_value(),
+            title: title.into_active_value(),
+            description: description.into_active_value(),
+            sticky: sticky.into_active_value(),
+            locked: locked.into_active_value(),
+            ..Default::default()
+        };
+
+        let thread = model.update(txn).await?;
+        Ok(thread)
+    }
+
+    /// Recalculates the cached post count and last post of a thread.
+    ///
+    /// This also marks the thread as having new activity.
+    pub async fn refresh_post_stats(
+        ctx: &ServiceContext<'_>,
+        forum_thread_id: i64,
+    ) -> Result<()> {
+        debug!("Refreshing post statistics for forum thread ID {forum_thread_id}");
+
+        let txn = ctx.transaction();
+        let condition = Condition::all()
+            .add(forum_post::Column::ForumThreadId.eq(forum_thread_id))
+            .add(forum_post::Column::DeletedAt.is_null());
+
+        let post_count = ForumPost::find()
+            .filter(condition.clone())
+            .count(txn)
+            .await?;
+
+        let last_post = ForumPost::find()
+            .filter(condition)
+            .order_by_desc(forum_post::Column::CreatedAt)
+            .one(txn)
+            .await?;
+
+        let model = forum_thread::ActiveModel {
+            forum_thread_id: Set(forum_thread_id),
+            updated_at: Set(Some(now())),
+            post_count: Set(i32::try_from(post_count).unwrap_or(i32::MAX)),
+            last_post_at: Set(last_post.as_ref().map(|post| post.created_at)),
+            last_post_by: Set(last_post.as_ref().map(|post| post.created_by)),
+            ..Default::default()
+        };
+
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    pub async fn delete(ctx: &ServiceContext<'_>, forum_thread_id: i64) -> Result<()> {
+        info!("Deleting forum thread ID {forum_thread_id}");
+
+        // Ensure the thread exists and is not already deleted
+        Self::get(ctx, forum_thread_id).await?;
+
+        let txn = ctx.transaction();
+        let model = forum_thread::ActiveModel {
+            forum_thread_id: Set(forum_thread_id),
+            deleted_at: Set(Some(now())),
+            ..Default::default()
+        };
+
+        model.update(txn).await?;
+        Ok(())
+    }
+
+    /// Gets a forum thread, if it exists and has not been deleted.
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        forum_thread_id: i64,
+    ) -> Result<Option<ForumThreadModel>> {
+        let txn = ctx.transaction();
+        let thread = ForumThread::find()
+            .filter(
+                Condition::all()
+                    .add(forum_thread::Column::ForumThreadId.eq(forum_thread_id))
+                    .add(forum_thread::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(thread)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        forum_thread_id: i64,
+    ) -> Result<ForumThreadModel> {
+        find_or_error!(Self::get_optional(ctx, forum_thread_id), ForumThread)
+    }
+
+    /// Gets the discussion thread for a page, if it has one.
+    pub async fn get_for_page_optional(
+        ctx: &ServiceContext<'_>,
+        page: &PageModel,
+    ) -> Result<Option<ForumThreadModel>> {
+        match page.discussion_thread_id {
+            Some(forum_thread_id) => Self::get_optional(ctx, forum_thread_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the discussion thread for a page, creating it if needed.
+    ///
+    /// If the page's previous thread was deleted, a fresh one is created.
+    pub async fn get_or_create_for_page(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+    ) -> Result<ForumThreadModel> {
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+
+        // Re-read the page with a lock, so concurrent requests can't each create a thread
+        let page = Page::find_by_id(page_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::PageNotFound)?;
+
+        if let Some(thread) = Self::get_for_page_optional(ctx, &page).await? {
+            return Ok(thread);
+        }
+
+        info!("Creating discussion thread for page ID {page_id} in site ID {site_id}");
+
+        let category =
+            ForumCategoryService::get_or_create_page_discussion(ctx, site_id).await?;
+        let revision = PageRevisionService::get_latest(ctx, site_id, page_id).await?;
+        let thread = forum_thread::ActiveModel {
+            created_by: Set(SYSTEM_USER_ID),
+            site_id: Set(site_id),
+            forum_category_id: Set(category.forum_category_id),
+            title: Set(revision.title),
+            description: Set(str!("")),
+            ..Default::default()
+        }
+        .insert(txn)
+        .await?;
+
+        let model = page::ActiveModel {
+            page_id: Set(page_id),
+            discussion_thread_id: Set(Some(thread.forum_thread_id)),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        Ok(thread)
+    }
+
+    /// Gets all threads in a forum category.
+    ///
+    /// Sticky threads come first, then the rest by most recent activity.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        forum_category_id: i64,
+    ) -> Result<Vec<ForumThreadModel>> {
+        let txn = ctx.transaction();
+        let threads = ForumThread::find()
+            .filter(
+                Condition::all()
+                    .add(forum_thread::Column::ForumCategoryId.eq(forum_category_id))
+                    .add(forum_thread::Column::DeletedAt.is_null()),
+            )
+            .order_by_desc(forum_thread::Column::Sticky)
+            .order_by_desc(forum_thread::Column::UpdatedAt)
+            .order_by_desc(forum_thread::Column::ForumThreadId)
+            .all(txn)
+            .await?;
+
+        Ok(threads)
+    }
+
+    /// Ensures the forum category exists and is part of the given site.
+    async fn check_category(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        forum_category_id: i64,
+    ) -> Result<()> {
+        let category = ForumCategoryService::get(ctx, forum_category_id).await?;
+        if category.site_id != site_id {
+            error!(
+                "Forum category ID {forum_category_id} is in site ID {}, not site ID {site_id}",
+                category.site_id,
+            );
+            return Err(Error::ForumCategoryNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn run_filter(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        title: Option<&String>,
+        description: Option<&String>,
+    ) -> Result<()> {
+        info!("Checking forum thread data against filters...");
+
+        let filter_matcher = FilterService::get_matcher(
+            ctx,
+            FilterClass::PlatformAndSite(site_id),
+            FilterType::Forum,
+        )
+        .await?;
+
+        if let Some(title) = title {
+            filter_matcher.verify(ctx, title).await?;
+        }
+
+        if let Some(description) = description {
+            filter_matcher.verify(ctx, description).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ * services/forum_thread/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::forum_post::Model as ForumPostModel;
+use crate::models::forum_thread::Model as ForumThreadModel;
+use crate::types::Maybe;
+use ftml::parsing::ParseError;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateForumThread {
+    pub site_id: i64,
+    pub forum_category_id: i64,
+    pub user_id: i64,
+    pub title: String,
+    pub description: String,
+    pub wikitext: String,
+
+    #[serde(default)]
+    pub bypass_filter: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CreateForumThreadOutput {
+    pub thread: ForumThreadModel,
+    pub post: ForumPostModel,
+    pub revision_id: i64,
+    pub parser_errors: Vec<ParseError>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetForumThread {
+    pub forum_thread_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetForumThreads {
+    pub forum_category_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateForumThread {
+    pub forum_thread_id: i64,
+
+    #[serde(default)]
+    pub bypass_filter: bool,
+
+    #[serde(flatten)]
+    pub body: UpdateForumThreadBody,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct UpdateForumThreadBody {
+    pub forum_category_id: Maybe<i64>,
+    pub title: Maybe<String>,
+    pub description: Maybe<String>,
+    pub sticky: Maybe<bool>,
+    pub locked: Maybe<bool>,
+}
@@ -30,12 +30,20 @@
 #![allow(dead_code)]
 
 use super::prelude::*;
+use crate::models::forum_category::{self, Entity as ForumCategory};
+use crate::models::forum_group::{self, Entity as ForumGroup};
+use crate::models::forum_post::{self, Entity as ForumPost};
+use crate::models::forum_post_revision;
+use crate::models::forum_thread::{self, Entity as ForumThread};
 use crate::models::page::{self, Entity as Page};
 use crate::models::page_category::Model as PageCategoryModel;
 use crate::models::sea_orm_active_enums::UserType;
 use crate::models::site::{self, Entity as Site};
 use crate::models::user::{self, Entity as User};
-use crate::services::{BlobService, CategoryService};
+use crate::services::forum_post::CreateForumPostRevision;
+use crate::services::{
+    BlobService, CategoryService, ForumPostService, ForumThreadService,
+};
 use crate::utils::get_category_name;
 
 #[derive(Debug)]
@@ -177,5 +185,170 @@ impl ImportService {
     // TODO page_vote
 
     // TODO file
-    // TODO forum
+
+    pub async fn add_forum_group(
+        ctx: &ServiceContext<'_>,
+        ImportForumGroup {
+            forum_group_id,
+            site_id,
+            name,
+            description,
+            sort_index,
+            visible,
+        }: ImportForumGroup,
+    ) -> Result<()> {
+        info!("Importing forum group '{}' in site ID {}", name, site_id);
+
+        let txn = ctx.transaction();
+        let group = forum_group::ActiveModel {
+            forum_group_id: Set(forum_group_id),
+            from_wikidot: Set(true),
+            site_id: Set(site_id),
+            name: Set(name),
+            description: Set(description),
+            sort_index: Set(sort_index),
+            visible: Set(visible),
+            ..Default::default()
+        };
+
+        ForumGroup::insert(group).exec(txn).await?;
+        Ok(())
+    }
+
+    pub async fn add_forum_category(
+        ctx: &ServiceContext<'_>,
+        ImportForumCategory {
+            forum_category_id,
+            forum_group_id,
+            site_id,
+            name,
+            description,
+            sort_index,
+            max_nest_level,
+            per_page_discussion,
+        }: ImportForumCategory,
+    ) -> Result<()> {
+        info!("Importing forum category '{}' in site ID {}", name, site_id);
+
+        let txn = ctx.transaction();
+        let mut category = forum_category::ActiveModel {
+            forum_category_id: Set(forum_category_id),
+            from_wikidot: Set(true),
+            site_id: Set(site_id),
+            forum_group_id: Set(forum_group_id),
+            name: Set(name),
+            description: Set(description),
+            sort_index: Set(sort_index),
+            per_page_discussion: Set(per_page_discussion),
+            ..Default::default()
+        };
+
+        // Wikidot uses null to mean the site-wide default
+        if let Some(max_nest_level) = max_nest_level {
+            category.max_nest_level = Set(max_nest_level);
+        }
+
+        ForumCategory::insert(category).exec(txn).await?;
+        Ok(())
+    }
+
+    pub async fn add_forum_thread(
+        ctx: &ServiceContext<'_>,
+        ImportForumThread {
+            forum_thread_id,
+            forum_category_id,
+            site_id,
+            user_id,
+            created_at,
+            title,
+            description,
+            sticky,
+            locked,
+        }: ImportForumThread,
+    ) -> Result<()> {
+        info!("Importing forum thread '{}' in site ID {}", title, site_id);
+
+        let txn = ctx.transaction();
+        let thread = forum_thread::ActiveModel {
+            forum_thread_id: Set(forum_thread_id),
+            created_at: Set(created_at),
+            from_wikidot: Set(true),
+            created_by: Set(user_id),
+            site_id: Set(site_id),
+            forum_category_id: Set(forum_category_id),
+            title: Set(title),
+            description: Set(description),
+            sticky: Set(sticky),
+            locked: Set(locked),
+            ..Default::default()
+        };
+
+        ForumThread::insert(thread).exec(txn).await?;
+        Ok(())
+    }
+
+    pub async fn add_forum_post(
+        ctx: &ServiceContext<'_>,
+        ImportForumPost {
+            forum_post_id,
+            forum_thread_id,
+            parent_post_id,
+            site_id,
+            user_id,
+            created_at,
+            revisions,
+        }: ImportForumPost,
+    ) -> Result<()> {
+        info!(
+            "Importing forum post ID {} in thread ID {} ({} revisions)",
+            forum_post_id,
+            forum_thread_id,
+            revisions.len(),
+        );
+
+        let txn = ctx.transaction();
+        let post = forum_post::ActiveModel {
+            forum_post_id: Set(forum_post_id),
+            created_at: Set(created_at),
+            from_wikidot: Set(true),
+            created_by: Set(user_id),
+            site_id: Set(site_id),
+            forum_thread_id: Set(forum_thread_id),
+            parent_post_id: Set(parent_post_id),
+            ..Default::default()
+        };
+
+        ForumPost::insert(post).exec(txn).await?;
+
+        for (revision_number, revision) in revisions.into_iter().enumerate() {
+            let ImportForumPostRevision {
+                user_id,
+                created_at,
+                title,
+                wikitext,
+            } = revision;
+
+            let (model, _) = ForumPostService::create_revision(
+                ctx,
+                site_id,
+                CreateForumPostRevision {
+                    forum_post_id,
+                    revision_number: revision_number as i32,
+                    user_id,
+                    title,
+                    wikitext,
+                },
+            )
+            .await?;
+
+            // Preserve the original timestamp and origin
+            let mut model = forum_post_revision::ActiveModel::from(model);
+            model.created_at = Set(created_at);
+            model.from_wikidot = Set(true);
+            model.update(txn).await?;
+        }
+
+        ForumThreadService::refresh_post_stats(ctx, forum_thread_id).await?;
+        Ok(())
+    }
 }
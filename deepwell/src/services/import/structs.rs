@@ -61,3 +61,65 @@ pub struct ImportPage {
     pub locked: bool,
     pub discussion_thread_id: Option<i64>,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct ImportForumGroup {
+    pub forum_group_id: i64,
+    pub site_id: i64,
+    pub name: String,
+    pub description: String,
+    pub sort_index: i32,
+    pub visible: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportForumCategory {
+    pub forum_category_id: i64,
+    pub forum_group_id: i64,
+    pub site_id: i64,
+    pub name: String,
+    pub description: String,
+    pub sort_index: i32,
+    pub max_nest_level: Option<i32>,
+    pub per_page_discussion: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportForumThread {
+    pub forum_thread_id: i64,
+    pub forum_category_id: i64,
+    pub site_id: i64,
+    pub user_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    pub title: String,
+    pub description: String,
+    pub sticky: bool,
+    pub locked: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportForumPost {
+    pub forum_post_id: i64,
+    pub forum_thread_id: i64,
+    pub parent_post_id: Option<i64>,
+    pub site_id: i64,
+    pub user_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+
+    /// All revisions of this post, oldest first.
+    pub revisions: Vec<ImportForumPostRevision>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportForumPostRevision {
+    pub user_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    pub title: String,
+    pub wikitext: String,
+}
@@ -21,9 +21,22 @@
 use super::prelude::*;
 use rsmq_async::RsmqConnection;
 use std::time::Duration;
+use time::OffsetDateTime;
 
 pub const JOB_QUEUE_NAME: &str = "job";
 
+/// The Redis key namespace RSMQ uses for its queues.
+///
+/// We don't specify one when connecting, so this is the library default.
+/// It's needed to inspect queued jobs, which RSMQ itself has no method for.
+const RSMQ_NAMESPACE: &str = "rsmq";
+
+/// Redis hash of jobs which have failed and are queued for retry, by job ID.
+const JOB_FAILED_KEY: &str = "job:failed";
+
+/// Redis hash of jobs which have exhausted their attempts, by job ID.
+const JOB_DEAD_KEY: &str = "job:dead";
+
 /// How long messages, after being delivered, cannot be delivered to another consumer.
 ///
 /// This feature is a part of job queues to prevent a job from being run twice by
@@ -39,6 +52,12 @@ pub const JOB_QUEUE_PROCESS_TIME: Option<Duration> = Some(Duration::from_secs(30
 /// How long to wait before messages are delivered to consumers.
 pub const JOB_QUEUE_DELAY: Option<Duration> = None;
 
+/// The longest delay RSMQ permits for a message, a little under 116 days.
+///
+/// Jobs which need to run further in the future than this must
+/// queue themselves again until they are due.
+pub const JOB_MAXIMUM_DELAY: Duration = Duration::from_secs(9999999);
+
 /// The maximum size, in bytes, that a job payload is allowed to be
 ///
 /// Presently, our jobs are mostly unit types, and the biggest variant
@@ -59,11 +78,273 @@ impl JobService {
         delay: Option<Duration>,
     ) -> Result<()> {
         info!("Queuing job {job:?} (delay {delay:?})");
-        let payload = serde_json::to_vec(job)?;
-        ctx.rsmq()
+        Self::send(ctx, &QueuedJob::from(job.clone()), delay).await?;
+        Ok(())
+    }
+
+    /// Adds the job to the queue, returning its ID.
+    async fn send(
+        ctx: &ServiceContext<'_>,
+        queued: &QueuedJob,
+        delay: Option<Duration>,
+    ) -> Result<String> {
+        let payload = serde_json::to_vec(queued)?;
+        let job_id = ctx
+            .rsmq()
             .send_message(JOB_QUEUE_NAME, payload, delay)
             .await?;
 
+        Ok(job_id)
+    }
+
+    /// Runs a recurring job now, outside of its regular schedule.
+    pub async fn trigger(ctx: &ServiceContext<'_>, job: RecurringJob) -> Result<String> {
+        info!("Triggering recurring job {job:?} on demand");
+
+        let queued = QueuedJob {
+            job: Job::from(job),
+            attempt: 0,
+            on_demand: true,
+        };
+
+        Self::send(ctx, &queued, None).await
+    }
+
+    /// Clears any failure record after a job finally succeeds.
+    pub async fn record_success(ctx: &ServiceContext<'_>, job_id: &str) -> Result<()> {
+        let mut conn = ctx.redis_connect().await?;
+        redis::cmd("HDEL")
+            .arg(JOB_FAILED_KEY)
+            .arg(job_id)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Handles a failed job attempt.
+    ///
+    /// The job is queued again with exponential backoff, or if it has
+    /// no attempts left, moved to the dead-letter list. The caller is
+    /// responsible for deleting the original message from the queue.
+    pub async fn record_failure(
+        ctx: &ServiceContext<'_>,
+        job_id: &str,
+        queued: QueuedJob,
+        error: String,
+    ) -> Result<()> {
+        let config = ctx.config();
+        let mut conn = ctx.redis_connect().await?;
+        let attempt = queued.attempt.saturating_add(1);
+        let failed_at = now();
+
+        redis::cmd("HDEL")
+            .arg(JOB_FAILED_KEY)
+            .arg(job_id)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        if attempt >= config.job_max_attempts {
+            error!("Job {job_id} failed on its last attempt ({attempt}), moving to dead-letter list");
+
+            let record = JobRecord {
+                job_id: str!(job_id),
+                queued: QueuedJob { attempt, ..queued },
+                visible_at: None,
+                error: Some(error),
+                failed_at: Some(failed_at),
+            };
+
+            redis::cmd("HSET")
+                .arg(JOB_DEAD_KEY)
+                .arg(job_id)
+                .arg(serde_json::to_string(&record)?)
+                .query_async::<_, ()>(&mut conn)
+                .await?;
+
+            return Ok(());
+        }
+
+        let delay = retry_delay(config.job_retry_delay, attempt);
+        warn!("Job {job_id} failed on attempt {attempt}, retrying in {delay:?}");
+
+        let queued = QueuedJob { attempt, ..queued };
+        let new_job_id = Self::send(ctx, &queued, Some(delay)).await?;
+        let record = JobRecord {
+            job_id: new_job_id,
+            queued,
+            visible_at: Some(failed_at + delay),
+            error: Some(error),
+            failed_at: Some(failed_at),
+        };
+
+        redis::cmd("HSET")
+            .arg(JOB_FAILED_KEY)
+            .arg(&record.job_id)
+            .arg(serde_json::to_string(&record)?)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_status(ctx: &ServiceContext<'_>) -> Result<JobQueueStatus> {
+        let attributes = ctx.rsmq().get_queue_attributes(JOB_QUEUE_NAME).await?;
+        let mut conn = ctx.redis_connect().await?;
+        let (failed, dead): (u64, u64) = redis::pipe()
+            .cmd("HLEN")
+            .arg(JOB_FAILED_KEY)
+            .cmd("HLEN")
+            .arg(JOB_DEAD_KEY)
+            .query_async(&mut conn)
+            .await?;
+
+        Ok(JobQueueStatus {
+            pending: attributes.msgs.saturating_sub(attributes.hiddenmsgs),
+            hidden: attributes.hiddenmsgs,
+            failed,
+            dead,
+            total_sent: attributes.totalsent,
+            total_received: attributes.totalrecv,
+        })
+    }
+
+    pub async fn get_jobs(
+        ctx: &ServiceContext<'_>,
+        GetJobs { kind, limit }: GetJobs,
+    ) -> Result<Vec<JobRecord>> {
+        let mut conn = ctx.redis_connect().await?;
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+
+        let key = match kind {
+            JobListKind::Failed => JOB_FAILED_KEY,
+            JobListKind::Dead => JOB_DEAD_KEY,
+            JobListKind::Queued => {
+                // Read directly from RSMQ's storage.
+                //
+                // The queue is a sorted set of job IDs, scored by the timestamp
+                // (in milliseconds) at which they become visible, with the
+                // payloads stored in a separate hash.
+                let queue_key = format!("{RSMQ_NAMESPACE}:{JOB_QUEUE_NAME}");
+                let entries: Vec<(String, i64)> = redis::cmd("ZRANGE")
+                    .arg(&queue_key)
+                    .arg(0)
+                    .arg(limit.saturating_sub(1))
+                    .arg("WITHSCORES")
+                    .query_async(&mut conn)
+                    .await?;
+
+                let mut jobs = Vec::with_capacity(entries.len());
+                for (job_id, visible_ms) in entries {
+                    let payload: Option<Vec<u8>> = redis::cmd("HGET")
+                        .arg(format!("{queue_key}:Q"))
+                        .arg(&job_id)
+                        .query_async(&mut conn)
+                        .await?;
+
+                    // Could have been deleted in the meantime
+                    let Some(payload) = payload else { continue };
+                    let queued = serde_json::from_slice(&payload)?;
+                    let visible_at = OffsetDateTime::from_unix_timestamp_nanos(
+                        i128::from(visible_ms) * 1_000_000,
+                    )
+                    .ok();
+
+                    jobs.push(JobRecord {
+                        job_id,
+                        queued,
+                        visible_at,
+                        error: None,
+                        failed_at: None,
+                    });
+                }
+
+                return Ok(jobs);
+            }
+        };
+
+        let values: Vec<String> =
+            redis::cmd("HVALS").arg(key).query_async(&mut conn).await?;
+
+        let mut jobs = values
+            .iter()
+            .map(|value| serde_json::from_str(value))
+            .collect::<StdResult<Vec<JobRecord>, _>>()?;
+
+        jobs.sort_by_key(|record| record.failed_at);
+        jobs.truncate(limit);
+        Ok(jobs)
+    }
+
+    /// Retries a job immediately.
+    ///
+    /// Dead jobs are queued again with a fresh set of attempts, and queued
+    /// jobs (such as those waiting to be retried) are made visible now.
+    /// Returns the ID of the job in the queue.
+    pub async fn retry(ctx: &ServiceContext<'_>, job_id: &str) -> Result<String> {
+        info!("Retrying job {job_id}");
+
+        let mut conn = ctx.redis_connect().await?;
+        let dead: Option<String> = redis::cmd("HGET")
+            .arg(JOB_DEAD_KEY)
+            .arg(job_id)
+            .query_async(&mut conn)
+            .await?;
+
+        if let Some(dead) = dead {
+            let record: JobRecord = serde_json::from_str(&dead)?;
+            let queued = QueuedJob {
+                attempt: 0,
+                ..record.queued
+            };
+
+            let new_job_id = Self::send(ctx, &queued, None).await?;
+            redis::cmd("HDEL")
+                .arg(JOB_DEAD_KEY)
+                .arg(job_id)
+                .query_async::<_, ()>(&mut conn)
+                .await?;
+
+            return Ok(new_job_id);
+        }
+
+        let queued: Option<i64> = redis::cmd("ZSCORE")
+            .arg(format!("{RSMQ_NAMESPACE}:{JOB_QUEUE_NAME}"))
+            .arg(job_id)
+            .query_async(&mut conn)
+            .await?;
+
+        if queued.is_none() {
+            return Err(Error::JobNotFound);
+        }
+
+        ctx.rsmq()
+            .change_message_visibility(JOB_QUEUE_NAME, job_id, Duration::ZERO)
+            .await?;
+
+        Ok(str!(job_id))
+    }
+
+    /// Removes a job, whether it is queued or dead.
+    pub async fn discard(ctx: &ServiceContext<'_>, job_id: &str) -> Result<()> {
+        info!("Discarding job {job_id}");
+
+        let mut conn = ctx.redis_connect().await?;
+        let (removed_dead, _): (u32, u32) = redis::pipe()
+            .cmd("HDEL")
+            .arg(JOB_DEAD_KEY)
+            .arg(job_id)
+            .cmd("HDEL")
+            .arg(JOB_FAILED_KEY)
+            .arg(job_id)
+            .query_async(&mut conn)
+            .await?;
+
+        let removed_queued = ctx.rsmq().delete_message(JOB_QUEUE_NAME, job_id).await?;
+        if removed_dead == 0 && !removed_queued {
+            return Err(Error::JobNotFound);
+        }
+
         Ok(())
     }
 
@@ -76,11 +357,13 @@ impl JobService {
     ///                   outdating, then this value should be incremented with each layer
     ///                   of job depth. This way we can avoid infinite loop conditions where
     ///                   jobs endlessly pile onto the queue, rerendering each other.
+    /// * `delay` &mdash; How long to wait before the rerender, if at all.
     pub async fn queue_rerender_page(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         page_id: i64,
         depth: u32,
+        delay: Option<Duration>,
     ) -> Result<()> {
         debug!("Queuing page rerender for page ID {page_id} and site ID {site_id}");
         Self::queue_job(
@@ -90,8 +373,36 @@ impl JobService {
                 page_id,
                 depth,
             },
-            None,
+            delay,
         )
         .await
     }
+
+    /// Queues a recalculation of all materialized page scores in a site.
+    pub async fn queue_refresh_scores(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<()> {
+        debug!("Queuing score refresh for site ID {site_id}");
+        Self::queue_job(ctx, &Job::RefreshScores { site_id }, None).await
+    }
+}
+
+/// Determines how long to wait before retrying a job.
+///
+/// The delay doubles with each attempt after the first.
+fn retry_delay(base: Duration, attempt: u16) -> Duration {
+    let exponent = u32::from(attempt.saturating_sub(1)).min(16);
+    base.saturating_mul(2_u32.pow(exponent))
+}
+
+#[test]
+fn test_retry_delay() {
+    let base = Duration::from_secs(10);
+
+    assert_eq!(retry_delay(base, 0), Duration::from_secs(10));
+    assert_eq!(retry_delay(base, 1), Duration::from_secs(10));
+    assert_eq!(retry_delay(base, 2), Duration::from_secs(20));
+    assert_eq!(retry_delay(base, 3), Duration::from_secs(40));
+    assert_eq!(retry_delay(base, 100), Duration::from_secs(655360));
 }
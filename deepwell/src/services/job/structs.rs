@@ -18,6 +18,8 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use time::OffsetDateTime;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case", tag = "job", content = "data")]
 pub enum Job {
@@ -28,7 +30,145 @@ pub enum Job {
     },
     PruneSessions,
     PruneText,
+    PruneDrafts,
     // TODO add job for pruning incomplete uploads (pending_blob table and corresponding columns for foreign keys)
     NameChangeRefill,
     LiftExpiredPunishments,
+    ExportSite {
+        backup_id: i64,
+    },
+    RefreshScores {
+        site_id: i64,
+    },
+    PublishScheduledPage {
+        schedule_id: i64,
+    },
+    WarnSiteStorage {
+        site_id: i64,
+    },
+}
+
+/// A job as stored in the queue, along with its delivery metadata.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueuedJob {
+    #[serde(flatten)]
+    pub job: Job,
+
+    /// How many times this job has already been attempted and failed.
+    #[serde(default)]
+    pub attempt: u16,
+
+    /// Whether this was triggered manually.
+    ///
+    /// Recurring jobs run this way do not queue their follow-up job,
+    /// since the regular schedule is still queued separately.
+    #[serde(default)]
+    pub on_demand: bool,
+}
+
+impl From<Job> for QueuedJob {
+    #[inline]
+    fn from(job: Job) -> QueuedJob {
+        QueuedJob {
+            job,
+            attempt: 0,
+            on_demand: false,
+        }
+    }
+}
+
+/// Recurring jobs which can be run on demand.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecurringJob {
+    PruneSessions,
+    PruneText,
+    PruneDrafts,
+    NameChangeRefill,
+    LiftExpiredPunishments,
+}
+
+impl From<RecurringJob> for Job {
+    fn from(job: RecurringJob) -> Job {
+        match job {
+            RecurringJob::PruneSessions => Job::PruneSessions,
+            RecurringJob::PruneText => Job::PruneText,
+            RecurringJob::PruneDrafts => Job::PruneDrafts,
+            RecurringJob::NameChangeRefill => Job::NameChangeRefill,
+            RecurringJob::LiftExpiredPunishments => Job::LiftExpiredPunishments,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobListKind {
+    /// Jobs waiting in the queue, including any being run or delayed.
+    Queued,
+
+    /// Jobs which have failed, and are queued to be retried.
+    Failed,
+
+    /// Jobs which have failed on every attempt, and will not be retried.
+    Dead,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetJobs {
+    pub kind: JobListKind,
+
+    #[serde(default = "default_job_limit")]
+    pub limit: u32,
+}
+
+fn default_job_limit() -> u32 {
+    100
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct JobReference {
+    pub job_id: String,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct TriggerJob {
+    pub job: RecurringJob,
+}
+
+/// Information about a job in the queue, or one which has failed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JobRecord {
+    pub job_id: String,
+
+    #[serde(flatten)]
+    pub queued: QueuedJob,
+
+    /// When this job is next available to workers, if queued.
+    #[serde(with = "time::serde::rfc3339::option", default)]
+    pub visible_at: Option<OffsetDateTime>,
+
+    /// The error from the last failed attempt, if any.
+    #[serde(default)]
+    pub error: Option<String>,
+
+    #[serde(with = "time::serde::rfc3339::option", default)]
+    pub failed_at: Option<OffsetDateTime>,
+}
+
+#[derive(Serialize, Debug, Copy, Clone)]
+pub struct JobQueueStatus {
+    /// Jobs which are ready to be picked up by a worker.
+    pub pending: u64,
+
+    /// Jobs which are currently being run, or are delayed.
+    pub hidden: u64,
+
+    /// Jobs which have failed and are awaiting a retry.
+    pub failed: u64,
+
+    /// Jobs which exhausted all attempts.
+    pub dead: u64,
+
+    pub total_sent: u64,
+    pub total_received: u64,
 }
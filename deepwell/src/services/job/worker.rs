@@ -22,7 +22,10 @@
 
 use super::prelude::*;
 use crate::api::ServerState;
-use crate::services::{PageRevisionService, SessionService, TextService, UserService};
+use crate::services::{
+    PageDraftService, PageRevisionService, PageScheduleService, ScoreService,
+    SessionService, SiteBackupService, StorageQuotaService, TextService, UserService,
+};
 use crate::utils::debug_pointer;
 use rsmq_async::{PooledRsmq, RsmqConnection, RsmqMessage};
 use sea_orm::TransactionTrait;
@@ -105,10 +108,10 @@ impl JobWorker {
     /// queue assumes the worker died or failed, and then the job is later available
     /// on the queue for workers to retry.
     ///
-    /// This means that, after a `receive_message()`, if and only if the job was
-    /// successfully run (aside from any cases where we specifically decide we do
-    /// not want this job to re-run), we will then run `delete_message()` so that
-    /// it is no longer enqueued.
+    /// This means that, after a `receive_message()`, we only run `delete_message()`
+    /// once the job's outcome has been handled. On success that is the end of it,
+    /// and on failure the job is either queued again as a new message (with
+    /// backoff) or moved to the dead-letter list by `JobService::record_failure()`.
     async fn main_loop(mut self) -> Infallible {
         trace!("Beginning main execution of worker ID {}", self.id);
 
@@ -160,21 +163,97 @@ impl JobWorker {
         debug!("* Previously received: {}", data.rc);
         debug!("* Created:             {}", data.sent);
         debug!("* Received:            {}", data.fr);
-        let job = serde_json::from_slice(&data.message)?;
 
-        let no_more_retries = data.rc >= u64::from(self.state.config.job_max_attempts);
-        if no_more_retries {
-            debug!("Last attempt for this message, it will not be retried if it fails");
-            self.rsmq.delete_message(JOB_QUEUE_NAME, &data.id).await?;
-        }
+        let queued: QueuedJob = match serde_json::from_slice(&data.message) {
+            Ok(queued) => queued,
+            Err(error) => {
+                error!("Unable to parse job {}, discarding: {error}", data.id);
+                self.rsmq.delete_message(JOB_QUEUE_NAME, &data.id).await?;
+                return Ok(JobProcessStatus::ReceivedJob);
+            }
+        };
 
-        debug!("Received job from queue: {job:?}");
+        debug!("Received job from queue: {queued:?}");
         trace!("Setting up ServiceContext for job processing");
         let txn = self.state.database.begin().await?;
         let ctx = &ServiceContext::new(&self.state, &txn);
 
+        // If the message keeps being received without the job either succeeding
+        // or failing, then the worker is crashing or timing out while running it.
+        //
+        // Since a failure is never recorded in that case, we cap it here instead.
+        if data.rc > u64::from(self.state.config.job_max_attempts) {
+            error!(
+                "Job {} has been received {} times without finishing, giving up",
+                data.id, data.rc,
+            );
+
+            let queued = QueuedJob {
+                attempt: self.state.config.job_max_attempts,
+                ..queued
+            };
+            JobService::record_failure(
+                ctx,
+                &data.id,
+                queued,
+                str!("Job did not finish within the processing time"),
+            )
+            .await?;
+            self.rsmq.delete_message(JOB_QUEUE_NAME, &data.id).await?;
+            txn.commit().await?;
+            return Ok(JobProcessStatus::ReceivedJob);
+        }
+
         trace!("Beginning job processing");
-        let next = match job {
+        let next = match self.run_job(ctx, &queued.job).await {
+            Ok(next) => next,
+            Err(error) => {
+                warn!("Job {} failed: {error}", data.id);
+
+                // Undo any partial work from the job, then record
+                // the failure in a fresh transaction.
+                txn.rollback().await?;
+                let txn = self.state.database.begin().await?;
+                let ctx = &ServiceContext::new(&self.state, &txn);
+                JobService::record_failure(ctx, &data.id, queued, error.to_string())
+                    .await?;
+
+                // The retry, if any, is its own message now
+                self.rsmq.delete_message(JOB_QUEUE_NAME, &data.id).await?;
+                txn.commit().await?;
+                return Ok(JobProcessStatus::ReceivedJob);
+            }
+        };
+
+        // Add follow-up job to queue, if required.
+        //
+        // Recurring jobs triggered on demand already have their next run
+        // scheduled, so they don't produce a follow-up.
+        match next {
+            NextJob::Next { .. } if queued.on_demand => {
+                debug!("Job execution finished, was run on demand so not scheduling follow-up job");
+            }
+            NextJob::Done => debug!("Job execution finished, no follow-up job to add"),
+            NextJob::Next { job, delay } => {
+                debug!("Job execution finished, follow-up job has been produced");
+                trace!("* Job:   {job:?}");
+                trace!("* Delay: {delay:?}");
+
+                JobService::queue_job(ctx, &job, delay).await?;
+            }
+        }
+
+        trace!("Job execution finished, deleting message");
+        JobService::record_success(ctx, &data.id).await?;
+        self.rsmq.delete_message(JOB_QUEUE_NAME, &data.id).await?;
+
+        trace!("Committing transaction, returning success");
+        txn.commit().await?;
+        Ok(JobProcessStatus::ReceivedJob)
+    }
+
+    async fn run_job(&self, ctx: &ServiceContext<'_>, job: &Job) -> Result<NextJob> {
+        let next = match *job {
             Job::RerenderPage {
                 site_id,
                 page_id,
@@ -202,6 +281,14 @@ impl JobWorker {
                     delay: Some(self.state.config.job_prune_text),
                 }
             }
+            Job::PruneDrafts => {
+                debug!("Pruning all expired page drafts");
+                PageDraftService::prune(ctx).await?;
+                NextJob::Next {
+                    job: Job::PruneDrafts,
+                    delay: Some(self.state.config.job_prune_draft),
+                }
+            }
             Job::NameChangeRefill => {
                 debug!("Checking users for those who can get a name change token refill");
                 UserService::refresh_name_change_tokens(ctx).await?;
@@ -224,31 +311,34 @@ impl JobWorker {
                     delay: Some(self.state.config.job_lift_expired_punishments),
                 }
             }
-        };
-
-        // Don't delete more than once
-        //
-        // NOTE: We're only at this point if the job succeeded.
-        if !no_more_retries {
-            trace!("Job execution finished, deleting message");
-            self.rsmq.delete_message(JOB_QUEUE_NAME, &data.id).await?;
-        }
-
-        // Add follow-up job to queue, if required.
-        match next {
-            NextJob::Done => debug!("Job execution finished, no follow-up job to add"),
-            NextJob::Next { job, delay } => {
-                debug!("Job execution finished, follow-up job has been produced");
-                trace!("* Job:   {job:?}");
-                trace!("* Delay: {delay:?}");
-
-                JobService::queue_job(ctx, &job, delay).await?;
+            Job::ExportSite { backup_id } => {
+                debug!("Exporting site for backup ID {backup_id}");
+                SiteBackupService::export(ctx, backup_id).await?;
+                NextJob::Done
             }
-        }
+            Job::RefreshScores { site_id } => {
+                debug!("Refreshing all page scores in site ID {site_id}");
+                ScoreService::refresh_site(ctx, site_id).await?;
+                NextJob::Done
+            }
+            Job::PublishScheduledPage { schedule_id } => {
+                debug!("Publishing scheduled page change ID {schedule_id}");
+                match PageScheduleService::publish(ctx, schedule_id).await? {
+                    Some(delay) => NextJob::Next {
+                        job: Job::PublishScheduledPage { schedule_id },
+                        delay: Some(delay),
+                    },
+                    None => NextJob::Done,
+                }
+            }
+            Job::WarnSiteStorage { site_id } => {
+                debug!("Warning site ID {site_id} about its storage usage");
+                StorageQuotaService::warn_site(ctx, site_id).await?;
+                NextJob::Done
+            }
+        };
 
-        trace!("Committing transaction, returning success");
-        txn.commit().await?;
-        Ok(JobProcessStatus::ReceivedJob)
+        Ok(next)
     }
 }
 
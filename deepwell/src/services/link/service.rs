@@ -26,6 +26,7 @@ use crate::models::page_link::{self, Entity as PageLink, Model as PageLinkModel}
 use crate::services::{PageService, SiteService};
 use crate::types::ConnectionType;
 use ftml::data::{Backlinks, PageRef};
+use sea_orm::sea_query::OnConflict;
 use sea_orm::NotSet;
 use std::collections::HashMap;
 
@@ -223,6 +224,50 @@ impl LinkService {
         Ok(GetLinksExternalToOutput { links })
     }
 
+    /// Records that a page lists the given pages through a ListPages module.
+    ///
+    /// These are used to outdate the listing page when the data it displays
+    /// for these pages changes, such as their scores. They are only ever added,
+    /// so pages which are no longer listed may cause unneeded rerenders.
+    pub async fn add_list_pages(
+        ctx: &ServiceContext<'_>,
+        from_page_id: i64,
+        to_page_ids: &[i64],
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let models = to_page_ids
+            .iter()
+            .filter(|&&to_page_id| to_page_id != from_page_id)
+            .map(|&to_page_id| page_connection::ActiveModel {
+                from_page_id: Set(from_page_id),
+                to_page_id: Set(to_page_id),
+                connection_type: Set(str!(ConnectionType::ListPages.name())),
+                created_at: NotSet,
+                updated_at: Set(Some(now())),
+                count: Set(1),
+            })
+            .collect::<Vec<_>>();
+
+        if models.is_empty() {
+            return Ok(());
+        }
+
+        PageConnection::insert_many(models)
+            .on_conflict(
+                OnConflict::columns([
+                    page_connection::Column::FromPageId,
+                    page_connection::Column::ToPageId,
+                    page_connection::Column::ConnectionType,
+                ])
+                .update_column(page_connection::Column::UpdatedAt)
+                .to_owned(),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn update(
         ctx: &ServiceContext<'_>,
         site_id: i64,
@@ -287,8 +332,17 @@ async fn update_connections(
     let txn = ctx.transaction();
 
     // Get existing connections
+    //
+    // ListPages connections aren't part of the backlinks, see add_list_pages()
     let mut connection_chunks = PageConnection::find()
-        .filter(page_connection::Column::FromPageId.eq(from_page_id))
+        .filter(
+            Condition::all()
+                .add(page_connection::Column::FromPageId.eq(from_page_id))
+                .add(
+                    page_connection::Column::ConnectionType
+                        .ne(ConnectionType::ListPages.name()),
+                ),
+        )
         .order_by_asc(page_connection::Column::CreatedAt)
         .paginate(txn, 100);
 
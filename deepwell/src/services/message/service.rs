@@ -19,6 +19,7 @@
  */
 
 use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
 use crate::models::message::{self, Entity as Message, Model as MessageModel};
 use crate::models::message_draft::{
     self, Entity as MessageDraft, Model as MessageDraftModel,
@@ -277,8 +278,25 @@ impl MessageService {
             )
             .await?;
 
-            // If recipient is a site user, then forward to corresponding site staff.
             let user = UserService::get(ctx, Reference::Id(recipient_user_id)).await?;
+
+            // Ensure sender is a contact, if the recipient requires it
+            //
+            // Messages to oneself and from the system user are always permitted.
+            if user.dm_contacts_only
+                && recipient_user_id != draft.user_id
+                && draft.user_id != SYSTEM_USER_ID
+            {
+                RelationService::check_user_contact(
+                    ctx,
+                    recipient_user_id,
+                    draft.user_id,
+                    "send a direct message to",
+                )
+                .await?;
+            }
+
+            // If recipient is a site user, then forward to corresponding site staff.
             if user.user_type == UserType::Site {
                 // TODO what to do if user is banned from site? needs to be possible to block
                 //      permabanned bad actors, but also allow normal banned users to message
@@ -60,11 +60,17 @@ pub mod alias;
 pub mod authentication;
 pub mod blob;
 pub mod category;
+pub mod diff;
 pub mod domain;
 pub mod email;
+pub mod feed;
 pub mod file;
 pub mod file_revision;
 pub mod filter;
+pub mod forum_category;
+pub mod forum_group;
+pub mod forum_post;
+pub mod forum_thread;
 pub mod import;
 pub mod job;
 pub mod link;
@@ -73,17 +79,25 @@ pub mod message_report;
 pub mod mfa;
 pub mod outdate;
 pub mod page;
+pub mod page_draft;
 pub mod page_query;
 pub mod page_revision;
+pub mod page_schedule;
 pub mod parent;
 pub mod password;
+pub mod rate_limit;
+pub mod recent_changes;
 pub mod relation;
 pub mod render;
 pub mod score;
 pub mod session;
 pub mod settings;
 pub mod site;
+pub mod site_backup;
+pub mod site_membership;
 pub mod special_page;
+pub mod storage_quota;
+pub mod tag;
 pub mod text;
 pub mod user;
 pub mod user_bot_owner;
@@ -95,11 +109,17 @@ pub use self::authentication::AuthenticationService;
 pub use self::blob::BlobService;
 pub use self::category::CategoryService;
 pub use self::context::ServiceContext;
+pub use self::diff::DiffService;
 pub use self::domain::DomainService;
 pub use self::error::*;
+pub use self::feed::FeedService;
 pub use self::file::FileService;
 pub use self::file_revision::FileRevisionService;
 pub use self::filter::FilterService;
+pub use self::forum_category::ForumCategoryService;
+pub use self::forum_group::ForumGroupService;
+pub use self::forum_post::ForumPostService;
+pub use self::forum_thread::ForumThreadService;
 pub use self::job::JobService;
 pub use self::link::LinkService;
 pub use self::message::MessageService;
@@ -107,18 +127,26 @@ pub use self::message_report::MessageReportService;
 pub use self::mfa::MfaService;
 pub use self::outdate::OutdateService;
 pub use self::page::PageService;
+pub use self::page_draft::PageDraftService;
 // TODO convert page attribution to a type of relation
 pub use self::page_query::PageQueryService;
 pub use self::page_revision::PageRevisionService;
+pub use self::page_schedule::PageScheduleService;
 pub use self::parent::ParentService;
 pub use self::password::PasswordService;
+pub use self::rate_limit::RateLimitService;
+pub use self::recent_changes::RecentChangesService;
 pub use self::relation::RelationService;
 pub use self::render::RenderService;
 pub use self::score::ScoreService;
 pub use self::session::SessionService;
 pub use self::settings::SettingsService;
 pub use self::site::SiteService;
+pub use self::site_backup::SiteBackupService;
+pub use self::site_membership::SiteMembershipService;
 pub use self::special_page::SpecialPageService;
+pub use self::storage_quota::StorageQuotaService;
+pub use self::tag::TagService;
 pub use self::text::TextService;
 pub use self::user::UserService;
 // TODO convert user-bot to a type of relation
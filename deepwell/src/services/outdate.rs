@@ -19,10 +19,20 @@
  */
 
 use super::prelude::*;
+use crate::api::ServerState;
 use crate::models::page::Model as PageModel;
 use crate::services::{JobService, LinkService, PageService};
 use crate::types::{ConnectionType, PageOrder};
 use crate::utils::split_category_name;
+use sea_orm::TransactionTrait;
+use std::cell::RefCell;
+use std::future::Future;
+use std::time::Duration as StdDuration;
+
+tokio::task_local! {
+    /// Pages whose scores changed in the current request, as `(site_id, page_id)`.
+    static PENDING_SCORES: RefCell<Vec<(i64, i64)>>;
+}
 
 #[derive(Debug)]
 pub struct OutdateService;
@@ -86,16 +96,165 @@ impl OutdateService {
         Ok(())
     }
 
+    /// Performs outdating tasks for a page whose score has changed.
+    ///
+    /// Scores are embedded in rendered HTML, so the page and any pages
+    /// including it need to be rerendered. This is debounced so that a
+    /// burst of votes only results in a single rerender, after a delay.
+    ///
+    /// Within a request, this is deferred until its transaction has
+    /// committed (see `with_pending_scores()`), so that a rolled back
+    /// vote cannot hold back the rerender for a later one.
+    pub async fn process_page_score(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<()> {
+        let deferred = PENDING_SCORES
+            .try_with(|pending| pending.borrow_mut().push((site_id, page_id)))
+            .is_ok();
+
+        if deferred {
+            debug!("Deferring score rerender for page ID {page_id} until commit");
+            return Ok(());
+        }
+
+        Self::queue_page_score(ctx, site_id, page_id).await
+    }
+
+    /// Runs a request, then processes any score changes once it has committed.
+    ///
+    /// This should wrap the whole request, including its transaction.
+    /// Failures here are only logged, since the request itself has succeeded.
+    pub async fn with_pending_scores<F, T, E>(
+        state: &ServerState,
+        future: F,
+    ) -> std::result::Result<T, E>
+    where
+        F: Future<Output = std::result::Result<T, E>>,
+    {
+        let (output, mut pending) = PENDING_SCORES
+            .scope(RefCell::new(Vec::new()), async {
+                let output = future.await;
+                let pending = PENDING_SCORES.with(RefCell::take);
+                (output, pending)
+            })
+            .await;
+
+        if output.is_ok() && !pending.is_empty() {
+            pending.sort_unstable();
+            pending.dedup();
+
+            if let Err(error) = Self::process_pending_scores(state, &pending).await {
+                error!("Unable to queue score rerenders for {pending:?}: {error}");
+            }
+        }
+
+        output
+    }
+
+    async fn process_pending_scores(
+        state: &ServerState,
+        pending: &[(i64, i64)],
+    ) -> Result<()> {
+        let txn = state.database.begin().await?;
+        let ctx = ServiceContext::new(state, &txn);
+
+        for &(site_id, page_id) in pending {
+            Self::queue_page_score(&ctx, site_id, page_id).await?;
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    async fn queue_page_score(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<()> {
+        let delay = ctx.config().job_score_rerender_delay;
+        if !Self::debounce_score(ctx, page_id, delay).await? {
+            debug!("Score rerender already pending for page ID {page_id}");
+            return Ok(());
+        }
+
+        let result = Self::queue_score_rerenders(ctx, site_id, page_id, delay).await;
+        if result.is_err() {
+            // Nothing was queued, so don't hold back the next attempt.
+            Self::clear_debounce_score(ctx, page_id).await?;
+        }
+
+        result
+    }
+
+    async fn queue_score_rerenders(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        delay: StdDuration,
+    ) -> Result<()> {
+        JobService::queue_rerender_page(ctx, site_id, page_id, 0, Some(delay)).await?;
+
+        for id in Self::get_displaying_pages(ctx, page_id).await? {
+            Self::outdate_after(ctx, id, 0, Some(delay)).await?;
+        }
+
+        Ok(())
+    }
+
     /// Queues the given pages for re-rendering.
+    #[inline]
     pub async fn outdate(
         ctx: &ServiceContext<'_>,
         page_id: i64,
         depth: u32,
+    ) -> Result<()> {
+        Self::outdate_after(ctx, page_id, depth, None).await
+    }
+
+    async fn outdate_after(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        depth: u32,
+        delay: Option<StdDuration>,
     ) -> Result<()> {
         let PageModel { site_id, .. } =
             PageService::get_direct(ctx, page_id, false).await?;
 
-        JobService::queue_rerender_page(ctx, site_id, page_id, depth + 1).await
+        JobService::queue_rerender_page(ctx, site_id, page_id, depth + 1, delay).await
+    }
+
+    /// Marks a score rerender as pending for this page.
+    ///
+    /// Returns `false` if one was already pending, in which case
+    /// no new rerender needs to be queued.
+    async fn debounce_score(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        delay: StdDuration,
+    ) -> Result<bool> {
+        let mut conn = ctx.redis_connect().await?;
+        let result: Option<String> = redis::cmd("SET")
+            .arg(score_debounce_key(page_id))
+            .arg(1)
+            .arg("EX")
+            .arg(delay.as_secs().max(1))
+            .arg("NX")
+            .query_async(&mut conn)
+            .await?;
+
+        Ok(result.is_some())
+    }
+
+    async fn clear_debounce_score(ctx: &ServiceContext<'_>, page_id: i64) -> Result<()> {
+        let mut conn = ctx.redis_connect().await?;
+        redis::cmd("DEL")
+            .arg(score_debounce_key(page_id))
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        Ok(())
     }
 
     pub async fn outdate_incoming_links(
@@ -122,22 +281,55 @@ impl OutdateService {
         page_id: i64,
         depth: u32,
     ) -> Result<()> {
+        for id in Self::get_including_pages(ctx, page_id).await? {
+            Self::outdate(ctx, id, depth).await?;
+        }
+        Ok(())
+    }
+
+    /// Gets the IDs of all other pages which include this one.
+    async fn get_including_pages(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Vec<i64>> {
         const CONNECTION_TYPES: &[ConnectionType] = &[
             ConnectionType::IncludeMessy,
             ConnectionType::IncludeElements,
             ConnectionType::Component,
         ];
 
-        for id in LinkService::get_to(ctx, page_id, Some(CONNECTION_TYPES))
+        Self::get_connected_pages(ctx, page_id, CONNECTION_TYPES).await
+    }
+
+    /// Gets the IDs of all other pages which include or list this one.
+    async fn get_displaying_pages(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Vec<i64>> {
+        const CONNECTION_TYPES: &[ConnectionType] = &[
+            ConnectionType::IncludeMessy,
+            ConnectionType::IncludeElements,
+            ConnectionType::Component,
+            ConnectionType::ListPages,
+        ];
+
+        Self::get_connected_pages(ctx, page_id, CONNECTION_TYPES).await
+    }
+
+    async fn get_connected_pages(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        connection_types: &[ConnectionType],
+    ) -> Result<Vec<i64>> {
+        let ids = LinkService::get_to(ctx, page_id, Some(connection_types))
             .await?
             .connections
             .iter()
             .map(|connection| connection.from_page_id)
             .filter(|id| *id != page_id)
-        {
-            Self::outdate(ctx, id, depth).await?;
-        }
-        Ok(())
+            .collect();
+
+        Ok(ids)
     }
 
     pub async fn outdate_templates(
@@ -178,3 +370,8 @@ impl OutdateService {
         Ok(())
     }
 }
+
+#[inline]
+fn score_debounce_key(page_id: i64) -> String {
+    format!("outdate:score:{page_id}")
+}
@@ -21,8 +21,10 @@
 use super::prelude::*;
 use crate::models::page::{self, Entity as Page, Model as PageModel};
 use crate::models::page_category::Model as PageCategoryModel;
+use crate::models::page_redirect::{self, Entity as PageRedirect};
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::sea_orm_active_enums::PageRevisionType;
+use crate::services::category::CategoryAction;
 use crate::services::filter::{FilterClass, FilterType};
 use crate::services::page_revision::{
     CreateFirstPageRevision, CreateFirstPageRevisionOutput, CreatePageRevision,
@@ -46,7 +48,7 @@ impl PageService {
         ctx: &ServiceContext<'_>,
         CreatePage {
             site_id,
-            wikitext,
+            mut wikitext,
             title,
             alt_title,
             mut slug,
@@ -75,10 +77,31 @@ impl PageService {
         }
 
         // Create category if not already present
-        let PageCategoryModel { category_id, .. } =
+        let category =
             CategoryService::get_or_create(ctx, site_id, get_category_name(&slug))
                 .await?;
 
+        CategoryService::check_permission(
+            ctx,
+            &category,
+            CategoryAction::Create,
+            user_id,
+        )
+        .await?;
+
+        let PageCategoryModel {
+            category_id,
+            template_page,
+            ..
+        } = category;
+
+        // Use the category's template if the page is being created empty
+        if wikitext.is_empty() {
+            if let Some(template_slug) = template_page {
+                wikitext = Self::get_template(ctx, site_id, &template_slug).await?;
+            }
+        }
+
         // Insert page
         let model = page::ActiveModel {
             site_id: Set(site_id),
@@ -141,11 +164,15 @@ impl PageService {
         }: EditPage<'_>,
     ) -> Result<Option<EditPageOutput>> {
         let txn = ctx.transaction();
+        let page = Self::get(ctx, site_id, reference).await?;
+        CategoryService::check_page_permission(ctx, &page, CategoryAction::Edit, user_id)
+            .await?;
+
         let PageModel {
             page_id,
             latest_revision_id,
             ..
-        } = Self::get(ctx, site_id, reference).await?;
+        } = page;
 
         // Perform filter validation
         Self::run_filter(
@@ -229,12 +256,21 @@ impl PageService {
         }: MovePage<'_>,
     ) -> Result<MovePageOutput> {
         let txn = ctx.transaction();
+        let page = Self::get(ctx, site_id, reference).await?;
+        CategoryService::check_page_permission(
+            ctx,
+            &page,
+            CategoryAction::Rename,
+            user_id,
+        )
+        .await?;
+
         let PageModel {
             page_id,
             slug: old_slug,
             latest_revision_id,
             ..
-        } = Self::get(ctx, site_id, reference).await?;
+        } = page;
 
         // Check last revision ID argument
         check_last_revision(None, latest_revision_id, last_revision_id)?;
@@ -249,11 +285,24 @@ impl PageService {
 
         Self::check_conflicts(ctx, site_id, &new_slug, "move").await?;
 
-        // Create category if not already present
-        let PageCategoryModel { category_id, .. } =
+        // Create category if not already present,
+        // moving into it is the same as creating a page there
+        let category =
             CategoryService::get_or_create(ctx, site_id, get_category_name(&new_slug))
                 .await?;
 
+        if category.category_id != page.page_category_id {
+            CategoryService::check_permission(
+                ctx,
+                &category,
+                CategoryAction::Create,
+                user_id,
+            )
+            .await?;
+        }
+
+        let category_id = category.category_id;
+
         // Get latest revision
         let last_revision =
             PageRevisionService::get_latest(ctx, site_id, page_id).await?;
@@ -331,11 +380,20 @@ impl PageService {
         }: DeletePage<'_>,
     ) -> Result<DeletePageOutput> {
         let txn = ctx.transaction();
+        let page = Self::get(ctx, site_id, reference).await?;
+        CategoryService::check_page_permission(
+            ctx,
+            &page,
+            CategoryAction::Delete,
+            user_id,
+        )
+        .await?;
+
         let PageModel {
             page_id,
             latest_revision_id,
             ..
-        } = Self::get(ctx, site_id, reference).await?;
+        } = page;
 
         // Get and check latest revision
         let last_revision =
@@ -407,6 +465,14 @@ impl PageService {
             CategoryService::get_or_create(ctx, site_id, get_category_name(&slug))
                 .await?;
 
+        CategoryService::check_permission(
+            ctx,
+            &category,
+            CategoryAction::Create,
+            user_id,
+        )
+        .await?;
+
         // Get latest revision
         let last_revision =
             PageRevisionService::get_latest(ctx, site_id, page_id).await?;
@@ -459,11 +525,15 @@ impl PageService {
         }: RollbackPage<'_>,
     ) -> Result<Option<EditPageOutput>> {
         let txn = ctx.transaction();
+        let page = Self::get(ctx, site_id, reference).await?;
+        CategoryService::check_page_permission(ctx, &page, CategoryAction::Edit, user_id)
+            .await?;
+
         let PageModel {
             page_id,
             latest_revision_id,
             ..
-        } = Self::get(ctx, site_id, reference).await?;
+        } = page;
 
         // Get target revision and latest revision
         let (target_revision, last_revision) = try_join!(
@@ -767,10 +837,100 @@ impl PageService {
         Ok(pages)
     }
 
+    /// Gets the wikitext of a category's template page.
+    ///
+    /// If the template page does not exist, then it is treated as empty.
+    async fn get_template(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        template_slug: &str,
+    ) -> Result<String> {
+        match Self::get_optional(ctx, site_id, Reference::Slug(cow!(template_slug)))
+            .await?
+        {
+            None => {
+                warn!("Category template page '{template_slug}' does not exist");
+                Ok(String::new())
+            }
+            Some(template) => {
+                let revision =
+                    PageRevisionService::get_latest(ctx, site_id, template.page_id)
+                        .await?;
+
+                TextService::get(ctx, &revision.wikitext_hash).await
+            }
+        }
+    }
+
+    /// Adds or replaces a redirect from an old slug to a page.
+    ///
+    /// Redirects are only followed if no page exists at that slug.
+    pub async fn add_redirect(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+        page_id: i64,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        debug!("Adding redirect from '{slug}' to page ID {page_id} in site ID {site_id}");
+
+        let existing = PageRedirect::find()
+            .filter(
+                Condition::all()
+                    .add(page_redirect::Column::SiteId.eq(site_id))
+                    .add(page_redirect::Column::Slug.eq(slug)),
+            )
+            .one(txn)
+            .await?;
+
+        match existing {
+            Some(redirect) => {
+                let mut model = redirect.into_active_model();
+                model.page_id = Set(page_id);
+                model.update(txn).await?;
+            }
+            None => {
+                let model = page_redirect::ActiveModel {
+                    site_id: Set(site_id),
+                    slug: Set(str!(slug)),
+                    page_id: Set(page_id),
+                    ..Default::default()
+                };
+                model.insert(txn).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the current slug of the page a slug redirects to, if any.
+    pub async fn get_redirect(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+    ) -> Result<Option<String>> {
+        let txn = ctx.transaction();
+        let target: Option<String> = PageRedirect::find()
+            .select_only()
+            .column(page::Column::Slug)
+            .join(JoinType::InnerJoin, page_redirect::Relation::Page.def())
+            .filter(
+                Condition::all()
+                    .add(page_redirect::Column::SiteId.eq(site_id))
+                    .add(page_redirect::Column::Slug.eq(slug))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .into_tuple()
+            .one(txn)
+            .await?;
+
+        Ok(target)
+    }
+
     /// Checks to see if a page already exists at the slug specified.
     ///
     /// If so, this method fails with `Error::PageExists`. Otherwise it returns nothing.
-    async fn check_conflicts(
+    pub async fn check_conflicts(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         slug: &str,
@@ -154,6 +154,7 @@ pub struct GetDeletedPageOutput {
 pub struct GetPageScoreOutput {
     pub page_id: i64,
     pub score: ScoreValue,
+    pub vote_count: i32,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -0,0 +1,41 @@
+/*
+ * services/page_draft/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The page draft service, for autosaving page edits in progress.
+//!
+//! Each user has at most one draft per page, which is replaced every
+//! time the editor autosaves. Drafts record the revision they were
+//! based on, so that when an edit is resumed after the page has
+//! changed, the conflict can be shown to the user.
+//!
+//! Drafts are removed when the edit is submitted, or by a recurring
+//! job once they have not been saved for a while.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::PageDraftService;
+pub use self::structs::*;
@@ -0,0 +1,354 @@
+/*
+ * services/page_draft/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::{self, Entity as Page, Model as PageModel};
+use crate::models::page_draft::{self, Entity as PageDraft, Model as PageDraftModel};
+use crate::services::diff::diff_text;
+use crate::services::{PageRevisionService, PageService, TextService};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::Insert;
+use std::cmp::Reverse;
+use time::OffsetDateTime;
+
+/// How many unchanged lines to show around each change in a conflict diff.
+const CONFLICT_CONTEXT_LINES: usize = 3;
+
+#[derive(Debug)]
+pub struct PageDraftService;
+
+impl PageDraftService {
+    /// Autosaves a user's draft of an edit to a page.
+    ///
+    /// Each user has only one draft per page, so this replaces any
+    /// draft which already exists. The draft is saved even if the page
+    /// has since been edited, so that no work is lost, but the conflict
+    /// is reported back to the caller.
+    pub async fn save(
+        ctx: &ServiceContext<'_>,
+        SavePageDraft {
+            site_id,
+            page: reference,
+            user_id,
+            base_revision_id,
+            wikitext,
+            title,
+            alt_title,
+            tags,
+            revision_comments: comments,
+        }: SavePageDraft<'_>,
+    ) -> Result<SavePageDraftOutput> {
+        let txn = ctx.transaction();
+        let PageModel {
+            page_id,
+            latest_revision_id,
+            ..
+        } = PageService::get(ctx, site_id, reference).await?;
+
+        info!("Saving draft of page ID {page_id} for user ID {user_id}");
+
+        // Ensure the base revision is actually of this page
+        let base_revision =
+            PageRevisionService::get_direct(ctx, base_revision_id).await?;
+        if base_revision.page_id != page_id {
+            error!(
+                "Draft base revision ID {base_revision_id} is for page ID {}, not {page_id}",
+                base_revision.page_id,
+            );
+            return Err(Error::PageRevisionNotFound);
+        }
+
+        let wikitext_hash = TextService::create(ctx, wikitext).await?;
+        let model = page_draft::ActiveModel {
+            site_id: Set(site_id),
+            page_id: Set(page_id),
+            user_id: Set(user_id),
+            base_revision_id: Set(base_revision_id),
+            wikitext_hash: Set(wikitext_hash.to_vec()),
+            title: Set(title),
+            alt_title: Set(alt_title),
+            tags: Set(tags),
+            comments: Set(comments),
+            ..Default::default()
+        };
+
+        let draft = Self::build_save(model, now())
+            .exec_with_returning(txn)
+            .await?;
+
+        Ok(SavePageDraftOutput {
+            draft_id: draft.draft_id,
+            saved_at: draft.updated_at.unwrap_or(draft.created_at),
+            conflict: latest_revision_id != Some(base_revision_id),
+        })
+    }
+
+    /// Builds the statement to save a draft, replacing any existing one.
+    ///
+    /// This is a single upsert on the user and page, so that concurrent
+    /// autosaves cannot both try to insert a new draft.
+    fn build_save(
+        model: page_draft::ActiveModel,
+        updated_at: OffsetDateTime,
+    ) -> Insert<page_draft::ActiveModel> {
+        PageDraft::insert(model).on_conflict(
+            OnConflict::columns([page_draft::Column::PageId, page_draft::Column::UserId])
+                .update_columns([
+                    page_draft::Column::BaseRevisionId,
+                    page_draft::Column::WikitextHash,
+                    page_draft::Column::Title,
+                    page_draft::Column::AltTitle,
+                    page_draft::Column::Tags,
+                    page_draft::Column::Comments,
+                ])
+                .value(page_draft::Column::UpdatedAt, updated_at)
+                .to_owned(),
+        )
+    }
+
+    /// Gets a user's draft of a page, in order to resume editing.
+    ///
+    /// If the page has been edited since the draft was started,
+    /// then the changes made since then are included.
+    pub async fn resume(
+        ctx: &ServiceContext<'_>,
+        GetPageDraft {
+            site_id,
+            page: reference,
+            user_id,
+        }: GetPageDraft<'_>,
+    ) -> Result<GetPageDraftOutput> {
+        let PageModel {
+            page_id,
+            latest_revision_id,
+            ..
+        } = PageService::get(ctx, site_id, reference).await?;
+
+        let draft = Self::get(ctx, page_id, user_id).await?;
+        let wikitext = TextService::get(ctx, &draft.wikitext_hash).await?;
+        let conflict = if latest_revision_id == Some(draft.base_revision_id) {
+            None
+        } else {
+            let base_revision =
+                PageRevisionService::get_direct(ctx, draft.base_revision_id).await?;
+            let latest_revision =
+                PageRevisionService::get_latest(ctx, site_id, page_id).await?;
+
+            let base_wikitext =
+                TextService::get(ctx, &base_revision.wikitext_hash).await?;
+            let latest_wikitext =
+                TextService::get(ctx, &latest_revision.wikitext_hash).await?;
+
+            Some(PageDraftConflict {
+                latest_revision_id: latest_revision.revision_id,
+                latest_revision_number: latest_revision.revision_number,
+                wikitext: diff_text(
+                    &base_wikitext,
+                    &latest_wikitext,
+                    Some(CONFLICT_CONTEXT_LINES),
+                ),
+            })
+        };
+
+        Ok(GetPageDraftOutput {
+            draft,
+            wikitext,
+            conflict,
+        })
+    }
+
+    /// Lists all of a user's drafts, most recently saved first.
+    ///
+    /// Drafts of pages which have since been deleted are not included.
+    pub async fn list(
+        ctx: &ServiceContext<'_>,
+        ListPageDrafts { user_id, site_id }: ListPageDrafts,
+    ) -> Result<Vec<PageDraftListItem>> {
+        let txn = ctx.transaction();
+        let mut condition = Condition::all()
+            .add(page_draft::Column::UserId.eq(user_id))
+            .add(page::Column::DeletedAt.is_null());
+
+        if let Some(site_id) = site_id {
+            condition = condition.add(page_draft::Column::SiteId.eq(site_id));
+        }
+
+        let mut drafts: Vec<_> = PageDraft::find()
+            .find_also_related(Page)
+            .filter(condition)
+            .all(txn)
+            .await?
+            .into_iter()
+            .filter_map(|(draft, page)| {
+                page.map(|page| PageDraftListItem {
+                    conflict: page.latest_revision_id != Some(draft.base_revision_id),
+                    slug: page.slug,
+                    draft,
+                })
+            })
+            .collect();
+
+        drafts.sort_by_key(|item| {
+            Reverse(item.draft.updated_at.unwrap_or(item.draft.created_at))
+        });
+
+        Ok(drafts)
+    }
+
+    /// Deletes a user's draft of a page, if one exists.
+    ///
+    /// Returns whether a draft was deleted.
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        user_id: i64,
+    ) -> Result<bool> {
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected } = PageDraft::delete_many()
+            .filter(
+                Condition::all()
+                    .add(page_draft::Column::PageId.eq(page_id))
+                    .add(page_draft::Column::UserId.eq(user_id)),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(rows_affected > 0)
+    }
+
+    /// Deletes all drafts which have not been saved within the expiry period.
+    ///
+    /// The text rows they referenced are left for `TextService::prune()`.
+    pub async fn prune(ctx: &ServiceContext<'_>) -> Result<u64> {
+        let expiry = match ctx.config().draft_expiry {
+            Some(expiry) => expiry,
+            None => {
+                debug!("Page drafts do not expire, skipping prune");
+                return Ok(0);
+            }
+        };
+
+        info!("Pruning all expired page drafts");
+
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected } = PageDraft::delete_many()
+            .filter(Self::expired_condition(now() - expiry))
+            .exec(txn)
+            .await?;
+
+        debug!("{rows_affected} expired page drafts were pruned");
+        Ok(rows_affected)
+    }
+
+    /// Matches drafts which were last saved before the cutoff.
+    fn expired_condition(cutoff: OffsetDateTime) -> Condition {
+        Condition::any()
+            .add(page_draft::Column::UpdatedAt.lt(cutoff))
+            .add(
+                Condition::all()
+                    .add(page_draft::Column::UpdatedAt.is_null())
+                    .add(page_draft::Column::CreatedAt.lt(cutoff)),
+            )
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        user_id: i64,
+    ) -> Result<PageDraftModel> {
+        find_or_error!(Self::get_optional(ctx, page_id, user_id), PageDraft)
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        user_id: i64,
+    ) -> Result<Option<PageDraftModel>> {
+        let txn = ctx.transaction();
+        let draft = PageDraft::find()
+            .filter(
+                Condition::all()
+                    .add(page_draft::Column::PageId.eq(page_id))
+                    .add(page_draft::Column::UserId.eq(user_id)),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(draft)
+    }
+}
+
+#[test]
+fn save_and_expiry_statements() {
+    use sea_orm::{DbBackend, QueryTrait};
+    use time::macros::datetime;
+
+    let timestamp = datetime!(2024-01-02 03:04:05 UTC);
+    let model = page_draft::ActiveModel {
+        site_id: Set(1),
+        page_id: Set(2),
+        user_id: Set(3),
+        base_revision_id: Set(4),
+        wikitext_hash: Set(vec![0xab]),
+        title: Set(str!("Title")),
+        alt_title: Set(None),
+        tags: Set(vec![]),
+        comments: Set(str!("")),
+        ..Default::default()
+    };
+
+    // Saving inserts a new draft, or overwrites the user's existing one
+    let sql = PageDraftService::build_save(model, timestamp)
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert!(sql.starts_with(r#"INSERT INTO "page_draft""#), "{sql}");
+    assert!(
+        sql.contains(r#"ON CONFLICT ("page_id", "user_id") DO UPDATE SET"#),
+        "Save doesn't upsert on the page and user: {sql}",
+    );
+    assert!(
+        sql.contains(r#""wikitext_hash" = "excluded"."wikitext_hash""#),
+        "Save doesn't overwrite the wikitext: {sql}",
+    );
+    assert!(
+        sql.contains(r#""updated_at" = '2024-01-02 03:04:05.000000 +00:00'"#),
+        "Save doesn't set the updated timestamp: {sql}",
+    );
+    assert!(
+        !sql.contains(r#""created_at" = "excluded""#),
+        "Save overwrites the creation timestamp: {sql}",
+    );
+
+    // Expiry uses the last save, falling back to creation
+    let sql = PageDraft::delete_many()
+        .filter(PageDraftService::expired_condition(timestamp))
+        .build(DbBackend::Postgres)
+        .to_string();
+
+    assert_eq!(
+        sql,
+        concat!(
+            r#"DELETE FROM "page_draft" WHERE "page_draft"."updated_at" < '2024-01-02 03:04:05.000000 +00:00' "#,
+            r#"OR ("page_draft"."updated_at" IS NULL AND "page_draft"."created_at" < '2024-01-02 03:04:05.000000 +00:00')"#,
+        ),
+    );
+}
@@ -0,0 +1,95 @@
+/*
+ * services/page_draft/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_draft::Model as PageDraftModel;
+use crate::services::diff::TextDiff;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SavePageDraft<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub user_id: i64,
+    pub base_revision_id: i64,
+    pub wikitext: String,
+    pub title: String,
+    pub alt_title: Option<String>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub revision_comments: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SavePageDraftOutput {
+    pub draft_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub saved_at: OffsetDateTime,
+
+    /// Whether the page has been edited since the draft's base revision.
+    pub conflict: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageDraft<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub user_id: i64,
+}
+
+pub type DeletePageDraft<'a> = GetPageDraft<'a>;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetPageDraftOutput {
+    #[serde(flatten)]
+    pub draft: PageDraftModel,
+    pub wikitext: String,
+    pub conflict: Option<PageDraftConflict>,
+}
+
+/// Describes how the page has changed since the draft was started.
+#[derive(Serialize, Debug, Clone)]
+pub struct PageDraftConflict {
+    pub latest_revision_id: i64,
+    pub latest_revision_number: i32,
+
+    /// The changes to the page's wikitext, from the base revision to the latest.
+    pub wikitext: TextDiff,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ListPageDrafts {
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub site_id: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PageDraftListItem {
+    #[serde(flatten)]
+    pub draft: PageDraftModel,
+    pub slug: String,
+    pub conflict: bool,
+}
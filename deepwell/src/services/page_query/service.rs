@@ -21,14 +21,22 @@
 #![allow(dead_code, unused_variables)] // TEMP
 
 use super::prelude::*;
+use crate::models::forum_thread::Entity as ForumThread;
+use crate::models::page::Model as PageModel;
 use crate::models::page::{self, Entity as Page};
 use crate::models::page_category::{self, Entity as PageCategory};
 use crate::models::page_connection::{self, Entity as PageConnection};
-use crate::models::page_parent::{self, Entity as PageParent};
-use crate::models::{page_revision, text};
-use crate::services::{PageService, ParentService};
-use sea_query::{Expr, Query};
-use std::convert::Infallible;
+use crate::models::page_score::{Entity as PageScore, Model as PageScoreModel};
+use crate::models::relation::{self, Entity as Relation};
+use crate::models::sea_orm_active_enums::RelationObjectType;
+use crate::models::{forum_thread, page_revision, page_score, text};
+use crate::services::relation::RelationType;
+use crate::services::{
+    LinkService, PageRevisionService, PageService, ParentService, ScoreService,
+    TextService,
+};
+use sea_query::{Expr, Query, SelectStatement, SimpleExpr};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug)]
 pub struct PageQueryService;
@@ -68,7 +76,7 @@ impl PageQueryService {
             pagination,
             variables,
         }: PageQuery<'_>,
-    ) -> Result<Infallible> {
+    ) -> Result<Vec<PageResult>> {
         info!("Building ListPages query from specification");
 
         let txn = ctx.transaction();
@@ -123,6 +131,8 @@ impl PageQueryService {
                             page_category::Column::Slug
                                 .is_not_in(cat_slugs!(excluded_categories)),
                         )
+                        // Hidden categories must be explicitly included
+                        .and_where(page_category::Column::Hidden.eq(false))
                         .to_owned(),
                 )
             }
@@ -159,6 +169,9 @@ impl PageQueryService {
         // Page Parents
         //
         // Adds constraints based on the presence of parent pages.
+        //
+        // Page parents are stored as relations, where the parent
+        // page is the "dest" and the child page is the "from".
 
         // Convenience macro to pull a list of page IDs which are parents
         // of the current page.
@@ -166,10 +179,12 @@ impl PageQueryService {
         // In the places where this is used, this could be implemented
         // as a subquery, meaning:
         //
-        // SELECT child_page_id FROM page_parent
-        // WHERE parent_page_id IN (
-        //     SELECT parent_page_id FROM page_parent
-        //     WHERE child_page_id = $0
+        // SELECT from_id FROM relation
+        // WHERE relation_type = 'parent'
+        // AND dest_id IN (
+        //     SELECT dest_id FROM relation
+        //     WHERE relation_type = 'parent'
+        //     AND from_id = $0
         // )
         //
         // However looking at the query plan, this would be implemented
@@ -188,18 +203,46 @@ impl PageQueryService {
             };
         }
 
+        // Builds a subquery of child page IDs for parents matching the condition.
+        fn children_of(condition: SimpleExpr) -> SelectStatement {
+            Query::select()
+                .column(relation::Column::FromId)
+                .from(Relation)
+                .and_where(
+                    relation::Column::RelationType.eq(RelationType::PageParent.value()),
+                )
+                .and_where(relation::Column::DestType.eq(RelationObjectType::Page))
+                .and_where(relation::Column::FromType.eq(RelationObjectType::Page))
+                .and_where(relation::Column::OverwrittenAt.is_null())
+                .and_where(relation::Column::DeletedAt.is_null())
+                .and_where(condition)
+                .to_owned()
+        }
+
+        // Gets the IDs of pages below each of the given pages, at any depth.
+        async fn get_descendants(
+            ctx: &ServiceContext<'_>,
+            site_id: i64,
+            references: &[Reference<'_>],
+        ) -> Result<Vec<HashSet<i64>>> {
+            let pages = PageService::get_pages(ctx, site_id, references).await?;
+            let mut descendants = Vec::with_capacity(pages.len());
+            for page in pages {
+                descendants
+                    .push(ParentService::get_descendant_ids(ctx, page.page_id).await?);
+            }
+            Ok(descendants)
+        }
+
         let page_parent_condition = match page_parent {
             // Pages with no parents.
-            // This means that there should be no rows in `page_parent`
+            // This means that there should be no relations
             // where they are the child page.
             PageParentSelector::NoParent => {
                 debug!("Selecting pages with no parents");
 
-                page::Column::PageId.not_in_subquery(
-                    Query::select()
-                        .column(page_parent::Column::ChildPageId)
-                        .from(PageParent)
-                        .to_owned(),
+                Condition::all().add(
+                    page::Column::PageId.not_in_subquery(children_of(Expr::value(true))),
                 )
             }
 
@@ -208,15 +251,9 @@ impl PageQueryService {
             PageParentSelector::SameParents => {
                 debug!("Selecting pages are siblings under the given parents");
 
-                page::Column::PageId.in_subquery(
-                    Query::select()
-                        .column(page_parent::Column::ChildPageId)
-                        .from(PageParent)
-                        .and_where(
-                            page_parent::Column::ParentPageId.is_in(get_parents!()),
-                        )
-                        .to_owned(),
-                )
+                Condition::all().add(page::Column::PageId.in_subquery(children_of(
+                    relation::Column::DestId.is_in(get_parents!()),
+                )))
             }
 
             // Pages which are not siblings of the current page,
@@ -224,43 +261,32 @@ impl PageQueryService {
             PageParentSelector::DifferentParents => {
                 debug!("Selecting pages which are not siblings under the given parents",);
 
-                let parents = ParentService::get_parents(
-                    ctx,
-                    current_site_id,
-                    Reference::Id(current_page_id),
-                )
-                .await?
-                .into_iter()
-                .map(|parent| parent.parent_page_id);
-
-                page::Column::PageId.in_subquery(
-                    Query::select()
-                        .column(page_parent::Column::ChildPageId)
-                        .from(PageParent)
-                        .and_where(
-                            page_parent::Column::ParentPageId.is_not_in(get_parents!()),
-                        )
-                        .to_owned(),
-                )
+                Condition::all().add(page::Column::PageId.in_subquery(children_of(
+                    relation::Column::DestId.is_not_in(get_parents!()),
+                )))
             }
 
             // Pages which are children of the current page.
             PageParentSelector::ChildOf => {
                 debug!("Selecting pages which are children of the current page",);
 
-                page::Column::PageId.in_subquery(
-                    Query::select()
-                        .column(page_parent::Column::ChildPageId)
-                        .from(PageParent)
-                        .and_where(page_parent::Column::ParentPageId.eq(current_page_id))
-                        .to_owned(),
-                )
+                Condition::all().add(page::Column::PageId.in_subquery(children_of(
+                    relation::Column::DestId.eq(current_page_id),
+                )))
+            }
+
+            // Pages which are below the current page, at any depth.
+            PageParentSelector::DescendantOf => {
+                debug!("Selecting pages which are descendants of the current page");
+
+                let descendants =
+                    ParentService::get_descendant_ids(ctx, current_page_id).await?;
+
+                Condition::all().add(page::Column::PageId.is_in(descendants))
             }
 
             // Pages with any of the specified parents.
-            // TODO: Possibly allow either *any* or *all* of specified parents
-            //       rather than only any, in the future.
-            PageParentSelector::HasParents(parents) => {
+            PageParentSelector::HasAnyParents(parents) => {
                 debug!("Selecting on pages which have one of the given as parents",);
 
                 let parent_ids = PageService::get_pages(ctx, queried_site_id, parents)
@@ -268,14 +294,53 @@ impl PageQueryService {
                     .into_iter()
                     .map(|page| page.page_id);
 
-                page::Column::PageId.in_subquery(
-                    Query::select()
-                        .column(page_parent::Column::ChildPageId)
-                        .from(PageParent)
-                        .and_where(page_parent::Column::ParentPageId.is_in(parent_ids))
-                        .to_owned(),
+                Condition::all().add(
+                    page::Column::PageId.in_subquery(children_of(
+                        relation::Column::DestId.is_in(parent_ids),
+                    )),
                 )
             }
+
+            // Pages with all of the specified parents.
+            PageParentSelector::HasAllParents(parents) => {
+                debug!("Selecting on pages which have all of the given as parents");
+
+                PageService::get_pages(ctx, queried_site_id, parents)
+                    .await?
+                    .into_iter()
+                    .fold(Condition::all(), |condition, page| {
+                        condition.add(page::Column::PageId.in_subquery(children_of(
+                            relation::Column::DestId.eq(page.page_id),
+                        )))
+                    })
+            }
+
+            // Pages below any of the specified pages, at any depth.
+            PageParentSelector::HasAnyAncestors(ancestors) => {
+                debug!("Selecting on pages which have one of the given as ancestors");
+
+                let page_ids = get_descendants(ctx, queried_site_id, ancestors)
+                    .await?
+                    .into_iter()
+                    .flatten()
+                    .collect::<HashSet<_>>();
+
+                Condition::all().add(page::Column::PageId.is_in(page_ids))
+            }
+
+            // Pages below all of the specified pages, at any depth.
+            PageParentSelector::HasAllAncestors(ancestors) => {
+                debug!("Selecting on pages which have all of the given as ancestors");
+
+                // Like HasAllParents, no ancestors means no restriction
+                get_descendants(ctx, queried_site_id, ancestors)
+                    .await?
+                    .into_iter()
+                    .reduce(|a, b| &a & &b)
+                    .map_or_else(Condition::all, |page_ids| {
+                        Condition::all().add(page::Column::PageId.is_in(page_ids))
+                    })
+            }
         };
         condition = condition.add(page_parent_condition);
 
@@ -328,6 +393,18 @@ impl PageQueryService {
                 query = query.join(JoinType::Join, page_revision::Relation::Text1.def());
             };
         }
+        macro_rules! join_score {
+            () => {
+                // Pages without a materialized score have no votes yet
+                query = query.join(JoinType::LeftJoin, page::Relation::PageScore.def());
+            };
+        }
+        macro_rules! join_discussion {
+            () => {
+                // Not every page has a discussion thread
+                query = query.join(JoinType::LeftJoin, page::Relation::ForumThread.def());
+            };
+        }
         // TODO other joins
 
         // Add on at the query-level (ORDER BY, LIMIT)
@@ -382,28 +459,44 @@ impl PageQueryService {
                     query = query.order_by(page::Column::UpdatedAt, order);
                 }
                 OrderProperty::Size => {
-                    error!("Ordering by page size, not yet implemented");
+                    debug!("Ordering by page size");
                     join_revision!();
                     join_text!();
-                    let col = Expr::col(text::Column::Contents);
-                    let expr = SimpleExpr::FunctionCall(Func::char_length(col));
-                    query = query.order_by(expr, order);
+                    // Compressed text has no contents column to take the character
+                    // length of, so this uses the stored length of the original
+                    // wikitext, in bytes. This only differs for non-ASCII text.
+                    query = query.order_by(text::Column::Size, order);
                 }
                 OrderProperty::Score => {
-                    error!("Ordering by score, not yet implemented");
-                    todo!() // TODO
+                    debug!("Ordering by score");
+                    join_score!();
+                    let expr = SimpleExpr::FunctionCall(Func::coalesce([
+                        Expr::col(page_score::Column::Score).into(),
+                        Expr::val(0.0).into(),
+                    ]));
+                    query = query.order_by(expr, order);
                 }
                 OrderProperty::Votes => {
-                    error!("Ordering by vote count, not yet implemented");
-                    todo!() // TODO
+                    debug!("Ordering by vote count");
+                    join_score!();
+                    let expr = SimpleExpr::FunctionCall(Func::coalesce([
+                        Expr::col(page_score::Column::VoteCount).into(),
+                        Expr::val(0).into(),
+                    ]));
+                    query = query.order_by(expr, order);
                 }
                 OrderProperty::Revisions => {
                     error!("Ordering by revision count, not yet implemented");
                     todo!() // TODO
                 }
                 OrderProperty::Comments => {
-                    error!("Ordering by comment count, not yet implemented");
-                    todo!() // TODO
+                    debug!("Ordering by comment count");
+                    join_discussion!();
+                    let expr = SimpleExpr::FunctionCall(Func::coalesce([
+                        Expr::col(forum_thread::Column::PostCount).into(),
+                        Expr::val(0).into(),
+                    ]));
+                    query = query.order_by(expr, order);
                 }
                 OrderProperty::Random => {
                     debug!("Ordering by random value");
@@ -435,9 +528,92 @@ impl PageQueryService {
         //      3. [14, 13, 12, 11, 10]
 
         // Execute it!
-        let result = query.all(txn).await?;
+        let pages = query.all(txn).await?;
+
+        // Track which pages are listed, so this page is rerendered when they change
+        let page_ids: Vec<i64> = pages.iter().map(|page| page.page_id).collect();
+        LinkService::add_list_pages(ctx, current_page_id, &page_ids).await?;
+
+        Self::build_results(ctx, pages).await
+    }
+
+    /// Gathers the data used by ListPages for each page selected by a query.
+    async fn build_results(
+        ctx: &ServiceContext<'_>,
+        pages: Vec<PageModel>,
+    ) -> Result<Vec<PageResult>> {
+        let txn = ctx.transaction();
+        let page_ids: Vec<i64> = pages.iter().map(|page| page.page_id).collect();
+
+        // Read materialized scores all at once, any missing ones are calculated below
+        let mut scores: HashMap<i64, PageScoreModel> = PageScore::find()
+            .filter(page_score::Column::PageId.is_in(page_ids))
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|model| (model.page_id, model))
+            .collect();
+
+        // Read comment information from discussion threads, if pages have them
+        let thread_ids: Vec<i64> = pages
+            .iter()
+            .filter_map(|page| page.discussion_thread_id)
+            .collect();
+
+        let comments: HashMap<i64, PageResultComments> = ForumThread::find()
+            .filter(
+                Condition::all()
+                    .add(forum_thread::Column::ForumThreadId.is_in(thread_ids))
+                    .add(forum_thread::Column::DeletedAt.is_null()),
+            )
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|thread| {
+                let comments = PageResultComments {
+                    count: thread.post_count,
+                    last_commented_at: thread.last_post_at,
+                    last_commented_by: thread.last_post_by,
+                };
+
+                (thread.forum_thread_id, comments)
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(pages.len());
+        for page in pages {
+            let PageModel {
+                page_id, site_id, ..
+            } = page;
+
+            let score = match scores.remove(&page_id) {
+                Some(model) => model,
+                None => ScoreService::get(ctx, page_id).await?,
+            };
+
+            let last_revision =
+                PageRevisionService::get_latest(ctx, site_id, page_id).await?;
+            let wikitext = TextService::get(ctx, &last_revision.wikitext_hash).await?;
+            let page_parents =
+                ParentService::get_parents(ctx, site_id, Reference::Id(page_id)).await?;
+
+            let comments = page
+                .discussion_thread_id
+                .and_then(|thread_id| comments.get(&thread_id))
+                .cloned()
+                .unwrap_or_default();
+
+            results.push(PageResult {
+                metadata: page,
+                last_revision,
+                comments,
+                page_parents,
+                wikitext,
+                score: ScoreService::score_value(&score),
+                vote_count: score.vote_count,
+            });
+        }
 
-        // TODO implement query construction
-        todo!()
+        Ok(results)
     }
 }
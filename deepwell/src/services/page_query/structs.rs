@@ -23,9 +23,9 @@
 
 use super::prelude::*;
 use crate::models::{
-    page::Model as PageModel, page_parent::Model as PageParentModel,
-    page_revision::Model as PageRevisionModel,
+    page::Model as PageModel, page_revision::Model as PageRevisionModel,
 };
+use crate::services::parent::PageParent;
 use crate::services::score::ScoreValue;
 use std::borrow::Cow;
 use time::OffsetDateTime;
@@ -82,8 +82,24 @@ pub enum PageParentSelector<'a> {
     /// Pages which are children of the page making the query.
     ChildOf,
 
-    /// Pages which have specified parent pages.
-    HasParents(&'a [Reference<'a>]),
+    /// Pages which are below the page making the query, at any depth.
+    DescendantOf,
+
+    /// Pages which have any of the specified parent pages.
+    HasAnyParents(&'a [Reference<'a>]),
+
+    /// Pages which have all of the specified parent pages.
+    ///
+    /// If none are specified, then all pages match.
+    HasAllParents(&'a [Reference<'a>]),
+
+    /// Pages which are below any of the specified pages, at any depth.
+    HasAnyAncestors(&'a [Reference<'a>]),
+
+    /// Pages which are below all of the specified pages, at any depth.
+    ///
+    /// If none are specified, then all pages match.
+    HasAllAncestors(&'a [Reference<'a>]),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -288,10 +304,21 @@ pub struct PageQueryOutput<'a>(&'a [PageResult]);
 
 #[derive(Serialize, Debug, PartialEq, Clone)]
 pub struct PageResult {
-    metadata: PageModel,
-    last_revision: PageRevisionModel,
-    // last_comment: TODO,
-    page_parents: Vec<PageParentModel>,
-    wikitext: String,
-    score: f32,
+    pub metadata: PageModel,
+    pub last_revision: PageRevisionModel,
+    pub comments: PageResultComments,
+    pub page_parents: Vec<PageParent>,
+    pub wikitext: String,
+    pub score: ScoreValue,
+    pub vote_count: i32,
+}
+
+/// Comment information for a page, from its discussion thread.
+///
+/// Pages without a discussion thread have no comments.
+#[derive(Serialize, Debug, Default, PartialEq, Eq, Clone)]
+pub struct PageResultComments {
+    pub count: i32,
+    pub last_commented_at: Option<OffsetDateTime>,
+    pub last_commented_by: Option<i64>,
 }
@@ -23,20 +23,24 @@ use crate::models::page_revision::{
     self, Entity as PageRevision, Model as PageRevisionModel,
 };
 use crate::models::sea_orm_active_enums::PageRevisionType;
+use crate::models::site::Model as SiteModel;
 use crate::services::render::RenderOutput;
 use crate::services::score::ScoreValue;
 use crate::services::{
-    LinkService, OutdateService, PageService, ParentService, RenderService, ScoreService,
-    SettingsService, SiteService, TextService,
+    CategoryService, LinkService, OutdateService, PageService, ParentService,
+    RenderService, ScoreService, SettingsService, SiteService, TagService, TextService,
 };
 use crate::types::FetchDirection;
 use crate::utils::{split_category, split_category_name};
-use ftml::data::PageInfo;
+use ftml::data::{PageInfo, PageRef};
 use ftml::layout::Layout;
+use ftml::render::html::HtmlOutput;
 use ftml::settings::{WikitextMode, WikitextSettings};
 use once_cell::sync::Lazy;
 use ref_map::*;
+use std::borrow::Cow;
 use std::num::NonZeroI32;
+use wikidot_normalize::normalize;
 
 /// The changes for the first revision.
 /// The first revision is always considered to have changed everything.
@@ -166,8 +170,14 @@ impl PageRevisionService {
 
         if let Maybe::Set(new_tags) = body.tags {
             if tags != new_tags {
-                changes.push(str!("tags"));
-                tags = new_tags;
+                // Only check against the site's tag rules when
+                // changed, so existing pages can still be edited.
+                let new_tags = TagService::validate(ctx, site_id, new_tags).await?;
+
+                if tags != new_tags {
+                    changes.push(str!("tags"));
+                    tags = new_tags;
+                }
             }
         }
 
@@ -176,9 +186,15 @@ impl PageRevisionService {
 
         // Get wikitext, set wikitext hash
         let wikitext = match body.wikitext {
-            // Insert new wikitext and update hash
+            // Insert new wikitext and update hash,
+            // storing it as a delta against the previous revision's
             Maybe::Set(new_wikitext) => {
-                let new_hash = TextService::create(ctx, new_wikitext.clone()).await?;
+                let new_hash = TextService::create_with_base(
+                    ctx,
+                    new_wikitext.clone(),
+                    &wikitext_hash,
+                )
+                .await?;
 
                 if wikitext_hash != new_hash {
                     changes.push(str!("wikitext"));
@@ -460,7 +476,7 @@ impl PageRevisionService {
         OutdateService::process_page_displace(ctx, site_id, page_id, &slug, 0).await?;
 
         // Delete parent-child relationships, if any
-        ParentService::remove_all(ctx, page_id).await?;
+        ParentService::remove_all(ctx, page_id, user_id).await?;
 
         // Insert the tombstone revision into the table
         let model = page_revision::ActiveModel {
@@ -609,31 +625,15 @@ impl PageRevisionService {
         site_id: i64,
         page_id: i64,
         wikitext: String,
-        RenderPageInfo {
-            layout,
-            slug,
-            title,
-            alt_title,
-            score,
-            tags,
-        }: RenderPageInfo<'_>,
+        render_input: RenderPageInfo<'_>,
     ) -> Result<RenderOutput> {
         // Get site
         let site = SiteService::get(ctx, Reference::from(site_id)).await?;
 
         // Set up parse context
-        let settings = WikitextSettings::from_mode(WikitextMode::Page, layout);
-        let (category_slug, page_slug) = split_category(slug);
-        let page_info = PageInfo {
-            page: cow!(page_slug),
-            category: cow_opt!(category_slug),
-            site: cow!(&site.slug),
-            title: cow!(title),
-            alt_title: cow_opt!(alt_title),
-            score,
-            tags: tags.iter().map(|s| cow!(s)).collect(),
-            language: cow!(&site.locale),
-        };
+        let settings =
+            WikitextSettings::from_mode(WikitextMode::Page, render_input.layout);
+        let page_info = build_page_info(&site, render_input);
 
         // Parse and render
         let output = RenderService::render(ctx, wikitext, &page_info, &settings).await?;
@@ -644,6 +644,164 @@ impl PageRevisionService {
         Ok(output)
     }
 
+    /// Renders wikitext as it would appear on a page, without saving anything.
+    ///
+    /// If the page exists, then its current title, tags, score, and layout are
+    /// used, unless overridden. Links and includes to pages which do not exist
+    /// are returned as warnings.
+    pub async fn preview(
+        ctx: &ServiceContext<'_>,
+        PreviewPageRevision {
+            site_id,
+            mut slug,
+            wikitext,
+            title,
+            alt_title,
+            tags,
+        }: PreviewPageRevision,
+    ) -> Result<PreviewPageRevisionOutput> {
+        normalize(&mut slug);
+        let site = SiteService::get(ctx, Reference::from(site_id)).await?;
+        let page =
+            PageService::get_optional(ctx, site_id, Reference::Slug(cow!(slug))).await?;
+
+        // Use the page's current state, or defaults if it doesn't exist yet
+        let (layout, score, current) = match page {
+            Some(page) => {
+                let (layout, score, revision) = try_join!(
+                    SettingsService::get_layout(ctx, site_id, Some(page.page_id)),
+                    ScoreService::score(ctx, page.page_id),
+                    Self::get_latest(ctx, site_id, page.page_id),
+                )?;
+
+                (
+                    layout,
+                    score,
+                    Some((revision.title, revision.alt_title, revision.tags)),
+                )
+            }
+            None => {
+                let layout = SettingsService::get_layout(ctx, site_id, None).await?;
+                (layout, ScoreValue::Integer(0), None)
+            }
+        };
+
+        let (current_title, current_alt_title, current_tags) =
+            current.unwrap_or_else(|| (str!(split_category(&slug).1), None, vec![]));
+
+        let title = title.unwrap_or(current_title);
+        let alt_title = match alt_title {
+            Maybe::Set(alt_title) => alt_title,
+            Maybe::Unset => current_alt_title,
+        };
+        let tags = tags.unwrap_or(current_tags);
+
+        // Parse and render
+        let render_input = RenderPageInfo {
+            layout,
+            slug: &slug,
+            title: &title,
+            alt_title: alt_title.ref_map(|s| s.as_str()),
+            score,
+            tags: &tags,
+        };
+
+        let settings = WikitextSettings::from_mode(WikitextMode::Page, layout);
+        let page_info = build_page_info(&site, render_input);
+        let (html_output, errors) =
+            RenderService::render_html(ctx, wikitext, &page_info, &settings).await?;
+
+        // Check for links to pages and categories which don't exist
+        let mut warnings = Vec::new();
+        let category_slug = split_category_name(&slug).0;
+        if CategoryService::get_optional(
+            ctx,
+            site_id,
+            Reference::Slug(cow!(category_slug)),
+        )
+        .await?
+        .is_none()
+        {
+            warnings.push(PreviewWarning::UnknownCategory {
+                site: None,
+                category: str!(category_slug),
+            });
+        }
+
+        let backlinks = &html_output.backlinks;
+        for page_ref in backlinks
+            .included_pages
+            .iter()
+            .chain(&backlinks.internal_links)
+        {
+            if let Some(warning) = Self::check_page_ref(ctx, site_id, page_ref).await? {
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            }
+        }
+
+        let HtmlOutput { body, meta, .. } = html_output;
+        Ok(PreviewPageRevisionOutput {
+            html: body,
+            meta,
+            errors,
+            warnings,
+        })
+    }
+
+    /// Checks that a linked or included page exists, returning a warning if not.
+    async fn check_page_ref(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_ref: &PageRef<'_>,
+    ) -> Result<Option<PreviewWarning>> {
+        let (site, to_site_id) = match page_ref.site() {
+            None => (None, site_id),
+            Some(site_slug) => {
+                match SiteService::get_optional(ctx, Reference::Slug(cow!(site_slug)))
+                    .await?
+                {
+                    Some(site) => (Some(str!(site_slug)), site.site_id),
+                    None => {
+                        return Ok(Some(PreviewWarning::MissingSite {
+                            site: str!(site_slug),
+                        }))
+                    }
+                }
+            }
+        };
+
+        let mut page_slug = str!(page_ref.page());
+        normalize(&mut page_slug);
+
+        let page_reference = Reference::Slug(cow!(page_slug));
+        if PageService::get_optional(ctx, to_site_id, page_reference)
+            .await?
+            .is_some()
+        {
+            return Ok(None);
+        }
+
+        let category_slug = split_category_name(&page_slug).0;
+        let category_reference = Reference::Slug(cow!(category_slug));
+        let warning =
+            match CategoryService::get_optional(ctx, to_site_id, category_reference)
+                .await?
+            {
+                Some(_) => PreviewWarning::MissingPage {
+                    site,
+                    page: page_slug,
+                },
+                None => PreviewWarning::UnknownCategory {
+                    site,
+                    category: str!(category_slug),
+                },
+            };
+
+        Ok(Some(warning))
+    }
+
     /// Re-renders a page.
     ///
     /// This fetches the latest revision for a page, and re-renders it.
@@ -688,31 +846,12 @@ impl PageRevisionService {
             }
         }
 
-        // Get data for page
-        let (wikitext, score, layout) = try_join!(
-            TextService::get(ctx, &revision.wikitext_hash),
-            ScoreService::score(ctx, page_id),
-            SettingsService::get_layout(ctx, site_id, Some(page_id)),
-        )?;
-
-        // This is necessary until we are able to replace the
-        // 'tags' column with TEXT[] instead of JSON.
-        let render_input = RenderPageInfo {
-            layout,
-            slug: &revision.slug,
-            title: &revision.title,
-            alt_title: revision.alt_title.ref_map(|s| s.as_str()),
-            score,
-            tags: &revision.tags,
-        };
-
         // TODO use html_output
         let RenderOutput {
             compiled_hash,
             compiled_generator,
             ..
-        } = Self::render_and_update_links(ctx, site_id, page_id, wikitext, render_input)
-            .await?;
+        } = Self::render_revision(ctx, site_id, page_id, &revision).await?;
 
         // Update descendents
         OutdateService::process_page_edit(ctx, site_id, page_id, &revision.slug, depth)
@@ -730,6 +869,53 @@ impl PageRevisionService {
         Ok(())
     }
 
+    /// Rebuilds the link connections of a page.
+    ///
+    /// This renders the latest revision of the page, but only updates
+    /// its links. Neither the revision nor dependent pages are changed.
+    pub async fn rebuild_links(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+    ) -> Result<()> {
+        let revision = Self::get_latest(ctx, site_id, page_id).await?;
+        info!(
+            "Rebuilding links: site ID {} page ID {} revision ID {}",
+            site_id, page_id, revision.revision_id,
+        );
+
+        Self::render_revision(ctx, site_id, page_id, &revision).await?;
+        Ok(())
+    }
+
+    /// Helper method to render an existing revision of a page.
+    async fn render_revision(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_id: i64,
+        revision: &PageRevisionModel,
+    ) -> Result<RenderOutput> {
+        // Get data for page
+        let (wikitext, score, layout) = try_join!(
+            TextService::get(ctx, &revision.wikitext_hash),
+            ScoreService::score(ctx, page_id),
+            SettingsService::get_layout(ctx, site_id, Some(page_id)),
+        )?;
+
+        // This is necessary until we are able to replace the
+        // 'tags' column with TEXT[] instead of JSON.
+        let render_input = RenderPageInfo {
+            layout,
+            slug: &revision.slug,
+            title: &revision.title,
+            alt_title: revision.alt_title.ref_map(|s| s.as_str()),
+            score,
+            tags: &revision.tags,
+        };
+
+        Self::render_and_update_links(ctx, site_id, page_id, wikitext, render_input).await
+    }
+
     /// Modifies an existing revision.
     ///
     /// Normally you should think of revisions as being immutable
@@ -945,6 +1131,31 @@ struct RenderPageInfo<'a> {
     tags: &'a [String],
 }
 
+/// Builds the page information used when rendering.
+fn build_page_info<'a>(
+    site: &'a SiteModel,
+    RenderPageInfo {
+        layout: _,
+        slug,
+        title,
+        alt_title,
+        score,
+        tags,
+    }: RenderPageInfo<'a>,
+) -> PageInfo<'a> {
+    let (category_slug, page_slug) = split_category(slug);
+    PageInfo {
+        page: cow!(page_slug),
+        category: category_slug.map(Cow::Borrowed),
+        site: cow!(&site.slug),
+        title: cow!(title),
+        alt_title: alt_title.map(Cow::Borrowed),
+        score,
+        tags: tags.iter().map(|s| cow!(s)).collect(),
+        language: cow!(&site.locale),
+    }
+}
+
 #[inline]
 fn replace_hash(dest: &mut Vec<u8>, src: &[u8]) {
     debug_assert_eq!(
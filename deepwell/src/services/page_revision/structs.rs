@@ -19,10 +19,12 @@
  */
 
 use super::prelude::*;
+use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::sea_orm_active_enums::PageRevisionType;
 use crate::types::{FetchDirection, PageDetails};
 use ftml::layout::Layout;
 use ftml::parsing::ParseError;
+use ftml::render::html::HtmlMeta;
 use std::num::NonZeroI32;
 use time::OffsetDateTime;
 
@@ -184,3 +186,122 @@ pub struct PageRevisionModelFiltered {
     pub slug: Option<String>,
     pub tags: Option<Vec<String>>,
 }
+
+impl From<PageRevisionModel> for PageRevisionModelFiltered {
+    /// Strips any hidden fields from the revision.
+    ///
+    /// Text fields are left unpopulated, the caller
+    /// is responsible for fetching them if needed.
+    fn from(model: PageRevisionModel) -> PageRevisionModelFiltered {
+        let PageRevisionModel {
+            revision_id,
+            revision_type,
+            created_at,
+            updated_at,
+            from_wikidot,
+            revision_number,
+            page_id,
+            site_id,
+            user_id,
+            changes,
+            wikitext_hash: _,
+            compiled_hash: _,
+            compiled_at,
+            compiled_generator,
+            comments,
+            hidden,
+            title,
+            mut alt_title,
+            slug,
+            tags,
+        } = model;
+
+        let mut comments = Some(comments);
+        let mut title = Some(title);
+        // alt-title is already Option and we're not doubling up
+        let mut slug = Some(slug);
+        let mut tags = Some(tags);
+
+        for field in &hidden {
+            // TODO hidden fields aren't standardized yet
+            match field.as_str() {
+                "wikitext" | "compiled" => (),
+                "comments" => comments = None,
+                "title" => title = None,
+                "alt_title" => alt_title = None,
+                "slug" => slug = None,
+                "tags" => tags = None,
+                _ => warn!(
+                    "Unknown field name in hidden for revision ID {revision_id}: {field}",
+                ),
+            }
+        }
+
+        PageRevisionModelFiltered {
+            revision_id,
+            revision_type,
+            created_at,
+            updated_at,
+            from_wikidot,
+            revision_number,
+            page_id,
+            site_id,
+            user_id,
+            changes,
+            wikitext: None,
+            compiled_html: None,
+            compiled_at,
+            compiled_generator,
+            comments,
+            hidden,
+            title,
+            alt_title,
+            slug,
+            tags,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PreviewPageRevision {
+    pub site_id: i64,
+    pub slug: String,
+    pub wikitext: String,
+
+    /// Overrides the page's current title, if set.
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// Overrides the page's current alternate title, if set.
+    #[serde(default)]
+    pub alt_title: Maybe<Option<String>>,
+
+    /// Overrides the page's current tags, if set.
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PreviewPageRevisionOutput {
+    pub html: String,
+    pub meta: Vec<HtmlMeta>,
+    pub errors: Vec<ParseError>,
+    pub warnings: Vec<PreviewWarning>,
+}
+
+/// Issues with the previewed wikitext which are not parser errors.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum PreviewWarning {
+    /// A linked or included page does not exist.
+    MissingPage { site: Option<String>, page: String },
+
+    /// A linked or included page is in a category which does not exist.
+    UnknownCategory {
+        site: Option<String>,
+        category: String,
+    },
+
+    /// A linked or included page is on a site which does not exist.
+    MissingSite { site: String },
+}
@@ -0,0 +1,41 @@
+/*
+ * services/page_schedule/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The page schedule service, for page changes which go live at a later time.
+//!
+//! A scheduled change is either the creation of a new page, or an edit
+//! to an existing one. It is stored as a pending row, and a job is queued
+//! to publish it, which goes through `PageService` as a regular creation
+//! or edit would at that time.
+//!
+//! Since queued jobs can only be delayed for so long, a job which is run
+//! before its change is due simply queues itself again.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::PageScheduleService;
+pub use self::structs::*;
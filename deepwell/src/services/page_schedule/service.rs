@@ -0,0 +1,454 @@
+/*
+ * services/page_schedule/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::page_schedule::{
+    self, Entity as PageSchedule, Model as PageScheduleModel,
+};
+use crate::models::sea_orm_active_enums::{PageScheduleStatus, PageScheduleType};
+use crate::services::job::{Job, JOB_MAXIMUM_DELAY};
+use crate::services::page::{CreatePage, CreatePageOutput, EditPage, EditPageBody};
+use crate::services::{JobService, PageService, TextService};
+use sea_orm::TransactionTrait;
+use std::time::Duration as StdDuration;
+use time::OffsetDateTime;
+use wikidot_normalize::normalize;
+
+#[derive(Debug)]
+pub struct PageScheduleService;
+
+impl PageScheduleService {
+    /// Schedules the creation of a new page.
+    ///
+    /// The slug is checked now, but the page can still fail to be
+    /// created if another page takes the slug before it is published.
+    pub async fn schedule_create(
+        ctx: &ServiceContext<'_>,
+        SchedulePageCreate {
+            site_id,
+            wikitext,
+            title,
+            alt_title,
+            mut slug,
+            revision_comments: comments,
+            user_id,
+            publish_at,
+        }: SchedulePageCreate,
+    ) -> Result<PageScheduleModel> {
+        info!("Scheduling creation of page {slug} in site ID {site_id} at {publish_at}");
+        check_publish_at(publish_at)?;
+
+        normalize(&mut slug);
+        PageService::check_conflicts(ctx, site_id, &slug, "schedule").await?;
+
+        let txn = ctx.transaction();
+        let wikitext_hash = TextService::create(ctx, wikitext).await?;
+        let model = page_schedule::ActiveModel {
+            schedule_type: Set(PageScheduleType::Create),
+            publish_at: Set(publish_at),
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            slug: Set(Some(slug)),
+            changes: Set(vec![str!("wikitext"), str!("title"), str!("alt_title")]),
+            wikitext_hash: Set(Some(wikitext_hash.to_vec())),
+            title: Set(Some(title)),
+            alt_title: Set(alt_title),
+            comments: Set(comments),
+            ..Default::default()
+        };
+        let schedule = model.insert(txn).await?;
+
+        Self::queue(ctx, &schedule).await?;
+        Ok(schedule)
+    }
+
+    /// Schedules an edit to an existing page.
+    ///
+    /// Only the fields which are set are changed, and they are applied
+    /// on top of whatever the latest revision is at the time of publishing.
+    pub async fn schedule_edit(
+        ctx: &ServiceContext<'_>,
+        SchedulePageEdit {
+            site_id,
+            page: reference,
+            revision_comments: comments,
+            user_id,
+            publish_at,
+            body:
+                EditPageBody {
+                    wikitext,
+                    title,
+                    alt_title,
+                    tags,
+                },
+        }: SchedulePageEdit<'_>,
+    ) -> Result<PageScheduleModel> {
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+
+        info!(
+            "Scheduling edit of page ID {page_id} in site ID {site_id} at {publish_at}"
+        );
+        check_publish_at(publish_at)?;
+
+        let mut changes = Vec::new();
+        let mut model = page_schedule::ActiveModel {
+            schedule_type: Set(PageScheduleType::Edit),
+            publish_at: Set(publish_at),
+            site_id: Set(site_id),
+            page_id: Set(Some(page_id)),
+            user_id: Set(user_id),
+            comments: Set(comments),
+            ..Default::default()
+        };
+
+        if let Maybe::Set(wikitext) = wikitext {
+            let wikitext_hash = TextService::create(ctx, wikitext).await?;
+            model.wikitext_hash = Set(Some(wikitext_hash.to_vec()));
+            changes.push(str!("wikitext"));
+        }
+
+        if let Maybe::Set(title) = title {
+            model.title = Set(Some(title));
+            changes.push(str!("title"));
+        }
+
+        if let Maybe::Set(alt_title) = alt_title {
+            model.alt_title = Set(alt_title);
+            changes.push(str!("alt_title"));
+        }
+
+        if let Maybe::Set(tags) = tags {
+            model.tags = Set(Some(tags));
+            changes.push(str!("tags"));
+        }
+
+        if changes.is_empty() {
+            error!("Scheduled edit has no changes");
+            return Err(Error::PageScheduleEmpty);
+        }
+
+        model.changes = Set(changes);
+        let schedule = model.insert(txn).await?;
+
+        Self::queue(ctx, &schedule).await?;
+        Ok(schedule)
+    }
+
+    /// Cancels a scheduled change which has not yet been published.
+    pub async fn cancel(
+        ctx: &ServiceContext<'_>,
+        schedule_id: i64,
+    ) -> Result<PageScheduleModel> {
+        info!("Cancelling scheduled page change ID {schedule_id}");
+
+        let txn = ctx.transaction();
+        let schedule = Self::get(ctx, schedule_id).await?;
+        if schedule.status != PageScheduleStatus::Pending {
+            error!(
+                "Scheduled page change ID {schedule_id} is not pending: {:?}",
+                schedule.status,
+            );
+            return Err(Error::PageScheduleNotPending);
+        }
+
+        // The queued job is left as is, it will see
+        // the cancellation and do nothing.
+        let model = page_schedule::ActiveModel {
+            schedule_id: Set(schedule_id),
+            status: Set(PageScheduleStatus::Cancelled),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+        let schedule = model.update(txn).await?;
+        Ok(schedule)
+    }
+
+    /// Publishes a scheduled change, if it is due.
+    ///
+    /// This is run by the job worker. If the change is not yet due,
+    /// then the remaining delay is returned so the job can be queued
+    /// again.
+    ///
+    /// The change is applied within a savepoint, so a failure can be
+    /// undone without aborting the job's transaction. If the failure is
+    /// transient then the error is propagated so the job is retried,
+    /// otherwise the change is marked as failed along with the error message.
+    pub async fn publish(
+        ctx: &ServiceContext<'_>,
+        schedule_id: i64,
+    ) -> Result<Option<StdDuration>> {
+        let schedule = match Self::get_optional(ctx, schedule_id).await? {
+            Some(schedule) => schedule,
+            None => {
+                warn!("Scheduled page change ID {schedule_id} does not exist, skipping");
+                return Ok(None);
+            }
+        };
+
+        if schedule.status != PageScheduleStatus::Pending {
+            debug!(
+                "Scheduled page change ID {schedule_id} is {:?}, skipping",
+                schedule.status,
+            );
+            return Ok(None);
+        }
+
+        if let Some(delay) = time_until(schedule.publish_at) {
+            debug!("Scheduled page change ID {schedule_id} is not due yet, waiting {delay:?}");
+            return Ok(Some(delay));
+        }
+
+        info!(
+            "Publishing scheduled page change ID {schedule_id} in site ID {}",
+            schedule.site_id,
+        );
+
+        let txn = ctx.transaction();
+        let savepoint = txn.begin().await?;
+        let state = ctx.state();
+        let inner_ctx = ServiceContext::new(&state, &savepoint);
+
+        match Self::publish_inner(&inner_ctx, &schedule).await {
+            Ok((page_id, revision_id)) => {
+                savepoint.commit().await?;
+
+                let model = page_schedule::ActiveModel {
+                    schedule_id: Set(schedule_id),
+                    status: Set(PageScheduleStatus::Published),
+                    updated_at: Set(Some(now())),
+                    published_at: Set(Some(now())),
+                    page_id: Set(Some(page_id)),
+                    revision_id: Set(revision_id),
+                    error: Set(None),
+                    ..Default::default()
+                };
+                model.update(txn).await?;
+                Ok(None)
+            }
+            Err(error) => {
+                savepoint.rollback().await?;
+
+                if error.is_transient() {
+                    warn!(
+                        "Unable to publish scheduled page change ID {schedule_id}, will retry: {error}",
+                    );
+                    return Err(error);
+                }
+
+                error!(
+                    "Unable to publish scheduled page change ID {schedule_id}: {error}"
+                );
+                let model = page_schedule::ActiveModel {
+                    schedule_id: Set(schedule_id),
+                    status: Set(PageScheduleStatus::Failed),
+                    updated_at: Set(Some(now())),
+                    error: Set(Some(error.to_string())),
+                    ..Default::default()
+                };
+                model.update(txn).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Applies the scheduled change, returning the page ID and the new revision ID.
+    ///
+    /// The revision ID is `None` if an edit didn't actually change anything.
+    async fn publish_inner(
+        ctx: &ServiceContext<'_>,
+        schedule: &PageScheduleModel,
+    ) -> Result<(i64, Option<i64>)> {
+        let has_change = |field| schedule.changes.iter().any(|change| change == field);
+        let wikitext = match schedule.wikitext_hash {
+            Some(ref hash) => Some(TextService::get(ctx, hash).await?),
+            None => None,
+        };
+
+        match schedule.schedule_type {
+            PageScheduleType::Create => {
+                let CreatePageOutput {
+                    page_id,
+                    revision_id,
+                    ..
+                } = PageService::create(
+                    ctx,
+                    CreatePage {
+                        site_id: schedule.site_id,
+                        wikitext: wikitext.unwrap_or_default(),
+                        title: schedule.title.clone().unwrap_or_default(),
+                        alt_title: schedule.alt_title.clone(),
+                        slug: schedule.slug.clone().unwrap_or_default(),
+                        layout: None,
+                        revision_comments: schedule.comments.clone(),
+                        user_id: schedule.user_id,
+                        bypass_filter: false,
+                    },
+                )
+                .await?;
+
+                Ok((page_id, Some(revision_id)))
+            }
+            PageScheduleType::Edit => {
+                let page_id = schedule.page_id.expect("Scheduled edit has NULL page_id");
+
+                let PageModel {
+                    latest_revision_id, ..
+                } = PageService::get(ctx, schedule.site_id, Reference::Id(page_id))
+                    .await?;
+
+                // Only set the fields listed in changes
+                macro_rules! field {
+                    ($name:expr, $value:expr $(,)?) => {
+                        if has_change($name) {
+                            Maybe::Set($value)
+                        } else {
+                            Maybe::Unset
+                        }
+                    };
+                }
+
+                let output = PageService::edit(
+                    ctx,
+                    EditPage {
+                        site_id: schedule.site_id,
+                        page: Reference::Id(page_id),
+                        last_revision_id: latest_revision_id
+                            .expect("Page row has NULL latest_revision_id"),
+                        revision_comments: schedule.comments.clone(),
+                        user_id: schedule.user_id,
+                        body: EditPageBody {
+                            wikitext: field!("wikitext", wikitext.unwrap_or_default()),
+                            title: field!(
+                                "title",
+                                schedule.title.clone().unwrap_or_default()
+                            ),
+                            alt_title: field!("alt_title", schedule.alt_title.clone()),
+                            tags: field!(
+                                "tags",
+                                schedule.tags.clone().unwrap_or_default()
+                            ),
+                        },
+                    },
+                )
+                .await?;
+
+                Ok((page_id, output.map(|output| output.revision_id)))
+            }
+        }
+    }
+
+    /// Gets all scheduled changes in a site, soonest first.
+    pub async fn list(
+        ctx: &ServiceContext<'_>,
+        ListPageSchedules { site_id, status }: ListPageSchedules,
+    ) -> Result<Vec<PageScheduleModel>> {
+        let txn = ctx.transaction();
+        let mut condition =
+            Condition::all().add(page_schedule::Column::SiteId.eq(site_id));
+
+        if let Some(status) = status {
+            condition = condition.add(page_schedule::Column::Status.eq(status));
+        }
+
+        let schedules = PageSchedule::find()
+            .filter(condition)
+            .order_by_asc(page_schedule::Column::PublishAt)
+            .order_by_asc(page_schedule::Column::ScheduleId)
+            .all(txn)
+            .await?;
+
+        Ok(schedules)
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        schedule_id: i64,
+    ) -> Result<Option<PageScheduleModel>> {
+        let txn = ctx.transaction();
+        let schedule = PageSchedule::find_by_id(schedule_id).one(txn).await?;
+        Ok(schedule)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        schedule_id: i64,
+    ) -> Result<PageScheduleModel> {
+        find_or_error!(Self::get_optional(ctx, schedule_id), PageSchedule)
+    }
+
+    /// Queues the job to publish this scheduled change.
+    async fn queue(ctx: &ServiceContext<'_>, schedule: &PageScheduleModel) -> Result<()> {
+        JobService::queue_job(
+            ctx,
+            &Job::PublishScheduledPage {
+                schedule_id: schedule.schedule_id,
+            },
+            time_until(schedule.publish_at),
+        )
+        .await
+    }
+}
+
+fn check_publish_at(publish_at: OffsetDateTime) -> Result<()> {
+    if publish_at <= now() {
+        error!("Scheduled publishing time {publish_at} is not in the future");
+        return Err(Error::PageScheduleInPast);
+    }
+
+    Ok(())
+}
+
+/// Gets how long until the given time, for use as a job delay.
+///
+/// Returns `None` if the time has already passed. The delay is capped
+/// to the longest allowed, in which case the job will need to be
+/// queued again when it runs.
+fn time_until(timestamp: OffsetDateTime) -> Option<StdDuration> {
+    let remaining = StdDuration::try_from(timestamp - now()).ok()?;
+    if remaining.is_zero() {
+        return None;
+    }
+
+    Some(remaining.min(JOB_MAXIMUM_DELAY))
+}
+
+#[test]
+fn publish_failures() {
+    use sea_orm::DbErr;
+    use time::Duration;
+
+    // Transient errors are retried, the rest mark the change as failed
+    assert!(Error::Database(DbErr::Custom(str!("connection reset"))).is_transient());
+    assert!(Error::RenderTimeout.is_transient());
+    assert!(!Error::PageSlugExists.is_transient());
+    assert!(!Error::PageNotFound.is_transient());
+    assert!(!Error::FilterViolation.is_transient());
+
+    // Due changes are published immediately
+    assert_eq!(time_until(now() - Duration::minutes(1)), None);
+    assert!(time_until(now() + Duration::minutes(1)).is_some());
+    assert_eq!(
+        time_until(now() + Duration::days(365 * 10)),
+        Some(JOB_MAXIMUM_DELAY),
+    );
+}
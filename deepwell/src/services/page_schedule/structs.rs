@@ -0,0 +1,67 @@
+/*
+ * services/page_schedule/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::PageScheduleStatus;
+use crate::services::page::EditPageBody;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SchedulePageCreate {
+    pub site_id: i64,
+    pub wikitext: String,
+    pub title: String,
+    pub alt_title: Option<String>,
+    pub slug: String,
+    pub revision_comments: String,
+    pub user_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub publish_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SchedulePageEdit<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub revision_comments: String,
+    pub user_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub publish_at: OffsetDateTime,
+
+    #[serde(flatten)]
+    pub body: EditPageBody,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageSchedule {
+    pub schedule_id: i64,
+}
+
+pub type CancelPageSchedule = GetPageSchedule;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ListPageSchedules {
+    pub site_id: i64,
+
+    #[serde(default)]
+    pub status: Option<PageScheduleStatus>,
+}
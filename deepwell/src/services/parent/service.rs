@@ -18,11 +18,82 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-// TODO replace ParentService with a new relation type
-
 use super::prelude::*;
-use crate::models::page_parent::{self, Entity as PageParent, Model as PageParentModel};
-use crate::services::PageService;
+use crate::models::page::{self, Entity as Page};
+use crate::models::relation::{self, Entity as Relation};
+use crate::models::sea_orm_active_enums::RelationObjectType;
+use crate::services::relation::{
+    CreatePageParent, GetPageParent, RelationDirection, RelationObject, RelationType,
+    RemovePageParent,
+};
+use crate::services::{PageService, RelationService};
+use sea_orm::{DatabaseBackend, Statement};
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+
+/// Takes a lock on the page hierarchy of a site, until the transaction ends.
+///
+/// This is an advisory lock keyed by the site ID alone, so any other
+/// advisory locks added in the future will need to use a distinct key space.
+///
+/// Parameters:
+/// * `$1` &mdash; The site ID to lock
+const HIERARCHY_LOCK_QUERY: &str = "SELECT pg_advisory_xact_lock($1)";
+
+/// Walks up the page hierarchy from a page, yielding each parent to child edge.
+///
+/// Parameters:
+/// * `$1` &mdash; The page ID to start from
+/// * `$2` &mdash; The maximum number of levels to walk
+/// * `$3` &mdash; The relation type value for page parents
+const ANCESTOR_EDGES_QUERY: &str = r#"
+WITH RECURSIVE edges (parent_id, child_id, depth) AS (
+        SELECT dest_id, from_id, 1
+        FROM relation
+        WHERE relation_type = $3
+        AND dest_type = 'page'
+        AND from_type = 'page'
+        AND overwritten_at IS NULL
+        AND deleted_at IS NULL
+        AND from_id = $1
+    UNION
+        SELECT r.dest_id, r.from_id, e.depth + 1
+        FROM relation r
+        JOIN edges e ON r.from_id = e.parent_id
+        WHERE r.relation_type = $3
+        AND r.dest_type = 'page'
+        AND r.from_type = 'page'
+        AND r.overwritten_at IS NULL
+        AND r.deleted_at IS NULL
+        AND e.depth < $2
+)
+SELECT DISTINCT parent_id, child_id FROM edges
+"#;
+
+/// Like `ANCESTOR_EDGES_QUERY`, but walks down the page hierarchy instead.
+const DESCENDANT_EDGES_QUERY: &str = r#"
+WITH RECURSIVE edges (parent_id, child_id, depth) AS (
+        SELECT dest_id, from_id, 1
+        FROM relation
+        WHERE relation_type = $3
+        AND dest_type = 'page'
+        AND from_type = 'page'
+        AND overwritten_at IS NULL
+        AND deleted_at IS NULL
+        AND dest_id = $1
+    UNION
+        SELECT r.dest_id, r.from_id, e.depth + 1
+        FROM relation r
+        JOIN edges e ON r.dest_id = e.child_id
+        WHERE r.relation_type = $3
+        AND r.dest_type = 'page'
+        AND r.from_type = 'page'
+        AND r.overwritten_at IS NULL
+        AND r.deleted_at IS NULL
+        AND e.depth < $2
+)
+SELECT DISTINCT parent_id, child_id FROM edges
+"#;
 
 #[derive(Debug)]
 pub struct ParentService;
@@ -30,57 +101,114 @@ pub struct ParentService;
 impl ParentService {
     /// Adds a parental relationship with the two given pages.
     ///
-    /// Both pages must be extant and on the same site.
+    /// Both pages must be extant and on the same site. The relationship
+    /// cannot make a page its own ancestor, or make the hierarchy
+    /// deeper than the configured maximum.
     ///
     /// # Returns
-    /// Returns `Some` with a model if the relationship was created,
+    /// Returns `Some` with the relationship if it was created,
     /// and `None` if it already existed.
     pub async fn create(
         ctx: &ServiceContext<'_>,
-        ParentDescription {
+        ModifyParent {
             site_id,
             parent: parent_reference,
             child: child_reference,
-        }: ParentDescription<'_>,
-    ) -> Result<Option<PageParentModel>> {
-        let txn = ctx.transaction();
-
+            user_id,
+        }: ModifyParent<'_>,
+    ) -> Result<Option<PageParent>> {
         let (parent_page, child_page) = try_join!(
             PageService::get(ctx, site_id, parent_reference),
             PageService::get(ctx, site_id, child_reference),
         )?;
 
+        let parent_page_id = parent_page.page_id;
+        let child_page_id = child_page.page_id;
+
         // Check if the two pages are the same
-        if parent_page.page_id == child_page.page_id {
-            error!(
-                "Cannot parent a page to itself (ID {})",
-                parent_page.page_id,
-            );
+        if parent_page_id == child_page_id {
+            error!("Cannot parent a page to itself (ID {parent_page_id})");
             return Err(Error::PageParentExists);
         }
 
+        // Lock the site's page hierarchy, so that concurrent changes to it
+        // wait for this one, and so see it in the checks below.
+        //
+        // Locking only these two pages isn't enough, since a cycle can be
+        // formed by several relationships being added at once.
+        let txn = ctx.transaction();
+        txn.execute(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            HIERARCHY_LOCK_QUERY,
+            [site_id.into()],
+        ))
+        .await?;
+
         // Check if this relationship already exists
-        let relationship =
-            PageParent::find_by_id((parent_page.page_id, child_page.page_id))
-                .one(txn)
-                .await?;
+        let description = GetPageParent {
+            parent_page_id,
+            child_page_id,
+        };
 
-        match relationship {
-            // Create new parent relationship
-            None => {
-                let model = page_parent::ActiveModel {
-                    parent_page_id: Set(parent_page.page_id),
-                    child_page_id: Set(child_page.page_id),
-                    ..Default::default()
-                };
-
-                let parent = model.insert(txn).await?;
-                Ok(Some(parent))
-            }
+        if RelationService::page_parent_exists(ctx, description).await? {
+            return Ok(None);
+        }
+
+        // Check that this won't create a cycle, or make the hierarchy too deep.
+        //
+        // One more level than the maximum is fetched, so that
+        // we can tell when an existing hierarchy is already too deep.
+        let maximum_depth = ctx.config().maximum_parent_depth;
+        let (ancestors, descendants) = try_join!(
+            Self::get_edges(
+                ctx,
+                parent_page_id,
+                ParentalRelationshipType::Parent,
+                maximum_depth + 1,
+            ),
+            Self::get_edges(
+                ctx,
+                child_page_id,
+                ParentalRelationshipType::Child,
+                maximum_depth + 1,
+            ),
+        )?;
 
-            // Parent relationship already exists
-            Some(_) => Ok(None),
+        if ancestors
+            .depths(parent_page_id, ParentalRelationshipType::Parent)
+            .contains_key(&child_page_id)
+        {
+            error!(
+                "Cannot parent page ID {child_page_id} to page ID {parent_page_id}, it is already an ancestor",
+            );
+            return Err(Error::PageParentCycle);
         }
+
+        let depth = ancestors.height(parent_page_id, ParentalRelationshipType::Parent)
+            + descendants.height(child_page_id, ParentalRelationshipType::Child)
+            + 1;
+
+        if depth > maximum_depth {
+            error!(
+                "Parenting page ID {child_page_id} to page ID {parent_page_id} would make the hierarchy too deep ({depth} > {maximum_depth})",
+            );
+            return Err(Error::PageParentTooDeep);
+        }
+
+        // Create new parent relationship
+        RelationService::create_page_parent(
+            ctx,
+            CreatePageParent {
+                parent_page_id,
+                child_page_id,
+                metadata: (),
+                created_by: user_id,
+            },
+        )
+        .await?;
+
+        let relation = RelationService::get_page_parent(ctx, description).await?;
+        Ok(Some(PageParent::from(relation)))
     }
 
     /// Removes the parental relationship with the two given pages.
@@ -90,31 +218,38 @@ impl ParentService {
     /// `false` if it was already absent.
     pub async fn remove(
         ctx: &ServiceContext<'_>,
-        ParentDescription {
+        ModifyParent {
             site_id,
             parent: parent_reference,
             child: child_reference,
-        }: ParentDescription<'_>,
+            user_id,
+        }: ModifyParent<'_>,
     ) -> Result<RemoveParentOutput> {
-        let txn = ctx.transaction();
-
         let (parent_page, child_page) = try_join!(
             PageService::get(ctx, site_id, parent_reference),
             PageService::get(ctx, site_id, child_reference),
         )?;
 
-        let DeleteResult { rows_affected } =
-            PageParent::delete_by_id((parent_page.page_id, child_page.page_id))
-                .exec(txn)
-                .await?;
+        let description = GetPageParent {
+            parent_page_id: parent_page.page_id,
+            child_page_id: child_page.page_id,
+        };
 
-        debug_assert!(
-            rows_affected <= 1,
-            "Rows deleted using ID was more than 1: {rows_affected}",
-        );
+        if !RelationService::page_parent_exists(ctx, description).await? {
+            return Ok(RemoveParentOutput { was_deleted: false });
+        }
 
-        let was_deleted = rows_affected == 1;
-        Ok(RemoveParentOutput { was_deleted })
+        RelationService::remove_page_parent(
+            ctx,
+            RemovePageParent {
+                parent_page_id: parent_page.page_id,
+                child_page_id: child_page.page_id,
+                removed_by: user_id,
+            },
+        )
+        .await?;
+
+        Ok(RemoveParentOutput { was_deleted: true })
     }
 
     pub async fn get_optional(
@@ -124,19 +259,22 @@ impl ParentService {
             parent: parent_reference,
             child: child_reference,
         }: ParentDescription<'_>,
-    ) -> Result<Option<PageParentModel>> {
-        let txn = ctx.transaction();
-
+    ) -> Result<Option<PageParent>> {
         let (parent_page, child_page) = try_join!(
             PageService::get(ctx, site_id, parent_reference),
             PageService::get(ctx, site_id, child_reference),
         )?;
 
-        let model = PageParent::find_by_id((parent_page.page_id, child_page.page_id))
-            .one(txn)
-            .await?;
+        let relation = RelationService::get_optional_page_parent(
+            ctx,
+            GetPageParent {
+                parent_page_id: parent_page.page_id,
+                child_page_id: child_page.page_id,
+            },
+        )
+        .await?;
 
-        Ok(model)
+        Ok(relation.map(PageParent::from))
     }
 
     #[inline]
@@ -144,7 +282,7 @@ impl ParentService {
     pub async fn get(
         ctx: &ServiceContext<'_>,
         description: ParentDescription<'_>,
-    ) -> Result<PageParentModel> {
+    ) -> Result<PageParent> {
         find_or_error!(Self::get_optional(ctx, description), PageParent)
     }
 
@@ -154,20 +292,24 @@ impl ParentService {
         site_id: i64,
         reference: Reference<'_>,
         relationship_type: ParentalRelationshipType,
-    ) -> Result<Vec<PageParentModel>> {
-        let txn = ctx.transaction();
+    ) -> Result<Vec<PageParent>> {
         let page_id = PageService::get_id(ctx, site_id, reference).await?;
-        let column = match relationship_type {
-            ParentalRelationshipType::Parent => page_parent::Column::ChildPageId,
-            ParentalRelationshipType::Child => page_parent::Column::ParentPageId,
+
+        // The child is the "from" side of the relation,
+        // so a page's parents are in that direction.
+        let direction = match relationship_type {
+            ParentalRelationshipType::Parent => RelationDirection::From,
+            ParentalRelationshipType::Child => RelationDirection::Dest,
         };
 
-        let models = PageParent::find()
-            .filter(column.eq(page_id))
-            .all(txn)
-            .await?;
+        let relations = RelationService::get_page_parent_entries(
+            ctx,
+            RelationObject::Page(page_id),
+            direction,
+        )
+        .await?;
 
-        Ok(models)
+        Ok(relations.into_iter().map(PageParent::from).collect())
     }
 
     /// Gets all children of the given page.
@@ -176,21 +318,38 @@ impl ParentService {
         ctx: &ServiceContext<'_>,
         site_id: i64,
         reference: Reference<'_>,
-    ) -> Result<Vec<PageParentModel>> {
+    ) -> Result<Vec<PageParent>> {
         Self::get_relationships(ctx, site_id, reference, ParentalRelationshipType::Child)
             .await
     }
 
-    /// Gets all parents of the given page.
+    /// Gets all parents of the given page, oldest first.
     pub async fn get_parents(
         ctx: &ServiceContext<'_>,
         site_id: i64,
         reference: Reference<'_>,
-    ) -> Result<Vec<PageParentModel>> {
+    ) -> Result<Vec<PageParent>> {
         Self::get_relationships(ctx, site_id, reference, ParentalRelationshipType::Parent)
             .await
     }
 
+    /// Gets all relationships where the parent is one of the given pages.
+    pub async fn get_all_for_pages(
+        ctx: &ServiceContext<'_>,
+        page_ids: Vec<i64>,
+    ) -> Result<Vec<PageParent>> {
+        let txn = ctx.transaction();
+        let relations = Relation::find()
+            .filter(
+                Self::relation_condition().add(relation::Column::DestId.is_in(page_ids)),
+            )
+            .order_by_asc(relation::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(relations.into_iter().map(PageParent::from).collect())
+    }
+
     /// Removes all parent relationships involving this page.
     ///
     /// Whether this page is a parent or a child, this method
@@ -198,14 +357,25 @@ impl ParentService {
     ///
     /// # Returns
     /// Returns the number of relationships deleted.
-    pub async fn remove_all(ctx: &ServiceContext<'_>, page_id: i64) -> Result<u64> {
+    pub async fn remove_all(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        user_id: i64,
+    ) -> Result<u64> {
         let txn = ctx.transaction();
 
-        let rows_deleted = PageParent::delete_many()
+        let rows_deleted = Relation::update_many()
+            .set(relation::ActiveModel {
+                deleted_at: Set(Some(now())),
+                deleted_by: Set(Some(user_id)),
+                ..Default::default()
+            })
             .filter(
-                Condition::any()
-                    .add(page_parent::Column::ParentPageId.eq(page_id))
-                    .add(page_parent::Column::ChildPageId.eq(page_id)),
+                Self::relation_condition().add(
+                    Condition::any()
+                        .add(relation::Column::DestId.eq(page_id))
+                        .add(relation::Column::FromId.eq(page_id)),
+                ),
             )
             .exec(txn)
             .await?
@@ -213,4 +383,223 @@ impl ParentService {
 
         Ok(rows_deleted)
     }
+
+    // Hierarchy
+
+    /// Gets all pages above this one, nearest first.
+    pub async fn get_ancestors(
+        ctx: &ServiceContext<'_>,
+        input: GetPageHierarchy<'_>,
+    ) -> Result<Vec<HierarchyPage>> {
+        Self::get_hierarchy(ctx, input, ParentalRelationshipType::Parent).await
+    }
+
+    /// Gets all pages below this one, nearest first.
+    pub async fn get_descendants(
+        ctx: &ServiceContext<'_>,
+        input: GetPageHierarchy<'_>,
+    ) -> Result<Vec<HierarchyPage>> {
+        Self::get_hierarchy(ctx, input, ParentalRelationshipType::Child).await
+    }
+
+    async fn get_hierarchy(
+        ctx: &ServiceContext<'_>,
+        GetPageHierarchy {
+            site_id,
+            page: reference,
+            max_depth,
+        }: GetPageHierarchy<'_>,
+        direction: ParentalRelationshipType,
+    ) -> Result<Vec<HierarchyPage>> {
+        let page_id = PageService::get_id(ctx, site_id, reference).await?;
+        let max_depth = Self::max_depth(ctx, max_depth);
+        let depths = Self::get_edges(ctx, page_id, direction, max_depth)
+            .await?
+            .depths(page_id, direction);
+
+        let slugs = Self::get_slugs(ctx, depths.keys().copied()).await?;
+        let mut pages = depths
+            .into_iter()
+            .filter_map(|(page_id, depth)| {
+                slugs.get(&page_id).map(|slug| HierarchyPage {
+                    page_id,
+                    slug: slug.clone(),
+                    depth,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        pages.sort_by(|a, b| a.depth.cmp(&b.depth).then_with(|| a.slug.cmp(&b.slug)));
+        Ok(pages)
+    }
+
+    /// Gets the IDs of all pages below this one, regardless of depth.
+    pub async fn get_descendant_ids(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<HashSet<i64>> {
+        let max_depth = ctx.config().maximum_parent_depth;
+        let depths =
+            Self::get_edges(ctx, page_id, ParentalRelationshipType::Child, max_depth)
+                .await?
+                .depths(page_id, ParentalRelationshipType::Child);
+
+        Ok(depths.into_keys().collect())
+    }
+
+    /// Gets the trail of pages from the top of the hierarchy down to this page.
+    ///
+    /// Where a page has several parents, the one it was given first is followed.
+    /// The page itself is not included.
+    pub async fn get_breadcrumbs(
+        ctx: &ServiceContext<'_>,
+        GetPageHierarchy {
+            site_id,
+            page: reference,
+            max_depth,
+        }: GetPageHierarchy<'_>,
+    ) -> Result<Vec<HierarchyPage>> {
+        let mut page_id = PageService::get_id(ctx, site_id, reference).await?;
+        let max_depth = Self::max_depth(ctx, max_depth);
+        let mut trail = Vec::new();
+        let mut seen = HashSet::from([page_id]);
+
+        for depth in 1..=max_depth {
+            let parents = Self::get_parents(ctx, site_id, Reference::Id(page_id)).await?;
+
+            match parents.first() {
+                Some(parent) if seen.insert(parent.parent_page_id) => {
+                    page_id = parent.parent_page_id;
+                    trail.push((page_id, depth));
+                }
+                _ => break,
+            }
+        }
+
+        let slugs =
+            Self::get_slugs(ctx, trail.iter().map(|(page_id, _)| *page_id)).await?;
+        let breadcrumbs = trail
+            .into_iter()
+            .rev()
+            .filter_map(|(page_id, depth)| {
+                slugs.get(&page_id).map(|slug| HierarchyPage {
+                    page_id,
+                    slug: slug.clone(),
+                    depth,
+                })
+            })
+            .collect();
+
+        Ok(breadcrumbs)
+    }
+
+    /// Gets this page and all of the pages below it as a tree.
+    pub async fn get_tree(
+        ctx: &ServiceContext<'_>,
+        GetPageHierarchy {
+            site_id,
+            page: reference,
+            max_depth,
+        }: GetPageHierarchy<'_>,
+    ) -> Result<PageTree> {
+        let page_id = PageService::get_id(ctx, site_id, reference).await?;
+        let max_depth = Self::max_depth(ctx, max_depth);
+        let edges =
+            Self::get_edges(ctx, page_id, ParentalRelationshipType::Child, max_depth)
+                .await?;
+
+        let page_ids = edges
+            .depths(page_id, ParentalRelationshipType::Child)
+            .into_keys()
+            .chain([page_id]);
+
+        let slugs = Self::get_slugs(ctx, page_ids).await?;
+        let tree = edges.tree(page_id, &|page_id| {
+            slugs.get(&page_id).cloned().unwrap_or_default()
+        });
+
+        Ok(tree)
+    }
+
+    /// Fetches the parent to child edges reachable from the given page.
+    ///
+    /// This uses a recursive query, going at most `max_depth`
+    /// levels in the given direction.
+    async fn get_edges(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        direction: ParentalRelationshipType,
+        max_depth: u32,
+    ) -> Result<ParentEdges> {
+        debug!(
+            "Getting {} of page ID {page_id} (max depth {max_depth})",
+            direction.name(),
+        );
+
+        let query = match direction {
+            ParentalRelationshipType::Parent => ANCESTOR_EDGES_QUERY,
+            ParentalRelationshipType::Child => DESCENDANT_EDGES_QUERY,
+        };
+
+        let txn = ctx.transaction();
+        let rows = txn
+            .query_all(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                query,
+                [
+                    page_id.into(),
+                    i32::try_from(max_depth).unwrap_or(i32::MAX).into(),
+                    RelationType::PageParent.value().into(),
+                ],
+            ))
+            .await?;
+
+        let mut edges = Vec::with_capacity(rows.len());
+        for row in rows {
+            let parent_id: i64 = row.try_get("", "parent_id")?;
+            let child_id: i64 = row.try_get("", "child_id")?;
+            edges.push((parent_id, child_id));
+        }
+
+        Ok(ParentEdges::new(edges))
+    }
+
+    async fn get_slugs<I>(
+        ctx: &ServiceContext<'_>,
+        page_ids: I,
+    ) -> Result<HashMap<i64, String>>
+    where
+        I: IntoIterator<Item = i64>,
+    {
+        let txn = ctx.transaction();
+        let pages = Page::find()
+            .filter(
+                Condition::all()
+                    .add(page::Column::PageId.is_in(page_ids))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .all(txn)
+            .await?;
+
+        Ok(pages
+            .into_iter()
+            .map(|page| (page.page_id, page.slug))
+            .collect())
+    }
+
+    /// Gets the depth to use, limited to the configured maximum.
+    fn max_depth(ctx: &ServiceContext<'_>, max_depth: Option<u32>) -> u32 {
+        let maximum = ctx.config().maximum_parent_depth;
+        max_depth.map_or(maximum, |depth| cmp::min(depth, maximum))
+    }
+
+    /// Condition matching all active page parent relations.
+    fn relation_condition() -> Condition {
+        Condition::all()
+            .add(relation::Column::RelationType.eq(RelationType::PageParent.value()))
+            .add(relation::Column::DestType.eq(RelationObjectType::Page))
+            .add(relation::Column::FromType.eq(RelationObjectType::Page))
+            .add(relation::Column::OverwrittenAt.is_null())
+            .add(relation::Column::DeletedAt.is_null())
+    }
 }
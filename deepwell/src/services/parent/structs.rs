@@ -18,9 +18,12 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::models::relation::Model as RelationModel;
 use crate::services::Error;
 use crate::types::Reference;
+use std::collections::{BTreeMap, BTreeSet};
 use std::str::FromStr;
+use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct ParentDescription<'a> {
@@ -29,10 +32,19 @@ pub struct ParentDescription<'a> {
     pub child: Reference<'a>,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct ModifyParent<'a> {
+    pub site_id: i64,
+    pub parent: Reference<'a>,
+    pub child: Reference<'a>,
+    pub user_id: i64,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct UpdateParents<'a> {
     pub site_id: i64,
     pub child: Reference<'a>,
+    pub user_id: i64,
     pub add: Option<Vec<Reference<'a>>>,
     pub remove: Option<Vec<Reference<'a>>>,
 }
@@ -84,3 +96,276 @@ pub struct UpdateParentsOutput {
     pub added: Option<Vec<i64>>,
     pub removed: Option<Vec<bool>>,
 }
+
+/// A parental relationship between two pages.
+///
+/// This is stored as a `PageParent` relation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PageParent {
+    pub parent_page_id: i64,
+    pub child_page_id: i64,
+    pub created_by: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl From<RelationModel> for PageParent {
+    fn from(relation: RelationModel) -> PageParent {
+        PageParent {
+            parent_page_id: relation.dest_id,
+            child_page_id: relation.from_id,
+            created_by: relation.created_by,
+            created_at: relation.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageHierarchy<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+
+    /// How many levels to go up or down. Limited to the configured maximum depth.
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+}
+
+/// A page somewhere above or below another in the hierarchy.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct HierarchyPage {
+    pub page_id: i64,
+    pub slug: String,
+
+    /// How many levels away this page is.
+    /// If it can be reached in multiple ways, this is the shortest.
+    pub depth: u32,
+}
+
+/// A page and all of its children, recursively.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PageTree {
+    pub page_id: i64,
+    pub slug: String,
+    pub children: Vec<PageTree>,
+}
+
+/// A set of parent to child edges, used for walking the page hierarchy.
+///
+/// Each edge is a `(parent_page_id, child_page_id)` pair.
+#[derive(Debug, Clone, Default)]
+pub struct ParentEdges {
+    children: BTreeMap<i64, BTreeSet<i64>>,
+    parents: BTreeMap<i64, BTreeSet<i64>>,
+}
+
+impl ParentEdges {
+    pub fn new<I>(edges: I) -> Self
+    where
+        I: IntoIterator<Item = (i64, i64)>,
+    {
+        let mut output = ParentEdges::default();
+        for (parent_id, child_id) in edges {
+            output
+                .children
+                .entry(parent_id)
+                .or_default()
+                .insert(child_id);
+            output
+                .parents
+                .entry(child_id)
+                .or_default()
+                .insert(parent_id);
+        }
+        output
+    }
+
+    fn next(&self, page_id: i64, direction: ParentalRelationshipType) -> &BTreeSet<i64> {
+        static EMPTY: BTreeSet<i64> = BTreeSet::new();
+
+        let map = match direction {
+            ParentalRelationshipType::Parent => &self.parents,
+            ParentalRelationshipType::Child => &self.children,
+        };
+
+        map.get(&page_id).unwrap_or(&EMPTY)
+    }
+
+    /// Gets the shortest distance to each page reachable from the starting page.
+    ///
+    /// The starting page itself is not included.
+    pub fn depths(
+        &self,
+        page_id: i64,
+        direction: ParentalRelationshipType,
+    ) -> BTreeMap<i64, u32> {
+        let mut depths = BTreeMap::new();
+        let mut current = vec![page_id];
+        let mut depth = 0;
+
+        while !current.is_empty() {
+            depth += 1;
+            let mut next = Vec::new();
+            for id in current {
+                for &other_id in self.next(id, direction) {
+                    if other_id != page_id && !depths.contains_key(&other_id) {
+                        depths.insert(other_id, depth);
+                        next.push(other_id);
+                    }
+                }
+            }
+            current = next;
+        }
+
+        depths
+    }
+
+    /// Gets the number of levels in the longest path from the starting page.
+    ///
+    /// The edges are expected to not have any cycles.
+    pub fn height(&self, page_id: i64, direction: ParentalRelationshipType) -> u32 {
+        fn visit(
+            edges: &ParentEdges,
+            page_id: i64,
+            direction: ParentalRelationshipType,
+            memo: &mut BTreeMap<i64, u32>,
+            path: &mut BTreeSet<i64>,
+        ) -> u32 {
+            if let Some(&height) = memo.get(&page_id) {
+                return height;
+            }
+
+            // Edges back to a page on the current path form a cycle, so skip them
+            path.insert(page_id);
+            let mut height = 0;
+            for &other_id in edges.next(page_id, direction) {
+                if !path.contains(&other_id) {
+                    height =
+                        height.max(visit(edges, other_id, direction, memo, path) + 1);
+                }
+            }
+
+            path.remove(&page_id);
+            memo.insert(page_id, height);
+            height
+        }
+
+        visit(
+            self,
+            page_id,
+            direction,
+            &mut BTreeMap::new(),
+            &mut BTreeSet::new(),
+        )
+    }
+
+    /// Builds the tree of children below the starting page.
+    ///
+    /// Pages which can be reached in multiple ways only appear once,
+    /// the first time they are reached, so the tree is no larger than
+    /// the hierarchy even if it has cycles.
+    ///
+    /// The slug lookup function is used to fill in page slugs.
+    pub fn tree<F>(&self, page_id: i64, slug: &F) -> PageTree
+    where
+        F: Fn(i64) -> String,
+    {
+        fn visit<F>(
+            edges: &ParentEdges,
+            page_id: i64,
+            slug: &F,
+            visited: &mut BTreeSet<i64>,
+        ) -> PageTree
+        where
+            F: Fn(i64) -> String,
+        {
+            let mut children = Vec::new();
+            for &child_id in edges.next(page_id, ParentalRelationshipType::Child) {
+                if visited.insert(child_id) {
+                    children.push(visit(edges, child_id, slug, visited));
+                }
+            }
+
+            PageTree {
+                page_id,
+                slug: slug(page_id),
+                children,
+            }
+        }
+
+        visit(self, page_id, slug, &mut BTreeSet::from([page_id]))
+    }
+}
+
+#[test]
+fn parent_edges() {
+    use ParentalRelationshipType::{Child, Parent};
+
+    // 1 -> 2 -> 3 -> 4, with a shortcut 1 -> 4
+    let edges = ParentEdges::new([(1, 2), (2, 3), (3, 4), (1, 4), (5, 4)]);
+
+    assert_eq!(
+        edges.depths(1, Child),
+        BTreeMap::from([(2, 1), (3, 2), (4, 1)]),
+    );
+    assert_eq!(
+        edges.depths(4, Parent),
+        BTreeMap::from([(3, 1), (1, 1), (5, 1), (2, 2)]),
+    );
+    assert_eq!(edges.depths(5, Parent), BTreeMap::new());
+
+    assert_eq!(edges.height(1, Child), 3);
+    assert_eq!(edges.height(4, Parent), 3);
+    assert_eq!(edges.height(4, Child), 0);
+    assert_eq!(edges.height(5, Child), 1);
+
+    let tree = edges.tree(2, &|page_id| format!("page-{page_id}"));
+    assert_eq!(
+        tree,
+        PageTree {
+            page_id: 2,
+            slug: str!("page-2"),
+            children: vec![PageTree {
+                page_id: 3,
+                slug: str!("page-3"),
+                children: vec![PageTree {
+                    page_id: 4,
+                    slug: str!("page-4"),
+                    children: vec![],
+                }],
+            }],
+        },
+    );
+
+    // Diamonds are only expanded once, and cycles terminate
+    let edges = ParentEdges::new([(1, 2), (1, 3), (2, 4), (3, 4), (4, 5), (5, 1)]);
+
+    assert_eq!(edges.height(1, Child), 3);
+    assert_eq!(edges.height(1, Parent), 3);
+
+    let tree = edges.tree(1, &|page_id| format!("page-{page_id}"));
+    let leaf = |page_id| PageTree {
+        page_id,
+        slug: format!("page-{page_id}"),
+        children: vec![],
+    };
+    assert_eq!(
+        tree,
+        PageTree {
+            page_id: 1,
+            slug: str!("page-1"),
+            children: vec![
+                PageTree {
+                    page_id: 2,
+                    slug: str!("page-2"),
+                    children: vec![PageTree {
+                        page_id: 4,
+                        slug: str!("page-4"),
+                        children: vec![leaf(5)],
+                    }],
+                },
+                leaf(3),
+            ],
+        },
+    );
+}
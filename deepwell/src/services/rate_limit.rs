@@ -0,0 +1,250 @@
+/*
+ * services/rate_limit.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Redis-backed rate limiting for RPC methods and authentication.
+//!
+//! Methods with a configured budget may only be called that many times per
+//! window by any one caller. Callers are identified by whichever of `user_id`,
+//! `session_token`, and `ip_address` are present in the request parameters,
+//! each counted separately so that rotating one of them does not reset the limit.
+//! Requests carrying none of these share a single counter for the method.
+//!
+//! Failed logins are tracked separately, and are used to escalate the delay
+//! slept after each failed attempt beyond the base `authentication_fail_delay`.
+//! This extra delay is slept only after the request's transaction has finished
+//! (see `with_response_delay()`), so that it does not hold a database connection.
+
+use super::prelude::*;
+use crate::hash::k12_hash;
+use redis::aio::MultiplexedConnection as RedisMultiplexedConnection;
+use serde_json::Value as JsonValue;
+use std::cell::Cell;
+use std::future::Future;
+use std::net::IpAddr;
+use std::time::Duration as StdDuration;
+use tokio::time;
+
+tokio::task_local! {
+    /// How long to wait before responding to the current request.
+    static RESPONSE_DELAY: Cell<StdDuration>;
+}
+
+#[derive(Debug)]
+pub struct RateLimitService;
+
+impl RateLimitService {
+    /// Checks if a call to this method is within budget for its caller, counting it if so.
+    ///
+    /// The parameters are the raw JSON passed to the method, if any.
+    /// Methods without a configured budget are not limited.
+    pub async fn check(
+        ctx: &ServiceContext<'_>,
+        method: &str,
+        params: Option<&str>,
+    ) -> Result<()> {
+        let config = ctx.config();
+        if !config.rate_limit_enable {
+            return Ok(());
+        }
+
+        let budget = match config.rate_limit_methods.get(method) {
+            Some(budget) => *budget,
+            None => return Ok(()),
+        };
+
+        let params = params
+            .and_then(|params| serde_json::from_str(params).ok())
+            .unwrap_or(JsonValue::Null);
+
+        let mut conn = ctx.redis_connect().await?;
+        for identity in get_identities(&params) {
+            let key = format!("rate-limit:method:{method}:{identity}");
+            let count =
+                Self::increment(&mut conn, &key, config.rate_limit_window).await?;
+
+            if count > budget {
+                warn!(
+                    "Rate limit exceeded for method {method} by {identity} ({count} calls, budget {budget})",
+                );
+                return Err(Error::TooManyRequests);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs a request, then sleeps for any delay it asked for before responding.
+    ///
+    /// This should wrap the whole request, including its transaction.
+    pub async fn with_response_delay<F: Future>(future: F) -> F::Output {
+        RESPONSE_DELAY
+            .scope(Cell::new(StdDuration::ZERO), async {
+                let output = future.await;
+                let delay = RESPONSE_DELAY.with(Cell::get);
+                if !delay.is_zero() {
+                    time::sleep(delay).await;
+                }
+
+                output
+            })
+            .await
+    }
+
+    /// Records a failed login, and delays the response by the escalated delay.
+    ///
+    /// Failures are counted by both IP address and the name or email attempted,
+    /// and the higher of the two determines the delay. The caller is expected to
+    /// have already slept the base delay, so only the excess is added here.
+    pub async fn login_failure_delay(
+        ctx: &ServiceContext<'_>,
+        ip_address: IpAddr,
+        name_or_email: &str,
+    ) -> Result<()> {
+        let config = ctx.config();
+        let mut conn = ctx.redis_connect().await?;
+
+        let ip_key = format!("rate-limit:login-fail:ip:{ip_address}");
+        let name_key = login_failure_name_key(name_or_email);
+        let failures = u32::max(
+            Self::increment(&mut conn, &ip_key, config.authentication_fail_window)
+                .await?,
+            Self::increment(&mut conn, &name_key, config.authentication_fail_window)
+                .await?,
+        );
+
+        let delay = escalated_delay(
+            config.authentication_fail_delay,
+            config.authentication_fail_delay_max,
+            failures,
+        );
+
+        debug!("Login failure {failures} for this client, delaying for {delay:?}");
+        let delay = delay.saturating_sub(config.authentication_fail_delay);
+        if RESPONSE_DELAY.try_with(|cell| cell.set(delay)).is_err() {
+            // Not running within a request, so there is no response to delay
+            time::sleep(delay).await;
+        }
+
+        Ok(())
+    }
+
+    /// Forgets failed logins for this name or email, after a successful login.
+    pub async fn clear_login_failures(
+        ctx: &ServiceContext<'_>,
+        name_or_email: &str,
+    ) -> Result<()> {
+        let mut conn = ctx.redis_connect().await?;
+        redis::cmd("DEL")
+            .arg(login_failure_name_key(name_or_email))
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Increments the counter at this key, which expires after the window.
+    ///
+    /// The expiry is only set when the counter is created, making this a
+    /// fixed window rather than one which extends on each call.
+    async fn increment(
+        conn: &mut RedisMultiplexedConnection,
+        key: &str,
+        window: StdDuration,
+    ) -> Result<u32> {
+        let (count,): (u32,) = redis::pipe()
+            .atomic()
+            .cmd("SET")
+            .arg(key)
+            .arg(0)
+            .arg("EX")
+            .arg(window.as_secs().max(1))
+            .arg("NX")
+            .ignore()
+            .cmd("INCR")
+            .arg(key)
+            .query_async(conn)
+            .await?;
+
+        Ok(count)
+    }
+}
+
+/// Gets the caller identities present in the given method parameters.
+///
+/// Session tokens are hashed, so that they are not stored in Redis.
+fn get_identities(params: &JsonValue) -> Vec<String> {
+    let mut identities = Vec::new();
+
+    if let Some(user_id) = params.get("user_id").and_then(JsonValue::as_i64) {
+        identities.push(format!("user:{user_id}"));
+    }
+
+    if let Some(session_token) = params.get("session_token").and_then(JsonValue::as_str) {
+        let hash = k12_hash(session_token.as_bytes());
+        identities.push(format!("session:{}", hex::encode(hash)));
+    }
+
+    if let Some(ip_address) = params.get("ip_address").and_then(JsonValue::as_str) {
+        identities.push(format!("ip:{ip_address}"));
+    }
+
+    if identities.is_empty() {
+        identities.push(str!("global"));
+    }
+
+    identities
+}
+
+fn login_failure_name_key(name_or_email: &str) -> String {
+    let hash = k12_hash(name_or_email.to_lowercase().as_bytes());
+    format!("rate-limit:login-fail:name:{}", hex::encode(hash))
+}
+
+/// Determines the delay after the given number of consecutive failures.
+///
+/// Doubles the base delay for each failure after the first, up to the maximum.
+fn escalated_delay(base: StdDuration, max: StdDuration, failures: u32) -> StdDuration {
+    let exponent = failures.saturating_sub(1).min(16);
+    base.saturating_mul(1 << exponent).min(max).max(base)
+}
+
+#[test]
+fn test_escalated_delay() {
+    const BASE: StdDuration = StdDuration::from_millis(100);
+    const MAX: StdDuration = StdDuration::from_millis(5000);
+
+    macro_rules! check {
+        ($failures:expr, $expected_ms:expr $(,)?) => {
+            assert_eq!(
+                escalated_delay(BASE, MAX, $failures),
+                StdDuration::from_millis($expected_ms),
+                "Actual escalated delay doesn't match expected",
+            );
+        };
+    }
+
+    check!(0, 100);
+    check!(1, 100);
+    check!(2, 200);
+    check!(3, 400);
+    check!(6, 3200);
+    check!(7, 5000);
+    check!(u32::MAX, 5000);
+}
@@ -0,0 +1,123 @@
+/*
+ * services/recent_changes/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Service to query the stream of page revisions made across a site.
+//!
+//! This is the backing data for the "recent changes" listing, as well
+//! as the syndication feeds produced by `FeedService`.
+
+use super::prelude::*;
+use crate::models::page::{self, Entity as Page};
+use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::services::page_revision::PageRevisionModelFiltered;
+use crate::services::CategoryService;
+use sea_query::Query;
+use std::cmp;
+
+#[derive(Debug)]
+pub struct RecentChangesService;
+
+impl RecentChangesService {
+    /// Gets the most recent page revisions in a site, newest first.
+    ///
+    /// The `before_id` argument gives the revision ID to search from, exclusive.
+    /// If `None`, then it starts from the most recent revision.
+    /// The output's `next_id` field should be passed as `before_id`
+    /// to retrieve the following page of results.
+    ///
+    /// All other fields are optional filters, which are only applied if present.
+    ///
+    /// The limit is capped by the configured maximum, and any hidden
+    /// fields of the returned revisions are stripped.
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        GetRecentChanges {
+            site_id,
+            category,
+            revision_types,
+            user_id,
+            since,
+            until,
+            before_id,
+            limit,
+        }: GetRecentChanges<'_>,
+    ) -> Result<GetRecentChangesOutput> {
+        let limit = cmp::min(limit, ctx.config().maximum_recent_changes);
+        info!("Getting recent changes for site ID {site_id} (limit {limit})");
+
+        let txn = ctx.transaction();
+
+        // Pages are filtered by category using a subquery,
+        // since revisions do not record their category directly.
+        let category_condition = match category {
+            None => None,
+            Some(reference) => {
+                let category = CategoryService::get(ctx, site_id, reference).await?;
+
+                Some(
+                    page_revision::Column::PageId.in_subquery(
+                        Query::select()
+                            .column(page::Column::PageId)
+                            .from(Page)
+                            .and_where(
+                                page::Column::PageCategoryId.eq(category.category_id),
+                            )
+                            .to_owned(),
+                    ),
+                )
+            }
+        };
+
+        let condition = Condition::all()
+            .add(page_revision::Column::SiteId.eq(site_id))
+            .add_option(category_condition)
+            .add_option(
+                revision_types
+                    .map(|types| page_revision::Column::RevisionType.is_in(types)),
+            )
+            .add_option(user_id.map(|id| page_revision::Column::UserId.eq(id)))
+            .add_option(since.map(|time| page_revision::Column::CreatedAt.gte(time)))
+            .add_option(until.map(|time| page_revision::Column::CreatedAt.lt(time)))
+            .add_option(before_id.map(|id| page_revision::Column::RevisionId.lt(id)));
+
+        let revisions = PageRevision::find()
+            .filter(condition)
+            .order_by_desc(page_revision::Column::RevisionId)
+            .limit(limit)
+            .all(txn)
+            .await?;
+
+        // Only provide a continuation if this page was filled,
+        // otherwise we know there are no more results.
+        let next_id = match revisions.last() {
+            Some(revision) if revisions.len() as u64 == limit => {
+                Some(revision.revision_id)
+            }
+            _ => None,
+        };
+
+        let revisions = revisions
+            .into_iter()
+            .map(PageRevisionModelFiltered::from)
+            .collect();
+
+        Ok(GetRecentChangesOutput { revisions, next_id })
+    }
+}
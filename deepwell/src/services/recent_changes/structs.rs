@@ -0,0 +1,55 @@
+/*
+ * services/recent_changes/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::PageRevisionType;
+use crate::services::page_revision::PageRevisionModelFiltered;
+use crate::types::Reference;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetRecentChanges<'a> {
+    pub site_id: i64,
+
+    #[serde(default)]
+    pub category: Option<Reference<'a>>,
+
+    #[serde(default)]
+    pub revision_types: Option<Vec<PageRevisionType>>,
+
+    #[serde(default)]
+    pub user_id: Option<i64>,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub since: Option<OffsetDateTime>,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub until: Option<OffsetDateTime>,
+
+    #[serde(default)]
+    pub before_id: Option<i64>,
+
+    pub limit: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetRecentChangesOutput {
+    pub revisions: Vec<PageRevisionModelFiltered>,
+    pub next_id: Option<i64>,
+}
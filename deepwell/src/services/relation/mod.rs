@@ -29,6 +29,7 @@
 //! For example:
 //! * `site` / `member` / `user` &mdash; User is a site member
 //! * `user` / `block` / `user` &mdash; User has blocked another user
+//! * `page` / `parent` / `page` &mdash; Page is the parent of another page
 
 #[allow(unused_imports)]
 mod prelude {
@@ -42,6 +43,7 @@ mod prelude {
 #[macro_use]
 mod macros;
 
+mod page_parent;
 mod page_star;
 mod page_watch;
 mod site_ban;
@@ -52,6 +54,7 @@ mod user_block;
 mod user_contact;
 mod user_follow;
 
+pub use self::page_parent::*;
 pub use self::page_star::*;
 pub use self::page_watch::*;
 pub use self::site_ban::*;
@@ -224,7 +227,7 @@ impl RelationService {
     }
 
     // TODO paginate
-    /// Gets all relations from the starting object in the given direction.
+    /// Gets all current relations from the starting object in the given direction.
     ///
     /// For instance, this can be used to get all blocked users, or all users who are blocking
     /// someone depending on the `RelationDirection`.
@@ -252,7 +255,9 @@ impl RelationService {
                 Condition::all()
                     .add(relation::Column::RelationType.eq(relation_type.value()))
                     .add(object_type_column.eq(object_type))
-                    .add(object_id_column.eq(object_id)),
+                    .add(object_id_column.eq(object_id))
+                    .add(relation::Column::OverwrittenAt.is_null())
+                    .add(relation::Column::DeletedAt.is_null()),
             )
             .order_by_asc(relation::Column::CreatedAt)
             .all(txn)
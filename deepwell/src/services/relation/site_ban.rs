@@ -20,6 +20,7 @@
 
 use super::prelude::*;
 use super::site_member::RemoveSiteMember;
+use crate::services::SiteMembershipService;
 use time::Date;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -58,7 +59,7 @@ impl RelationService {
             },
         )
         .await?;
-        // TODO: remove site member applications
+        SiteMembershipService::remove_applications(ctx, site_id, user_id).await?;
         // TODO: remove site roles
 
         create_operation!(
@@ -131,12 +131,11 @@ pub enum RelationType {
     #[allow(dead_code)] // TEMP
     SiteApplication,
     SiteMember,
+    PageParent,
     PageStar,
     PageWatch,
     UserFollow,
-    #[allow(dead_code)] // TEMP
     UserContact,
-    #[allow(dead_code)] // TEMP
     UserContactRequest,
     UserBlock,
 }
@@ -152,6 +151,7 @@ impl RelationType {
             RelationType::SiteBan => "ban",
             RelationType::SiteApplication => "application",
             RelationType::SiteMember => "member",
+            RelationType::PageParent => "parent",
             RelationType::PageStar => "star",
             RelationType::PageWatch => "watch",
             RelationType::UserFollow => "follow",
@@ -176,6 +176,7 @@ impl RelationType {
             RelationType::SiteBan => t!(Site, User),
             RelationType::SiteApplication => t!(Site, User),
             RelationType::SiteMember => t!(Site, User),
+            RelationType::PageParent => t!(Page, Page),
             RelationType::PageStar => t!(Page, User),
             RelationType::PageWatch => t!(Page, User),
             RelationType::UserFollow => t!(User, User),
@@ -66,8 +66,7 @@ impl RelationService {
                     removed_by: created_by,
                 },
             ),
-            // TODO add user_contact
-            // TODO add user_contact_request
+            Self::clear_user_contacts(ctx, blocked_user, blocking_user, created_by),
         )?;
 
         create_operation!(
@@ -18,4 +18,314 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-// TODO implement contacts and contact requests
+//! Contacts are a mutual relationship between two users, like a friends list.
+//!
+//! They are established by one user sending a contact request, which the other
+//! user then accepts. Because contacts are mutual, accepting a request creates
+//! one contact relation in each direction, and removing a contact removes both.
+
+use super::prelude::*;
+
+impl_relation!(
+    UserContact,
+    User,
+    contact_user,
+    User,
+    user,
+    (),
+    NO_CREATE_IMPL,
+);
+
+impl_relation!(
+    UserContactRequest,
+    User,
+    requested_user,
+    User,
+    requesting_user,
+    (),
+    NO_CREATE_IMPL,
+);
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetUserContacts {
+    pub user: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetUserContactRequests {
+    pub user: i64,
+    pub direction: ContactRequestDirection,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContactRequestDirection {
+    /// Requests sent to this user.
+    Incoming,
+
+    /// Requests sent by this user.
+    Outgoing,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct AcceptUserContactRequest {
+    pub requested_user: i64,
+    pub requesting_user: i64,
+    pub accepted_by: i64,
+}
+
+impl RelationService {
+    /// Sends a contact request from one user to another.
+    ///
+    /// If the other user has already sent a request to this user,
+    /// then that request is accepted instead.
+    pub async fn create_user_contact_request(
+        ctx: &ServiceContext<'_>,
+        CreateUserContactRequest {
+            requested_user,
+            requesting_user,
+            created_by,
+            metadata: (),
+        }: CreateUserContactRequest,
+    ) -> Result<()> {
+        if requested_user == requesting_user {
+            error!("User ID {requesting_user} cannot add themselves as a contact");
+            return Err(Error::UserContactSelf);
+        }
+
+        // Cannot request if blocked
+        Self::check_user_block(
+            ctx,
+            requested_user,
+            requesting_user,
+            "request contact with",
+        )
+        .await?;
+
+        if Self::user_contact_exists(
+            ctx,
+            GetUserContact {
+                contact_user: requested_user,
+                user: requesting_user,
+            },
+        )
+        .await?
+        {
+            error!("User ID {requesting_user} is already a contact of user ID {requested_user}");
+            return Err(Error::UserContactExists);
+        }
+
+        // If there is a pending request the other way, accept that instead
+        if Self::user_contact_request_exists(
+            ctx,
+            GetUserContactRequest {
+                requested_user: requesting_user,
+                requesting_user: requested_user,
+            },
+        )
+        .await?
+        {
+            debug!("Contact request already exists in the other direction, accepting it");
+            return Self::accept_user_contact_request(
+                ctx,
+                AcceptUserContactRequest {
+                    requested_user: requesting_user,
+                    requesting_user: requested_user,
+                    accepted_by: created_by,
+                },
+            )
+            .await;
+        }
+
+        create_operation!(
+            ctx,
+            UserContactRequest,
+            User,
+            requested_user,
+            User,
+            requesting_user,
+            created_by,
+        )
+    }
+
+    /// Accepts a pending contact request, making both users contacts of each other.
+    ///
+    /// Declining or cancelling a request is done via `remove_user_contact_request()`.
+    pub async fn accept_user_contact_request(
+        ctx: &ServiceContext<'_>,
+        AcceptUserContactRequest {
+            requested_user,
+            requesting_user,
+            accepted_by,
+        }: AcceptUserContactRequest,
+    ) -> Result<()> {
+        Self::check_user_block(
+            ctx,
+            requested_user,
+            requesting_user,
+            "accept contact request from",
+        )
+        .await?;
+
+        // Fails if there is no such request
+        Self::remove_user_contact_request(
+            ctx,
+            RemoveUserContactRequest {
+                requested_user,
+                requesting_user,
+                removed_by: accepted_by,
+            },
+        )
+        .await?;
+
+        Self::create_user_contact(
+            ctx,
+            CreateUserContact {
+                contact_user: requested_user,
+                user: requesting_user,
+                metadata: (),
+                created_by: accepted_by,
+            },
+        )
+        .await?;
+
+        Self::create_user_contact(
+            ctx,
+            CreateUserContact {
+                contact_user: requesting_user,
+                user: requested_user,
+                metadata: (),
+                created_by: accepted_by,
+            },
+        )
+        .await
+    }
+
+    /// Creates one direction of a contact relationship.
+    ///
+    /// Contacts should only be created by accepting a contact request.
+    async fn create_user_contact(
+        ctx: &ServiceContext<'_>,
+        CreateUserContact {
+            contact_user,
+            user,
+            created_by,
+            metadata: (),
+        }: CreateUserContact,
+    ) -> Result<()> {
+        create_operation!(ctx, UserContact, User, contact_user, User, user, created_by,)
+    }
+
+    /// Gets all of a user's current contacts.
+    pub async fn get_user_contacts(
+        ctx: &ServiceContext<'_>,
+        GetUserContacts { user }: GetUserContacts,
+    ) -> Result<Vec<RelationModel>> {
+        Self::get_user_contact_entries(
+            ctx,
+            RelationObject::User(user),
+            RelationDirection::From,
+        )
+        .await
+    }
+
+    /// Gets all pending contact requests sent to or by a user.
+    pub async fn get_user_contact_requests(
+        ctx: &ServiceContext<'_>,
+        GetUserContactRequests { user, direction }: GetUserContactRequests,
+    ) -> Result<Vec<RelationModel>> {
+        let direction = match direction {
+            ContactRequestDirection::Incoming => RelationDirection::Dest,
+            ContactRequestDirection::Outgoing => RelationDirection::From,
+        };
+
+        Self::get_user_contact_request_entries(ctx, RelationObject::User(user), direction)
+            .await
+    }
+
+    /// Removes a contact between two users, in both directions.
+    pub async fn remove_mutual_user_contact(
+        ctx: &ServiceContext<'_>,
+        RemoveUserContact {
+            contact_user,
+            user,
+            removed_by,
+        }: RemoveUserContact,
+    ) -> Result<()> {
+        Self::remove_user_contact(
+            ctx,
+            RemoveUserContact {
+                contact_user,
+                user,
+                removed_by,
+            },
+        )
+        .await?;
+
+        Self::remove_user_contact(
+            ctx,
+            RemoveUserContact {
+                contact_user: user,
+                user: contact_user,
+                removed_by,
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes all contacts and contact requests between two users, in either direction.
+    ///
+    /// Unlike the other removal methods, relations which are not present are ignored.
+    pub async fn clear_user_contacts(
+        ctx: &ServiceContext<'_>,
+        user_id_1: i64,
+        user_id_2: i64,
+        removed_by: i64,
+    ) -> Result<()> {
+        let relation_types =
+            [RelationType::UserContact, RelationType::UserContactRequest];
+        let pairs = [(user_id_1, user_id_2), (user_id_2, user_id_1)];
+
+        for relation_type in relation_types {
+            for (dest, from) in pairs {
+                let reference = RelationReference::Relationship {
+                    relation_type,
+                    dest: RelationObject::User(dest),
+                    from: RelationObject::User(from),
+                };
+
+                if Self::exists(ctx, reference).await? {
+                    Self::remove(ctx, reference, removed_by).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Helper method for rejecting an action if the first user does not have the second as a contact.
+    pub async fn check_user_contact(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        contact_user_id: i64,
+        action: &str,
+    ) -> Result<()> {
+        let is_contact = Self::user_contact_exists(
+            ctx,
+            GetUserContact {
+                contact_user: contact_user_id,
+                user: user_id,
+            },
+        )
+        .await?;
+
+        if !is_contact {
+            error!("User ID {contact_user_id} cannot {action} user ID {user_id} because they are not a contact");
+            return Err(Error::UserNotContact);
+        }
+
+        Ok(())
+    }
+}
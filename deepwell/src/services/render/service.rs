@@ -28,12 +28,34 @@ pub struct RenderService;
 impl RenderService {
     pub async fn render(
         ctx: &ServiceContext<'_>,
-        mut wikitext: String,
+        wikitext: String,
         page_info: &PageInfo<'_>,
         settings: &WikitextSettings,
     ) -> Result<RenderOutput> {
         let compiled_generator = FTML_VERSION.clone();
+        let (html_output, errors) =
+            Self::render_html(ctx, wikitext, page_info, settings).await?;
+
+        // Insert compiled HTML into text table
+        let compiled_hash = TextService::create(ctx, html_output.body.clone()).await?;
 
+        // Build and return
+        Ok(RenderOutput {
+            html_output,
+            errors,
+            compiled_hash,
+            compiled_at: now(),
+            compiled_generator,
+        })
+    }
+
+    /// Parses and renders wikitext, without storing the output anywhere.
+    pub async fn render_html(
+        ctx: &ServiceContext<'_>,
+        mut wikitext: String,
+        page_info: &PageInfo<'_>,
+        settings: &WikitextSettings,
+    ) -> Result<(HtmlOutput, Vec<ParseError>)> {
         // Isolate the actual render task.
         // This way we can cut it off if it times out.
 
@@ -53,16 +75,6 @@ impl RenderService {
         // and this error variant is not specific to all timeouts.
         .map_err(|_| Error::RenderTimeout)?;
 
-        // Insert compiled HTML into text table
-        let compiled_hash = TextService::create(ctx, html_output.body.clone()).await?;
-
-        // Build and return
-        Ok(RenderOutput {
-            html_output,
-            errors,
-            compiled_hash,
-            compiled_at: now(),
-            compiled_generator,
-        })
+        Ok((html_output, errors))
     }
 }
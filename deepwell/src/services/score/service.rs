@@ -20,28 +20,152 @@
 
 use super::impls::*;
 use super::prelude::*;
+use crate::models::page::{self, Entity as Page};
+use crate::models::page_category;
+use crate::models::page_score::{self, Entity as PageScore, Model as PageScoreModel};
+use sea_orm::sea_query::OnConflict;
 
 #[derive(Debug)]
 pub struct ScoreService;
 
 impl ScoreService {
+    /// Gets the score for a page.
+    ///
+    /// This reads the materialized score, see `get()`.
     pub async fn score(ctx: &ServiceContext<'_>, page_id: i64) -> Result<ScoreValue> {
+        let model = Self::get(ctx, page_id).await?;
+        Ok(Self::score_value(&model))
+    }
+
+    /// Gets the materialized score and vote count for a page.
+    ///
+    /// If the page doesn't have one yet, it is calculated and stored.
+    pub async fn get(ctx: &ServiceContext<'_>, page_id: i64) -> Result<PageScoreModel> {
+        match Self::get_optional(ctx, page_id).await? {
+            Some(model) => Ok(model),
+            None => Self::refresh(ctx, page_id).await,
+        }
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Option<PageScoreModel>> {
+        let txn = ctx.transaction();
+        let model = PageScore::find_by_id(page_id).one(txn).await?;
+        Ok(model)
+    }
+
+    /// Recalculates the score for a page from its votes, and stores it.
+    ///
+    /// This must be called whenever a page's active votes change.
+    pub async fn refresh(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<PageScoreModel> {
+        debug!("Refreshing materialized score for page ID {page_id}");
+
         let txn = ctx.transaction();
         let condition = Self::build_condition(page_id);
         let scorer = Self::get_scorer(ctx, page_id).await?;
-        let score = scorer.score(txn, condition).await?;
-        Ok(score)
+        let score = scorer.score(txn, condition.clone()).await?;
+        let vote_count = PageVote::find().filter(condition).count(txn).await?;
+        let vote_count = i32::try_from(vote_count).unwrap_or(i32::MAX);
+
+        let (score, score_integer) = match score {
+            ScoreValue::Integer(value) => (value as f64, Some(value)),
+            ScoreValue::Float(value) => (value, None),
+        };
+
+        // Upsert, since concurrent reads can both find no score and refresh it
+        let model = page_score::ActiveModel {
+            page_id: Set(page_id),
+            updated_at: Set(now()),
+            score: Set(score),
+            score_integer: Set(score_integer),
+            vote_count: Set(vote_count),
+        };
+
+        let model = PageScore::insert(model)
+            .on_conflict(
+                OnConflict::column(page_score::Column::PageId)
+                    .update_columns([
+                        page_score::Column::UpdatedAt,
+                        page_score::Column::Score,
+                        page_score::Column::ScoreInteger,
+                        page_score::Column::VoteCount,
+                    ])
+                    .to_owned(),
+            )
+            .exec_with_returning(txn)
+            .await?;
+
+        Ok(model)
+    }
+
+    /// Recalculates the scores of all pages in a site.
+    ///
+    /// Used to repair scores after a change in scoring, or for imported votes.
+    /// Returns the number of pages which were refreshed.
+    pub async fn refresh_site(ctx: &ServiceContext<'_>, site_id: i64) -> Result<u64> {
+        info!("Refreshing materialized scores for all pages in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let page_ids: Vec<i64> = Page::find()
+            .select_only()
+            .column(page::Column::PageId)
+            .filter(page::Column::SiteId.eq(site_id))
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        for &page_id in &page_ids {
+            Self::refresh(ctx, page_id).await?;
+        }
+
+        Ok(page_ids.len() as u64)
+    }
+
+    /// Converts a materialized score back into its original value.
+    pub fn score_value(model: &PageScoreModel) -> ScoreValue {
+        match model.score_integer {
+            Some(value) => ScoreValue::Integer(value),
+            None => ScoreValue::Float(model.score),
+        }
     }
 
     /// Gets the correct `Scorer` implementation for this page.
     ///
-    /// Currently stubbed, will be implemented when relevant settings are added.
+    /// This is the score type of the page's category, if it has one set.
     pub async fn get_scorer(
-        _ctx: &ServiceContext<'_>,
-        _page_id: i64,
-    ) -> Result<&'static impl Scorer> {
-        // TODO
-        Ok(&TestScorer)
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<&'static dyn Scorer> {
+        let txn = ctx.transaction();
+        let score_type: Option<Option<String>> = Page::find_by_id(page_id)
+            .select_only()
+            .column(page_category::Column::ScoreType)
+            .join(JoinType::InnerJoin, page::Relation::PageCategory.def())
+            .into_tuple()
+            .one(txn)
+            .await?;
+
+        let scorer: &'static dyn Scorer = match score_type.flatten() {
+            None => &TestScorer, // TODO add platform and site defaults
+            Some(score_type) => match score_type.parse()? {
+                ScoreType::Null => &NullScorer,
+                ScoreType::Test => &TestScorer,
+                ScoreType::Sum => &SumScorer,
+                ScoreType::Mean => &MeanScorer,
+                ScoreType::Percent => &PercentScorer,
+                ScoreType::Median => {
+                    warn!("Median scoring is not implemented, using default scorer");
+                    &TestScorer
+                }
+            },
+        };
+
+        Ok(scorer)
     }
 
     /// Helper method for retrieving a `VoteMap` for a page.
@@ -18,7 +18,10 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::services::Error as ServiceError;
 use std::collections::BTreeMap;
+use std::str::FromStr;
+use strum_macros::EnumIter;
 
 pub use crate::services::vote::VoteValue;
 
@@ -29,7 +32,8 @@ pub enum VoteType {
     FiveStar,
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(EnumIter, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
 pub enum ScoreType {
     Null,
     Test,
@@ -39,6 +43,35 @@ pub enum ScoreType {
     Percent,
 }
 
+impl ScoreType {
+    pub fn name(self) -> &'static str {
+        match self {
+            ScoreType::Null => "null",
+            ScoreType::Test => "test",
+            ScoreType::Sum => "sum",
+            ScoreType::Mean => "mean",
+            ScoreType::Median => "median",
+            ScoreType::Percent => "percent",
+        }
+    }
+}
+
+impl FromStr for ScoreType {
+    type Err = ServiceError;
+
+    fn from_str(value: &str) -> Result<ScoreType, ServiceError> {
+        match value {
+            "null" => Ok(ScoreType::Null),
+            "test" => Ok(ScoreType::Test),
+            "sum" => Ok(ScoreType::Sum),
+            "mean" => Ok(ScoreType::Mean),
+            "median" => Ok(ScoreType::Median),
+            "percent" => Ok(ScoreType::Percent),
+            _ => Err(ServiceError::InvalidEnumValue),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct VoteMap {
     inner: BTreeMap<VoteValue, u64>,
@@ -88,3 +121,26 @@ impl VoteMap {
         self.inner.iter().map(|(&value, &count)| (value, count))
     }
 }
+
+/// Ensure `ScoreType::name()` produces the same output as serde.
+#[test]
+fn score_type_name_serde() {
+    use strum::IntoEnumIterator;
+
+    for variant in ScoreType::iter() {
+        let output = serde_json::to_string(&variant).expect("Unable to serialize JSON");
+        let serde_name: String =
+            serde_json::from_str(&output).expect("Unable to deserialize JSON");
+
+        assert_eq!(
+            &serde_name,
+            variant.name(),
+            "Serde name does not match variant name",
+        );
+
+        let converted: ScoreType =
+            serde_name.as_str().parse().expect("Could not convert item");
+
+        assert_eq!(converted, variant, "Converted item does not match variant");
+    }
+}
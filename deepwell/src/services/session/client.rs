@@ -0,0 +1,161 @@
+/*
+ * services/session/client.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Helpers for identifying the client a session is being used from.
+//!
+//! These are deliberately coarse. Device descriptions only name the browser
+//! and operating system, so they can be shown back to the user without
+//! revealing anything more specific (such as location) about the session.
+
+use std::net::IpAddr;
+
+/// Produces a human-readable description of the device from a user-agent.
+///
+/// For instance, `Firefox on Linux` or `Safari on iOS`.
+pub fn describe_device(user_agent: &str) -> String {
+    format!("{} on {}", parse_browser(user_agent), parse_os(user_agent))
+}
+
+fn parse_browser(user_agent: &str) -> &'static str {
+    // Order matters, since most browsers also claim to be
+    // Chrome and/or Safari for compatibility.
+    const BROWSERS: [(&str, &str); 9] = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Opera", "Opera"),
+        ("FxiOS/", "Firefox"),
+        ("Firefox/", "Firefox"),
+        ("CriOS/", "Chrome"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+        ("curl/", "curl"),
+    ];
+
+    BROWSERS
+        .iter()
+        .find(|(pattern, _)| user_agent.contains(pattern))
+        .map(|(_, name)| *name)
+        .unwrap_or("Unknown browser")
+}
+
+fn parse_os(user_agent: &str) -> &'static str {
+    // Android and ChromeOS must be checked before Linux,
+    // and iOS before macOS, since they also contain those strings.
+    const SYSTEMS: [(&str, &str); 8] = [
+        ("Windows", "Windows"),
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iOS"),
+        ("CrOS", "ChromeOS"),
+        ("Mac OS X", "macOS"),
+        ("Macintosh", "macOS"),
+        ("Linux", "Linux"),
+    ];
+
+    SYSTEMS
+        .iter()
+        .find(|(pattern, _)| user_agent.contains(pattern))
+        .map(|(_, name)| *name)
+        .unwrap_or("unknown OS")
+}
+
+/// Determines if both addresses are within the same subnet.
+///
+/// The prefix lengths are in bits, per address family. Addresses of
+/// different families never match, except for IPv4-mapped IPv6 addresses,
+/// which are compared as the IPv4 address they represent.
+pub fn same_subnet(
+    first: IpAddr,
+    second: IpAddr,
+    ipv4_prefix: u8,
+    ipv6_prefix: u8,
+) -> bool {
+    fn mask(bits: u32, prefix: u8) -> u128 {
+        match prefix {
+            0 => 0,
+            _ => !0 << (bits - u32::from(prefix).min(bits)),
+        }
+    }
+
+    match (first.to_canonical(), second.to_canonical()) {
+        (IpAddr::V4(first), IpAddr::V4(second)) => {
+            let mask = mask(32, ipv4_prefix);
+            u128::from(u32::from(first)) & mask == u128::from(u32::from(second)) & mask
+        }
+        (IpAddr::V6(first), IpAddr::V6(second)) => {
+            let mask = mask(128, ipv6_prefix);
+            u128::from(first) & mask == u128::from(second) & mask
+        }
+        _ => false,
+    }
+}
+
+#[test]
+fn test_describe_device() {
+    macro_rules! check {
+        ($user_agent:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                describe_device($user_agent),
+                $expected,
+                "Actual device description doesn't match expected",
+            );
+        };
+    }
+
+    check!("", "Unknown browser on unknown OS");
+    check!(
+        "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/115.0",
+        "Firefox on Linux",
+    );
+    check!(
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0",
+        "Edge on Windows",
+    );
+    check!(
+        "Mozilla/5.0 (Linux; Android 14) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+        "Chrome on Android",
+    );
+    check!(
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1",
+        "Safari on iOS",
+    );
+    check!("curl/8.4.0", "curl on unknown OS");
+}
+
+#[test]
+fn test_same_subnet() {
+    macro_rules! check {
+        ($first:expr, $second:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                same_subnet($first.parse().unwrap(), $second.parse().unwrap(), 24, 48),
+                $expected,
+                "Actual subnet comparison doesn't match expected",
+            );
+        };
+    }
+
+    check!("192.0.2.1", "192.0.2.1", true);
+    check!("192.0.2.1", "192.0.2.254", true);
+    check!("192.0.2.1", "192.0.3.1", false);
+    check!("2001:db8:1::1", "2001:db8:1:ffff::1", true);
+    check!("2001:db8:1::1", "2001:db8:2::1", false);
+    check!("::ffff:192.0.2.1", "192.0.2.7", true);
+    check!("192.0.2.1", "2001:db8:1::1", false);
+}
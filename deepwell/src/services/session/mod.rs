@@ -24,6 +24,7 @@ mod prelude {
     pub use super::structs::*;
 }
 
+mod client;
 mod service;
 mod structs;
 
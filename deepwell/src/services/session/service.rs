@@ -29,13 +29,27 @@
 //! which represents the current session. It has a somewhat short
 //! expiry (30 minutes) which needs to be renewed by the client
 //! periodically.
+//!
+//! Sessions can optionally be bound to the client which created them,
+//! rejecting use from another subnet or user-agent. Full logins also
+//! record the user's device, so that users can be alerted when their
+//! account is logged into from somewhere new.
 
+use super::client::{describe_device, same_subnet};
 use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
 use crate::models::session::{self, Entity as Session, Model as SessionModel};
 use crate::models::user::{self, Entity as User, Model as UserModel};
+use crate::models::user_device::{self, Entity as UserDevice};
+use crate::services::message::CreateMessageDraft;
+use crate::services::{MessageService, UserService};
 use crate::utils::assert_is_csprng;
+use fluent::{FluentArgs, FluentValue};
 use rand::distributions::{Alphanumeric, DistString};
 use rand::thread_rng;
+use sea_orm::TransactionTrait;
+use std::net::IpAddr;
+use unic_langid::LanguageIdentifier;
 
 #[derive(Debug)]
 pub struct SessionService;
@@ -71,16 +85,126 @@ impl SessionService {
             user_id: Set(user_id),
             created_at: Set(now),
             expires_at: Set(expiry),
+            last_seen_at: Set(now),
             ip_address: Set(str!(ip_address)), // TODO inet type?
             user_agent: Set(user_agent),
             restricted: Set(restricted),
         };
 
-        let SessionModel { session_token, .. } = model.insert(txn).await?;
+        let SessionModel {
+            session_token,
+            user_agent,
+            ..
+        } = model.insert(txn).await?;
         info!("Created new session token");
+
+        // Restricted sessions are not a complete login,
+        // so only record the device once MFA is passed.
+        if !restricted {
+            Self::record_device(ctx, user_id, &user_agent).await?;
+        }
+
         Ok(session_token)
     }
 
+    /// Records the device for a completed login, alerting the user if it is new.
+    ///
+    /// The first device a user logs in from is not alerted on,
+    /// since that is the login immediately following account creation.
+    async fn record_device(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        user_agent: &str,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let device = describe_device(user_agent);
+        debug!("Recording login device '{device}' for user ID {user_id}");
+
+        let known_devices = UserDevice::find()
+            .filter(user_device::Column::UserId.eq(user_id))
+            .all(txn)
+            .await?;
+
+        if known_devices.iter().any(|known| known.device == device) {
+            let model = user_device::ActiveModel {
+                user_id: Set(user_id),
+                device: Set(device),
+                last_seen_at: Set(now()),
+                ..Default::default()
+            };
+            model.update(txn).await?;
+            return Ok(());
+        }
+
+        let model = user_device::ActiveModel {
+            user_id: Set(user_id),
+            device: Set(device.clone()),
+            ..Default::default()
+        };
+        model.insert(txn).await?;
+
+        // Not being able to send the notification shouldn't prevent the login,
+        // so it is done in a savepoint which can be undone on failure.
+        if !known_devices.is_empty() && ctx.config().session_notify_new_device {
+            let savepoint = txn.begin().await?;
+            let state = ctx.state();
+            let inner_ctx = ServiceContext::new(&state, &savepoint);
+
+            match Self::notify_new_device(&inner_ctx, user_id, &device).await {
+                Ok(()) => savepoint.commit().await?,
+                Err(error) => {
+                    warn!("Unable to notify user ID {user_id} of new device: {error}");
+                    savepoint.rollback().await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends the user a system message informing them of a login from a new device.
+    async fn notify_new_device(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        device: &str,
+    ) -> Result<()> {
+        info!("Notifying user ID {user_id} of login from new device '{device}'");
+
+        let user = UserService::get(ctx, Reference::Id(user_id)).await?;
+        let mut locales = Vec::with_capacity(user.locales.len());
+        for locale in &user.locales {
+            locales.push(LanguageIdentifier::from_bytes(locale.as_bytes())?);
+        }
+
+        let mut args = FluentArgs::new();
+        args.set("device", fluent_str!(device));
+
+        let localization = ctx.localization();
+        let subject =
+            localization.translate(&locales, "emails-new-login.subject", &args)?;
+        let wikitext =
+            localization.translate(&locales, "emails-new-login.body", &args)?;
+
+        let draft = MessageService::create_draft(
+            ctx,
+            CreateMessageDraft {
+                user_id: SYSTEM_USER_ID,
+                recipients: vec![user_id],
+                carbon_copy: vec![],
+                blind_carbon_copy: vec![],
+                locale: user.locales[0].clone(),
+                subject: subject.into_owned(),
+                wikitext: wikitext.into_owned(),
+                reply_to: None,
+                forwarded_from: None,
+            },
+        )
+        .await?;
+
+        MessageService::send(ctx, &draft.external_id).await?;
+        Ok(())
+    }
+
     /// Securely generates a new session token.
     ///
     /// Example generated token: `wj:T9iF6vfjoYYE20QzrybV2C1V4K0LchHXsNVipX8G1GZ9vSJf0rvQpJ4YC8c8MAQ3`.
@@ -124,28 +248,105 @@ impl SessionService {
         Ok(session)
     }
 
+    /// Gets a session model from its token, checking it against the client using it.
+    ///
+    /// Depending on configuration, the session is rejected if the client's
+    /// IP address is outside the subnet it was created from, or if the
+    /// client's user-agent differs. Client information which is being checked
+    /// must be provided, otherwise the session is rejected.
+    ///
+    /// On success, the session's last seen timestamp is updated.
+    pub async fn validate(
+        ctx: &ServiceContext<'_>,
+        ValidateSession {
+            session_token,
+            ip_address,
+            user_agent,
+        }: ValidateSession<'_>,
+    ) -> Result<SessionModel> {
+        info!("Validating session with token {session_token}");
+
+        let config = ctx.config();
+        let session = Self::get(ctx, session_token).await?;
+
+        if config.session_enforce_ip_subnet {
+            let Some(ip_address) = ip_address else {
+                warn!(
+                    "Session for user ID {} used without an IP address to check",
+                    session.user_id,
+                );
+                return Err(Error::SessionClientMismatch);
+            };
+
+            let matches = match session.ip_address.parse::<IpAddr>() {
+                Ok(session_ip_address) => same_subnet(
+                    session_ip_address,
+                    ip_address,
+                    config.session_ipv4_subnet_prefix,
+                    config.session_ipv6_subnet_prefix,
+                ),
+                Err(error) => {
+                    warn!(
+                        "Session IP address '{}' is not valid: {error}",
+                        session.ip_address,
+                    );
+                    false
+                }
+            };
+
+            if !matches {
+                warn!(
+                    "Session for user ID {} used from IP address {ip_address}, outside subnet of {}",
+                    session.user_id, session.ip_address,
+                );
+                return Err(Error::SessionClientMismatch);
+            }
+        }
+
+        if config.session_enforce_user_agent
+            && user_agent != Some(session.user_agent.as_str())
+        {
+            warn!(
+                "Session for user ID {} used from a different or missing user-agent",
+                session.user_id,
+            );
+            return Err(Error::SessionClientMismatch);
+        }
+
+        let txn = ctx.transaction();
+        let model = session::ActiveModel {
+            session_token: Set(session.session_token),
+            last_seen_at: Set(now()),
+            ..Default::default()
+        };
+        let session = model.update(txn).await?;
+        Ok(session)
+    }
+
     /// Gets the associated `UserModel` from an active session.
     ///
-    /// Performs a join rather than two separate fetches.
+    /// The session is checked against the client using it, as in `validate()`.
     /// Yields an error if the given session token does not exist or is expired.
     ///
     /// The `restricted` status must match the argument passed.
     pub async fn get_user(
         ctx: &ServiceContext<'_>,
-        session_token: &str,
+        session: ValidateSession<'_>,
         restricted: bool,
     ) -> Result<UserModel> {
         info!("Looking up user for session token");
 
+        let session = Self::validate(ctx, session).await?;
+        if session.restricted != restricted {
+            error!(
+                "Session restricted status does not match (expected {restricted}, was {})",
+                session.restricted,
+            );
+            return Err(Error::UserNotFound);
+        }
+
         let txn = ctx.transaction();
-        let user = User::find()
-            .join(JoinType::Join, user::Relation::Session.def())
-            .filter(
-                Condition::all()
-                    .add(session::Column::SessionToken.eq(session_token))
-                    .add(session::Column::ExpiresAt.gt(now()))
-                    .add(session::Column::Restricted.eq(restricted)),
-            )
+        let user = User::find_by_id(session.user_id)
             .one(txn)
             .await?
             .ok_or(Error::UserNotFound)?;
@@ -190,8 +391,17 @@ impl SessionService {
     ) -> Result<String> {
         info!("Renewing session ID {old_session_token}");
 
-        // Get existing session to ensure the token matches the passed user ID.
-        let old_session = Self::get(ctx, &old_session_token).await?;
+        // Get existing session to ensure the token matches the passed user ID,
+        // and that it is being renewed by the same client it was created for.
+        let old_session = Self::validate(
+            ctx,
+            ValidateSession {
+                session_token: &old_session_token,
+                ip_address: Some(ip_address),
+                user_agent: Some(&user_agent),
+            },
+        )
+        .await?;
         if old_session.user_id != user_id {
             error!(
                 "Requested session renewal, user IDs do not match! (current: {}, request: {})",
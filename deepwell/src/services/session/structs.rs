@@ -18,6 +18,7 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::client::describe_device;
 use crate::models::session::Model as SessionModel;
 use std::net::IpAddr;
 
@@ -37,12 +38,34 @@ pub struct RenewSession {
     pub user_agent: String,
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct ValidateSession<'a> {
+    pub session_token: &'a str,
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<&'a str>,
+}
+
 pub type GetOtherSessions = InvalidateOtherSessions;
 
 #[derive(Serialize, Debug, Clone)]
 pub struct GetOtherSessionsOutput {
-    pub current: SessionModel,
-    pub others: Vec<SessionModel>,
+    pub current: SessionInfo,
+    pub others: Vec<SessionInfo>,
+}
+
+/// A session, along with a description of the device it was created from.
+#[derive(Serialize, Debug, Clone)]
+pub struct SessionInfo {
+    #[serde(flatten)]
+    pub session: SessionModel,
+    pub device: String,
+}
+
+impl From<SessionModel> for SessionInfo {
+    fn from(session: SessionModel) -> SessionInfo {
+        let device = describe_device(&session.user_agent);
+        SessionInfo { session, device }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -22,17 +22,36 @@ use wikidot_normalize::normalize;
 
 use super::prelude::*;
 use crate::constants::SYSTEM_USER_ID;
+use crate::hash::slice_to_blob_hash;
+use crate::models::file::{self, Entity as File};
+use crate::models::filter::{self, Entity as Filter};
+use crate::models::page::{self, Entity as Page, Model as PageModel};
+use crate::models::page_category::{self, Entity as PageCategory};
 use crate::models::sea_orm_active_enums::{AliasType, UserType};
 use crate::models::site::{self, Entity as Site, Model as SiteModel};
+use crate::models::site_tag::{self, Entity as SiteTag};
+use crate::models::site_tag_group::{self, Entity as SiteTagGroup};
 use crate::services::alias::CreateAlias;
+use crate::services::file_revision::CreateFirstFileRevision;
+use crate::services::page::{CreatePage, CreatePageOutput, EditPage, EditPageBody};
 use crate::services::relation::CreateSiteUser;
 use crate::services::user::{CreateUser, UpdateUserBody};
-use crate::services::{AliasService, Error, RelationService, UserService};
-use crate::utils::validate_locale;
+use crate::services::{
+    AliasService, Error, FileRevisionService, PageRevisionService, PageService,
+    PasswordService, RelationService, TextService, UserService,
+};
+use crate::utils::{split_category_name, validate_locale};
 use ftml::layout::Layout;
 use ref_map::*;
 use sea_orm::NotSet;
 use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Pages which are always copied from a template site, if present.
+const TEMPLATE_SYSTEM_PAGES: [&str; 2] = ["nav:side", "nav:top"];
+
+/// The revision comments for pages and files copied from a template site.
+const TEMPLATE_REVISION_COMMENTS: &str = "Copied from template site";
 
 #[derive(Debug)]
 pub struct SiteService;
@@ -48,6 +67,7 @@ impl SiteService {
             default_page,
             layout,
             locale,
+            template,
         }: CreateSite,
     ) -> Result<CreateSiteOutput> {
         let txn = ctx.transaction();
@@ -61,6 +81,29 @@ impl SiteService {
         // Validate locale.
         validate_locale(&locale)?;
 
+        // Get template site, if any.
+        //
+        // Its default page and layout are used if not otherwise specified.
+        let template_site = match template {
+            None => None,
+            Some(ref template) => {
+                let site = Self::get(ctx, Reference::Id(template.site_id)).await?;
+                if !site.is_template {
+                    error!("Site ID {} is not published as a template", site.site_id);
+                    return Err(Error::SiteNotTemplate);
+                }
+
+                Some(site)
+            }
+        };
+
+        let default_page = default_page
+            .or_else(|| template_site.as_ref().map(|site| site.default_page.clone()));
+
+        let layout = layout
+            .map(|l| str!(l.value()))
+            .or_else(|| template_site.and_then(|site| site.layout));
+
         // Insert into database
         let model = site::ActiveModel {
             slug: Set(slug.clone()),
@@ -71,7 +114,7 @@ impl SiteService {
                 Some(slug) => Set(slug),
                 None => NotSet,
             },
-            layout: Set(layout.map(|l| str!(l.value()))),
+            layout: Set(layout),
             locale: Set(locale.clone()),
             ..Default::default()
         };
@@ -115,6 +158,11 @@ impl SiteService {
         )
         .await?;
 
+        // Copy from template site
+        if let Some(template) = template {
+            Self::copy_template(ctx, site.site_id, user.user_id, template).await?;
+        }
+
         // Return
         Ok(CreateSiteOutput {
             site_id: site.site_id,
@@ -123,6 +171,269 @@ impl SiteService {
         })
     }
 
+    /// Copies the contents of a template site into a newly created one.
+    ///
+    /// This includes the site's tags, filters, and the chosen categories
+    /// along with their settings. The system pages (the navigation bars,
+    /// and the templates of each copied category) are created anew by
+    /// the site user, along with their attached files. Page history
+    /// is not copied.
+    async fn copy_template(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        site_user_id: i64,
+        SiteTemplate {
+            site_id: template_id,
+            categories: chosen_categories,
+        }: SiteTemplate,
+    ) -> Result<()> {
+        info!("Copying template site ID {template_id} into site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let config = ctx.config();
+
+        // Copy tag configuration
+        let mut tag_group_ids = HashMap::new();
+        let tag_groups = SiteTagGroup::find()
+            .filter(site_tag_group::Column::SiteId.eq(template_id))
+            .all(txn)
+            .await?;
+
+        for group in tag_groups {
+            let model = site_tag_group::ActiveModel {
+                site_id: Set(site_id),
+                name: Set(group.name),
+                description: Set(group.description),
+                exclusive: Set(group.exclusive),
+                required: Set(group.required),
+                ..Default::default()
+            };
+            let new_group = model.insert(txn).await?;
+            tag_group_ids.insert(group.tag_group_id, new_group.tag_group_id);
+        }
+
+        let tags = SiteTag::find()
+            .filter(site_tag::Column::SiteId.eq(template_id))
+            .all(txn)
+            .await?;
+
+        for tag in tags {
+            let model = site_tag::ActiveModel {
+                site_id: Set(site_id),
+                tag_group_id: Set(tag
+                    .tag_group_id
+                    .and_then(|id| tag_group_ids.get(&id).copied())),
+                name: Set(tag.name),
+                description: Set(tag.description),
+                aliases: Set(tag.aliases),
+                ..Default::default()
+            };
+            model.insert(txn).await?;
+        }
+
+        // Copy filters
+        let filters = Filter::find()
+            .filter(
+                Condition::all()
+                    .add(filter::Column::SiteId.eq(template_id))
+                    .add(filter::Column::DeletedAt.is_null()),
+            )
+            .all(txn)
+            .await?;
+
+        for filter in filters {
+            let model = filter::ActiveModel {
+                site_id: Set(Some(site_id)),
+                affects_user: Set(filter.affects_user),
+                affects_email: Set(filter.affects_email),
+                affects_page: Set(filter.affects_page),
+                affects_file: Set(filter.affects_file),
+                affects_forum: Set(filter.affects_forum),
+                regex: Set(filter.regex),
+                description: Set(filter.description),
+                ..Default::default()
+            };
+            model.insert(txn).await?;
+        }
+
+        // Copy chosen categories
+        let categories: Vec<_> = PageCategory::find()
+            .filter(page_category::Column::SiteId.eq(template_id))
+            .all(txn)
+            .await?
+            .into_iter()
+            .filter(|category| match chosen_categories {
+                Some(ref slugs) => slugs.contains(&category.slug),
+                None => true,
+            })
+            .collect();
+
+        for category in &categories {
+            let model = page_category::ActiveModel {
+                site_id: Set(site_id),
+                slug: Set(category.slug.clone()),
+                layout: Set(category.layout.clone()),
+                template_page: Set(category.template_page.clone()),
+                license: Set(category.license.clone()),
+                permissions: Set(category.permissions.clone()),
+                score_type: Set(category.score_type.clone()),
+                hidden: Set(category.hidden),
+                ..Default::default()
+            };
+            model.insert(txn).await?;
+        }
+
+        // Copy system pages
+        let pages = Page::find()
+            .filter(
+                Condition::all()
+                    .add(page::Column::SiteId.eq(template_id))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .all(txn)
+            .await?;
+
+        for page in pages {
+            let (category_slug, page_slug) = split_category_name(&page.slug);
+            let is_template = categories.iter().any(|category| {
+                (category.slug == category_slug
+                    && page_slug == config.special_page_template)
+                    || category.template_page.as_deref() == Some(page.slug.as_str())
+            });
+
+            if is_template || TEMPLATE_SYSTEM_PAGES.contains(&page.slug.as_str()) {
+                Self::copy_template_page(ctx, site_id, site_user_id, &page).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a copy of a template site's page, and its files, in the new site.
+    async fn copy_template_page(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        site_user_id: i64,
+        page: &PageModel,
+    ) -> Result<()> {
+        debug!("Copying template page '{}'", page.slug);
+
+        let txn = ctx.transaction();
+        let template_id = page.site_id;
+        let revision =
+            PageRevisionService::get_latest(ctx, template_id, page.page_id).await?;
+        let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+        let layout = page
+            .layout
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(|_| Error::InvalidEnumValue)?;
+
+        let CreatePageOutput {
+            page_id,
+            revision_id,
+            ..
+        } = PageService::create(
+            ctx,
+            CreatePage {
+                site_id,
+                wikitext,
+                title: revision.title,
+                alt_title: revision.alt_title,
+                slug: page.slug.clone(),
+                layout,
+                revision_comments: str!(TEMPLATE_REVISION_COMMENTS),
+                user_id: site_user_id,
+                bypass_filter: true,
+            },
+        )
+        .await?;
+
+        // Tags can only be set by editing
+        if !revision.tags.is_empty() {
+            PageService::edit(
+                ctx,
+                EditPage {
+                    site_id,
+                    page: Reference::Id(page_id),
+                    last_revision_id: revision_id,
+                    revision_comments: str!(TEMPLATE_REVISION_COMMENTS),
+                    user_id: site_user_id,
+                    body: EditPageBody {
+                        tags: Maybe::Set(revision.tags),
+                        ..Default::default()
+                    },
+                },
+            )
+            .await?;
+        }
+
+        // Copy attached files, which refer to the same blobs
+        let files = File::find()
+            .filter(
+                Condition::all()
+                    .add(file::Column::PageId.eq(page.page_id))
+                    .add(file::Column::DeletedAt.is_null()),
+            )
+            .all(txn)
+            .await?;
+
+        for file in files {
+            let revision = FileRevisionService::get_latest(
+                ctx,
+                template_id,
+                page.page_id,
+                file.file_id,
+            )
+            .await?;
+
+            let model = file::ActiveModel {
+                name: Set(file.name.clone()),
+                site_id: Set(site_id),
+                page_id: Set(page_id),
+                ..Default::default()
+            };
+            let new_file = model.insert(txn).await?;
+
+            FileRevisionService::create_first(
+                ctx,
+                CreateFirstFileRevision {
+                    site_id,
+                    page_id,
+                    file_id: new_file.file_id,
+                    user_id: site_user_id,
+                    name: file.name,
+                    s3_hash: slice_to_blob_hash(&revision.s3_hash),
+                    size_hint: revision.size_hint,
+                    mime_hint: revision.mime_hint,
+                    blob_created: false,
+                    licensing: revision.licensing,
+                    revision_comments: str!(TEMPLATE_REVISION_COMMENTS),
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets all sites which are published as templates.
+    pub async fn get_templates(ctx: &ServiceContext<'_>) -> Result<Vec<SiteModel>> {
+        let txn = ctx.transaction();
+        let sites = Site::find()
+            .filter(
+                Condition::all()
+                    .add(site::Column::IsTemplate.eq(true))
+                    .add(site::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(site::Column::Name)
+            .all(txn)
+            .await?;
+
+        Ok(sites)
+    }
+
     /// Update site information.
     pub async fn update(
         ctx: &ServiceContext<'_>,
@@ -140,7 +451,10 @@ impl SiteService {
         // For updating the corresponding site user
         let site_user_id =
             RelationService::get_site_user_id_for_site(ctx, site.site_id).await?;
-        let mut site_user_body = UpdateUserBody::default();
+        let mut site_user_body = UpdateUserBody {
+            changed_by: Some(updating_user_id),
+            ..Default::default()
+        };
 
         if let Maybe::Set(name) = input.name {
             model.name = Set(name);
@@ -171,6 +485,27 @@ impl SiteService {
             model.layout = Set(layout.map(|l| str!(l.value())));
         }
 
+        if let Maybe::Set(is_template) = input.is_template {
+            model.is_template = Set(is_template);
+        }
+
+        if let Maybe::Set(join_policy) = input.join_policy {
+            model.join_policy = Set(join_policy);
+        }
+
+        if let Maybe::Set(join_password) = input.join_password {
+            let hash = match join_password {
+                Some(password) => Some(PasswordService::new_hash(&password)?),
+                None => None,
+            };
+
+            model.join_password = Set(hash);
+        }
+
+        if let Maybe::Set(join_questions) = input.join_questions {
+            model.join_questions = Set(join_questions);
+        }
+
         // Update site
         model.updated_at = Set(Some(now()));
         let new_site = model.update(txn).await?;
@@ -19,6 +19,7 @@
  */
 
 use crate::models::alias::Model as AliasModel;
+use crate::models::sea_orm_active_enums::SiteJoinPolicy;
 use crate::models::site::Model as SiteModel;
 use crate::models::site_domain::Model as SiteDomainModel;
 use crate::types::{Maybe, Reference};
@@ -33,6 +34,19 @@ pub struct CreateSite {
     pub default_page: Option<String>,
     pub layout: Option<Layout>,
     pub locale: String,
+
+    #[serde(default)]
+    pub template: Option<SiteTemplate>,
+}
+
+/// Which template site to create a new site from.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SiteTemplate {
+    pub site_id: i64,
+
+    /// The slugs of the categories to copy. If absent, all are copied.
+    #[serde(default)]
+    pub categories: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -51,6 +65,7 @@ pub struct GetSite<'a> {
 pub struct GetSiteOutput {
     #[serde(flatten)]
     pub site: SiteModel,
+    pub has_join_password: bool,
     pub aliases: Vec<AliasModel>,
     pub domains: Vec<SiteDomainModel>,
 }
@@ -73,4 +88,8 @@ pub struct UpdateSiteBody {
     pub description: Maybe<String>,
     pub locale: Maybe<String>,
     pub layout: Maybe<Option<Layout>>,
+    pub is_template: Maybe<bool>,
+    pub join_policy: Maybe<SiteJoinPolicy>,
+    pub join_password: Maybe<Option<String>>,
+    pub join_questions: Maybe<Vec<String>>,
 }
@@ -0,0 +1,39 @@
+/*
+ * services/site_backup/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Service for exporting a whole site to a portable archive, and restoring it.
+//!
+//! Archives are a single JSON document (see `SiteArchive`), containing
+//! everything needed to re-create the site on this or another instance.
+//! They are written incrementally (see `ArchiveWriter`) so that exporting
+//! a large site does not require holding the whole archive in memory.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+mod writer;
+
+pub use self::service::{SiteBackupService, BACKUP_DIRECTORY, SITE_ARCHIVE_VERSION};
+pub use self::structs::*;
@@ -0,0 +1,841 @@
+/*
+ * services/site_backup/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use super::writer::ArchiveWriter;
+use crate::hash::{blob_hash_to_hex, BlobHash};
+use crate::models::file::{self, Entity as File};
+use crate::models::file_revision::{self, Entity as FileRevision};
+use crate::models::page::{self, Entity as Page};
+use crate::models::page_attribution::{self, Entity as PageAttribution};
+use crate::models::page_category::{self, Entity as PageCategory};
+use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::page_vote::{self, Entity as PageVote};
+use crate::models::relation;
+use crate::models::sea_orm_active_enums::{
+    RelationObjectType, SiteBackupStatus, SiteJoinPolicy,
+};
+use crate::models::site::{self, Model as SiteModel};
+use crate::models::site_backup::{self, Entity as SiteBackup, Model as SiteBackupModel};
+use crate::models::user::{self, Entity as User};
+use crate::services::job::{Job, JobService};
+use crate::services::relation::RelationType;
+use crate::services::site::{CreateSite, CreateSiteOutput};
+use crate::services::{
+    BlobService, ParentService, ScoreService, SiteService, TextService,
+};
+use data_encoding::BASE64;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::{env, io};
+use time::format_description::well_known::Rfc3339;
+use tokio::fs;
+
+/// The current version of the site archive format.
+///
+/// This should be incremented whenever `SiteArchive` changes
+/// in a way which is not backwards-compatible.
+pub const SITE_ARCHIVE_VERSION: u32 = 1;
+
+/// The subdirectory in blob storage where site backup archives are kept.
+pub const BACKUP_DIRECTORY: &str = "backups";
+
+#[derive(Debug)]
+pub struct SiteBackupService;
+
+impl SiteBackupService {
+    /// Creates a new site backup, and queues the job to export it.
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateSiteBackup { site_id, user_id }: CreateSiteBackup,
+    ) -> Result<SiteBackupModel> {
+        info!(
+            "Creating site backup for site ID {site_id} (requested by user ID {user_id})"
+        );
+
+        // Ensure the site exists before queueing anything
+        let txn = ctx.transaction();
+        SiteService::get(ctx, Reference::Id(site_id)).await?;
+
+        let model = site_backup::ActiveModel {
+            site_id: Set(site_id),
+            created_by: Set(user_id),
+            ..Default::default()
+        };
+        let backup = model.insert(txn).await?;
+
+        JobService::queue_job(
+            ctx,
+            &Job::ExportSite {
+                backup_id: backup.backup_id,
+            },
+            None,
+        )
+        .await?;
+
+        Ok(backup)
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        backup_id: i64,
+    ) -> Result<Option<SiteBackupModel>> {
+        let txn = ctx.transaction();
+        let backup = SiteBackup::find_by_id(backup_id).one(txn).await?;
+        Ok(backup)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        backup_id: i64,
+    ) -> Result<SiteBackupModel> {
+        find_or_error!(Self::get_optional(ctx, backup_id), SiteBackup)
+    }
+
+    /// Gets all backups for a site, most recent first.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<SiteBackupModel>> {
+        let txn = ctx.transaction();
+        let backups = SiteBackup::find()
+            .filter(site_backup::Column::SiteId.eq(site_id))
+            .order_by_desc(site_backup::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(backups)
+    }
+
+    /// Exports the site for the given backup, writing the archive to storage.
+    ///
+    /// This is run by the job worker. If the export fails, the backup is
+    /// marked as failed along with the error message, and the error is
+    /// propagated so the job is retried.
+    pub async fn export(ctx: &ServiceContext<'_>, backup_id: i64) -> Result<()> {
+        let backup = Self::get(ctx, backup_id).await?;
+        if backup.status == SiteBackupStatus::Completed {
+            warn!("Site backup ID {backup_id} has already completed, skipping");
+            return Ok(());
+        }
+
+        info!(
+            "Exporting site ID {} for site backup ID {backup_id}",
+            backup.site_id,
+        );
+
+        Self::update_tracking(
+            ctx,
+            site_backup::ActiveModel {
+                backup_id: Set(backup_id),
+                updated_at: Set(Some(now())),
+                status: Set(SiteBackupStatus::Running),
+                error: Set(None),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        match Self::export_inner(ctx, &backup).await {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                error!("Unable to export site backup ID {backup_id}: {error}");
+                Self::update_tracking(
+                    ctx,
+                    site_backup::ActiveModel {
+                        backup_id: Set(backup_id),
+                        updated_at: Set(Some(now())),
+                        status: Set(SiteBackupStatus::Failed),
+                        error: Set(Some(error.to_string())),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+
+                Err(error)
+            }
+        }
+    }
+
+    async fn export_inner(
+        ctx: &ServiceContext<'_>,
+        backup: &SiteBackupModel,
+    ) -> Result<()> {
+        let backup_id = backup.backup_id;
+        let site = SiteService::get(ctx, Reference::Id(backup.site_id)).await?;
+        let storage_path = format!("{}-{}.json", site.slug, backup_id);
+        let staging_path = Self::staging_path(ctx, &storage_path).await?;
+
+        // Write the archive to a staging file, removing it if anything fails
+        let (archive_hash, archive_size) =
+            match Self::write_archive(ctx, backup, site, &staging_path).await {
+                Ok(output) => output,
+                Err(error) => {
+                    if let Err(error) = fs::remove_file(&staging_path).await {
+                        warn!("Unable to remove partial site backup archive: {error}");
+                    }
+
+                    return Err(error);
+                }
+            };
+
+        Self::store_archive(ctx, &storage_path, &staging_path).await?;
+
+        info!("Finished site backup ID {backup_id}, wrote {archive_size} bytes to '{storage_path}'");
+
+        Self::update_tracking(
+            ctx,
+            site_backup::ActiveModel {
+                backup_id: Set(backup_id),
+                updated_at: Set(Some(now())),
+                completed_at: Set(Some(now())),
+                status: Set(SiteBackupStatus::Completed),
+                storage_path: Set(Some(storage_path)),
+                archive_size: Set(Some(archive_size)),
+                archive_hash: Set(Some(archive_hash.to_vec())),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Gathers the site's contents and writes them out as an archive.
+    ///
+    /// Pages, text and blob contents are written as they are fetched,
+    /// so only one of each is held in memory at a time.
+    async fn write_archive(
+        ctx: &ServiceContext<'_>,
+        backup: &SiteBackupModel,
+        site: SiteModel,
+        staging_path: &Path,
+    ) -> Result<(BlobHash, i64)> {
+        let txn = ctx.transaction();
+        let backup_id = backup.backup_id;
+        let site_id = backup.site_id;
+
+        let categories = PageCategory::find()
+            .filter(page_category::Column::SiteId.eq(site_id))
+            .order_by_asc(page_category::Column::CategoryId)
+            .all(txn)
+            .await?;
+
+        let pages = Page::find()
+            .filter(page::Column::SiteId.eq(site_id))
+            .order_by_asc(page::Column::PageId)
+            .all(txn)
+            .await?;
+
+        let page_ids = pages.iter().map(|page| page.page_id).collect::<Vec<_>>();
+        let parents = ParentService::get_all_for_pages(ctx, page_ids)
+            .await?
+            .into_iter()
+            .map(|parent| SiteArchiveParent {
+                parent_page_id: parent.parent_page_id,
+                child_page_id: parent.child_page_id,
+                created_at: parent.created_at,
+                created_by: parent.created_by,
+            })
+            .collect::<Vec<_>>();
+
+        let progress_total = i32::try_from(pages.len()).unwrap_or(i32::MAX);
+        Self::update_progress(ctx, backup_id, 0, progress_total).await?;
+
+        let mut writer = ArchiveWriter::create(staging_path).await?;
+        writer.field("version", &SITE_ARCHIVE_VERSION).await?;
+        let exported_at = now()
+            .format(&Rfc3339)
+            .expect("Timestamp out of range for RFC 3339");
+
+        writer.field("exported_at", &exported_at).await?;
+        writer.field("site", &site).await?;
+        writer.field("categories", &categories).await?;
+        writer.field("parents", &parents).await?;
+
+        // Write each page and everything hanging off of it
+        let mut user_ids = HashSet::new();
+        let mut text_hashes = HashSet::new();
+        let mut blob_hashes = HashSet::new();
+
+        user_ids.extend(parents.iter().map(|parent| parent.created_by));
+        writer.begin_list("pages").await?;
+
+        for (index, page) in pages.into_iter().enumerate() {
+            debug!("Exporting page ID {} ('{}')", page.page_id, page.slug);
+
+            let revisions = PageRevision::find()
+                .filter(page_revision::Column::PageId.eq(page.page_id))
+                .order_by_asc(page_revision::Column::RevisionNumber)
+                .all(txn)
+                .await?;
+
+            for revision in &revisions {
+                user_ids.insert(revision.user_id);
+                text_hashes.insert(revision.wikitext_hash.clone());
+                text_hashes.insert(revision.compiled_hash.clone());
+            }
+
+            let votes = PageVote::find()
+                .filter(page_vote::Column::PageId.eq(page.page_id))
+                .order_by_asc(page_vote::Column::PageVoteId)
+                .all(txn)
+                .await?;
+
+            for vote in &votes {
+                user_ids.insert(vote.user_id);
+                user_ids.extend(vote.disabled_by);
+            }
+
+            let attributions = PageAttribution::find()
+                .filter(page_attribution::Column::PageId.eq(page.page_id))
+                .all(txn)
+                .await?;
+
+            user_ids.extend(attributions.iter().map(|attr| attr.user_id));
+
+            let files = File::find()
+                .filter(file::Column::PageId.eq(page.page_id))
+                .order_by_asc(file::Column::FileId)
+                .all(txn)
+                .await?;
+
+            let mut archive_files = Vec::with_capacity(files.len());
+            for file in files {
+                let revisions = FileRevision::find()
+                    .filter(file_revision::Column::FileId.eq(file.file_id))
+                    .order_by_asc(file_revision::Column::RevisionNumber)
+                    .all(txn)
+                    .await?;
+
+                for revision in &revisions {
+                    user_ids.insert(revision.user_id);
+                    blob_hashes.insert(revision.s3_hash.clone());
+                }
+
+                archive_files.push(SiteArchiveFile { file, revisions });
+            }
+
+            writer
+                .list_entry(&SiteArchivePage {
+                    page,
+                    revisions,
+                    votes,
+                    attributions,
+                    files: archive_files,
+                })
+                .await?;
+
+            let progress_done = i32::try_from(index + 1).unwrap_or(i32::MAX);
+            Self::update_progress(ctx, backup_id, progress_done, progress_total).await?;
+        }
+
+        writer.end_list().await?;
+
+        // Record who the referenced users are, so they can be matched on restore
+        let users = User::find()
+            .filter(user::Column::UserId.is_in(user_ids))
+            .order_by_asc(user::Column::UserId)
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|user| SiteArchiveUser {
+                user_id: user.user_id,
+                slug: user.slug,
+                name: user.name,
+            })
+            .collect::<Vec<_>>();
+
+        writer.field("users", &users).await?;
+
+        // Write all referenced text and blob contents
+        writer.begin_map("texts").await?;
+        for hash in text_hashes {
+            let contents = TextService::get(ctx, &hash).await?;
+            writer.map_entry(&hex::encode(&hash), &contents).await?;
+        }
+        writer.end_map().await?;
+
+        writer.begin_map("blobs").await?;
+        for hash in blob_hashes {
+            let data = BlobService::get(ctx, &hash).await?;
+            writer
+                .map_entry(&blob_hash_to_hex(&hash), &BASE64.encode(&data))
+                .await?;
+        }
+        writer.end_map().await?;
+
+        writer.finish().await
+    }
+
+    /// Re-creates a site from a backup archive.
+    ///
+    /// The archive is read from the same storage backups are written to,
+    /// so archives from other instances must first be copied there.
+    ///
+    /// All IDs are newly allocated. Users are matched to users on this instance
+    /// by slug. Those which have no match are replaced by the restoring user as
+    /// revision authors, and their votes and attributions are dropped.
+    pub async fn restore(
+        ctx: &ServiceContext<'_>,
+        RestoreSiteBackup {
+            storage_path,
+            slug,
+            user_id,
+        }: RestoreSiteBackup,
+    ) -> Result<RestoreSiteBackupOutput> {
+        info!(
+            "Restoring site backup '{storage_path}' as site '{slug}' (user ID {user_id})"
+        );
+
+        let txn = ctx.transaction();
+        let data = Self::read_archive(ctx, &storage_path).await?;
+        let archive: SiteArchive = serde_json::from_slice(&data).map_err(|error| {
+            error!("Unable to parse site backup archive: {error}");
+            Error::SiteBackupArchiveInvalid
+        })?;
+
+        if archive.version != SITE_ARCHIVE_VERSION {
+            error!(
+                "Site backup archive has unsupported version {} (expected {})",
+                archive.version, SITE_ARCHIVE_VERSION,
+            );
+            return Err(Error::SiteBackupArchiveInvalid);
+        }
+
+        let SiteArchive {
+            site: archived_site,
+            users,
+            categories,
+            pages,
+            parents,
+            texts,
+            blobs,
+            ..
+        } = archive;
+
+        // Create site
+        let CreateSiteOutput { site_id, slug, .. } = SiteService::create(
+            ctx,
+            CreateSite {
+                slug,
+                name: archived_site.name,
+                tagline: archived_site.tagline,
+                description: archived_site.description,
+                default_page: Some(archived_site.default_page),
+                layout: None,
+                locale: archived_site.locale,
+                template: None,
+            },
+        )
+        .await?;
+
+        // The layout is stored as a string, so set it directly
+        //
+        // The join password hash is not exported, so password-protected sites
+        // are restored as invite-only, until staff set a new password.
+        let join_policy = match archived_site.join_policy {
+            SiteJoinPolicy::Password => {
+                warn!("Join password is not archived, restoring site as invite-only");
+                SiteJoinPolicy::Invite
+            }
+            join_policy => join_policy,
+        };
+
+        let model = site::ActiveModel {
+            site_id: Set(site_id),
+            layout: Set(archived_site.layout),
+            join_policy: Set(join_policy),
+            join_password: Set(None),
+            join_questions: Set(archived_site.join_questions),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        // Upload contents, verifying they match their addresses
+        for (hex_hash, contents) in texts {
+            let hash = TextService::create(ctx, contents).await?;
+            if hex::encode(hash) != hex_hash {
+                error!("Text contents do not match hash {hex_hash}");
+                return Err(Error::SiteBackupArchiveInvalid);
+            }
+        }
+
+        for (hex_hash, encoded) in blobs {
+            let data = BASE64.decode(encoded.as_bytes()).map_err(|error| {
+                error!("Unable to decode blob {hex_hash}: {error}");
+                Error::SiteBackupArchiveInvalid
+            })?;
+
+            let output = BlobService::create(ctx, &data).await?;
+            if !data.is_empty() && blob_hash_to_hex(&output.hash).as_str() != hex_hash {
+                error!("Blob contents do not match hash {hex_hash}");
+                return Err(Error::SiteBackupArchiveInvalid);
+            }
+        }
+
+        // Match up archived users with users on this instance
+        let user_ids = Self::get_user_mapping(ctx, &users).await?;
+        let map_user = |id: i64| user_ids.get(&id).copied().unwrap_or(user_id);
+
+        // Create categories
+        let mut category_ids = HashMap::new();
+        for category in categories {
+            let model = page_category::ActiveModel {
+                created_at: Set(category.created_at),
+                updated_at: Set(category.updated_at),
+                site_id: Set(site_id),
+                slug: Set(category.slug),
+                layout: Set(category.layout),
+                template_page: Set(category.template_page),
+                license: Set(category.license),
+                permissions: Set(category.permissions),
+                score_type: Set(category.score_type),
+                hidden: Set(category.hidden),
+                ..Default::default()
+            };
+            let new_category = model.insert(txn).await?;
+            category_ids.insert(category.category_id, new_category.category_id);
+        }
+
+        // Create pages first, so other items can refer to them by new ID
+        let mut page_ids = HashMap::new();
+        for SiteArchivePage { page, .. } in &pages {
+            let page_category_id = *category_ids
+                .get(&page.page_category_id)
+                .ok_or(Error::SiteBackupArchiveInvalid)?;
+
+            let model = page::ActiveModel {
+                created_at: Set(page.created_at),
+                updated_at: Set(page.updated_at),
+                deleted_at: Set(page.deleted_at),
+                from_wikidot: Set(page.from_wikidot),
+                site_id: Set(site_id),
+                page_category_id: Set(page_category_id),
+                slug: Set(page.slug.clone()),
+                layout: Set(page.layout.clone()),
+                ..Default::default()
+            };
+            let new_page = model.insert(txn).await?;
+            page_ids.insert(page.page_id, new_page.page_id);
+        }
+
+        let page_count = pages.len();
+        let mut file_count = 0;
+
+        for SiteArchivePage {
+            page,
+            revisions,
+            votes,
+            attributions,
+            files,
+        } in pages
+        {
+            let page_id = page_ids[&page.page_id];
+
+            let mut revision_ids = HashMap::new();
+            for revision in revisions {
+                let model = page_revision::ActiveModel {
+                    revision_type: Set(revision.revision_type),
+                    created_at: Set(revision.created_at),
+                    updated_at: Set(revision.updated_at),
+                    revision_number: Set(revision.revision_number),
+                    page_id: Set(page_id),
+                    site_id: Set(site_id),
+                    user_id: Set(map_user(revision.user_id)),
+                    from_wikidot: Set(revision.from_wikidot),
+                    changes: Set(revision.changes),
+                    wikitext_hash: Set(revision.wikitext_hash),
+                    compiled_hash: Set(revision.compiled_hash),
+                    compiled_at: Set(revision.compiled_at),
+                    compiled_generator: Set(revision.compiled_generator),
+                    comments: Set(revision.comments),
+                    hidden: Set(revision.hidden),
+                    title: Set(revision.title),
+                    alt_title: Set(revision.alt_title),
+                    slug: Set(revision.slug),
+                    tags: Set(revision.tags),
+                    ..Default::default()
+                };
+                let new_revision = model.insert(txn).await?;
+                revision_ids.insert(revision.revision_id, new_revision.revision_id);
+            }
+
+            let model = page::ActiveModel {
+                page_id: Set(page_id),
+                latest_revision_id: Set(page
+                    .latest_revision_id
+                    .and_then(|id| revision_ids.get(&id).copied())),
+                ..Default::default()
+            };
+            model.update(txn).await?;
+
+            for vote in votes {
+                let Some(&vote_user_id) = user_ids.get(&vote.user_id) else {
+                    continue;
+                };
+
+                let model = page_vote::ActiveModel {
+                    created_at: Set(vote.created_at),
+                    deleted_at: Set(vote.deleted_at),
+                    disabled_at: Set(vote.disabled_at),
+                    disabled_by: Set(vote.disabled_by.map(map_user)),
+                    from_wikidot: Set(vote.from_wikidot),
+                    page_id: Set(page_id),
+                    user_id: Set(vote_user_id),
+                    value: Set(vote.value),
+                    ..Default::default()
+                };
+                model.insert(txn).await?;
+            }
+            ScoreService::refresh(ctx, page_id).await?;
+
+            for attribution in attributions {
+                let Some(&attribution_user_id) = user_ids.get(&attribution.user_id)
+                else {
+                    continue;
+                };
+
+                let model = page_attribution::ActiveModel {
+                    page_id: Set(page_id),
+                    user_id: Set(attribution_user_id),
+                    attribution_type: Set(attribution.attribution_type),
+                    attribution_date: Set(attribution.attribution_date),
+                    created_at: Set(attribution.created_at),
+                };
+                model.insert(txn).await?;
+            }
+
+            for SiteArchiveFile { file, revisions } in files {
+                let model = file::ActiveModel {
+                    created_at: Set(file.created_at),
+                    updated_at: Set(file.updated_at),
+                    deleted_at: Set(file.deleted_at),
+                    from_wikidot: Set(file.from_wikidot),
+                    name: Set(file.name),
+                    page_id: Set(page_id),
+                    site_id: Set(site_id),
+                    ..Default::default()
+                };
+                let new_file = model.insert(txn).await?;
+                file_count += 1;
+
+                for revision in revisions {
+                    let model = file_revision::ActiveModel {
+                        revision_type: Set(revision.revision_type),
+                        created_at: Set(revision.created_at),
+                        revision_number: Set(revision.revision_number),
+                        file_id: Set(new_file.file_id),
+                        page_id: Set(page_ids
+                            .get(&revision.page_id)
+                            .copied()
+                            .unwrap_or(page_id)),
+                        site_id: Set(site_id),
+                        user_id: Set(map_user(revision.user_id)),
+                        name: Set(revision.name),
+                        s3_hash: Set(revision.s3_hash),
+                        mime_hint: Set(revision.mime_hint),
+                        size_hint: Set(revision.size_hint),
+                        licensing: Set(revision.licensing),
+                        changes: Set(revision.changes),
+                        comments: Set(revision.comments),
+                        hidden: Set(revision.hidden),
+                        ..Default::default()
+                    };
+                    model.insert(txn).await?;
+                }
+            }
+
+            // Rebuild links and compiled output for this instance
+            JobService::queue_rerender_page(ctx, site_id, page_id, 0, None).await?;
+        }
+
+        for parent in parents {
+            if let (Some(&parent_page_id), Some(&child_page_id)) = (
+                page_ids.get(&parent.parent_page_id),
+                page_ids.get(&parent.child_page_id),
+            ) {
+                // Inserted directly to keep the original timestamp
+                let created_by = map_user(parent.created_by);
+                let model = relation::ActiveModel {
+                    relation_type: Set(str!(RelationType::PageParent.value())),
+                    dest_type: Set(RelationObjectType::Page),
+                    dest_id: Set(parent_page_id),
+                    from_type: Set(RelationObjectType::Page),
+                    from_id: Set(child_page_id),
+                    metadata: Set(serde_json::to_value(())?),
+                    created_by: Set(created_by),
+                    created_at: Set(parent.created_at),
+                    ..Default::default()
+                };
+                model.insert(txn).await?;
+            }
+        }
+
+        Ok(RestoreSiteBackupOutput {
+            site_id,
+            slug,
+            page_count,
+            file_count,
+        })
+    }
+
+    /// Maps the IDs of archived users to users on this instance with the same slug.
+    ///
+    /// Archived users with no such match are not included.
+    async fn get_user_mapping(
+        ctx: &ServiceContext<'_>,
+        users: &[SiteArchiveUser],
+    ) -> Result<HashMap<i64, i64>> {
+        let txn = ctx.transaction();
+        let slugs = users.iter().map(|user| user.slug.as_str());
+        let local_users = User::find()
+            .filter(user::Column::Slug.is_in(slugs))
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|user| (user.slug, user.user_id))
+            .collect::<HashMap<_, _>>();
+
+        let mapping = users
+            .iter()
+            .filter_map(|user| {
+                let local_user_id = local_users.get(&user.slug)?;
+                debug!(
+                    "Mapping archived user '{}' (ID {}) to user ID {}",
+                    user.name, user.user_id, local_user_id,
+                );
+
+                Some((user.user_id, *local_user_id))
+            })
+            .collect();
+
+        Ok(mapping)
+    }
+
+    async fn update_progress(
+        ctx: &ServiceContext<'_>,
+        backup_id: i64,
+        progress_done: i32,
+        progress_total: i32,
+    ) -> Result<()> {
+        Self::update_tracking(
+            ctx,
+            site_backup::ActiveModel {
+                backup_id: Set(backup_id),
+                updated_at: Set(Some(now())),
+                progress_done: Set(progress_done),
+                progress_total: Set(progress_total),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Updates the tracking row for a site backup.
+    ///
+    /// This uses the main database connection rather than the job's
+    /// transaction, so that progress is visible while the export is
+    /// still running, and failures are recorded even on rollback.
+    async fn update_tracking(
+        ctx: &ServiceContext<'_>,
+        model: site_backup::ActiveModel,
+    ) -> Result<()> {
+        let state = ctx.state();
+        model.update(&state.database).await?;
+        Ok(())
+    }
+
+    /// Gets the path an archive is written to before it is stored.
+    ///
+    /// For local backups this is beside the final file, so it can be moved
+    /// into place, otherwise it is a temporary file to upload from.
+    async fn staging_path(
+        ctx: &ServiceContext<'_>,
+        storage_path: &str,
+    ) -> Result<PathBuf> {
+        match ctx.config().backup_local_directory {
+            Some(ref directory) => {
+                fs::create_dir_all(directory).await?;
+                Ok(directory.join(format!("{storage_path}.partial")))
+            }
+            None => Ok(env::temp_dir().join(format!("deepwell-backup-{storage_path}"))),
+        }
+    }
+
+    async fn store_archive(
+        ctx: &ServiceContext<'_>,
+        storage_path: &str,
+        staging_path: &Path,
+    ) -> Result<()> {
+        match ctx.config().backup_local_directory {
+            Some(ref directory) => {
+                let path = directory.join(storage_path);
+                debug!("Moving site backup archive to {}", path.display());
+                fs::rename(staging_path, path).await?;
+                Ok(())
+            }
+            None => {
+                let path = format!("{BACKUP_DIRECTORY}/{storage_path}");
+                debug!("Uploading site backup archive to blob storage at {path}");
+                let result = ctx
+                    .blob_storage()
+                    .put_file(&path, staging_path, "application/json")
+                    .await;
+
+                fs::remove_file(staging_path).await?;
+                result
+            }
+        }
+    }
+
+    async fn read_archive(
+        ctx: &ServiceContext<'_>,
+        storage_path: &str,
+    ) -> Result<Vec<u8>> {
+        // Only allow plain relative paths within the backup storage area
+        let path = Path::new(storage_path);
+        let valid = !storage_path.is_empty()
+            && path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+
+        if !valid {
+            error!("Invalid site backup storage path: '{storage_path}'");
+            return Err(Error::BadRequest);
+        }
+
+        match ctx.config().backup_local_directory {
+            Some(ref directory) => match fs::read(directory.join(path)).await {
+                Ok(data) => Ok(data),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                    Err(Error::SiteBackupArchiveNotFound)
+                }
+                Err(error) => Err(Error::Io(error)),
+            },
+            None => {
+                let path = format!("{BACKUP_DIRECTORY}/{storage_path}");
+                match ctx.blob_storage().get(&path).await? {
+                    Some(data) => Ok(data),
+                    None => Err(Error::SiteBackupArchiveNotFound),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+/*
+ * services/site_backup/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::file::Model as FileModel;
+use crate::models::file_revision::Model as FileRevisionModel;
+use crate::models::page::Model as PageModel;
+use crate::models::page_attribution::Model as PageAttributionModel;
+use crate::models::page_category::Model as PageCategoryModel;
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::page_vote::Model as PageVoteModel;
+use crate::models::site::Model as SiteModel;
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct CreateSiteBackup {
+    pub site_id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetSiteBackup {
+    pub backup_id: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetSiteBackups {
+    pub site_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RestoreSiteBackup {
+    pub storage_path: String,
+    pub slug: String,
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RestoreSiteBackupOutput {
+    pub site_id: i64,
+    pub slug: String,
+    pub page_count: usize,
+    pub file_count: usize,
+}
+
+/// The full contents of a site backup archive.
+///
+/// All IDs are as they were on the exporting instance, and are
+/// remapped to newly-allocated IDs when the archive is restored.
+///
+/// Archives are written field by field (see `ArchiveWriter`),
+/// so fields here may appear in any order in the document.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SiteArchive {
+    pub version: u32,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+
+    pub site: SiteModel,
+
+    /// Every user referenced by the archive, so they can be matched
+    /// up with users on the restoring instance.
+    pub users: Vec<SiteArchiveUser>,
+
+    pub categories: Vec<PageCategoryModel>,
+    pub pages: Vec<SiteArchivePage>,
+    pub parents: Vec<SiteArchiveParent>,
+
+    /// Text contents used by page revisions, keyed by hex hash.
+    pub texts: BTreeMap<String, String>,
+
+    /// Blob contents used by file revisions, keyed by hex hash.
+    /// The values are base64-encoded.
+    pub blobs: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SiteArchiveUser {
+    pub user_id: i64,
+    pub slug: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SiteArchivePage {
+    pub page: PageModel,
+    pub revisions: Vec<PageRevisionModel>,
+    pub votes: Vec<PageVoteModel>,
+    pub attributions: Vec<PageAttributionModel>,
+    pub files: Vec<SiteArchiveFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SiteArchiveFile {
+    pub file: FileModel,
+    pub revisions: Vec<FileRevisionModel>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SiteArchiveParent {
+    pub parent_page_id: i64,
+    pub child_page_id: i64,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    pub created_by: i64,
+}
@@ -0,0 +1,182 @@
+/*
+ * services/site_backup/writer.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Incremental writer for site backup archives.
+//!
+//! Archives can be far larger than is reasonable to hold in memory, so
+//! rather than serializing a whole `SiteArchive` at once, each value is
+//! serialized and written out as soon as it is available, with the
+//! surrounding JSON object and collections emitted by hand.
+
+use super::prelude::*;
+use crate::hash::{slice_to_blob_hash, BlobHash};
+use serde::Serialize;
+use sha2::{Digest, Sha512};
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+#[derive(Debug)]
+pub struct ArchiveWriter {
+    file: BufWriter<File>,
+    hasher: Sha512,
+    size: u64,
+
+    /// Whether any top-level fields have been written yet.
+    has_fields: bool,
+
+    /// Whether any entries have been written to the open list or map.
+    has_entries: bool,
+}
+
+impl ArchiveWriter {
+    pub async fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path).await?;
+        let mut writer = ArchiveWriter {
+            file: BufWriter::new(file),
+            hasher: Sha512::new(),
+            size: 0,
+            has_fields: false,
+            has_entries: false,
+        };
+
+        writer.write_raw(b"{").await?;
+        Ok(writer)
+    }
+
+    /// Writes a complete top-level field.
+    pub async fn field<T: Serialize>(&mut self, name: &str, value: &T) -> Result<()> {
+        self.write_key(name).await?;
+        self.write_value(value).await
+    }
+
+    /// Starts a top-level field containing a list, see `list_entry()`.
+    pub async fn begin_list(&mut self, name: &str) -> Result<()> {
+        self.write_key(name).await?;
+        self.has_entries = false;
+        self.write_raw(b"[").await
+    }
+
+    pub async fn list_entry<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        self.write_separator().await?;
+        self.write_value(value).await
+    }
+
+    pub async fn end_list(&mut self) -> Result<()> {
+        self.write_raw(b"]").await
+    }
+
+    /// Starts a top-level field containing a map, see `map_entry()`.
+    pub async fn begin_map(&mut self, name: &str) -> Result<()> {
+        self.write_key(name).await?;
+        self.has_entries = false;
+        self.write_raw(b"{").await
+    }
+
+    pub async fn map_entry<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        self.write_separator().await?;
+        self.write_value(key).await?;
+        self.write_raw(b":").await?;
+        self.write_value(value).await
+    }
+
+    pub async fn end_map(&mut self) -> Result<()> {
+        self.write_raw(b"}").await
+    }
+
+    /// Closes the archive, returning its hash and size in bytes.
+    pub async fn finish(mut self) -> Result<(BlobHash, i64)> {
+        self.write_raw(b"}").await?;
+        self.file.flush().await?;
+
+        let hash = slice_to_blob_hash(&self.hasher.finalize());
+        let size = i64::try_from(self.size).expect("Archive size exceeds i64");
+        Ok((hash, size))
+    }
+
+    async fn write_key(&mut self, name: &str) -> Result<()> {
+        if self.has_fields {
+            self.write_raw(b",").await?;
+        }
+
+        self.has_fields = true;
+        self.write_value(name).await?;
+        self.write_raw(b":").await
+    }
+
+    async fn write_separator(&mut self) -> Result<()> {
+        if self.has_entries {
+            self.write_raw(b",").await?;
+        }
+
+        self.has_entries = true;
+        Ok(())
+    }
+
+    async fn write_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let data = serde_json::to_vec(value)?;
+        self.write_raw(&data).await
+    }
+
+    async fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+        self.file.write_all(data).await?;
+        self.hasher.update(data);
+        self.size += data.len() as u64;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn archive_writer() {
+    use serde_json::json;
+    use std::env;
+
+    let path =
+        env::temp_dir().join(format!("deepwell-test-archive-{}", std::process::id()));
+    let mut writer = ArchiveWriter::create(&path).await.unwrap();
+    writer.field("version", &1).await.unwrap();
+    writer.begin_list("empty").await.unwrap();
+    writer.end_list().await.unwrap();
+    writer.begin_list("pages").await.unwrap();
+    writer.list_entry(&"a").await.unwrap();
+    writer.list_entry(&"b").await.unwrap();
+    writer.end_list().await.unwrap();
+    writer.begin_map("texts").await.unwrap();
+    writer.map_entry("x", &"y").await.unwrap();
+    writer.map_entry("z", &"w").await.unwrap();
+    writer.end_map().await.unwrap();
+    let (hash, size) = writer.finish().await.unwrap();
+
+    let data = tokio::fs::read(&path).await.unwrap();
+    tokio::fs::remove_file(&path).await.unwrap();
+
+    let value: serde_json::Value = serde_json::from_slice(&data).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "version": 1,
+            "empty": [],
+            "pages": ["a", "b"],
+            "texts": { "x": "y", "z": "w" },
+        }),
+    );
+    assert_eq!(size, data.len() as i64);
+    assert_eq!(hash, crate::hash::sha512_hash(&data));
+}
@@ -0,0 +1,44 @@
+/*
+ * services/site_membership/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The site membership service, for users joining sites.
+//!
+//! How a user may become a member depends on the site's join policy.
+//! Open sites may be joined directly, and password-protected sites
+//! may be joined with the right password. Sites accepting applications
+//! have users submit answers to the site's questions, which staff then
+//! accept or reject.
+//!
+//! Invitations are tokens created by site staff, and let a user join
+//! regardless of the join policy, until they expire or run out of uses.
+//!
+//! Membership itself is stored as a `SiteMember` relation.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::SiteMembershipService;
+pub use self::structs::*;
@@ -0,0 +1,480 @@
+/*
+ * services/site_membership/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::{SiteApplicationStatus, SiteJoinPolicy};
+use crate::models::site_application::{
+    self, Entity as SiteApplication, Model as SiteApplicationModel,
+};
+use crate::models::site_invitation::{
+    self, Entity as SiteInvitation, Model as SiteInvitationModel,
+};
+use crate::services::relation::{
+    CreateSiteMember, GetSiteBan, GetSiteMember, SiteMemberAccepted, SiteMemberData,
+};
+use crate::services::{PasswordService, RelationService, SiteService};
+use crate::utils::assert_is_csprng;
+use rand::distributions::{Alphanumeric, DistString};
+use rand::thread_rng;
+use sea_query::Expr;
+
+/// The length of generated invitation tokens.
+pub const INVITATION_TOKEN_LENGTH: usize = 32;
+
+#[derive(Debug)]
+pub struct SiteMembershipService;
+
+impl SiteMembershipService {
+    // Joining
+
+    /// Joins a site directly, as permitted by its join policy.
+    ///
+    /// If an invitation token is provided, then it is used instead,
+    /// and the site's join policy is not consulted.
+    pub async fn join(
+        ctx: &ServiceContext<'_>,
+        JoinSite {
+            site_id,
+            user_id,
+            password,
+            token,
+        }: JoinSite,
+    ) -> Result<()> {
+        info!("User ID {user_id} is joining site ID {site_id}");
+        Self::check_not_member(ctx, site_id, user_id).await?;
+
+        let accepted = match token {
+            Some(token) => {
+                let invitation = Self::use_invitation(ctx, site_id, &token).await?;
+                SiteMemberAccepted::Invitation(invitation.created_by)
+            }
+            None => {
+                let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+                match (site.join_policy, password, site.join_password) {
+                    (SiteJoinPolicy::Open, _, _) => SiteMemberAccepted::SelfJoined,
+                    (SiteJoinPolicy::Password, Some(password), Some(hash)) => {
+                        PasswordService::verify(ctx, &password, &hash).await?;
+                        SiteMemberAccepted::Password
+                    }
+                    (policy, _, _) => {
+                        warn!("Site ID {site_id} cannot be joined directly (policy {policy:?})");
+                        return Err(Error::SiteJoinNotAllowed);
+                    }
+                }
+            }
+        };
+
+        RelationService::create_site_member(
+            ctx,
+            CreateSiteMember {
+                site_id,
+                user_id,
+                metadata: SiteMemberData { accepted },
+                created_by: user_id,
+            },
+        )
+        .await?;
+
+        // Any outstanding application is moot now
+        Self::remove_applications(ctx, site_id, user_id).await?;
+        Ok(())
+    }
+
+    // Applications
+
+    /// Submits an application to join a site.
+    ///
+    /// There must be one answer for each of the site's join questions.
+    pub async fn apply(
+        ctx: &ServiceContext<'_>,
+        ApplyToSite {
+            site_id,
+            user_id,
+            answers,
+        }: ApplyToSite,
+    ) -> Result<SiteApplicationModel> {
+        info!("User ID {user_id} is applying to join site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        if site.join_policy != SiteJoinPolicy::Application {
+            warn!("Site ID {site_id} does not accept applications");
+            return Err(Error::SiteJoinNotAllowed);
+        }
+
+        if answers.len() != site.join_questions.len() {
+            error!(
+                "Application has {} answers, but site ID {site_id} has {} questions",
+                answers.len(),
+                site.join_questions.len(),
+            );
+            return Err(Error::SiteApplicationAnswersMismatch);
+        }
+
+        RelationService::check_site_ban(ctx, GetSiteBan { site_id, user_id }, "apply to")
+            .await?;
+        Self::check_not_member(ctx, site_id, user_id).await?;
+
+        let pending = SiteApplication::find()
+            .filter(
+                Condition::all()
+                    .add(site_application::Column::SiteId.eq(site_id))
+                    .add(site_application::Column::UserId.eq(user_id))
+                    .add(
+                        site_application::Column::Status
+                            .eq(SiteApplicationStatus::Pending),
+                    ),
+            )
+            .one(txn)
+            .await?;
+
+        if pending.is_some() {
+            error!("User ID {user_id} already has a pending application to site ID {site_id}");
+            return Err(Error::SiteApplicationExists);
+        }
+
+        let model = site_application::ActiveModel {
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            answers: Set(answers),
+            ..Default::default()
+        };
+        let application = model.insert(txn).await?;
+        Ok(application)
+    }
+
+    /// Accepts a pending application, making the applicant a site member.
+    pub async fn accept_application(
+        ctx: &ServiceContext<'_>,
+        input: ReviewSiteApplication,
+    ) -> Result<SiteApplicationModel> {
+        let reviewer_id = input.user_id;
+        let application =
+            Self::review_application(ctx, input, SiteApplicationStatus::Accepted).await?;
+
+        RelationService::create_site_member(
+            ctx,
+            CreateSiteMember {
+                site_id: application.site_id,
+                user_id: application.user_id,
+                metadata: SiteMemberData {
+                    accepted: SiteMemberAccepted::Accepted(reviewer_id),
+                },
+                created_by: reviewer_id,
+            },
+        )
+        .await?;
+
+        Ok(application)
+    }
+
+    /// Rejects a pending application.
+    #[inline]
+    pub async fn reject_application(
+        ctx: &ServiceContext<'_>,
+        input: ReviewSiteApplication,
+    ) -> Result<SiteApplicationModel> {
+        Self::review_application(ctx, input, SiteApplicationStatus::Rejected).await
+    }
+
+    async fn review_application(
+        ctx: &ServiceContext<'_>,
+        ReviewSiteApplication {
+            application_id,
+            user_id,
+            message,
+        }: ReviewSiteApplication,
+        status: SiteApplicationStatus,
+    ) -> Result<SiteApplicationModel> {
+        info!("User ID {user_id} is reviewing site application ID {application_id} as {status:?}");
+
+        let txn = ctx.transaction();
+        let application = Self::get_application(ctx, application_id).await?;
+        if application.status != SiteApplicationStatus::Pending {
+            error!("Site application ID {application_id} has already been reviewed");
+            return Err(Error::SiteApplicationNotPending);
+        }
+
+        let model = site_application::ActiveModel {
+            application_id: Set(application_id),
+            status: Set(status),
+            reviewed_at: Set(Some(now())),
+            reviewed_by: Set(Some(user_id)),
+            review_message: Set(message),
+            ..Default::default()
+        };
+        let application = model.update(txn).await?;
+        Ok(application)
+    }
+
+    /// Deletes any pending applications a user has to a site.
+    ///
+    /// Used when the user no longer needs to be reviewed,
+    /// for instance because they joined or were banned.
+    pub async fn remove_applications(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: i64,
+    ) -> Result<()> {
+        debug!(
+            "Removing pending applications for user ID {user_id} to site ID {site_id}"
+        );
+
+        let txn = ctx.transaction();
+        SiteApplication::delete_many()
+            .filter(
+                Condition::all()
+                    .add(site_application::Column::SiteId.eq(site_id))
+                    .add(site_application::Column::UserId.eq(user_id))
+                    .add(
+                        site_application::Column::Status
+                            .eq(SiteApplicationStatus::Pending),
+                    ),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_application_optional(
+        ctx: &ServiceContext<'_>,
+        application_id: i64,
+    ) -> Result<Option<SiteApplicationModel>> {
+        let txn = ctx.transaction();
+        let application = SiteApplication::find_by_id(application_id).one(txn).await?;
+        Ok(application)
+    }
+
+    #[inline]
+    pub async fn get_application(
+        ctx: &ServiceContext<'_>,
+        application_id: i64,
+    ) -> Result<SiteApplicationModel> {
+        find_or_error!(
+            Self::get_application_optional(ctx, application_id),
+            SiteApplication,
+        )
+    }
+
+    /// Gets the applications to a site, oldest first.
+    pub async fn list_applications(
+        ctx: &ServiceContext<'_>,
+        ListSiteApplications { site_id, status }: ListSiteApplications,
+    ) -> Result<Vec<SiteApplicationModel>> {
+        let txn = ctx.transaction();
+        let applications = SiteApplication::find()
+            .filter(
+                Condition::all()
+                    .add(site_application::Column::SiteId.eq(site_id))
+                    .add_option(
+                        status.map(|status| site_application::Column::Status.eq(status)),
+                    ),
+            )
+            .order_by_asc(site_application::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(applications)
+    }
+
+    // Invitations
+
+    /// Creates a new invitation token for a site.
+    pub async fn create_invitation(
+        ctx: &ServiceContext<'_>,
+        CreateSiteInvitation {
+            site_id,
+            user_id,
+            expires_at,
+            max_uses,
+        }: CreateSiteInvitation,
+    ) -> Result<SiteInvitationModel> {
+        info!("User ID {user_id} is creating an invitation for site ID {site_id}");
+
+        if expires_at.is_some_and(|expires_at| expires_at <= now())
+            || max_uses.is_some_and(|max_uses| max_uses <= 0)
+        {
+            error!("Invitation expiry or use limit is invalid");
+            return Err(Error::SiteInvitationInvalid);
+        }
+
+        let txn = ctx.transaction();
+        SiteService::get(ctx, Reference::Id(site_id)).await?;
+
+        let model = site_invitation::ActiveModel {
+            created_by: Set(user_id),
+            site_id: Set(site_id),
+            token: Set(Self::new_token()),
+            expires_at: Set(expires_at),
+            max_uses: Set(max_uses),
+            ..Default::default()
+        };
+        let invitation = model.insert(txn).await?;
+        Ok(invitation)
+    }
+
+    /// Revokes an invitation, so it can no longer be used.
+    pub async fn revoke_invitation(
+        ctx: &ServiceContext<'_>,
+        RevokeSiteInvitation {
+            invitation_id,
+            user_id,
+        }: RevokeSiteInvitation,
+    ) -> Result<SiteInvitationModel> {
+        info!("User ID {user_id} is revoking site invitation ID {invitation_id}");
+
+        let txn = ctx.transaction();
+        let invitation = Self::get_invitation(ctx, invitation_id).await?;
+        if invitation.revoked_at.is_some() {
+            debug!("Site invitation ID {invitation_id} is already revoked");
+            return Ok(invitation);
+        }
+
+        let model = site_invitation::ActiveModel {
+            invitation_id: Set(invitation_id),
+            revoked_at: Set(Some(now())),
+            revoked_by: Set(Some(user_id)),
+            ..Default::default()
+        };
+        let invitation = model.update(txn).await?;
+        Ok(invitation)
+    }
+
+    /// Counts a use of an invitation, checking that it is still valid.
+    async fn use_invitation(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        token: &str,
+    ) -> Result<SiteInvitationModel> {
+        let txn = ctx.transaction();
+        let invitation = SiteInvitation::find()
+            .filter(
+                Condition::all()
+                    .add(site_invitation::Column::SiteId.eq(site_id))
+                    .add(site_invitation::Column::Token.eq(token)),
+            )
+            .one(txn)
+            .await?
+            .ok_or(Error::SiteInvitationNotFound)?;
+
+        // Check validity and count the use in a single statement, so that
+        // concurrent uses cannot take an invitation past its limit.
+        let rows_updated = SiteInvitation::update_many()
+            .col_expr(
+                site_invitation::Column::UseCount,
+                Expr::col(site_invitation::Column::UseCount).add(1),
+            )
+            .filter(
+                Condition::all()
+                    .add(
+                        site_invitation::Column::InvitationId
+                            .eq(invitation.invitation_id),
+                    )
+                    .add(site_invitation::Column::RevokedAt.is_null())
+                    .add(
+                        Condition::any()
+                            .add(site_invitation::Column::ExpiresAt.is_null())
+                            .add(site_invitation::Column::ExpiresAt.gt(now())),
+                    )
+                    .add(
+                        Condition::any()
+                            .add(site_invitation::Column::MaxUses.is_null())
+                            .add(
+                                Expr::col(site_invitation::Column::UseCount)
+                                    .lt(Expr::col(site_invitation::Column::MaxUses)),
+                            ),
+                    ),
+            )
+            .exec(txn)
+            .await?
+            .rows_affected;
+
+        if rows_updated == 0 {
+            warn!(
+                "Site invitation ID {} can no longer be used",
+                invitation.invitation_id,
+            );
+            return Err(Error::SiteInvitationExpired);
+        }
+
+        Self::get_invitation(ctx, invitation.invitation_id).await
+    }
+
+    pub async fn get_invitation_optional(
+        ctx: &ServiceContext<'_>,
+        invitation_id: i64,
+    ) -> Result<Option<SiteInvitationModel>> {
+        let txn = ctx.transaction();
+        let invitation = SiteInvitation::find_by_id(invitation_id).one(txn).await?;
+        Ok(invitation)
+    }
+
+    #[inline]
+    pub async fn get_invitation(
+        ctx: &ServiceContext<'_>,
+        invitation_id: i64,
+    ) -> Result<SiteInvitationModel> {
+        find_or_error!(
+            Self::get_invitation_optional(ctx, invitation_id),
+            SiteInvitation,
+        )
+    }
+
+    /// Gets all invitations for a site, most recent first.
+    pub async fn list_invitations(
+        ctx: &ServiceContext<'_>,
+        ListSiteInvitations { site_id }: ListSiteInvitations,
+    ) -> Result<Vec<SiteInvitationModel>> {
+        let txn = ctx.transaction();
+        let invitations = SiteInvitation::find()
+            .filter(site_invitation::Column::SiteId.eq(site_id))
+            .order_by_desc(site_invitation::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(invitations)
+    }
+
+    // Helpers
+
+    async fn check_not_member(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: i64,
+    ) -> Result<()> {
+        if RelationService::site_member_exists(ctx, GetSiteMember { site_id, user_id })
+            .await?
+        {
+            error!("User ID {user_id} is already a member of site ID {site_id}");
+            return Err(Error::SiteMemberExists);
+        }
+
+        Ok(())
+    }
+
+    /// Securely generates a new invitation token.
+    fn new_token() -> String {
+        debug!("Generating a new site invitation token");
+        let mut rng = thread_rng();
+        assert_is_csprng(&rng);
+        Alphanumeric.sample_string(&mut rng, INVITATION_TOKEN_LENGTH)
+    }
+}
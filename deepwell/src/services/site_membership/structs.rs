@@ -0,0 +1,91 @@
+/*
+ * services/site_membership/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::SiteApplicationStatus;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct JoinSite {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    /// The join password, for sites with the `password` join policy.
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// An invitation token, which may be used on any site.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApplyToSite {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    /// Answers to the site's join questions, in order.
+    pub answers: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetSiteApplication {
+    pub application_id: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct ListSiteApplications {
+    pub site_id: i64,
+
+    #[serde(default)]
+    pub status: Option<SiteApplicationStatus>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReviewSiteApplication {
+    pub application_id: i64,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct CreateSiteInvitation {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+
+    #[serde(default)]
+    pub max_uses: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct ListSiteInvitations {
+    pub site_id: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct RevokeSiteInvitation {
+    pub invitation_id: i64,
+    pub user_id: i64,
+}
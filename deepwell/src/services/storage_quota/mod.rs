@@ -0,0 +1,45 @@
+/*
+ * services/storage_quota/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The storage quota service, for limiting how much file data is kept.
+//!
+//! Usage is counted over file revisions, since each revision retains its
+//! blob even after the file is edited or deleted. Because blobs are stored
+//! by hash, a blob is only counted once however many revisions use it.
+//!
+//! A user's usage is the blobs they introduced in file revisions, plus any
+//! uploads they have started but not finished. A site's usage is the blobs
+//! of every file revision on the site.
+//!
+//! Quotas default to the values in the configuration, but can be
+//! overridden for particular users or sites. Site staff are sent a
+//! message, through a job, when an upload brings the site close to its quota.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::StorageQuotaService;
+pub use self::structs::*;
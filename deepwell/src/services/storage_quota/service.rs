@@ -0,0 +1,356 @@
+/*
+ * services/storage_quota/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
+use crate::models::file_revision::{self, Entity as FileRevision};
+use crate::models::site::{self, Entity as Site, Model as SiteModel};
+use crate::models::user::{self, Entity as User, Model as UserModel};
+use crate::services::job::Job;
+use crate::services::message::CreateMessageDraft;
+use crate::services::{
+    JobService, MessageService, RelationService, SiteService, UserService,
+};
+use fluent::{FluentArgs, FluentValue};
+use sea_orm::{DatabaseBackend, Statement};
+use unic_langid::LanguageIdentifier;
+
+/// Totals the size of the blobs a user has introduced in file revisions.
+///
+/// Parameters:
+/// * `$1` &mdash; The user ID
+const USER_USAGE_QUERY: &str = r#"
+SELECT COALESCE(SUM(size_hint), 0)::BIGINT AS total
+FROM (
+    SELECT DISTINCT ON (s3_hash) size_hint
+    FROM file_revision
+    WHERE user_id = $1
+    AND 'blob' = ANY(changes)
+) AS blobs
+"#;
+
+/// Totals the size of the uploads a user has started, but not finished.
+///
+/// Parameters:
+/// * `$1` &mdash; The user ID
+const USER_PENDING_QUERY: &str = r#"
+SELECT COALESCE(SUM(expected_length), 0)::BIGINT AS total
+FROM blob_pending
+WHERE created_by = $1
+AND s3_hash IS NULL
+AND expires_at > now()
+"#;
+
+/// Totals the size of the blobs used by all file revisions on a site.
+///
+/// Parameters:
+/// * `$1` &mdash; The site ID
+const SITE_USAGE_QUERY: &str = r#"
+SELECT COALESCE(SUM(size_hint), 0)::BIGINT AS total
+FROM (
+    SELECT DISTINCT ON (s3_hash) size_hint
+    FROM file_revision
+    WHERE site_id = $1
+) AS blobs
+"#;
+
+#[derive(Debug)]
+pub struct StorageQuotaService;
+
+impl StorageQuotaService {
+    pub async fn get_user_usage(
+        ctx: &ServiceContext<'_>,
+        reference: Reference<'_>,
+    ) -> Result<StorageUsage> {
+        let user = UserService::get(ctx, reference).await?;
+        Self::user_usage(ctx, &user).await
+    }
+
+    pub async fn get_site_usage(
+        ctx: &ServiceContext<'_>,
+        reference: Reference<'_>,
+    ) -> Result<StorageUsage> {
+        let site = SiteService::get(ctx, reference).await?;
+        Self::site_usage(ctx, &site).await
+    }
+
+    /// Overrides the storage quota for a user.
+    ///
+    /// Setting the quota to `None` reverts the user to the configured default.
+    pub async fn set_user_quota(
+        ctx: &ServiceContext<'_>,
+        SetUserStorageQuota { user, quota }: SetUserStorageQuota<'_>,
+    ) -> Result<StorageUsage> {
+        info!("Setting storage quota for user {user:?} to {quota:?}");
+        Self::check_quota(quota)?;
+
+        let txn = ctx.transaction();
+        let user_id = UserService::get_id(ctx, user).await?;
+        let model = user::ActiveModel {
+            user_id: Set(user_id),
+            storage_quota: Set(quota),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+        let user = model.update(txn).await?;
+        Self::user_usage(ctx, &user).await
+    }
+
+    /// Overrides the storage quota for a site.
+    ///
+    /// Setting the quota to `None` reverts the site to the configured default.
+    pub async fn set_site_quota(
+        ctx: &ServiceContext<'_>,
+        SetSiteStorageQuota { site, quota }: SetSiteStorageQuota<'_>,
+    ) -> Result<StorageUsage> {
+        info!("Setting storage quota for site {site:?} to {quota:?}");
+        Self::check_quota(quota)?;
+
+        let txn = ctx.transaction();
+        let site_id = SiteService::get_id(ctx, site).await?;
+        let model = site::ActiveModel {
+            site_id: Set(site_id),
+            storage_quota: Set(quota),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+        let site = model.update(txn).await?;
+        Self::site_usage(ctx, &site).await
+    }
+
+    /// Checks that a user has room to upload a blob of the given size.
+    ///
+    /// Since the blob's hash isn't known until the upload is finished,
+    /// this cannot account for the user having uploaded it before.
+    ///
+    /// The user's row is locked for the rest of the transaction,
+    /// so that concurrent uploads cannot each pass the check.
+    pub async fn check_user_upload(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        size: i64,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let user = User::find_by_id(user_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::UserNotFound)?;
+
+        let usage = Self::user_usage(ctx, &user).await?;
+
+        if usage.would_exceed(size) {
+            error!(
+                "Upload of {size} bytes would exceed storage quota for user ID {user_id} ({usage:?})",
+            );
+
+            return Err(Error::UserStorageQuotaExceeded);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a site has room for a blob being added to it.
+    ///
+    /// Blobs already used by a file on the site take up no more space.
+    /// If the blob brings the site past the warning threshold, then
+    /// a job is queued to send its staff a message.
+    ///
+    /// The site's row is locked for the rest of the transaction,
+    /// so that concurrent uploads cannot each pass the check.
+    pub async fn check_site_upload(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        hash: &[u8],
+        size: i64,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let site = Site::find_by_id(site_id)
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::SiteNotFound)?;
+
+        let existing = FileRevision::find()
+            .filter(
+                Condition::all()
+                    .add(file_revision::Column::SiteId.eq(site_id))
+                    .add(file_revision::Column::S3Hash.eq(hash)),
+            )
+            .one(txn)
+            .await?;
+
+        if existing.is_some() {
+            debug!("Blob is already used on site ID {site_id}, no additional storage");
+            return Ok(());
+        }
+
+        let usage = Self::site_usage(ctx, &site).await?;
+
+        if usage.would_exceed(size) {
+            error!(
+                "Upload of {size} bytes would exceed storage quota for site ID {site_id} ({usage:?})",
+            );
+
+            return Err(Error::SiteStorageQuotaExceeded);
+        }
+
+        // Only warn when first crossing the threshold, not on every upload after
+        let config = ctx.config();
+        let used = usage.used.saturating_add(size);
+        if !usage.warning && is_warning(used, usage.quota, config.storage_warning_percent)
+        {
+            JobService::queue_job(ctx, &Job::WarnSiteStorage { site_id }, None).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the site's staff a message if it is nearing its storage quota.
+    ///
+    /// Usage is checked again here, since the upload which
+    /// queued the warning may not have been committed.
+    pub async fn warn_site(ctx: &ServiceContext<'_>, site_id: i64) -> Result<()> {
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let usage = Self::site_usage(ctx, &site).await?;
+
+        match usage.quota {
+            Some(quota) if usage.warning => {
+                Self::notify_site(ctx, &site, usage.used, quota).await
+            }
+            _ => {
+                debug!("Site ID {site_id} is no longer near its storage quota");
+                Ok(())
+            }
+        }
+    }
+
+    async fn user_usage(
+        ctx: &ServiceContext<'_>,
+        user: &UserModel,
+    ) -> Result<StorageUsage> {
+        let config = ctx.config();
+        let used = Self::get_total(ctx, USER_USAGE_QUERY, user.user_id).await?;
+        let pending = Self::get_total(ctx, USER_PENDING_QUERY, user.user_id).await?;
+
+        Ok(StorageUsage::new(
+            used,
+            pending,
+            user.storage_quota.or(config.maximum_user_storage),
+            user.storage_quota.is_some(),
+            config.storage_warning_percent,
+        ))
+    }
+
+    async fn site_usage(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+    ) -> Result<StorageUsage> {
+        let config = ctx.config();
+        let used = Self::get_total(ctx, SITE_USAGE_QUERY, site.site_id).await?;
+
+        Ok(StorageUsage::new(
+            used,
+            0,
+            site.storage_quota.or(config.maximum_site_storage),
+            site.storage_quota.is_some(),
+            config.storage_warning_percent,
+        ))
+    }
+
+    async fn get_total(ctx: &ServiceContext<'_>, query: &str, id: i64) -> Result<i64> {
+        let txn = ctx.transaction();
+        let row = txn
+            .query_one(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                query,
+                [id.into()],
+            ))
+            .await?;
+
+        match row {
+            Some(row) => Ok(row.try_get("", "total")?),
+            None => Ok(0),
+        }
+    }
+
+    fn check_quota(quota: Option<i64>) -> Result<()> {
+        match quota {
+            Some(quota) if quota < 0 => {
+                error!("Storage quota cannot be negative: {quota}");
+                Err(Error::StorageQuotaInvalid)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sends the site's staff a system message warning that it is nearing its storage quota.
+    async fn notify_site(
+        ctx: &ServiceContext<'_>,
+        site: &SiteModel,
+        used: i64,
+        quota: i64,
+    ) -> Result<()> {
+        info!(
+            "Notifying staff of site ID {} that it is using {used} of {quota} bytes",
+            site.site_id,
+        );
+
+        const MEBIBYTE: i64 = 1024 * 1024;
+
+        let site_user_id =
+            RelationService::get_site_user_id_for_site(ctx, site.site_id).await?;
+        let locales = [LanguageIdentifier::from_bytes(site.locale.as_bytes())?];
+
+        let mut args = FluentArgs::new();
+        args.set("site", fluent_str!(site.name));
+        args.set(
+            "percent",
+            FluentValue::from(used.saturating_mul(100) / quota),
+        );
+        args.set("used", FluentValue::from(used / MEBIBYTE));
+        args.set("quota", FluentValue::from(quota / MEBIBYTE));
+
+        let localization = ctx.localization();
+        let subject =
+            localization.translate(&locales, "emails-storage-warning.subject", &args)?;
+        let wikitext =
+            localization.translate(&locales, "emails-storage-warning.body", &args)?;
+
+        let draft = MessageService::create_draft(
+            ctx,
+            CreateMessageDraft {
+                user_id: SYSTEM_USER_ID,
+                recipients: vec![site_user_id],
+                carbon_copy: vec![],
+                blind_carbon_copy: vec![],
+                locale: site.locale.clone(),
+                subject: subject.into_owned(),
+                wikitext: wikitext.into_owned(),
+                reply_to: None,
+                forwarded_from: None,
+            },
+        )
+        .await?;
+
+        MessageService::send(ctx, &draft.external_id).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+/*
+ * services/storage_quota/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetUserStorageQuota<'a> {
+    pub user: Reference<'a>,
+
+    /// The quota in bytes, or `None` to use the configured default.
+    pub quota: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetSiteStorageQuota<'a> {
+    pub site: Reference<'a>,
+
+    /// The quota in bytes, or `None` to use the configured default.
+    pub quota: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Bytes used by file revisions, counting each blob once.
+    pub used: i64,
+
+    /// Bytes promised by uploads which have been started but not finished.
+    ///
+    /// Always zero for sites, since an upload is not tied to a site until it is used.
+    pub pending: i64,
+
+    /// The quota in bytes, or `None` if there is no limit.
+    pub quota: Option<i64>,
+
+    /// Whether the quota is specific to this user or site,
+    /// rather than the configured default.
+    pub quota_override: bool,
+
+    /// Whether usage is at or above the warning threshold.
+    pub warning: bool,
+}
+
+impl StorageUsage {
+    pub fn new(
+        used: i64,
+        pending: i64,
+        quota: Option<i64>,
+        quota_override: bool,
+        warning_percent: u8,
+    ) -> Self {
+        StorageUsage {
+            used,
+            pending,
+            quota,
+            quota_override,
+            warning: is_warning(used.saturating_add(pending), quota, warning_percent),
+        }
+    }
+
+    /// Determines if adding an upload of the given size would exceed the quota.
+    pub fn would_exceed(&self, size: i64) -> bool {
+        match self.quota {
+            None => false,
+            Some(quota) => {
+                self.used.saturating_add(self.pending).saturating_add(size) > quota
+            }
+        }
+    }
+}
+
+/// Determines if the given usage is close enough to the quota to warrant a warning.
+///
+/// A quota of zero disallows all uploads, so there is nothing to warn about.
+pub fn is_warning(used: i64, quota: Option<i64>, warning_percent: u8) -> bool {
+    match quota {
+        None | Some(0) => false,
+        Some(quota) => {
+            i128::from(used) * 100 >= i128::from(quota) * i128::from(warning_percent)
+        }
+    }
+}
+
+#[test]
+fn storage_usage() {
+    assert!(!is_warning(0, None, 90));
+    assert!(!is_warning(i64::MAX, None, 90));
+    assert!(!is_warning(100, Some(0), 90));
+    assert!(!is_warning(89, Some(100), 90));
+    assert!(is_warning(90, Some(100), 90));
+    assert!(is_warning(150, Some(100), 90));
+    assert!(is_warning(i64::MAX, Some(i64::MAX), 100));
+    assert!(!is_warning(i64::MAX - 1, Some(i64::MAX), 100));
+
+    let usage = StorageUsage::new(60, 20, Some(100), false, 90);
+    assert!(!usage.warning);
+    assert!(!usage.would_exceed(20));
+    assert!(usage.would_exceed(21));
+    assert!(usage.would_exceed(i64::MAX));
+
+    let usage = StorageUsage::new(95, 0, Some(100), true, 90);
+    assert!(usage.warning);
+
+    let usage = StorageUsage::new(i64::MAX, 0, None, false, 90);
+    assert!(!usage.warning);
+    assert!(!usage.would_exceed(i64::MAX));
+}
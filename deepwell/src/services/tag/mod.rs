@@ -0,0 +1,44 @@
+/*
+ * services/tag/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The tag service, for managing each site's tag configuration.
+//!
+//! A site can configure a list of allowed tags, optionally organized into
+//! groups. As soon as any tags are configured, only those tags (or their
+//! aliases, which are replaced with the tag they refer to) may be added to
+//! pages. Groups can also be exclusive, permitting at most one of their tags
+//! on a page, or required, needing at least one of their tags on each page.
+//!
+//! These rules are enforced whenever a page revision changes its tags.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::rules::{is_hidden_tag, is_valid_tag, TagRules};
+    pub use super::structs::*;
+}
+
+mod rules;
+mod service;
+mod structs;
+
+pub use self::rules::{is_hidden_tag, is_valid_tag, TagRules};
+pub use self::service::TagService;
+pub use self::structs::*;
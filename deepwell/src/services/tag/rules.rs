@@ -0,0 +1,220 @@
+/*
+ * services/tag/rules.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::site_tag::Model as SiteTagModel;
+use crate::models::site_tag_group::Model as SiteTagGroupModel;
+use std::collections::{HashMap, HashSet};
+
+/// The tag configuration for a site, in a form which tags can be checked against.
+#[derive(Debug, Default)]
+pub struct TagRules {
+    /// Whether only configured tags (and their aliases) may be used.
+    restricted: bool,
+
+    /// Maps every configured tag name and alias to its canonical tag name.
+    names: HashMap<String, String>,
+
+    /// The groups which have rules to enforce.
+    groups: Vec<TagGroupRule>,
+}
+
+#[derive(Debug)]
+struct TagGroupRule {
+    name: String,
+    exclusive: bool,
+    required: bool,
+    tags: HashSet<String>,
+}
+
+impl TagRules {
+    pub fn new(groups: &[SiteTagGroupModel], tags: &[SiteTagModel]) -> Self {
+        let mut names = HashMap::new();
+        for tag in tags {
+            names.insert(tag.name.clone(), tag.name.clone());
+
+            for alias in &tag.aliases {
+                names.insert(alias.clone(), tag.name.clone());
+            }
+        }
+
+        let groups = groups
+            .iter()
+            .filter(|group| group.exclusive || group.required)
+            .map(|group| TagGroupRule {
+                name: group.name.clone(),
+                exclusive: group.exclusive,
+                required: group.required,
+                tags: tags
+                    .iter()
+                    .filter(|tag| tag.tag_group_id == Some(group.tag_group_id))
+                    .map(|tag| tag.name.clone())
+                    .collect(),
+            })
+            .collect();
+
+        TagRules {
+            restricted: !tags.is_empty(),
+            names,
+            groups,
+        }
+    }
+
+    /// Checks a page's tags against these rules.
+    ///
+    /// Returns the normalized list of tags, with aliases
+    /// replaced by their canonical names and duplicates removed.
+    pub fn apply(&self, tags: Vec<String>) -> Result<Vec<String>> {
+        let mut output = Vec::with_capacity(tags.len());
+        let mut not_allowed = Vec::new();
+
+        for tag in tags {
+            let tag = tag.trim();
+            if tag.is_empty() {
+                continue;
+            }
+
+            if !is_valid_tag(tag) {
+                return Err(Error::TagInvalid(str!(tag)));
+            }
+
+            let tag = match self.names.get(tag) {
+                Some(name) => name.clone(),
+                None if self.restricted => {
+                    not_allowed.push(str!(tag));
+                    continue;
+                }
+                None => str!(tag),
+            };
+
+            if !output.contains(&tag) {
+                output.push(tag);
+            }
+        }
+
+        if !not_allowed.is_empty() {
+            return Err(Error::TagNotAllowed(not_allowed));
+        }
+
+        for group in &self.groups {
+            let present = output
+                .iter()
+                .filter(|tag| group.tags.contains(*tag))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if group.exclusive && present.len() > 1 {
+                return Err(Error::TagGroupExclusive {
+                    group: group.name.clone(),
+                    tags: present,
+                });
+            }
+
+            if group.required && present.is_empty() {
+                return Err(Error::TagGroupRequired(group.name.clone()));
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Determines if this is a valid tag name.
+///
+/// Tags are separated by spaces in wikitext, so they
+/// cannot contain any whitespace or control characters.
+pub fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty() && !tag.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// Determines if this is a hidden tag.
+///
+/// Like pages, tags beginning with `_` are hidden, and are
+/// not shown in a page's regular tag list.
+#[inline]
+pub fn is_hidden_tag(tag: &str) -> bool {
+    tag.starts_with('_')
+}
+
+#[test]
+fn test_tag_rules() {
+    fn group(name: &str, exclusive: bool, required: bool, tags: &[&str]) -> TagGroupRule {
+        TagGroupRule {
+            name: str!(name),
+            exclusive,
+            required,
+            tags: tags.iter().map(|tag| str!(tag)).collect(),
+        }
+    }
+
+    macro_rules! tags {
+        ($($tag:expr),* $(,)?) => {
+            vec![$(str!($tag)),*]
+        };
+    }
+
+    // Unrestricted
+    let rules = TagRules::default();
+    assert_eq!(
+        rules.apply(tags!["scp", " euclid ", "", "scp"]).unwrap(),
+        tags!["scp", "euclid"],
+    );
+    assert!(matches!(
+        rules.apply(tags!["two words"]),
+        Err(Error::TagInvalid(_)),
+    ));
+
+    // Restricted, with aliases and groups
+    let rules = TagRules {
+        restricted: true,
+        names: [
+            ("scp", "scp"),
+            ("tale", "tale"),
+            ("safe", "safe"),
+            ("euclid", "euclid"),
+            ("euclide", "euclid"),
+            ("_cc", "_cc"),
+        ]
+        .iter()
+        .map(|(name, canonical)| (str!(name), str!(canonical)))
+        .collect(),
+        groups: vec![
+            group("Type", false, true, &["scp", "tale"]),
+            group("Class", true, false, &["safe", "euclid"]),
+        ],
+    };
+
+    assert_eq!(
+        rules.apply(tags!["scp", "euclide", "_cc"]).unwrap(),
+        tags!["scp", "euclid", "_cc"],
+    );
+    assert!(matches!(
+        rules.apply(tags!["scp", "keter", "apollyon"]),
+        Err(Error::TagNotAllowed(tags)) if tags == tags!["keter", "apollyon"],
+    ));
+    assert!(matches!(
+        rules.apply(tags!["scp", "safe", "euclid"]),
+        Err(Error::TagGroupExclusive { tags, .. }) if tags == tags!["safe", "euclid"],
+    ));
+    assert!(matches!(
+        rules.apply(tags!["safe"]),
+        Err(Error::TagGroupRequired(group)) if group == "Type",
+    ));
+}
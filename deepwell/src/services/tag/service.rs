@@ -0,0 +1,569 @@
+/*
+ * services/tag/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::{self, Entity as Page};
+use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::site_tag::{self, Entity as SiteTag, Model as SiteTagModel};
+use crate::models::site_tag_group::{
+    self, Entity as SiteTagGroup, Model as SiteTagGroupModel,
+};
+use crate::services::page::{EditPage, EditPageBody};
+use crate::services::PageService;
+use sea_query::{Expr, Query};
+
+#[derive(Debug)]
+pub struct TagService;
+
+impl TagService {
+    // Tag groups
+
+    pub async fn create_group(
+        ctx: &ServiceContext<'_>,
+        CreateTagGroup {
+            site_id,
+            name,
+            description,
+            exclusive,
+            required,
+        }: CreateTagGroup,
+    ) -> Result<SiteTagGroupModel> {
+        info!("Creating tag group '{name}' in site ID {site_id}");
+
+        Self::check_group_conflicts(ctx, site_id, None, &name).await?;
+
+        let txn = ctx.transaction();
+        let model = site_tag_group::ActiveModel {
+            site_id: Set(site_id),
+            name: Set(name),
+            description: Set(description),
+            exclusive: Set(exclusive),
+            required: Set(required),
+            ..Default::default()
+        };
+
+        let group = model.insert(txn).await?;
+        Ok(group)
+    }
+
+    pub async fn update_group(
+        ctx: &ServiceContext<'_>,
+        UpdateTagGroup {
+            tag_group_id,
+            body:
+                UpdateTagGroupBody {
+                    name,
+                    description,
+                    exclusive,
+                    required,
+                },
+        }: UpdateTagGroup,
+    ) -> Result<SiteTagGroupModel> {
+        info!("Updating tag group ID {tag_group_id}");
+
+        let group = Self::get_group(ctx, tag_group_id).await?;
+        if let Maybe::Set(ref name) = name {
+            Self::check_group_conflicts(ctx, group.site_id, Some(tag_group_id), name)
+                .await?;
+        }
+
+        let txn = ctx.transaction();
+        let model = site_tag_group::ActiveModel {
+            tag_group_id: Set(tag_group_id),
+            updated_at: Set(Some(now())),
+            name: name.into_active_value(),
+            description: description.into_active_value(),
+            exclusive: exclusive.into_active_value(),
+            required: required.into_active_value(),
+            ..Default::default()
+        };
+
+        let group = model.update(txn).await?;
+        Ok(group)
+    }
+
+    /// Deletes a tag group.
+    ///
+    /// Any tags in the group remain allowed, but no longer belong to a group.
+    pub async fn delete_group(ctx: &ServiceContext<'_>, tag_group_id: i64) -> Result<()> {
+        info!("Deleting tag group ID {tag_group_id}");
+
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected, .. } =
+            SiteTagGroup::delete_by_id(tag_group_id).exec(txn).await?;
+
+        if rows_affected == 0 {
+            return Err(Error::TagGroupNotFound);
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_group_optional(
+        ctx: &ServiceContext<'_>,
+        tag_group_id: i64,
+    ) -> Result<Option<SiteTagGroupModel>> {
+        let txn = ctx.transaction();
+        let group = SiteTagGroup::find_by_id(tag_group_id).one(txn).await?;
+        Ok(group)
+    }
+
+    #[inline]
+    pub async fn get_group(
+        ctx: &ServiceContext<'_>,
+        tag_group_id: i64,
+    ) -> Result<SiteTagGroupModel> {
+        find_or_error!(Self::get_group_optional(ctx, tag_group_id), TagGroup)
+    }
+
+    async fn check_group_conflicts(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tag_group_id: Option<i64>,
+        name: &str,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let result = SiteTagGroup::find()
+            .filter(
+                Condition::all()
+                    .add(site_tag_group::Column::SiteId.eq(site_id))
+                    .add(site_tag_group::Column::Name.eq(name))
+                    .add_option(
+                        tag_group_id.map(|id| site_tag_group::Column::TagGroupId.ne(id)),
+                    ),
+            )
+            .one(txn)
+            .await?;
+
+        match result {
+            None => Ok(()),
+            Some(_) => {
+                error!("Tag group '{name}' already exists in site ID {site_id}");
+                Err(Error::TagGroupExists)
+            }
+        }
+    }
+
+    // Tags
+
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateTag {
+            site_id,
+            name,
+            tag_group_id,
+            description,
+            aliases,
+        }: CreateTag,
+    ) -> Result<SiteTagModel> {
+        info!("Creating tag '{name}' in site ID {site_id}");
+
+        let name = normalize_tag(name)?;
+        let aliases = normalize_aliases(&name, aliases)?;
+        Self::check_conflicts(ctx, site_id, None, &name, &aliases).await?;
+
+        if let Some(tag_group_id) = tag_group_id {
+            Self::check_group_site(ctx, site_id, tag_group_id).await?;
+        }
+
+        let txn = ctx.transaction();
+        let model = site_tag::ActiveModel {
+            site_id: Set(site_id),
+            tag_group_id: Set(tag_group_id),
+            name: Set(name),
+            description: Set(description),
+            aliases: Set(aliases),
+            ..Default::default()
+        };
+
+        let tag = model.insert(txn).await?;
+        Ok(tag)
+    }
+
+    pub async fn update(
+        ctx: &ServiceContext<'_>,
+        UpdateTag {
+            tag_id,
+            body:
+                UpdateTagBody {
+                    name,
+                    tag_group_id,
+                    description,
+                    aliases,
+                },
+        }: UpdateTag,
+    ) -> Result<SiteTagModel> {
+        info!("Updating tag ID {tag_id}");
+
+        let tag = Self::get(ctx, tag_id).await?;
+        let site_id = tag.site_id;
+
+        // Both are needed to check for conflicts, if either is changed
+        let (name, aliases) = match (name, aliases) {
+            (Maybe::Unset, Maybe::Unset) => (Maybe::Unset, Maybe::Unset),
+            (name, aliases) => {
+                let name = match name {
+                    Maybe::Set(name) => normalize_tag(name)?,
+                    Maybe::Unset => tag.name,
+                };
+                let aliases = match aliases {
+                    Maybe::Set(aliases) => aliases,
+                    Maybe::Unset => tag.aliases,
+                };
+                let aliases = normalize_aliases(&name, aliases)?;
+
+                Self::check_conflicts(ctx, site_id, Some(tag_id), &name, &aliases)
+                    .await?;
+
+                (Maybe::Set(name), Maybe::Set(aliases))
+            }
+        };
+
+        if let Maybe::Set(Some(tag_group_id)) = tag_group_id {
+            Self::check_group_site(ctx, site_id, tag_group_id).await?;
+        }
+
+        let txn = ctx.transaction();
+        let model = site_tag::ActiveModel {
+            tag_id: Set(tag_id),
+            updated_at: Set(Some(now())),
+            name: name.into_active_value(),
+            tag_group_id: tag_group_id.into_active_value(),
+            description: description.into_active_value(),
+            aliases: aliases.into_active_value(),
+            ..Default::default()
+        };
+
+        let tag = model.update(txn).await?;
+        Ok(tag)
+    }
+
+    /// Removes a tag from the site's configuration.
+    ///
+    /// This does not change any pages which already have this tag.
+    pub async fn delete(ctx: &ServiceContext<'_>, tag_id: i64) -> Result<()> {
+        info!("Deleting tag ID {tag_id}");
+
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected, .. } =
+            SiteTag::delete_by_id(tag_id).exec(txn).await?;
+
+        if rows_affected == 0 {
+            return Err(Error::TagNotFound);
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        tag_id: i64,
+    ) -> Result<Option<SiteTagModel>> {
+        let txn = ctx.transaction();
+        let tag = SiteTag::find_by_id(tag_id).one(txn).await?;
+        Ok(tag)
+    }
+
+    #[inline]
+    pub async fn get(ctx: &ServiceContext<'_>, tag_id: i64) -> Result<SiteTagModel> {
+        find_or_error!(Self::get_optional(ctx, tag_id), Tag)
+    }
+
+    /// Gets the full tag configuration for a site.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<GetSiteTagsOutput> {
+        let (groups, tags) = Self::get_site_config(ctx, site_id).await?;
+        let tags = tags
+            .into_iter()
+            .map(|tag| SiteTagOutput {
+                hidden: is_hidden_tag(&tag.name),
+                tag,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(GetSiteTagsOutput {
+            restricted: !tags.is_empty(),
+            groups,
+            tags,
+        })
+    }
+
+    /// Checks the given tags against the site's tag rules.
+    ///
+    /// Returns the normalized tags, with aliases replaced
+    /// by the tags they refer to.
+    pub async fn validate(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tags: Vec<String>,
+    ) -> Result<Vec<String>> {
+        debug!("Validating {} tags for site ID {site_id}", tags.len());
+
+        let (groups, site_tags) = Self::get_site_config(ctx, site_id).await?;
+        TagRules::new(&groups, &site_tags).apply(tags)
+    }
+
+    /// Renames a tag on every page in a site.
+    ///
+    /// If the tag is configured, then it is renamed there too, or merged
+    /// into the new tag if that already exists. Each page has a new revision
+    /// created, and any page whose new tags would violate the site's tag
+    /// rules is skipped instead.
+    pub async fn rename(
+        ctx: &ServiceContext<'_>,
+        RenameTag {
+            site_id,
+            user_id,
+            from,
+            to,
+            keep_alias,
+        }: RenameTag,
+    ) -> Result<RenameTagOutput> {
+        info!("Renaming tag '{from}' to '{to}' in site ID {site_id}");
+
+        let to = normalize_tag(to)?;
+        if from == to {
+            return Ok(RenameTagOutput {
+                renamed: 0,
+                skipped: vec![],
+            });
+        }
+
+        Self::rename_config(ctx, site_id, &from, &to, keep_alias).await?;
+
+        // Find all pages currently using the old tag
+        let txn = ctx.transaction();
+        let revisions: Vec<(i64, i64, Vec<String>)> = PageRevision::find()
+            .select_only()
+            .column(page_revision::Column::PageId)
+            .column(page_revision::Column::RevisionId)
+            .column(page_revision::Column::Tags)
+            .filter(
+                Condition::all()
+                    .add(
+                        page_revision::Column::RevisionId.in_subquery(
+                            Query::select()
+                                .column(page::Column::LatestRevisionId)
+                                .from(Page)
+                                .and_where(page::Column::SiteId.eq(site_id))
+                                .and_where(page::Column::DeletedAt.is_null())
+                                .to_owned(),
+                        ),
+                    )
+                    .add(Expr::cust_with_values("$1 = ANY(tags)", [from.clone()])),
+            )
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let mut renamed = 0;
+        let mut skipped = Vec::new();
+
+        for (page_id, revision_id, tags) in revisions {
+            let tags = tags
+                .into_iter()
+                .map(|tag| if tag == from { to.clone() } else { tag })
+                .collect();
+
+            let input = EditPage {
+                site_id,
+                page: Reference::Id(page_id),
+                last_revision_id: revision_id,
+                revision_comments: format!("Renamed tag '{from}' to '{to}'"),
+                user_id,
+                body: EditPageBody {
+                    tags: Maybe::Set(tags),
+                    ..Default::default()
+                },
+            };
+
+            // Tag rules are checked before anything is written,
+            // so a page which fails them can simply be skipped.
+            match PageService::edit(ctx, input).await {
+                Ok(_) => renamed += 1,
+                Err(
+                    error @ (Error::TagInvalid(_)
+                    | Error::TagNotAllowed(_)
+                    | Error::TagGroupExclusive { .. }
+                    | Error::TagGroupRequired(_)),
+                ) => {
+                    warn!("Skipping tag rename for page ID {page_id}: {error}");
+                    skipped.push(page_id);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(RenameTagOutput { renamed, skipped })
+    }
+
+    /// Renames a tag in the site's configuration, if it is configured.
+    async fn rename_config(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        from: &str,
+        to: &str,
+        keep_alias: bool,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let (_, tags) = Self::get_site_config(ctx, site_id).await?;
+        let source = match tags.iter().find(|tag| tag.name == from) {
+            Some(tag) => tag,
+            None => return Ok(()),
+        };
+
+        let mut aliases = source.aliases.clone();
+        if keep_alias {
+            aliases.push(str!(from));
+        }
+
+        match tags.iter().find(|tag| tag.name == to) {
+            // Merge into the existing tag
+            Some(target) => {
+                debug!("Merging tag '{from}' into existing tag '{to}'");
+                aliases.extend(target.aliases.iter().cloned());
+                let aliases = normalize_aliases(to, aliases)?;
+
+                SiteTag::delete_by_id(source.tag_id).exec(txn).await?;
+                let model = site_tag::ActiveModel {
+                    tag_id: Set(target.tag_id),
+                    updated_at: Set(Some(now())),
+                    aliases: Set(aliases),
+                    ..Default::default()
+                };
+                model.update(txn).await?;
+            }
+
+            // Rename the tag itself
+            None => {
+                debug!("Renaming configured tag '{from}' to '{to}'");
+                let aliases = normalize_aliases(to, aliases)?;
+                Self::check_conflicts(ctx, site_id, Some(source.tag_id), to, &aliases)
+                    .await?;
+
+                let model = site_tag::ActiveModel {
+                    tag_id: Set(source.tag_id),
+                    updated_at: Set(Some(now())),
+                    name: Set(str!(to)),
+                    aliases: Set(aliases),
+                    ..Default::default()
+                };
+                model.update(txn).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets all the tag groups and tags configured for a site.
+    async fn get_site_config(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<(Vec<SiteTagGroupModel>, Vec<SiteTagModel>)> {
+        let txn = ctx.transaction();
+        let groups = SiteTagGroup::find()
+            .filter(site_tag_group::Column::SiteId.eq(site_id))
+            .order_by_asc(site_tag_group::Column::Name)
+            .all(txn)
+            .await?;
+
+        let tags = SiteTag::find()
+            .filter(site_tag::Column::SiteId.eq(site_id))
+            .order_by_asc(site_tag::Column::Name)
+            .all(txn)
+            .await?;
+
+        Ok((groups, tags))
+    }
+
+    /// Ensures no other tag in the site uses any of these names.
+    async fn check_conflicts(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tag_id: Option<i64>,
+        name: &str,
+        aliases: &[String],
+    ) -> Result<()> {
+        let (_, tags) = Self::get_site_config(ctx, site_id).await?;
+        let conflict = tags
+            .iter()
+            .filter(|tag| Some(tag.tag_id) != tag_id)
+            .find(|tag| {
+                let names =
+                    || std::iter::once(name).chain(aliases.iter().map(String::as_str));
+
+                names().any(|name| name == tag.name)
+                    || names().any(|name| tag.aliases.iter().any(|alias| alias == name))
+            });
+
+        match conflict {
+            None => Ok(()),
+            Some(tag) => {
+                error!(
+                    "Tag '{name}' conflicts with existing tag '{}' in site ID {site_id}",
+                    tag.name,
+                );
+                Err(Error::TagExists)
+            }
+        }
+    }
+
+    /// Ensures the tag group exists and belongs to the given site.
+    async fn check_group_site(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        tag_group_id: i64,
+    ) -> Result<()> {
+        let group = Self::get_group(ctx, tag_group_id).await?;
+        if group.site_id != site_id {
+            error!(
+                "Tag group ID {tag_group_id} is in site ID {}, not {site_id}",
+                group.site_id,
+            );
+            return Err(Error::TagGroupNotFound);
+        }
+
+        Ok(())
+    }
+}
+
+fn normalize_tag(tag: String) -> Result<String> {
+    let tag = tag.trim();
+    if !is_valid_tag(tag) {
+        error!("Tag name '{tag}' is invalid");
+        return Err(Error::TagInvalid(str!(tag)));
+    }
+
+    Ok(str!(tag))
+}
+
+fn normalize_aliases(name: &str, aliases: Vec<String>) -> Result<Vec<String>> {
+    let mut output: Vec<String> = Vec::with_capacity(aliases.len());
+    for alias in aliases {
+        let alias = normalize_tag(alias)?;
+        if alias != name && !output.contains(&alias) {
+            output.push(alias);
+        }
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,145 @@
+/*
+ * services/tag/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::site_tag::Model as SiteTagModel;
+use crate::models::site_tag_group::Model as SiteTagGroupModel;
+use crate::types::Maybe;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateTagGroup {
+    pub site_id: i64,
+    pub name: String,
+    pub description: String,
+
+    #[serde(default)]
+    pub exclusive: bool,
+
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateTagGroup {
+    pub tag_group_id: i64,
+
+    #[serde(flatten)]
+    pub body: UpdateTagGroupBody,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct UpdateTagGroupBody {
+    pub name: Maybe<String>,
+    pub description: Maybe<String>,
+    pub exclusive: Maybe<bool>,
+    pub required: Maybe<bool>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetTagGroup {
+    pub tag_group_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateTag {
+    pub site_id: i64,
+    pub name: String,
+
+    #[serde(default)]
+    pub tag_group_id: Option<i64>,
+
+    #[serde(default)]
+    pub description: String,
+
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateTag {
+    pub tag_id: i64,
+
+    #[serde(flatten)]
+    pub body: UpdateTagBody,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct UpdateTagBody {
+    pub name: Maybe<String>,
+    pub tag_group_id: Maybe<Option<i64>>,
+    pub description: Maybe<String>,
+    pub aliases: Maybe<Vec<String>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetTag {
+    pub tag_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetSiteTags {
+    pub site_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetSiteTagsOutput {
+    /// Whether only the configured tags may be used on this site.
+    ///
+    /// This is the case as soon as any tags are configured.
+    pub restricted: bool,
+    pub groups: Vec<SiteTagGroupModel>,
+    pub tags: Vec<SiteTagOutput>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SiteTagOutput {
+    #[serde(flatten)]
+    pub tag: SiteTagModel,
+    pub hidden: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ValidateTags {
+    pub site_id: i64,
+    pub tags: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RenameTag {
+    pub site_id: i64,
+    pub user_id: i64,
+    pub from: String,
+    pub to: String,
+
+    /// Whether to keep the old name as an alias of the new one.
+    #[serde(default)]
+    pub keep_alias: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RenameTagOutput {
+    /// How many pages had the tag renamed.
+    pub renamed: u64,
+
+    /// Pages which still have the old tag, because the
+    /// new tags would have violated the site's tag rules.
+    pub skipped: Vec<i64>,
+}
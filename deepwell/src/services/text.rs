@@ -23,14 +23,82 @@
 //!
 //! It uses content-addressable storage, meaning that data is uniquely
 //! identified by its hash.
+//!
+//! Text is stored zstd-compressed where this saves space. Text can also
+//! be stored as a delta against a "base" text, which is compressed using
+//! the base as a dictionary. This is used for page revisions, where each
+//! revision is usually nearly identical to the previous one.
+//!
+//! Base texts are never deltas themselves, so decoding a row requires
+//! at most one other row. All of this is transparent to callers, who
+//! only ever see the original string.
 
 use super::prelude::*;
-use crate::hash::{k12_hash, TextHash, TEXT_HASH_LENGTH};
+use crate::hash::{k12_hash, HashVerification, TextHash, TEXT_HASH_LENGTH};
+use crate::models::forum_post_revision::{self, Entity as ForumPostRevision};
 use crate::models::message_draft::{self, Entity as MessageDraft};
 use crate::models::message_record::{self, Entity as MessageRecord};
+use crate::models::page_draft::{self, Entity as PageDraft};
 use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::page_schedule::{self, Entity as PageSchedule};
 use crate::models::text::{self, Entity as Text};
 use sea_query::Query;
+use std::ops::AddAssign;
+use zstd::bulk::{Compressor, Decompressor};
+
+/// Text shorter than this many bytes is always stored as-is.
+///
+/// Compression has a fixed overhead, so small strings
+/// are not worth the effort of compressing.
+const MINIMUM_COMPRESS_LENGTH: usize = 128;
+
+/// How many rows to process at once when compacting text.
+const COMPACT_BATCH_SIZE: u64 = 100;
+
+/// How the contents of a text row are stored.
+#[derive(Debug)]
+enum TextEncoding {
+    /// Stored as-is, in `contents`.
+    Plain,
+
+    /// Stored compressed, in `compressed_contents`.
+    Compressed(Vec<u8>),
+
+    /// Stored compressed in `compressed_contents`, using the text
+    /// at `base_hash` as the dictionary.
+    Delta { base_hash: Vec<u8>, data: Vec<u8> },
+}
+
+impl TextEncoding {
+    fn stored_len(&self, contents: &str) -> usize {
+        match self {
+            TextEncoding::Plain => contents.len(),
+            TextEncoding::Compressed(data) => data.len(),
+            TextEncoding::Delta { data, .. } => data.len(),
+        }
+    }
+}
+
+/// Summary of text rows rewritten during compaction.
+#[derive(Serialize, Debug, Default, Copy, Clone)]
+pub struct TextCompaction {
+    /// How many rows were compressed.
+    pub rows: u64,
+
+    /// The total size of those rows before compaction, in bytes.
+    pub original_bytes: u64,
+
+    /// The total size of those rows after compaction, in bytes.
+    pub stored_bytes: u64,
+}
+
+impl AddAssign for TextCompaction {
+    fn add_assign(&mut self, other: TextCompaction) {
+        self.rows += other.rows;
+        self.original_bytes += other.original_bytes;
+        self.stored_bytes += other.stored_bytes;
+    }
+}
 
 #[derive(Debug)]
 pub struct TextService;
@@ -50,13 +118,15 @@ impl TextService {
         }
 
         let txn = ctx.transaction();
-        let contents = Text::find()
+        let model = Text::find()
             .filter(text::Column::Hash.eq(hash))
             .one(txn)
-            .await?
-            .map(|model| model.contents);
+            .await?;
 
-        Ok(contents)
+        match model {
+            Some(model) => Self::decode(ctx, model).await.map(Some),
+            None => Ok(None),
+        }
     }
 
     #[inline]
@@ -64,11 +134,14 @@ impl TextService {
         find_or_error!(Self::get_optional(ctx, hash), Text)
     }
 
-    #[inline]
     pub async fn exists(ctx: &ServiceContext<'_>, hash: &[u8]) -> Result<bool> {
-        Self::get_optional(ctx, hash)
-            .await
-            .map(|text| text.is_some())
+        let txn = ctx.transaction();
+        let count = Text::find()
+            .filter(text::Column::Hash.eq(hash))
+            .count(txn)
+            .await?;
+
+        Ok(count > 0)
     }
 
     /// Possibly retrieve text, if a flag is set.
@@ -91,27 +164,284 @@ impl TextService {
     }
 
     /// Creates a text entry with this data, if it does not already exist.
+    #[inline]
     pub async fn create(ctx: &ServiceContext<'_>, contents: String) -> Result<TextHash> {
+        Self::create_inner(ctx, contents, None).await
+    }
+
+    /// Creates a text entry with this data, possibly as a delta against another text.
+    ///
+    /// The base should be a similar text, such as the wikitext of the
+    /// previous revision. It is only used if delta encoding is enabled
+    /// and the delta is smaller than compressing the text on its own.
+    #[inline]
+    pub async fn create_with_base(
+        ctx: &ServiceContext<'_>,
+        contents: String,
+        base_hash: &[u8],
+    ) -> Result<TextHash> {
+        Self::create_inner(ctx, contents, Some(base_hash)).await
+    }
+
+    async fn create_inner(
+        ctx: &ServiceContext<'_>,
+        contents: String,
+        base_hash: Option<&[u8]>,
+    ) -> Result<TextHash> {
         let txn = ctx.transaction();
         let hash = k12_hash(contents.as_bytes());
 
         if !Self::exists(ctx, &hash).await? {
-            let model = text::ActiveModel {
-                hash: Set(hash.to_vec()),
-                contents: Set(contents),
-            };
-
+            let encoding = Self::encode(ctx, &hash, &contents, base_hash).await?;
+            let model = build_model(&hash, contents, encoding);
             Text::insert(model).exec(txn).await?;
         }
 
         Ok(hash)
     }
 
+    /// Determines the smallest way to store this text.
+    async fn encode(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        contents: &str,
+        base_hash: Option<&[u8]>,
+    ) -> Result<TextEncoding> {
+        if contents.len() < MINIMUM_COMPRESS_LENGTH {
+            return Ok(TextEncoding::Plain);
+        }
+
+        let config = ctx.config();
+        let level = config.text_compression_level;
+        let mut encoding =
+            TextEncoding::Compressed(compress_text(contents, level, None)?);
+
+        if config.text_delta_encoding {
+            if let Some(base_hash) = base_hash {
+                if let Some((base_hash, base)) =
+                    Self::get_base(ctx, base_hash, hash).await?
+                {
+                    let data = compress_text(contents, level, Some(&base))?;
+                    if data.len() < encoding.stored_len(contents) {
+                        encoding = TextEncoding::Delta { base_hash, data };
+                    }
+                }
+            }
+        }
+
+        if encoding.stored_len(contents) >= contents.len() {
+            return Ok(TextEncoding::Plain);
+        }
+
+        Ok(encoding)
+    }
+
+    /// Gets the text which can be used as a base for a delta against the given text.
+    ///
+    /// Since bases cannot be deltas themselves, if the given text is one,
+    /// then its own base is used instead. Returns `None` if there is no
+    /// suitable base, such as if it would be the text being stored itself.
+    async fn get_base(
+        ctx: &ServiceContext<'_>,
+        base_hash: &[u8],
+        hash: &[u8],
+    ) -> Result<Option<(Vec<u8>, String)>> {
+        // The base is locked until the transaction ends, so that
+        // compaction cannot turn it into a delta in the meantime.
+        let txn = ctx.transaction();
+        let model = match Text::find_by_id(base_hash.to_vec())
+            .lock_shared()
+            .one(txn)
+            .await?
+        {
+            Some(model) => model,
+            None => return Ok(None),
+        };
+
+        let model = match model.base_hash {
+            Some(ref root_hash) => Text::find_by_id(root_hash.clone())
+                .lock_shared()
+                .one(txn)
+                .await?
+                .ok_or(Error::TextCorrupted)?,
+            None => model,
+        };
+
+        if model.hash == hash {
+            return Ok(None);
+        }
+
+        let base_hash = model.hash.clone();
+        let base = decode_standalone(model)?;
+        Ok(Some((base_hash, base)))
+    }
+
+    /// Gets the original contents of a text row, fetching its base if needed.
+    async fn decode(ctx: &ServiceContext<'_>, model: text::Model) -> Result<String> {
+        let base_hash = match model.base_hash {
+            Some(ref base_hash) => base_hash.clone(),
+            None => return decode_standalone(model),
+        };
+
+        let txn = ctx.transaction();
+        let base = match Text::find_by_id(base_hash).one(txn).await? {
+            Some(base) => decode_standalone(base)?,
+            None => {
+                error!(
+                    "Base text for text row {} does not exist",
+                    hex::encode(&model.hash),
+                );
+                return Err(Error::TextCorrupted);
+            }
+        };
+
+        match model.compressed_contents {
+            Some(ref data) => decompress_text(data, model.size, Some(&base)),
+            None => {
+                error!(
+                    "Text row {} has a base but no compressed contents",
+                    hex::encode(&model.hash),
+                );
+                Err(Error::TextCorrupted)
+            }
+        }
+    }
+
+    /// Compresses the text for all the revisions of a page.
+    ///
+    /// Each revision is stored as a delta against the text of the revision
+    /// before it, where that is smaller. Only text stored as-is is changed,
+    /// so this is safe to run again, such as after it is interrupted.
+    pub async fn compact_page(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<TextCompaction> {
+        info!("Compacting text for all revisions of page ID {page_id}");
+
+        let txn = ctx.transaction();
+        let revisions: Vec<(Vec<u8>, Vec<u8>)> = PageRevision::find()
+            .select_only()
+            .column(page_revision::Column::WikitextHash)
+            .column(page_revision::Column::CompiledHash)
+            .filter(page_revision::Column::PageId.eq(page_id))
+            .order_by_asc(page_revision::Column::RevisionNumber)
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        let mut output = TextCompaction::default();
+        let mut previous: Option<(Vec<u8>, Vec<u8>)> = None;
+
+        for (wikitext_hash, compiled_hash) in revisions {
+            let (previous_wikitext, previous_compiled) = match previous {
+                Some((ref wikitext, ref compiled)) => {
+                    (Some(wikitext.as_slice()), Some(compiled.as_slice()))
+                }
+                None => (None, None),
+            };
+
+            output += Self::compact_row(ctx, &wikitext_hash, previous_wikitext).await?;
+            output += Self::compact_row(ctx, &compiled_hash, previous_compiled).await?;
+            previous = Some((wikitext_hash, compiled_hash));
+        }
+
+        Ok(output)
+    }
+
+    /// Compresses all text rows still stored as-is.
+    ///
+    /// This is for text not belonging to a page revision, or which
+    /// was otherwise missed by `compact_page()`. No deltas are used.
+    pub async fn compact_remaining(ctx: &ServiceContext<'_>) -> Result<TextCompaction> {
+        info!("Compacting all remaining uncompressed text");
+
+        let txn = ctx.transaction();
+        let minimum_size = i64::try_from(MINIMUM_COMPRESS_LENGTH).unwrap_or(i64::MAX);
+        let mut output = TextCompaction::default();
+        let mut last_hash = Vec::new();
+
+        loop {
+            let hashes: Vec<Vec<u8>> = Text::find()
+                .select_only()
+                .column(text::Column::Hash)
+                .filter(
+                    Condition::all()
+                        .add(text::Column::Contents.is_not_null())
+                        .add(text::Column::Size.gte(minimum_size))
+                        .add(text::Column::Hash.gt(last_hash)),
+                )
+                .order_by_asc(text::Column::Hash)
+                .limit(COMPACT_BATCH_SIZE)
+                .into_tuple()
+                .all(txn)
+                .await?;
+
+            last_hash = match hashes.last() {
+                Some(hash) => hash.clone(),
+                None => break,
+            };
+
+            for hash in hashes {
+                output += Self::compact_row(ctx, &hash, None).await?;
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Compresses one text row, if it is currently stored as-is.
+    async fn compact_row(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        base_hash: Option<&[u8]>,
+    ) -> Result<TextCompaction> {
+        // Lock the row first, so any text being created as a delta against
+        // it is committed before checking whether it is a base, and any
+        // created afterwards sees it as a delta and uses its base instead.
+        let txn = ctx.transaction();
+        let model = Text::find_by_id(hash.to_vec())
+            .lock_exclusive()
+            .one(txn)
+            .await?
+            .ok_or(Error::TextNotFound)?;
+
+        let contents = match model.contents {
+            Some(contents) if contents.len() >= MINIMUM_COMPRESS_LENGTH => contents,
+            _ => return Ok(TextCompaction::default()),
+        };
+
+        // If other rows are already deltas against this one,
+        // then it must not become a delta itself.
+        let is_base = Text::find()
+            .filter(text::Column::BaseHash.eq(hash))
+            .count(txn)
+            .await?
+            > 0;
+
+        let base_hash = if is_base { None } else { base_hash };
+        let encoding = Self::encode(ctx, hash, &contents, base_hash).await?;
+        if let TextEncoding::Plain = encoding {
+            return Ok(TextCompaction::default());
+        }
+
+        let original_bytes = contents.len() as u64;
+        let stored_bytes = encoding.stored_len(&contents) as u64;
+        build_model(hash, contents, encoding).update(txn).await?;
+
+        Ok(TextCompaction {
+            rows: 1,
+            original_bytes,
+            stored_bytes,
+        })
+    }
+
     /// Searches for any text rows which are unused.
     ///
     /// This is rare, but can happen when text is invalidated,
     /// such as rerendering pages.
-    pub async fn prune(ctx: &ServiceContext<'_>) -> Result<()> {
+    ///
+    /// Returns the number of rows which were removed.
+    pub async fn prune(ctx: &ServiceContext<'_>) -> Result<u64> {
         macro_rules! not_in_column {
             ($table:expr, $column:expr $(,)?) => {
                 text::Column::Hash.not_in_subquery(
@@ -123,6 +453,10 @@ impl TextService {
         // All foreign keys of text.hash should have conditions here.
         // These foreign key constraints prevent us from deleting anything
         // actually used.
+        //
+        // This includes text.base_hash itself, since a text may only be
+        // used as the base of a delta, such as the wikitext of a revision
+        // which was later changed.
         let txn = ctx.transaction();
         let DeleteResult { rows_affected, .. } = Text::delete_many()
             .filter(
@@ -135,6 +469,7 @@ impl TextService {
                         PageRevision,
                         page_revision::Column::CompiledHash,
                     ))
+                    .add(not_in_column!(PageDraft, page_draft::Column::WikitextHash,))
                     .add(not_in_column!(
                         MessageDraft,
                         message_draft::Column::WikitextHash,
@@ -150,13 +485,178 @@ impl TextService {
                     .add(not_in_column!(
                         MessageRecord,
                         message_record::Column::CompiledHash,
-                    )),
-                // TODO add forum_post_revision
+                    ))
+                    .add(not_in_column!(
+                        ForumPostRevision,
+                        forum_post_revision::Column::WikitextHash,
+                    ))
+                    .add(not_in_column!(
+                        ForumPostRevision,
+                        forum_post_revision::Column::CompiledHash,
+                    ))
+                    .add(
+                        // Nullable column, NOT IN fails on any NULLs
+                        text::Column::Hash.not_in_subquery(
+                            Query::select()
+                                .column(page_schedule::Column::WikitextHash)
+                                .from(PageSchedule)
+                                .and_where(
+                                    page_schedule::Column::WikitextHash.is_not_null(),
+                                )
+                                .to_owned(),
+                        ),
+                    )
+                    .add(
+                        text::Column::Hash.not_in_subquery(
+                            Query::select()
+                                .column(text::Column::BaseHash)
+                                .from(Text)
+                                .and_where(text::Column::BaseHash.is_not_null())
+                                .to_owned(),
+                        ),
+                    ),
             )
             .exec(txn)
             .await?;
 
         debug!("Pruned {rows_affected} unused text rows");
-        Ok(())
+        Ok(rows_affected)
+    }
+
+    /// Checks that all text rows still match their hashes.
+    ///
+    /// Since text is content-addressed, a mismatch means the row
+    /// has been corrupted or modified outside of DEEPWELL.
+    pub async fn verify(ctx: &ServiceContext<'_>) -> Result<HashVerification> {
+        info!("Verifying hashes of all text rows");
+
+        let txn = ctx.transaction();
+        let mut output = HashVerification::default();
+        let mut text_chunks = Text::find()
+            .order_by_asc(text::Column::Hash)
+            .paginate(txn, 100);
+
+        while let Some(texts) = text_chunks.fetch_and_next().await? {
+            for text in texts {
+                output.checked += 1;
+
+                let hash = text.hash.clone();
+                let matches = match Self::decode(ctx, text).await {
+                    Ok(contents) => k12_hash(contents.as_bytes()) == *hash,
+                    Err(_) => false,
+                };
+
+                if !matches {
+                    let hex_hash = hex::encode(&hash);
+                    error!("Text row {hex_hash} does not match its hash");
+                    output.mismatched.push(hex_hash);
+                }
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Builds the row for a text with the given encoding.
+fn build_model(
+    hash: &[u8],
+    contents: String,
+    encoding: TextEncoding,
+) -> text::ActiveModel {
+    let size = i64::try_from(contents.len()).expect("Text size exceeds i64");
+    let (contents, compressed_contents, base_hash) = match encoding {
+        TextEncoding::Plain => (Some(contents), None, None),
+        TextEncoding::Compressed(data) => (None, Some(data), None),
+        TextEncoding::Delta { base_hash, data } => (None, Some(data), Some(base_hash)),
+    };
+
+    text::ActiveModel {
+        hash: Set(hash.to_vec()),
+        contents: Set(contents),
+        compressed_contents: Set(compressed_contents),
+        base_hash: Set(base_hash),
+        size: Set(size),
     }
 }
+
+/// Gets the original contents of a text row which is not a delta.
+fn decode_standalone(model: text::Model) -> Result<String> {
+    match model {
+        text::Model {
+            base_hash: None,
+            contents: Some(contents),
+            ..
+        } => Ok(contents),
+        text::Model {
+            base_hash: None,
+            compressed_contents: Some(ref data),
+            size,
+            ..
+        } => decompress_text(data, size, None),
+        _ => {
+            error!(
+                "Text row {} cannot be decoded without a base",
+                hex::encode(&model.hash),
+            );
+            Err(Error::TextCorrupted)
+        }
+    }
+}
+
+/// Compresses text, optionally using another text as the dictionary.
+fn compress_text(contents: &str, level: i32, base: Option<&str>) -> Result<Vec<u8>> {
+    let mut compressor = match base {
+        Some(base) => Compressor::with_dictionary(level, base.as_bytes())?,
+        None => Compressor::new(level)?,
+    };
+
+    let data = compressor.compress(contents.as_bytes())?;
+    Ok(data)
+}
+
+/// Decompresses text produced by `compress_text()`.
+///
+/// The size is that of the original text, and the base
+/// must be the same dictionary it was compressed with.
+fn decompress_text(data: &[u8], size: i64, base: Option<&str>) -> Result<String> {
+    let capacity = usize::try_from(size).map_err(|_| Error::TextCorrupted)?;
+    let mut decompressor = match base {
+        Some(base) => Decompressor::with_dictionary(base.as_bytes())?,
+        None => Decompressor::new()?,
+    };
+
+    let bytes = decompressor.decompress(data, capacity).map_err(|error| {
+        error!("Unable to decompress text: {error}");
+        Error::TextCorrupted
+    })?;
+
+    String::from_utf8(bytes).map_err(|_| {
+        error!("Decompressed text is not valid UTF-8");
+        Error::TextCorrupted
+    })
+}
+
+#[test]
+fn test_compress_text() {
+    let base = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(20);
+    let contents = format!("{base}Sed do eiusmod tempor incididunt ut labore.");
+    let size = i64::try_from(contents.len()).unwrap();
+
+    // Without a base
+    let data = compress_text(&contents, 3, None).expect("Unable to compress");
+    assert!(data.len() < contents.len());
+    let output = decompress_text(&data, size, None).expect("Unable to decompress");
+    assert_eq!(output, contents);
+
+    // As a delta
+    let delta = compress_text(&contents, 3, Some(&base)).expect("Unable to compress");
+    assert!(delta.len() < data.len());
+    let output =
+        decompress_text(&delta, size, Some(&base)).expect("Unable to decompress");
+    assert_eq!(output, contents);
+
+    // Wrong base
+    let result = decompress_text(&delta, size, None);
+    assert!(result.is_err());
+}
@@ -21,12 +21,15 @@
 use super::prelude::*;
 use crate::models::sea_orm_active_enums::{AliasType, UserType};
 use crate::models::user::{self, Entity as User, Model as UserModel};
+use crate::models::user_name_change::{
+    self, Entity as UserNameChange, Model as UserNameChangeModel,
+};
 use crate::services::alias::CreateAlias;
 use crate::services::blob::{BlobService, FinalizeBlobUploadOutput};
 use crate::services::email::{EmailClassification, EmailService};
 use crate::services::filter::{FilterClass, FilterType};
 use crate::services::{AliasService, FilterService, PasswordService};
-use crate::utils::regex_replace_in_place;
+use crate::utils::{get_regular_slug, regex_replace_in_place};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use sea_orm::ActiveValue;
@@ -256,11 +259,25 @@ impl UserService {
             .map(|user| user.is_some())
     }
 
+    #[inline]
     pub async fn get_optional(
         ctx: &ServiceContext<'_>,
-        mut reference: Reference<'_>,
+        reference: Reference<'_>,
     ) -> Result<Option<UserModel>> {
+        let (user, _) = Self::get_optional_aliased(ctx, reference).await?;
+        Ok(user)
+    }
+
+    /// Like `get_optional()`, but also returns if the user was found via a user alias.
+    ///
+    /// This means the slug passed in is a former name of the user, and
+    /// callers may want to redirect to the user's current slug.
+    pub async fn get_optional_aliased(
+        ctx: &ServiceContext<'_>,
+        mut reference: Reference<'_>,
+    ) -> Result<(Option<UserModel>, bool)> {
         let txn = ctx.transaction();
+        let mut aliased = false;
 
         // If slug, determine if this is a user alias.
         //
@@ -279,6 +296,7 @@ impl UserService {
                 // Rewrite reference so in the "real" user search
                 // we locate directly via user ID.
                 reference = Reference::Id(alias.target_id);
+                aliased = true;
             }
         }
 
@@ -296,7 +314,7 @@ impl UserService {
             }
         };
 
-        Ok(user)
+        Ok((user, aliased))
     }
 
     #[inline]
@@ -359,7 +377,16 @@ impl UserService {
 
         // Add each field
         if let Maybe::Set(name) = input.name {
-            Self::update_name(ctx, name, &user, &mut model, input.bypass_filter).await?;
+            let changed_by = input.changed_by.unwrap_or(user.user_id);
+            Self::update_name(
+                ctx,
+                name,
+                &user,
+                &mut model,
+                changed_by,
+                input.bypass_filter,
+            )
+            .await?;
         }
 
         if let Maybe::Set(email) = input.email {
@@ -421,14 +448,23 @@ impl UserService {
             model.user_page = Set(user_page);
         }
 
+        if let Maybe::Set(dm_contacts_only) = input.dm_contacts_only {
+            model.dm_contacts_only = Set(dm_contacts_only);
+        }
+
         if let Maybe::Set(uploaded_blob_id) = input.avatar_uploaded_blob_id {
             let s3_hash = match uploaded_blob_id {
                 None => None,
                 Some(uploaded_blob_id) => {
                     let config = ctx.config();
                     let FinalizeBlobUploadOutput { hash, size, .. } =
-                        BlobService::finish_upload(ctx, user.user_id, &uploaded_blob_id)
-                            .await?;
+                        BlobService::finish_upload(
+                            ctx,
+                            user.user_id,
+                            &uploaded_blob_id,
+                            None,
+                        )
+                        .await?;
 
                     if size > config.maximum_avatar_size {
                         error!(
@@ -473,6 +509,7 @@ impl UserService {
         new_name: String,
         user: &UserModel,
         model: &mut user::ActiveModel,
+        changed_by: i64,
         bypass_filter: bool,
     ) -> Result<()> {
         // Regardless of the number of name change tokens,
@@ -499,6 +536,12 @@ impl UserService {
 
             // Set model, but return early, we don't deduct a
             // name change token or create a new user alias.
+            if new_name != user.name {
+                Self::add_name_change(ctx, user, changed_by, &new_name, &new_slug, false)
+                    .await?;
+                model.last_renamed_at = Set(Some(now()));
+            }
+
             model.name = Set(new_name);
             return Ok(());
         }
@@ -510,8 +553,11 @@ impl UserService {
 
             // Swap user alias for old slug
             AliasService::swap(ctx, alias.alias_id, old_slug).await?;
+            Self::add_name_change(ctx, user, changed_by, &new_name, &new_slug, false)
+                .await?;
 
             // Set model, but return early, we don't deduct a name change token
+            model.last_renamed_at = Set(Some(now()));
             model.name = Set(new_name);
             model.slug = Set(new_slug);
 
@@ -553,7 +599,10 @@ impl UserService {
             old_slug, new_slug,
         );
 
+        Self::add_name_change(ctx, user, changed_by, &new_name, &new_slug, true).await?;
+
         model.name_changes_left = Set(user.name_changes_left - 1);
+        model.last_renamed_at = Set(Some(now()));
         model.name = Set(new_name);
         model.slug = Set(new_slug);
 
@@ -573,6 +622,67 @@ impl UserService {
         Ok(())
     }
 
+    /// Records a rename in the user's name history.
+    async fn add_name_change(
+        ctx: &ServiceContext<'_>,
+        user: &UserModel,
+        changed_by: i64,
+        new_name: &str,
+        new_slug: &str,
+        used_token: bool,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let model = user_name_change::ActiveModel {
+            user_id: Set(user.user_id),
+            changed_by: Set(changed_by),
+            old_name: Set(user.name.clone()),
+            old_slug: Set(user.slug.clone()),
+            new_name: Set(str!(new_name)),
+            new_slug: Set(str!(new_slug)),
+            used_token: Set(used_token),
+            ..Default::default()
+        };
+        model.insert(txn).await?;
+        Ok(())
+    }
+
+    /// Gets all the renames of a user, most recent first.
+    pub async fn get_name_history(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<Vec<UserNameChangeModel>> {
+        let txn = ctx.transaction();
+        let changes = UserNameChange::find()
+            .filter(user_name_change::Column::UserId.eq(user_id))
+            .order_by_desc(user_name_change::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(changes)
+    }
+
+    /// Finds all renames away from the given name, most recent first.
+    ///
+    /// The name is normalized before searching, so this matches
+    /// regardless of capitalization or punctuation differences.
+    /// Deleted users are included, since the history is still relevant.
+    pub async fn find_past_name(
+        ctx: &ServiceContext<'_>,
+        name: &str,
+    ) -> Result<Vec<UserNameChangeModel>> {
+        let slug = get_regular_slug(name);
+        info!("Finding users formerly named '{slug}'");
+
+        let txn = ctx.transaction();
+        let changes = UserNameChange::find()
+            .filter(user_name_change::Column::OldSlug.eq(slug))
+            .order_by_desc(user_name_change::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(changes)
+    }
+
     pub async fn refresh_name_change_tokens(ctx: &ServiceContext<'_>) -> Result<()> {
         info!("Refreshing name change tokens for all users who need one");
 
@@ -767,7 +877,7 @@ impl UserService {
 }
 
 fn get_user_slug(name: &str, user_type: UserType) -> String {
-    use crate::utils::{get_regular_slug, get_slug};
+    use crate::utils::get_slug;
 
     if user_type == UserType::Site {
         debug_assert!(
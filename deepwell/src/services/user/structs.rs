@@ -55,6 +55,14 @@ pub struct GetUserOutput {
     #[serde(flatten)]
     pub user: UserModel,
     pub aliases: Vec<AliasModel>,
+
+    /// The user's current slug, if they were found by a former name.
+    pub redirect_user: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FindUserPastName {
+    pub name: String,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -80,6 +88,12 @@ pub struct UpdateUserBody {
     pub location: Maybe<Option<String>>,
     pub biography: Maybe<Option<String>>,
     pub user_page: Maybe<Option<String>>,
+    pub dm_contacts_only: Maybe<bool>,
+
+    /// Who is making this change, if not the user themselves.
+    /// Recorded in the user's name history if they are renamed.
+    #[serde(default)]
+    pub changed_by: Option<i64>,
 
     #[serde(default)]
     pub bypass_filter: bool,
@@ -30,15 +30,18 @@
 //! requesting domain and session token into a site and user, respectively.
 
 use super::prelude::*;
+use crate::models::forum_thread::Model as ForumThreadModel;
 use crate::models::page::Model as PageModel;
+use crate::models::page_category::Model as PageCategoryModel;
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::site::Model as SiteModel;
 use crate::services::domain::SiteDomainResult;
 use crate::services::render::RenderOutput;
+use crate::services::session::ValidateSession;
 use crate::services::special_page::{GetSpecialPageOutput, SpecialPageType};
 use crate::services::{
-    DomainService, PageRevisionService, PageService, SessionService, SpecialPageService,
-    TextService, UserService,
+    CategoryService, DomainService, ForumThreadService, PageRevisionService, PageService,
+    ScoreService, SessionService, SpecialPageService, TextService, UserService,
 };
 use crate::utils::split_category;
 use fluent::{FluentArgs, FluentValue};
@@ -47,6 +50,7 @@ use ftml::render::html::HtmlOutput;
 use ref_map::*;
 use std::borrow::Cow;
 use std::mem;
+use std::net::IpAddr;
 use unic_langid::LanguageIdentifier;
 use wikidot_normalize::normalize;
 
@@ -61,6 +65,8 @@ impl ViewService {
             locales: locales_str,
             route,
             session_token,
+            ip_address,
+            user_agent,
         }: GetPageView,
     ) -> Result<GetPageViewOutput> {
         info!(
@@ -83,6 +89,8 @@ impl ViewService {
             &mut locales,
             &domain,
             session_token.ref_map(|s| s.as_str()),
+            ip_address,
+            user_agent.ref_map(|s| s.as_str()),
         )
         .await?
         {
@@ -98,18 +106,18 @@ impl ViewService {
             Some(PageRoute { slug, extra }) => (slug, extra),
         };
 
-        let redirect_page = Self::should_redirect_page(page_full_slug);
+        let mut redirect_page = Self::should_redirect_page(page_full_slug);
         let options = PageOptions::parse(page_extra);
 
         // Get page, revision, and text fields
         let (category_slug, page_only_slug) = split_category(page_full_slug);
-        let page_info = PageInfo {
+        let mut page_info = PageInfo {
             page: cow!(page_only_slug),
             category: cow_opt!(category_slug),
             site: cow!(&site.slug),
             title: cow!(page_only_slug),
             alt_title: None,
-            score: ScoreValue::Integer(0), // TODO configurable default score value
+            score: ScoreValue::Integer(0), // missing pages have no votes, see below
             tags: vec![],
 
             // TODO Determine what locale should be passed here.
@@ -132,6 +140,8 @@ impl ViewService {
             Found {
                 page: PageModel,
                 page_revision: PageRevisionModel,
+                discussion_thread: Option<ForumThreadModel>,
+                license: Option<String>,
             },
             Missing,
             Private,
@@ -188,16 +198,36 @@ impl ViewService {
                         TextService::get(ctx, &page_revision.compiled_hash),
                     )?;
 
+                    // Only fetch the discussion if requested.
+                    // This doesn't create one, that happens when it is first
+                    // requested through page_discussion_get.
+                    let discussion_thread = if options.comments {
+                        ForumThreadService::get_for_page_optional(ctx, &page).await?
+                    } else {
+                        None
+                    };
+
+                    // Pages are published under their category's license, if it has one
+                    let PageCategoryModel { license, .. } = CategoryService::get(
+                        ctx,
+                        page.site_id,
+                        Reference::Id(page.page_category_id),
+                    )
+                    .await?;
+
                     (
                         PageStatus::Found {
                             page,
                             page_revision,
+                            discussion_thread,
+                            license,
                         },
                         wikitext,
                         compiled_html,
                     )
                 } else {
                     warn!("User doesn't have page access, returning permission page");
+                    page_info.score = ScoreService::score(ctx, page.page_id).await?;
 
                     let (page_status, page_type) = if user_permissions.is_banned() {
                         (PageStatus::Banned, SpecialPageType::Banned)
@@ -232,6 +262,13 @@ impl ViewService {
             }
             // The page is missing, fetch the "missing page" data (_404).
             None => {
+                // If this slug was left behind by a rename, send the user along.
+                if redirect_page.is_none() && !options.no_redirect {
+                    redirect_page =
+                        PageService::get_redirect(ctx, site.site_id, page_full_slug)
+                            .await?;
+                }
+
                 let GetSpecialPageOutput {
                     wikitext,
                     render_output,
@@ -258,8 +295,6 @@ impl ViewService {
             }
         };
 
-        // TODO Check if user-agent and IP match?
-
         let viewer = Viewer {
             site,
             redirect_site,
@@ -270,11 +305,15 @@ impl ViewService {
             PageStatus::Found {
                 page,
                 page_revision,
+                discussion_thread,
+                license,
             } => GetPageViewOutput::PageFound {
                 viewer,
                 options,
                 page,
                 page_revision,
+                discussion_thread,
+                license,
                 redirect_page,
                 wikitext,
                 compiled_html,
@@ -312,6 +351,8 @@ impl ViewService {
             locales: locales_str,
             user: user_ref,
             session_token,
+            ip_address,
+            user_agent,
         }: GetUserView<'_>,
     ) -> Result<GetUserViewOutput> {
         info!(
@@ -329,6 +370,8 @@ impl ViewService {
             &mut locales,
             &domain,
             session_token.ref_map(|s| s.as_str()),
+            ip_address,
+            user_agent.ref_map(|s| s.as_str()),
         )
         .await?
         {
@@ -338,20 +381,30 @@ impl ViewService {
             }
         };
 
-        // TODO Check if user-agent and IP match?
-
         // Get data to return for this user.
-        let user = match user_ref {
-            Some(user_ref) => UserService::get_optional(ctx, user_ref).await?,
+        let (user, aliased) = match user_ref {
+            Some(user_ref) => UserService::get_optional_aliased(ctx, user_ref).await?,
             // For users visiting their own user info page
-            None => viewer
-                .user_session
-                .as_ref()
-                .map(|session| session.user.clone()),
+            None => {
+                let user = viewer
+                    .user_session
+                    .as_ref()
+                    .map(|session| session.user.clone());
+
+                (user, false)
+            }
         };
 
         let output = match user {
-            Some(user) => GetUserViewOutput::UserFound { viewer, user },
+            Some(user) => {
+                // If found by a former name, redirect to the current one
+                let redirect_user = aliased.then(|| user.slug.clone());
+                GetUserViewOutput::UserFound {
+                    viewer,
+                    user,
+                    redirect_user,
+                }
+            }
             None => GetUserViewOutput::UserMissing { viewer },
         };
 
@@ -364,6 +417,8 @@ impl ViewService {
             domain,
             locales: locales_str,
             session_token,
+            ip_address,
+            user_agent,
         }: GetAdminView,
     ) -> Result<GetAdminViewOutput> {
         info!(
@@ -382,6 +437,8 @@ impl ViewService {
             &mut locales,
             &domain,
             session_token.ref_map(|s| s.as_str()),
+            ip_address,
+            user_agent.ref_map(|s| s.as_str()),
         )
         .await?
         {
@@ -465,6 +522,10 @@ impl ViewService {
     /// * Hostname of request → Site ID and data
     /// * Session token → User ID and their permissions
     ///
+    /// The session is validated against the requesting client's IP address and
+    /// user-agent, per the session binding configuration. If either is enforced
+    /// but not provided, the session is rejected.
+    ///
     /// Then using this information, the caller can perform some common
     /// operations, such as slug normalization or redirect site aliases.
     pub async fn get_viewer(
@@ -472,6 +533,8 @@ impl ViewService {
         locales: &mut Vec<LanguageIdentifier>,
         domain: &str,
         session_token: Option<&str>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<&str>,
     ) -> Result<ViewerResult> {
         info!("Getting viewer data from domain '{domain}' and session token");
 
@@ -480,7 +543,15 @@ impl ViewService {
             None => None,
             Some("") => None,
             Some(token) => {
-                let session = SessionService::get(ctx, token).await?;
+                let session = SessionService::validate(
+                    ctx,
+                    ValidateSession {
+                        session_token: token,
+                        ip_address,
+                        user_agent,
+                    },
+                )
+                .await?;
                 let user = UserService::get(ctx, Reference::Id(session.user_id)).await?;
 
                 // Prefer what the user has set over what the browser is requesting
@@ -20,11 +20,13 @@
 
 use super::options::PageOptions;
 use super::prelude::*;
+use crate::models::forum_thread::Model as ForumThreadModel;
 use crate::models::page::Model as PageModel;
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::session::Model as SessionModel;
 use crate::models::site::Model as SiteModel;
 use crate::models::user::Model as UserModel;
+use std::net::IpAddr;
 
 // TODO replace with actual user permissions type
 #[derive(Serialize, Deserialize, Debug, Copy, Clone)]
@@ -41,6 +43,8 @@ impl UserPermissions {
 pub struct GetPageView {
     pub domain: String,
     pub session_token: Option<String>,
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
     pub route: Option<PageRoute>,
     pub locales: Vec<String>,
 }
@@ -60,6 +64,11 @@ pub enum GetPageViewOutput {
         options: PageOptions,
         page: PageModel,
         page_revision: PageRevisionModel,
+        discussion_thread: Option<ForumThreadModel>,
+
+        /// The category's license for the page, if it overrides the site's.
+        license: Option<String>,
+
         redirect_page: Option<String>,
         wikitext: String,
         compiled_html: String,
@@ -92,6 +101,8 @@ pub enum GetPageViewOutput {
 pub struct GetUserView<'a> {
     pub domain: String,
     pub session_token: Option<String>,
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
     pub user: Option<Reference<'a>>,
     pub locales: Vec<String>,
 }
@@ -103,6 +114,7 @@ pub enum GetUserViewOutput {
         #[serde(flatten)]
         viewer: Viewer,
         user: UserModel,
+        redirect_user: Option<String>,
     },
 
     UserMissing {
@@ -119,6 +131,8 @@ pub enum GetUserViewOutput {
 pub struct GetAdminView {
     pub domain: String,
     pub session_token: Option<String>,
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
     pub locales: Vec<String>,
 }
 
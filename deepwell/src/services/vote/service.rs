@@ -21,6 +21,7 @@
 
 use super::prelude::*;
 use crate::models::page_vote::{self, Entity as PageVote, Model as PageVoteModel};
+use crate::services::{OutdateService, PageService, ScoreService};
 use sea_orm::IntoActiveModel;
 
 #[derive(Debug)]
@@ -69,6 +70,7 @@ impl VoteService {
         };
 
         let vote = model.insert(txn).await?;
+        Self::process_score_change(ctx, page_id).await?;
         Ok(Some(vote))
     }
 
@@ -123,6 +125,7 @@ impl VoteService {
         }
 
         let model = vote.update(txn).await?;
+        Self::process_score_change(ctx, model.page_id).await?;
         Ok(model)
     }
 
@@ -135,9 +138,18 @@ impl VoteService {
         vote.deleted_at = Set(Some(now()));
 
         let model = vote.update(txn).await?;
+        Self::process_score_change(ctx, model.page_id).await?;
         Ok(model)
     }
 
+    /// Updates the page's score, and outdates its rendered HTML.
+    async fn process_score_change(ctx: &ServiceContext<'_>, page_id: i64) -> Result<()> {
+        ScoreService::refresh(ctx, page_id).await?;
+
+        let page = PageService::get_direct(ctx, page_id, false).await?;
+        OutdateService::process_page_score(ctx, page.site_id, page_id).await
+    }
+
     /// Gets votes for either a page or a user.
     ///
     /// The `start_id` argument gives the start ID to search from, exclusive.
@@ -0,0 +1,414 @@
+/*
+ * storage/local.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Blob storage backed by a directory on the local filesystem.
+//!
+//! Since there is no external service for clients to upload to, DEEPWELL
+//! issues its own signed upload URLs and receives the data itself.
+//! See `LocalUploadLayer`.
+//!
+//! The key used to sign upload URLs is generated when the server starts,
+//! so any URLs issued before a restart are no longer valid afterwards.
+
+use super::{BlobStorage, StoredObject, LOCAL_UPLOAD_ROUTE};
+use crate::services::{Error, Result};
+use crate::utils::assert_is_csprng;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use rand::{thread_rng, RngCore};
+use sha2::Sha256;
+use std::fmt::{self, Debug};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use time::OffsetDateTime;
+use tokio::fs;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The suffix of the file alongside each object which stores its MIME type.
+const MIME_SUFFIX: &str = ".mime";
+
+/// The suffix of temporary files which objects are written to before being
+/// moved into place, so that a partially-written object is never visible.
+const TEMP_SUFFIX: &str = ".partial";
+
+/// The MIME type reported for objects which do not have one stored.
+const DEFAULT_MIME: &str = "application/octet-stream";
+
+pub struct LocalStorage {
+    directory: PathBuf,
+    upload_url: String,
+    signing_key: Vec<u8>,
+}
+
+impl LocalStorage {
+    pub async fn new(
+        directory: &Path,
+        upload_url: &str,
+        signing_key: Option<&[u8]>,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(directory).await?;
+
+        let signing_key = match signing_key {
+            Some(key) => key.to_vec(),
+            None => {
+                warn!(
+                    "No local upload signing key set, generating one. \
+                     Upload URLs will not work across restarts or other instances.",
+                );
+
+                let mut key = vec![0; 32];
+                let mut rng = thread_rng();
+                assert_is_csprng(&rng);
+                rng.fill_bytes(&mut key);
+                key
+            }
+        };
+
+        Ok(LocalStorage {
+            directory: directory.to_path_buf(),
+            upload_url: upload_url.trim_end_matches('/').to_owned(),
+            signing_key,
+        })
+    }
+
+    /// Gets the filesystem path for an object.
+    ///
+    /// Only plain relative paths are permitted, so objects
+    /// cannot be read or written outside of the storage directory.
+    fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let relative = Path::new(path);
+        let valid = !path.is_empty()
+            && !path.ends_with(MIME_SUFFIX)
+            && !path.ends_with(TEMP_SUFFIX)
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+
+        if !valid {
+            error!("Invalid local blob storage path: '{path}'");
+            return Err(Error::BadRequest);
+        }
+
+        Ok(self.directory.join(relative))
+    }
+
+    fn sign(&self, path: &str, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.signing_key)
+            .expect("HMAC accepts keys of any size");
+
+        mac.update(path.as_bytes());
+        mac.update(b"\n");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+}
+
+#[async_trait]
+impl BlobStorage for LocalStorage {
+    async fn presign_upload(&self, path: &str, expiry_secs: u32) -> Result<String> {
+        // Ensure the path is valid before issuing a URL for it
+        self.resolve(path)?;
+
+        let expires = OffsetDateTime::now_utc().unix_timestamp() + i64::from(expiry_secs);
+        let signature = hex::encode(self.sign(path, expires).finalize().into_bytes());
+
+        Ok(format!(
+            "{}{}{}?expires={}&signature={}",
+            self.upload_url, LOCAL_UPLOAD_ROUTE, path, expires, signature,
+        ))
+    }
+
+    async fn receive_upload(&self, path: &str, query: &str, data: &[u8]) -> Result<()> {
+        let mut expires = None;
+        let mut signature = None;
+
+        for pair in query.split('&') {
+            match pair.split_once('=') {
+                Some(("expires", value)) => expires = value.parse::<i64>().ok(),
+                Some(("signature", value)) => signature = hex::decode(value).ok(),
+                _ => (),
+            }
+        }
+
+        let (Some(expires), Some(signature)) = (expires, signature) else {
+            warn!("Upload to '{path}' is missing its signature");
+            return Err(Error::BlobUploadRejected);
+        };
+
+        if OffsetDateTime::now_utc().unix_timestamp() > expires {
+            warn!("Upload URL for '{path}' has expired");
+            return Err(Error::BlobUploadRejected);
+        }
+
+        if self.sign(path, expires).verify_slice(&signature).is_err() {
+            warn!("Upload to '{path}' has an invalid signature");
+            return Err(Error::BlobUploadRejected);
+        }
+
+        debug!("Receiving upload of {} bytes to '{path}'", data.len());
+        let file_path = self.resolve(path)?;
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        write_atomic(&file_path, data).await?;
+        Ok(())
+    }
+
+    async fn put_file(&self, path: &str, source: &Path, mime: &str) -> Result<()> {
+        let file_path = self.resolve(path)?;
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        write_atomic(&mime_path(&file_path), mime.as_bytes()).await?;
+
+        let temp_path = temp_path(&file_path);
+        if let Err(error) = fs::copy(source, &temp_path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(Error::Io(error));
+        }
+
+        fs::rename(temp_path, file_path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.resolve(path)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(Error::Io(error)),
+        }
+    }
+
+    async fn head(&self, path: &str) -> Result<Option<StoredObject>> {
+        let file_path = self.resolve(path)?;
+        let metadata = match fs::metadata(&file_path).await {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(Error::Io(error)),
+        };
+
+        let mime = match fs::read_to_string(mime_path(&file_path)).await {
+            Ok(mime) => mime,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => str!(DEFAULT_MIME),
+            Err(error) => return Err(Error::Io(error)),
+        };
+
+        Ok(Some(StoredObject {
+            size: metadata.len().try_into().expect("File size exceeds i64"),
+            mime,
+            last_modified: OffsetDateTime::from(metadata.modified()?),
+        }))
+    }
+
+    async fn put(&self, path: &str, data: &[u8], mime: &str) -> Result<()> {
+        let file_path = self.resolve(path)?;
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        write_atomic(&mime_path(&file_path), mime.as_bytes()).await?;
+        write_atomic(&file_path, data).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, path: &str) -> Result<()> {
+        let file_path = self.resolve(path)?;
+
+        for path in [mime_path(&file_path), file_path] {
+            match fs::remove_file(path).await {
+                Ok(()) => (),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => (),
+                Err(error) => return Err(Error::Io(error)),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<(String, Option<OffsetDateTime>)>> {
+        let mut objects = Vec::new();
+        let mut entries = fs::read_dir(&self.directory).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+
+            if name.ends_with(MIME_SUFFIX) || name.ends_with(TEMP_SUFFIX) {
+                continue;
+            }
+
+            let last_modified = metadata.modified().ok().map(OffsetDateTime::from);
+            objects.push((name, last_modified));
+        }
+
+        Ok(objects)
+    }
+}
+
+impl Debug for LocalStorage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LocalStorage")
+            .field("directory", &self.directory)
+            .field("upload_url", &self.upload_url)
+            .finish_non_exhaustive()
+    }
+}
+
+fn mime_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(MIME_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// Gets a unique temporary path in the same directory as the file.
+///
+/// Being in the same directory means it is on the same filesystem,
+/// so it can be renamed over the file atomically.
+fn temp_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(format!(".{:016x}{TEMP_SUFFIX}", thread_rng().next_u64()));
+    PathBuf::from(path)
+}
+
+/// Writes a file by way of a temporary file, so that it is replaced all at once.
+async fn write_atomic(file_path: &Path, data: &[u8]) -> io::Result<()> {
+    let temp_path = temp_path(file_path);
+    if let Err(error) = fs::write(&temp_path, data).await {
+        let _ = fs::remove_file(&temp_path).await;
+        return Err(error);
+    }
+
+    fs::rename(temp_path, file_path).await
+}
+
+#[test]
+fn local_paths() {
+    let storage = LocalStorage {
+        directory: PathBuf::from("/srv/blobs"),
+        upload_url: str!("http://localhost"),
+        signing_key: vec![0; 32],
+    };
+
+    assert_eq!(
+        storage.resolve("apple").unwrap(),
+        PathBuf::from("/srv/blobs/apple"),
+    );
+    assert_eq!(
+        storage.resolve("backups/site.json").unwrap(),
+        PathBuf::from("/srv/blobs/backups/site.json"),
+    );
+
+    for path in [
+        "",
+        "/etc/passwd",
+        "../apple",
+        "backups/../../apple",
+        "./apple",
+        "apple.mime",
+        "apple.0123456789abcdef.partial",
+    ] {
+        assert!(
+            matches!(storage.resolve(path), Err(Error::BadRequest)),
+            "Path '{path}' was not rejected",
+        );
+    }
+}
+
+#[tokio::test]
+async fn local_uploads() {
+    let directory = std::env::temp_dir().join(format!(
+        "deepwell-test-local-storage-{}",
+        std::process::id()
+    ));
+    let storage = LocalStorage::new(&directory, "http://localhost/", Some(b"test-key"))
+        .await
+        .unwrap();
+
+    macro_rules! check_rejected {
+        ($path:expr, $query:expr $(,)?) => {
+            assert!(
+                matches!(
+                    storage.receive_upload($path, $query, b"banana").await,
+                    Err(Error::BlobUploadRejected),
+                ),
+                "Upload was not rejected",
+            );
+        };
+    }
+
+    // Signed URL is accepted
+    let url = storage.presign_upload("upload", 60).await.unwrap();
+    let (_, query) = url.split_once('?').unwrap();
+    storage
+        .receive_upload("upload", query, b"apple")
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.get("upload").await.unwrap().as_deref(),
+        Some(b"apple" as &[u8]),
+    );
+
+    // Signature doesn't apply to other paths, and must be intact
+    check_rejected!("other", query);
+    check_rejected!("upload", &query.replace("signature=", "signature=00"));
+    check_rejected!("upload", "");
+
+    // Expired, but otherwise valid, signature
+    let expires = OffsetDateTime::now_utc().unix_timestamp() - 1;
+    let signature = hex::encode(storage.sign("upload", expires).finalize().into_bytes());
+    check_rejected!(
+        "upload",
+        &format!("expires={expires}&signature={signature}")
+    );
+
+    // Rejected uploads don't change the object
+    assert_eq!(
+        storage.get("upload").await.unwrap().as_deref(),
+        Some(b"apple" as &[u8]),
+    );
+
+    // Writes replace the object, without leaving temporary files behind
+    storage
+        .put("upload", b"cherry", "text/plain")
+        .await
+        .unwrap();
+    let object = storage.head("upload").await.unwrap().unwrap();
+    assert_eq!(object.size, 6);
+    assert_eq!(object.mime, "text/plain");
+
+    let mut names = Vec::new();
+    let mut entries = fs::read_dir(&directory).await.unwrap();
+    while let Some(entry) = entries.next_entry().await.unwrap() {
+        names.push(entry.file_name().into_string().unwrap());
+    }
+    names.sort();
+    assert_eq!(names, ["upload", "upload.mime"]);
+
+    fs::remove_dir_all(&directory).await.unwrap();
+}
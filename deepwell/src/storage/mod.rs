@@ -0,0 +1,113 @@
+/*
+ * storage/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Backends for storing blobs.
+//!
+//! Blobs are kept in a flat namespace of paths. Finalized blobs are stored
+//! at the hex representation of their hash, while other data (such as pending
+//! uploads or site backups) is kept under subdirectories.
+//!
+//! Which backend is used is selected in the configuration, see `[file]`.
+
+mod local;
+mod s3;
+mod upload;
+
+pub use self::local::LocalStorage;
+pub use self::s3::S3Storage;
+pub use self::upload::{LocalUploadLayer, LOCAL_UPLOAD_ROUTE};
+
+use crate::config::{BlobStorageConfig, Config, Secrets};
+use crate::services::{Error, Result};
+use async_trait::async_trait;
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::Arc;
+use time::OffsetDateTime;
+
+/// Information about a stored object, without its contents.
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub size: i64,
+    pub mime: String,
+    pub last_modified: OffsetDateTime,
+}
+
+#[async_trait]
+pub trait BlobStorage: Debug + Send + Sync {
+    /// Creates a URL which a client can `PUT` the contents of a new object to.
+    async fn presign_upload(&self, path: &str, expiry_secs: u32) -> Result<String>;
+
+    /// Accepts an upload sent directly to DEEPWELL.
+    ///
+    /// This is only used by backends whose presigned URLs point at DEEPWELL
+    /// itself. The `query` is the query string of the request, which contains
+    /// whatever is needed to verify the upload is permitted.
+    async fn receive_upload(
+        &self,
+        _path: &str,
+        _query: &str,
+        _data: &[u8],
+    ) -> Result<()> {
+        Err(Error::BadRequest)
+    }
+
+    /// Retrieves the contents of an object, if it exists.
+    async fn get(&self, path: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Retrieves information about an object, if it exists.
+    async fn head(&self, path: &str) -> Result<Option<StoredObject>>;
+
+    /// Writes an object, replacing any existing one at that path.
+    async fn put(&self, path: &str, data: &[u8], mime: &str) -> Result<()>;
+
+    /// Writes an object from a file on disk, without reading it all into memory.
+    async fn put_file(&self, path: &str, source: &Path, mime: &str) -> Result<()>;
+
+    /// Deletes an object. Does nothing if it does not exist.
+    async fn delete(&self, path: &str) -> Result<()>;
+
+    /// Lists all objects which are not in a subdirectory,
+    /// with their last modified time (if known).
+    async fn list(&self) -> Result<Vec<(String, Option<OffsetDateTime>)>>;
+}
+
+/// Sets up the blob storage backend specified in the configuration.
+pub async fn open(
+    config: &Config,
+    secrets: &Secrets,
+) -> anyhow::Result<Arc<dyn BlobStorage>> {
+    match config.blob_storage {
+        BlobStorageConfig::S3 => {
+            info!("Opening S3 bucket");
+            let storage = S3Storage::new(secrets)?;
+            Ok(Arc::new(storage))
+        }
+        BlobStorageConfig::Local {
+            ref directory,
+            ref upload_url,
+        } => {
+            info!("Opening local blob storage at {}", directory.display());
+            let signing_key = secrets.local_upload_key.as_deref().map(str::as_bytes);
+            let storage = LocalStorage::new(directory, upload_url, signing_key).await?;
+            Ok(Arc::new(storage))
+        }
+    }
+}
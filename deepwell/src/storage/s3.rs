@@ -0,0 +1,183 @@
+/*
+ * storage/s3.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Blob storage backed by an S3 bucket.
+//!
+//! Uploads are made directly to S3 using presigned URLs.
+
+use super::{BlobStorage, StoredObject};
+use crate::config::Secrets;
+use crate::services::{Error, Result};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use s3::bucket::Bucket;
+use s3::request::request_trait::ResponseData;
+use std::path::Path;
+use std::str;
+use std::time::Duration;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::OffsetDateTime;
+use tokio::fs::File;
+
+#[derive(Debug)]
+pub struct S3Storage {
+    bucket: Box<Bucket>,
+}
+
+impl S3Storage {
+    pub fn new(secrets: &Secrets) -> anyhow::Result<Self> {
+        let Some(ref s3) = secrets.s3 else {
+            return Err(anyhow!(
+                "S3 blob storage is selected, but S3_BUCKET is not set"
+            ));
+        };
+
+        let mut bucket =
+            Bucket::new(&s3.bucket, s3.region.clone(), s3.credentials.clone())?;
+
+        if s3.path_style {
+            bucket = bucket.with_path_style();
+        }
+
+        bucket.request_timeout = Some(Duration::from_millis(500));
+        Ok(S3Storage { bucket })
+    }
+}
+
+#[async_trait]
+impl BlobStorage for S3Storage {
+    async fn presign_upload(&self, path: &str, expiry_secs: u32) -> Result<String> {
+        let url = self
+            .bucket
+            .presign_put(path, expiry_secs, None, None)
+            .await?;
+        Ok(url)
+    }
+
+    async fn get(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        let response = self.bucket.get_object(path).await?;
+        match response.status_code() {
+            200 => Ok(Some(response.into())),
+            404 => Ok(None),
+            _ => s3_error(&response, "fetching S3 object"),
+        }
+    }
+
+    async fn head(&self, path: &str) -> Result<Option<StoredObject>> {
+        let (result, status) = self.bucket.head_object(path).await?;
+
+        match status {
+            200 | 204 => {
+                // Headers should be passed in
+                let size = result.content_length.ok_or(Error::S3Response)?;
+                let mime = result.content_type.ok_or(Error::S3Response)?;
+                let last_modified = {
+                    let timestamp = result.last_modified.ok_or(Error::S3Response)?;
+
+                    OffsetDateTime::parse(&timestamp, &Rfc2822)
+                        .map_err(|_| Error::S3Response)?
+                };
+
+                Ok(Some(StoredObject {
+                    size,
+                    mime,
+                    last_modified,
+                }))
+            }
+            404 => Ok(None),
+            _ => {
+                error!("Error while heading S3 object (HTTP {status})");
+                Err(Error::S3Response)
+            }
+        }
+    }
+
+    async fn put(&self, path: &str, data: &[u8], mime: &str) -> Result<()> {
+        let response = self
+            .bucket
+            .put_object_with_content_type(path, data, mime)
+            .await?;
+
+        // We assume all unexpected statuses are errors, even if 1XX or 2XX
+        match response.status_code() {
+            200 => Ok(()),
+            _ => s3_error(&response, "uploading S3 object"),
+        }
+    }
+
+    async fn put_file(&self, path: &str, source: &Path, mime: &str) -> Result<()> {
+        let mut file = File::open(source).await?;
+        let response = self
+            .bucket
+            .put_object_stream_with_content_type(&mut file, path, mime)
+            .await?;
+
+        match response.status_code() {
+            200 => Ok(()),
+            status => {
+                error!("Error while streaming S3 object upload (HTTP {status})");
+                Err(Error::S3Response)
+            }
+        }
+    }
+
+    async fn delete(&self, path: &str) -> Result<()> {
+        let response = self.bucket.delete_object(path).await?;
+        match response.status_code() {
+            204 => Ok(()),
+            _ => s3_error(&response, "deleting S3 object"),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<(String, Option<OffsetDateTime>)>> {
+        let results = self.bucket.list(String::new(), Some(str!("/"))).await?;
+        let objects = results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .map(|object| {
+                let last_modified =
+                    OffsetDateTime::parse(&object.last_modified, &Rfc3339).ok();
+
+                (object.key, last_modified)
+            })
+            .collect();
+
+        Ok(objects)
+    }
+}
+
+/// Helper method to parse out an S3 error response and print the message (if any).
+fn s3_error<T>(response: &ResponseData, action: &str) -> Result<T> {
+    let error_message = match str::from_utf8(response.bytes()) {
+        Ok("") => "(no content)",
+        Ok(m) => m,
+        Err(_) => "(invalid UTF-8)",
+    };
+
+    error!(
+        "Error while {} (HTTP {}): {}",
+        action,
+        response.status_code(),
+        error_message,
+    );
+
+    // TODO replace with S3 backend-specific error
+    Err(Error::S3Response)
+}
@@ -0,0 +1,209 @@
+/*
+ * storage/upload.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2024 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! HTTP middleware which receives uploads for local blob storage.
+//!
+//! This sits in front of the JSON-RPC server, intercepting requests
+//! to the upload route and passing everything else through.
+
+use crate::api::ServerState;
+use crate::services::Error;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http::header::{
+    ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+    ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_LENGTH,
+};
+use http::{HeaderValue, Method, StatusCode};
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use jsonrpsee::server::{HttpBody, HttpRequest, HttpResponse};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// The route which signed upload URLs for local blob storage point to.
+pub const LOCAL_UPLOAD_ROUTE: &str = "/blob-upload/";
+
+#[derive(Debug, Clone)]
+pub struct LocalUploadLayer {
+    state: ServerState,
+}
+
+impl LocalUploadLayer {
+    pub fn new(state: &ServerState) -> Self {
+        LocalUploadLayer {
+            state: Arc::clone(state),
+        }
+    }
+}
+
+impl<S> Layer<S> for LocalUploadLayer {
+    type Service = LocalUploadService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LocalUploadService {
+            inner,
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalUploadService<S> {
+    inner: S,
+    state: ServerState,
+}
+
+impl<S> Service<HttpRequest> for LocalUploadService<S>
+where
+    S: Service<HttpRequest, Response = HttpResponse>,
+    S::Future: Send + 'static,
+{
+    type Response = HttpResponse;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<HttpResponse, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: HttpRequest) -> Self::Future {
+        if !request.uri().path().starts_with(LOCAL_UPLOAD_ROUTE) {
+            return Box::pin(self.inner.call(request));
+        }
+
+        let state = Arc::clone(&self.state);
+        Box::pin(async move {
+            let status = match *request.method() {
+                // CORS preflight, since uploads come from the browser
+                Method::OPTIONS => StatusCode::NO_CONTENT,
+                Method::PUT => receive_upload(&state, request).await,
+                _ => StatusCode::METHOD_NOT_ALLOWED,
+            };
+
+            Ok(build_response(status))
+        })
+    }
+}
+
+async fn receive_upload(state: &ServerState, request: HttpRequest) -> StatusCode {
+    let path = request.uri().path()[LOCAL_UPLOAD_ROUTE.len()..].to_owned();
+    let query = request.uri().query().unwrap_or("").to_owned();
+    let limit = usize::try_from(state.config.maximum_blob_size).unwrap_or(usize::MAX);
+    let declared_size = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+
+    let data = match read_upload(&path, request.into_body(), declared_size, limit).await {
+        Ok(data) => data,
+        Err(status) => return status,
+    };
+
+    match state
+        .blob_storage
+        .receive_upload(&path, &query, &data)
+        .await
+    {
+        Ok(()) => StatusCode::OK,
+        Err(Error::BlobUploadRejected) => StatusCode::FORBIDDEN,
+        Err(Error::BadRequest) => StatusCode::BAD_REQUEST,
+        Err(error) => {
+            error!("Unable to store upload to '{path}': {error}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Reads the body of an upload, checking it against the size limit
+/// and the size declared in the request, if any.
+async fn read_upload(
+    path: &str,
+    body: HttpBody,
+    declared_size: Option<usize>,
+    limit: usize,
+) -> Result<Bytes, StatusCode> {
+    if declared_size.is_some_and(|size| size > limit) {
+        warn!("Upload to '{path}' declares a size over the maximum blob size");
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let data = match Limited::new(body, limit).collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(error) if error.is::<LengthLimitError>() => {
+            warn!("Upload to '{path}' exceeds the maximum blob size");
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        Err(error) => {
+            warn!("Unable to read upload to '{path}': {error}");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    if declared_size.is_some_and(|size| size != data.len()) {
+        warn!(
+            "Upload to '{path}' was {} bytes, but declared {declared_size:?}",
+            data.len(),
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(data)
+}
+
+fn build_response(status: StatusCode) -> HttpResponse {
+    let mut response = HttpResponse::new(HttpBody::empty());
+    *response.status_mut() = status;
+
+    let headers = response.headers_mut();
+    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    headers.insert(
+        ACCESS_CONTROL_ALLOW_METHODS,
+        HeaderValue::from_static("PUT"),
+    );
+    headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, HeaderValue::from_static("*"));
+    response
+}
+
+#[tokio::test]
+async fn upload_size() {
+    macro_rules! check {
+        ($data:expr, $declared_size:expr, $expected:expr $(,)?) => {{
+            let data: &[u8] = $data;
+            let body = HttpBody::from(data.to_vec());
+            let result = read_upload("test", body, $declared_size, 8).await;
+            assert_eq!(
+                result,
+                $expected.map(Bytes::from_static),
+                "Actual upload result doesn't match expected",
+            );
+        }};
+    }
+
+    check!(b"", None, Ok(b"" as &[u8]));
+    check!(b"apple", None, Ok(b"apple" as &[u8]));
+    check!(b"apple", Some(5), Ok(b"apple" as &[u8]));
+    check!(b"12345678", Some(8), Ok(b"12345678" as &[u8]));
+    check!(b"123456789", None, Err(StatusCode::PAYLOAD_TOO_LARGE));
+    check!(b"apple", Some(9), Err(StatusCode::PAYLOAD_TOO_LARGE));
+    check!(b"apple", Some(4), Err(StatusCode::BAD_REQUEST));
+    check!(b"apple", Some(6), Err(StatusCode::BAD_REQUEST));
+}
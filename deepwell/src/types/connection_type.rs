@@ -30,6 +30,7 @@ pub enum ConnectionType {
     Component,
     Link,
     Redirect,
+    ListPages,
 }
 
 impl ConnectionType {
@@ -40,6 +41,7 @@ impl ConnectionType {
             ConnectionType::Component => "component",
             ConnectionType::Link => "link",
             ConnectionType::Redirect => "redirect",
+            ConnectionType::ListPages => "list-pages",
         }
     }
 }
@@ -54,6 +56,7 @@ impl FromStr for ConnectionType {
             "component" => Ok(ConnectionType::Component),
             "link" => Ok(ConnectionType::Link),
             "redirect" => Ok(ConnectionType::Redirect),
+            "list-pages" => Ok(ConnectionType::ListPages),
             _ => Err(ServiceError::InvalidEnumValue),
         }
     }
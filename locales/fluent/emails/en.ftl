@@ -24,3 +24,17 @@ emails-reset-password =
     *[other] { $count } minutes.
   }
   .outro = If you did not request a password reset, no further action is required.
+
+emails-new-login =
+  .subject = New Login to Your Account
+  .body =
+    Your { -service-name } account was just logged into from a device we have not seen before: **{ $device }**.
+
+    If this was you, no further action is required. If not, please change your password and log out all other sessions from your account settings.
+
+emails-storage-warning =
+  .subject = { $site } Is Nearing Its Storage Quota
+  .body =
+    Files on **{ $site }** now use { $used } MiB of its { $quota } MiB storage quota ({ $percent }%).
+
+    Once the quota is reached, no new files can be uploaded. Please contact an administrator if the site needs more space.
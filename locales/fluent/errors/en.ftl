@@ -0,0 +1,171 @@
+### Errors returned by the backend
+### Each message corresponds to an error kind in DEEPWELL.
+
+## General
+
+errors-raw = An unexpected error occurred.
+
+## Missing data
+
+errors-general-not-found = The requested item could not be found.
+errors-alias-not-found = This alias does not exist.
+errors-relation-not-found = This relation does not exist.
+errors-user-not-found = This user does not exist.
+errors-site-not-found = This site does not exist.
+errors-page-not-found = This page does not exist.
+errors-page-category-not-found = This page category does not exist.
+errors-page-parent-not-found = This page parent does not exist.
+errors-page-revision-not-found = This page revision does not exist.
+errors-file-not-found = This file does not exist.
+errors-file-revision-not-found = This file revision does not exist.
+errors-vote-not-found = This vote does not exist.
+errors-filter-not-found = This filter does not exist.
+errors-custom-domain-not-found = This custom domain does not exist.
+errors-message-not-found = This message does not exist.
+errors-message-draft-not-found = This message draft does not exist.
+errors-blob-not-found = This upload does not exist.
+errors-text-not-found = This text does not exist.
+errors-site-backup-not-found = This site backup does not exist.
+errors-site-backup-archive-not-found = This site backup archive does not exist.
+errors-forum-group-not-found = This forum group does not exist.
+errors-forum-category-not-found = This forum category does not exist.
+errors-forum-thread-not-found = This forum thread does not exist.
+errors-forum-post-not-found = This forum post does not exist.
+errors-forum-post-revision-not-found = This forum post revision does not exist.
+errors-job-not-found = This job does not exist.
+errors-tag-not-found = This tag does not exist.
+errors-tag-group-not-found = This tag group does not exist.
+errors-page-draft-not-found = There is no saved draft for this page.
+errors-page-schedule-not-found = This scheduled page change does not exist.
+errors-site-application-not-found = This site application does not exist.
+errors-site-invitation-not-found = This invitation does not exist.
+
+## Existing data
+
+errors-user-exists = A user with this name already exists.
+errors-user-mfa-exists = Multi-factor authentication is already set up.
+errors-site-exists = A site with this name already exists.
+errors-page-exists = This page already exists.
+errors-page-slug-exists = A page already exists at this address.
+errors-page-parent-exists = This page already has that parent.
+errors-file-exists = A file with this name already exists.
+errors-filter-exists = This filter already exists.
+errors-custom-domain-exists = This custom domain is already in use.
+errors-user-contact-exists = You are already contacts with this user.
+errors-tag-exists = A tag or tag alias with this name already exists.
+errors-tag-group-exists = A tag group with this name already exists.
+errors-page-category-exists = A page category with this name already exists.
+errors-site-member-exists = You are already a member of this site.
+errors-site-application-exists = You have already applied to join this site.
+
+## Server errors
+
+errors-rate-limited = Too many requests are being made to an external service. Please try again later.
+errors-web-request = An external service could not be reached.
+errors-authentication-backend = An error occurred while trying to log in.
+errors-render-timeout = The page took too long to render.
+errors-email-verification = The email address could not be verified.
+errors-s3-service = The file storage service returned an error.
+errors-s3-response = The file storage service failed to respond properly.
+errors-serde = An internal error occurred while processing data.
+errors-database = An internal database error occurred.
+errors-cryptography = An internal cryptography error occurred.
+errors-magic = The file type could not be determined.
+errors-otp = An error occurred with the one-time password.
+errors-redis = An internal cache error occurred.
+errors-rsmq = An internal job queue error occurred.
+errors-io = An internal input or output error occurred.
+errors-text-corrupted = Stored text could not be read.
+
+## Request errors
+
+errors-bad-request = The request is malformed or incorrect.
+errors-invalid-enum-value = The request contains an invalid value.
+errors-filter-violation = This content is not allowed on this site.
+errors-insufficient-name-changes = You do not have any name changes remaining.
+errors-cannot-hide-latest-revision = The contents of the latest page revision cannot be hidden.
+errors-filter-regex-invalid = A content filter on this site is invalid.
+errors-filter-not-deleted = This filter has not been deleted.
+errors-file-name-empty = File names cannot be empty.
+errors-file-name-too-long = This file name is too long.
+errors-file-mime-empty = The file type cannot be empty.
+errors-file-not-deleted = This file has not been deleted.
+errors-page-not-deleted = This page has not been deleted.
+errors-page-slug-empty = Page addresses cannot be empty.
+errors-site-slug-empty = Site addresses cannot be empty.
+errors-user-name-too-short = This username is too short.
+errors-user-slug-empty = Usernames cannot be empty.
+errors-user-email-empty = Email addresses cannot be empty.
+errors-message-subject-empty = Message subjects cannot be empty.
+errors-message-subject-too-long = This message subject is too long.
+errors-message-body-empty = Messages cannot be empty.
+errors-message-body-too-long = This message is too long.
+errors-message-no-recipients = Messages must have at least one recipient.
+errors-message-too-many-recipients = This message has too many recipients.
+errors-blob-wrong-user = This upload belongs to a different user.
+errors-blob-too-big = This upload is too large.
+errors-blob-not-uploaded = The file has not been uploaded yet.
+errors-blob-size-mismatch = The uploaded file does not have the expected size.
+errors-blob-upload-rejected = This upload link is invalid or has expired.
+errors-not-latest-revision-id = This page was changed by someone else in the meantime.
+errors-site-backup-archive-invalid = This site backup archive is invalid or from an unsupported version.
+errors-forum-thread-locked = This forum thread is locked.
+errors-forum-thread-title-empty = Forum thread titles cannot be empty.
+errors-forum-post-body-empty = Forum posts cannot be empty.
+errors-forum-post-too-deep = This reply is nested too deeply.
+errors-forum-post-parent-mismatch = The post being replied to is in a different thread.
+errors-tag-invalid = The tag "{ $tag }" is not valid.
+errors-tag-not-allowed = { $count ->
+    [one] This tag is not allowed on this site: { $tags }
+   *[other] These tags are not allowed on this site: { $tags }
+  }
+errors-tag-group-exclusive = Only one tag from the group "{ $group }" may be used, found: { $tags }
+errors-tag-group-required = At least one tag from the group "{ $group }" is required.
+errors-page-category-slug-invalid = This page category name is not valid.
+errors-page-category-not-empty = This page category still has pages in it.
+errors-page-category-default = This cannot be done to the default page category.
+errors-page-schedule-in-past = Changes can only be scheduled for a time in the future.
+errors-page-schedule-not-pending = This scheduled change has already been published or cancelled.
+errors-page-schedule-empty = This scheduled edit does not change anything.
+errors-site-not-template = This site is not available as a template.
+errors-site-join-not-allowed = This site cannot be joined this way.
+errors-site-application-not-pending = This application has already been accepted or rejected.
+errors-site-application-answers-mismatch = Every question must be answered to apply to this site.
+errors-site-invitation-expired = This invitation has expired or can no longer be used.
+errors-site-invitation-invalid = Invitations must expire in the future and allow at least one use.
+errors-page-parent-cycle = A page cannot be made a child of one of its own children.
+errors-page-parent-too-deep = This would make the page hierarchy too deep.
+errors-user-storage-quota-exceeded = This upload would exceed your storage quota.
+errors-site-storage-quota-exceeded = This upload would exceed the site's storage quota.
+errors-storage-quota-invalid = The storage quota cannot be negative.
+errors-too-many-requests = Too many requests have been made. Please wait a moment and try again.
+
+## Localization
+
+errors-locale-invalid = This locale is not valid.
+errors-locale-missing = There are no messages for this locale.
+errors-locale-message-missing = This message does not exist for this locale.
+errors-locale-message-value-missing = This message has no value.
+errors-locale-message-attribute-missing = This message does not have that attribute.
+errors-no-locales-specified = No locales were specified.
+
+## Login
+
+errors-empty-password = A password is required.
+errors-invalid-email = This email address is not valid.
+errors-disallowed-email = This email address is not allowed.
+
+## Relationships
+
+errors-site-blocked-user = You cannot do this because you are blocked from this site.
+errors-user-blocked-user = You cannot do this because you are blocked by this user.
+errors-user-not-contact = This user only allows this from their contacts.
+errors-user-contact-self = You cannot add yourself as a contact.
+
+## Authentication
+
+errors-invalid-authentication = Invalid username, password, or one-time code.
+errors-invalid-session-token = Your session is invalid. Please log in again.
+errors-session-user-id = Your session belongs to a different user. Please log in again.
+errors-session-client-mismatch = Your session was created on a different device. Please log in again.
+errors-category-permission-denied = You do not have permission to do this in this category.